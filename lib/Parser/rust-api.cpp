@@ -367,6 +367,8 @@ struct ParserFlags {
   ParserDialect dialect = ParserDialect::JavaScript;
   /// Store doc-comment block at the top of the file.
   bool storeDocBlock = false;
+  /// Store all comments in the file.
+  bool storeComments = false;
 };
 
 enum class DiagKind : uint32_t {
@@ -450,6 +452,37 @@ enum class MagicCommentKind : uint32_t {
   SourceMappingUrl = 1,
 };
 
+enum class CommentKind : uint32_t {
+  Line,
+  Block,
+  Hashbang,
+};
+
+CommentKind toCommentKind(parser::StoredComment::Kind k) {
+  switch (k) {
+    default:
+      assert(false);
+    case parser::StoredComment::Kind::Line:
+      return CommentKind::Line;
+    case parser::StoredComment::Kind::Block:
+      return CommentKind::Block;
+    case parser::StoredComment::Kind::Hashbang:
+      return CommentKind::Hashbang;
+  }
+}
+
+/// A temporary struct describing a comment, returned to Rust.
+struct Comment {
+  /// What kind of comment.
+  CommentKind kind;
+  /// Source range of the comment, including the delimiters.
+  SMRange range;
+
+  Comment(const parser::StoredComment &comment)
+      : kind(toCommentKind(comment.getKind())),
+        range(comment.getSourceRange()) {}
+};
+
 /// This object contains the entire parser state.
 struct ParserContext {
   /// Parser context with allocators, string table, etc.
@@ -470,6 +503,9 @@ struct ParserContext {
   /// Doc block at the top of the file.
   std::string docBlock_{};
 
+  /// All comments in the file, if storeComments was provided at parse time.
+  std::vector<Comment> comments_{};
+
   explicit ParserContext() {
     context_.getSourceErrorManager().setDiagHandler(
         [](const llvh::SMDiagnostic &diag, void *ctx) {
@@ -558,6 +594,7 @@ hermes_parser_parse(ParserFlags flags, const char *source, size_t len) {
 
   parser::JSParser parser(
       parserCtx->context_, parserCtx->bufId_, hermes::parser::FullParse);
+  parser.setStoreComments(flags.storeComments);
   auto ast = parser.parse();
 
   if (!parserCtx->firstError_) {
@@ -566,6 +603,8 @@ hermes_parser_parse(ParserFlags flags, const char *source, size_t len) {
       parserCtx->addError("Internal error");
     } else {
       parserCtx->ast_ = *ast;
+      for (const auto &comment : parser.getStoredComments())
+        parserCtx->comments_.push_back(comment);
     }
   }
   return parserCtx.release();
@@ -666,3 +705,9 @@ extern "C" DataRef hermes_get_node_name(ESTree::Node *n) {
 extern "C" DataRef hermes_parser_get_doc_block(ParserContext *parserCtx) {
   return toDataRef(parserCtx->docBlock_);
 }
+
+/// \return the comments in the file in source order if storeComments was
+/// provided at parse time and the parse was successful.
+extern "C" DataRef hermes_parser_get_comments(const ParserContext *parserCtx) {
+  return toDataRef(parserCtx->comments_);
+}
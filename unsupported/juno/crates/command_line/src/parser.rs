@@ -142,7 +142,7 @@ impl Parser<'_> {
     }
 
     fn parse_long_arg(&mut self, arg: &str) -> Result<(), String> {
-        let (name, value) = if let Some((name, value)) = arg.split_once('=') {
+        let (name, mut value) = if let Some((name, value)) = arg.split_once('=') {
             (name, Some(value))
         } else {
             (arg, None)
@@ -157,6 +157,17 @@ impl Parser<'_> {
             ));
         };
 
+        // A required value may also follow in the next argument "--foo bar".
+        if value.is_none()
+            && opt.0.info().expected_value == ExpectedValue::Required
+            && self.cur_arg < self.args.len() - 1
+            && !(self.args[self.cur_arg + 1].starts_with('-')
+                && self.args[self.cur_arg + 1].len() > 1)
+        {
+            self.cur_arg += 1;
+            value = Some(&self.args[self.cur_arg]);
+        }
+
         match opt.0.parse_value(value, opt.1) {
             Ok(_) => Ok(()),
             Err(e) => Err(format!(
@@ -245,6 +256,39 @@ mod tests {
         assert_eq!(*max_heap, 10);
     }
 
+    #[test]
+    fn test_long_separate_value() {
+        let mut options = CommandLine::new("juno");
+
+        let max_heap = Opt::<u32>::new(
+            &mut options,
+            OptDesc {
+                long: Some("max-heap"),
+                ..Default::default()
+            },
+        );
+        let define = Opt::<String>::new(
+            &mut options,
+            OptDesc {
+                long: Some("define"),
+                list: true,
+                ..Default::default()
+            },
+        );
+
+        let vec1: Vec<String> = "test --max-heap 10 --define A=1 --define=B=2"
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect();
+        let p = Parser::new(options, &vec1);
+        p.parse().expect("parse should succeed");
+
+        assert_eq!(*max_heap, 10);
+        assert_eq!(define.num_values(), 2);
+        assert_eq!(define[0], "A=1");
+        assert_eq!(define[1], "B=2");
+    }
+
     #[test]
     fn test_short() {
         let mut options = CommandLine::new("juno");
@@ -518,6 +518,7 @@ fn run() -> anyhow::Result<()> {
             enable_jsx: false,
             dialect: ParserDialect::JavaScript,
             store_doc_block: false,
+            store_comments: false,
         },
         &buf,
     );
@@ -533,6 +534,7 @@ fn run() -> anyhow::Result<()> {
                 metadata: ast::TemplateMetadata {
                     phantom: Default::default(),
                     range: program.metadata.range,
+                    comments: program.metadata.comments,
                 },
                 body: program.body,
             },
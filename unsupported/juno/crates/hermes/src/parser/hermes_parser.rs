@@ -5,7 +5,7 @@
  * LICENSE file in the root directory of this source tree.
  */
 
-use super::node::{Node, NodePtr, NodePtrOpt, SMLoc, StringRef};
+use super::node::{Node, NodePtr, NodePtrOpt, SMLoc, SMRange, StringRef};
 use crate::utf::utf8_with_surrogates_to_string_lossy;
 use juno_support::NullTerminatedBuf;
 use std::fmt::Formatter;
@@ -48,6 +48,8 @@ pub struct ParserFlags {
     pub dialect: ParserDialect,
     /// Store doc-comment block at the top of the file.
     pub store_doc_block: bool,
+    /// Store all comments in the file.
+    pub store_comments: bool,
 }
 
 impl Default for ParserFlags {
@@ -57,6 +59,7 @@ impl Default for ParserFlags {
             enable_jsx: false,
             dialect: ParserDialect::JavaScript,
             store_doc_block: false,
+            store_comments: false,
         }
     }
 }
@@ -191,6 +194,39 @@ impl MagicCommentKind {
     }
 }
 
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CommentKind {
+    /// Comment that begins with `//`.
+    Line,
+    /// Comment that is delimited by `/*` and `*/`.
+    Block,
+    /// Comment that begins with `#!` and starts at the first byte of the file.
+    Hashbang,
+}
+
+/// A comment stored by the parser.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct StoredComment {
+    /// What kind of comment.
+    pub kind: CommentKind,
+    /// Source range of the comment, including the delimiters.
+    pub range: SMRange,
+}
+
+impl StoredComment {
+    /// Return the comment with the delimiters stripped.
+    pub fn value(&self) -> &[u8] {
+        let start = unsafe { self.range.start.as_ptr().add(2) };
+        let end = match self.kind {
+            CommentKind::Block => unsafe { self.range.end.as_ptr().sub(2) },
+            CommentKind::Line | CommentKind::Hashbang => self.range.end.as_ptr(),
+        };
+        unsafe { std::slice::from_raw_parts(start, end.offset_from(start) as usize) }
+    }
+}
+
 #[repr(C)]
 struct ParserContext {
     _unused: i32,
@@ -236,6 +272,10 @@ extern "C" {
     fn hermes_get_node_name(node: NodePtr) -> DataRef<'static, u8>;
     /// Return the doc block for the file if `storeDocBlock` was provided at parse time.
     fn hermes_parser_get_doc_block<'a>(parser_ctx: *const ParserContext) -> DataRef<'a, u8>;
+    /// Return the comments in the file if `storeComments` was provided at parse time.
+    fn hermes_parser_get_comments<'a>(
+        parser_ctx: *const ParserContext,
+    ) -> DataRef<'a, StoredComment>;
 }
 
 pub struct HermesParser<'a> {
@@ -286,6 +326,12 @@ impl HermesParser<'_> {
         }
     }
 
+    /// Return a slice containing all comments in the file in source order,
+    /// if `store_comments` was set in the flags and the parse was successful.
+    pub fn comments(&self) -> &[StoredComment] {
+        unsafe { hermes_parser_get_comments(self.parser_ctx).as_slice() }
+    }
+
     /// Return a slice containing all parser messages.
     pub fn messages(&self) -> &[DiagMessage] {
         unsafe { hermes_parser_get_messages(self.parser_ctx).as_slice() }
@@ -390,4 +436,26 @@ mod tests {
             "my map URL"
         );
     }
+
+    #[test]
+    fn comments() {
+        let buf = NullTerminatedBuf::from_str_check(
+            "// first
+            var p = /* second */ 0;",
+        );
+        let p = HermesParser::parse(
+            ParserFlags {
+                store_comments: true,
+                ..Default::default()
+            },
+            &buf,
+        );
+        assert!(!p.has_errors());
+        let comments = p.comments();
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].kind, CommentKind::Line);
+        assert_eq!(comments[0].value(), b" first");
+        assert_eq!(comments[1].kind, CommentKind::Block);
+        assert_eq!(comments[1].value(), b" second ");
+    }
 }
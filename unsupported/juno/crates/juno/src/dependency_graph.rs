@@ -0,0 +1,424 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Graph of the dependencies between modules, built from the results of semantic resolution.
+
+use crate::resolve_dependency::DependencyKind;
+use crate::sema::SemContext;
+use juno_support::json::{JSONEmitter, Pretty};
+use juno_support::source_manager::{SourceId, SourceManager, SourceRange};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{self, Write};
+
+/// A dependency of one module on another.
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub from: SourceId,
+    /// The module the specifier was resolved to, if it could be resolved.
+    pub to: Option<SourceId>,
+    pub kind: DependencyKind,
+    pub specifier: String,
+    /// The range of the specifier in `from`.
+    pub range: SourceRange,
+}
+
+/// The modules which were resolved and their dependencies.
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    /// The resolved modules and the modules their dependencies resolved to, sorted by ID.
+    modules: Vec<SourceId>,
+    /// The index of each module in `modules`.
+    indices: HashMap<SourceId, usize>,
+    edges: Vec<Edge>,
+    /// The distinct modules each module depends on, as indices sorted in increasing order.
+    successors: Vec<Vec<usize>>,
+    /// The distinct modules which depend on each module, as indices sorted in increasing order.
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl DependencyGraph {
+    /// Build the graph of the `(id, sem)` modules and their dependencies.
+    pub fn new<'a>(modules: impl IntoIterator<Item = (SourceId, &'a SemContext)>) -> Self {
+        let mut edges = vec![];
+        let mut ids = HashSet::new();
+        for (id, sem) in modules {
+            ids.insert(id);
+            for dep in sem.dependencies() {
+                edges.push(Edge {
+                    from: id,
+                    to: dep.target,
+                    kind: dep.kind,
+                    specifier: dep.specifier.clone(),
+                    range: dep.range,
+                });
+                ids.extend(dep.target);
+            }
+        }
+        let mut modules: Vec<SourceId> = ids.into_iter().collect();
+        modules.sort_by_key(|id| id.0);
+        let indices: HashMap<SourceId, usize> =
+            modules.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let mut successors = vec![vec![]; modules.len()];
+        for edge in &edges {
+            if let Some(to) = edge.to {
+                successors[indices[&edge.from]].push(indices[&to]);
+            }
+        }
+        for succ in &mut successors {
+            succ.sort_unstable();
+            succ.dedup();
+        }
+        let mut predecessors = vec![vec![]; modules.len()];
+        for (i, succ) in successors.iter().enumerate() {
+            for &j in succ {
+                predecessors[j].push(i);
+            }
+        }
+        DependencyGraph {
+            modules,
+            indices,
+            edges,
+            successors,
+            predecessors,
+        }
+    }
+
+    /// All modules in the graph, sorted by ID.
+    pub fn modules(&self) -> &[SourceId] {
+        &self.modules
+    }
+
+    /// All dependencies in the order of their modules, and then the order they appear.
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// The distinct modules which `id` depends on.
+    pub fn dependencies(&self, id: SourceId) -> impl Iterator<Item = SourceId> + '_ {
+        self.indices
+            .get(&id)
+            .into_iter()
+            .flat_map(move |&i| self.successors[i].iter().map(move |&j| self.modules[j]))
+    }
+
+    /// The distinct modules which depend on `id`.
+    pub fn dependents(&self, id: SourceId) -> Vec<SourceId> {
+        match self.indices.get(&id) {
+            Some(&index) => self.predecessors[index]
+                .iter()
+                .map(|&i| self.modules[i])
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// The number of modules which `id` depends on directly or indirectly, besides itself.
+    pub fn transitive_closure_size(&self, id: SourceId) -> usize {
+        let start = match self.indices.get(&id) {
+            Some(&start) => start,
+            None => return 0,
+        };
+        let mut visited = vec![false; self.modules.len()];
+        visited[start] = true;
+        let mut queue = VecDeque::from([start]);
+        let mut size = 0;
+        while let Some(i) = queue.pop_front() {
+            for &j in &self.successors[i] {
+                if !visited[j] {
+                    visited[j] = true;
+                    size += 1;
+                    queue.push_back(j);
+                }
+            }
+        }
+        size
+    }
+
+    /// The number of modules which each module depends on directly or indirectly besides
+    /// itself, in the order of `modules()`. The modules reachable from each strongly connected
+    /// component are only found once, from those of the components it depends on.
+    pub fn transitive_closure_sizes(&self) -> Vec<usize> {
+        // Each component is found after all of the components it depends on.
+        let components = self.strongly_connected_components();
+        let mut component_of = vec![0; self.modules.len()];
+        for (c, component) in components.iter().enumerate() {
+            for &i in component {
+                component_of[i] = c;
+            }
+        }
+
+        // The components each component depends on directly or indirectly, as bit sets.
+        let words = components.len().div_ceil(64);
+        let mut reachable: Vec<Vec<u64>> = Vec::with_capacity(components.len());
+        let mut sizes = vec![0; self.modules.len()];
+        for (c, component) in components.iter().enumerate() {
+            let mut successors: Vec<usize> = component
+                .iter()
+                .flat_map(|&i| self.successors[i].iter().map(|&j| component_of[j]))
+                .filter(|&d| d != c)
+                .collect();
+            successors.sort_unstable();
+            successors.dedup();
+            let mut reach = vec![0u64; words];
+            for d in successors {
+                reach[d / 64] |= 1 << (d % 64);
+                for (word, other) in reach.iter_mut().zip(&reachable[d]) {
+                    *word |= other;
+                }
+            }
+            // The other modules of a component depend on each module in it.
+            let mut size = component.len() - 1;
+            for (w, &word) in reach.iter().enumerate() {
+                let mut bits = word;
+                while bits != 0 {
+                    size += components[w * 64 + bits.trailing_zeros() as usize].len();
+                    bits &= bits - 1;
+                }
+            }
+            for &i in component {
+                sizes[i] = size;
+            }
+            reachable.push(reach);
+        }
+        sizes
+    }
+
+    /// A cycle through each group of modules which depend on each other, as the path of
+    /// modules from the module with the lowest ID back to itself.
+    pub fn cycles(&self) -> Vec<Vec<SourceId>> {
+        let mut components = self.strongly_connected_components();
+        components.sort_by_key(|component| component[0]);
+        components
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || self.successors[component[0]].contains(&component[0])
+            })
+            .map(|component| {
+                self.cycle_path(&component)
+                    .into_iter()
+                    .map(|i| self.modules[i])
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The strongly connected components of the graph, each sorted in increasing order.
+    /// Found with Tarjan's algorithm, without recursion so long chains of modules don't overflow
+    /// the stack, so each component comes after all of the components it depends on.
+    fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        let n = self.modules.len();
+        let mut index: Vec<Option<usize>> = vec![None; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = vec![];
+        let mut next_index = 0;
+        let mut components = vec![];
+        for root in 0..n {
+            if index[root].is_some() {
+                continue;
+            }
+            // Each frame is a module and the position of the next successor to visit.
+            let mut frames = vec![(root, 0)];
+            index[root] = Some(next_index);
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            while let Some(&(v, pos)) = frames.last() {
+                if let Some(&w) = self.successors[v].get(pos) {
+                    frames.last_mut().unwrap().1 += 1;
+                    match index[w] {
+                        None => {
+                            index[w] = Some(next_index);
+                            low[w] = next_index;
+                            next_index += 1;
+                            stack.push(w);
+                            on_stack[w] = true;
+                            frames.push((w, 0));
+                        }
+                        Some(w_index) if on_stack[w] => low[v] = low[v].min(w_index),
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+                frames.pop();
+                if let Some(&(u, _)) = frames.last() {
+                    low[u] = low[u].min(low[v]);
+                }
+                if Some(low[v]) == index[v] {
+                    let mut component = vec![];
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    component.sort_unstable();
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// The shortest path from the first module of `component` back to itself, staying within
+    /// the strongly connected `component`.
+    fn cycle_path(&self, component: &[usize]) -> Vec<usize> {
+        let start = component[0];
+        let mut parents: HashMap<usize, usize> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(i) = queue.pop_front() {
+            for &j in &self.successors[i] {
+                if j == start {
+                    // Follow the parents from `i` back to `start`.
+                    let mut path = vec![i];
+                    while let Some(&parent) = path.last().and_then(|last| parents.get(last)) {
+                        path.push(parent);
+                    }
+                    path.reverse();
+                    path.push(start);
+                    return path;
+                }
+                if component.binary_search(&j).is_ok() && !parents.contains_key(&j) {
+                    parents.insert(j, i);
+                    queue.push_back(j);
+                }
+            }
+        }
+        unreachable!("strongly connected components must contain a cycle")
+    }
+
+    /// Write the graph to `out` as JSON, with the modules, their metrics, their dependencies
+    /// and the cycles. Modules are identified by their ID.
+    pub fn dump_json<W: Write>(
+        &self,
+        out: W,
+        sm: &SourceManager,
+        pretty: Pretty,
+    ) -> io::Result<()> {
+        let mut emitter = JSONEmitter::new(out, pretty);
+        emitter.open_dict();
+
+        let transitive_sizes = self.transitive_closure_sizes();
+        emitter.emit_key("modules");
+        emitter.open_array();
+        for (i, &id) in self.modules.iter().enumerate() {
+            emitter.open_dict();
+            emitter.emit_key("id");
+            emitter.emit_number(id.0 as f64);
+            emitter.emit_key("name");
+            emitter.emit_string(sm.source_name(id));
+            emitter.emit_key("dependencies");
+            emitter.emit_number(self.successors[i].len() as f64);
+            emitter.emit_key("dependents");
+            emitter.emit_number(self.predecessors[i].len() as f64);
+            emitter.emit_key("transitiveDependencies");
+            emitter.emit_number(transitive_sizes[i] as f64);
+            emitter.close_dict();
+        }
+        emitter.close_array();
+
+        emitter.emit_key("edges");
+        emitter.open_array();
+        for edge in &self.edges {
+            emitter.open_dict();
+            emitter.emit_key("from");
+            emitter.emit_number(edge.from.0 as f64);
+            emitter.emit_key("to");
+            match edge.to {
+                Some(to) => emitter.emit_number(to.0 as f64),
+                None => emitter.emit_null(),
+            }
+            emitter.emit_key("kind");
+            emitter.emit_string(edge.kind.name());
+            emitter.emit_key("lazy");
+            emitter.emit_bool(edge.kind.is_lazy());
+            emitter.emit_key("specifier");
+            emitter.emit_string(&edge.specifier);
+            emitter.emit_key("resolved");
+            emitter.emit_bool(edge.to.is_some());
+            emitter.emit_key("line");
+            emitter.emit_number(edge.range.start.line as f64);
+            emitter.emit_key("column");
+            emitter.emit_number(edge.range.start.col as f64);
+            emitter.close_dict();
+        }
+        emitter.close_array();
+
+        emitter.emit_key("cycles");
+        emitter.open_array();
+        for cycle in self.cycles() {
+            emitter.open_array();
+            for id in cycle {
+                emitter.emit_number(id.0 as f64);
+            }
+            emitter.close_array();
+        }
+        emitter.close_array();
+
+        emitter.close_dict();
+        emitter.end()
+    }
+
+    /// Write the graph to `out` in the DOT language of Graphviz. Lazy dependencies are dashed,
+    /// dependencies in cycles are red, and unresolved specifiers are dashed boxes.
+    pub fn dump_dot<W: Write>(&self, mut out: W, sm: &SourceManager) -> io::Result<()> {
+        let mut in_cycle = HashSet::new();
+        for cycle in self.cycles() {
+            for pair in cycle.windows(2) {
+                in_cycle.insert((pair[0], pair[1]));
+            }
+        }
+
+        writeln!(out, "digraph dependencies {{")?;
+        for &id in &self.modules {
+            writeln!(
+                out,
+                "  m{} [label=\"{}\"];",
+                id.0,
+                dot_escape(sm.source_name(id))
+            )?;
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            let mut attributes = vec![format!("label=\"{}\"", dot_escape(&edge.specifier))];
+            if edge.kind.is_lazy() {
+                attributes.push("style=dashed".to_string());
+            }
+            let to = match edge.to {
+                Some(to) => {
+                    if in_cycle.contains(&(edge.from, to)) {
+                        attributes.push("color=red".to_string());
+                    }
+                    format!("m{}", to.0)
+                }
+                None => {
+                    writeln!(
+                        out,
+                        "  u{} [label=\"{}\", shape=box, style=dashed];",
+                        i,
+                        dot_escape(&edge.specifier)
+                    )?;
+                    format!("u{}", i)
+                }
+            };
+            writeln!(
+                out,
+                "  m{} -> {} [{}];",
+                edge.from.0,
+                to,
+                attributes.join(", ")
+            )?;
+        }
+        writeln!(out, "}}")
+    }
+}
+
+/// Escape `s` for a quoted string in the DOT language.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
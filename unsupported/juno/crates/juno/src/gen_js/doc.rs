@@ -0,0 +1,323 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Document-based layout engine, in the style of Wadler's "A prettier printer".
+//!
+//! The generated text is described as a tree of [`Doc`]s, in which groups delimit
+//! the parts of the output which should be printed on a single line if they fit
+//! within the print width. Otherwise, the line breaks directly inside the group are
+//! taken, and nested groups get another chance to fit on their own lines.
+
+use std::io::{self, Write};
+
+/// Element of the document to lay out.
+#[derive(Debug)]
+pub enum Doc {
+    /// Text which contains no newlines.
+    Text(String),
+    /// A space when the enclosing group is flat, a newline otherwise.
+    Line,
+    /// Nothing when the enclosing group is flat, a newline otherwise.
+    SoftLine,
+    /// A newline, which forces every enclosing group to break.
+    HardLine,
+    /// A newline without indentation after it, which forces every enclosing group to break.
+    LiteralLine,
+    /// Records the position at which it is printed, used for source maps.
+    Mark,
+    /// Contents whose line breaks are indented one more level.
+    Indent(Vec<Doc>),
+    /// Contents which are printed flat if they fit in the print width.
+    Group {
+        contents: Vec<Doc>,
+        /// Whether the group contains a hard line break, so it can never be flat.
+        should_break: bool,
+    },
+}
+
+/// Kind of a container which is still being built.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Frame {
+    Root,
+    Indent,
+    Group,
+}
+
+/// Builds a [`Doc`] tree incrementally, in output order.
+pub struct DocBuilder {
+    /// Containers which are still open, with their contents so far
+    /// and whether they contain a hard line break.
+    stack: Vec<(Frame, Vec<Doc>, bool)>,
+}
+
+impl DocBuilder {
+    pub fn new() -> Self {
+        DocBuilder {
+            stack: vec![(Frame::Root, vec![], false)],
+        }
+    }
+
+    fn push(&mut self, doc: Doc) {
+        self.stack.last_mut().unwrap().1.push(doc);
+    }
+
+    /// Append `s`, which must not contain any newlines.
+    pub fn text(&mut self, s: &str) {
+        if let Some(Doc::Text(last)) = self.stack.last_mut().unwrap().1.last_mut() {
+            last.push_str(s);
+        } else {
+            self.push(Doc::Text(s.to_string()));
+        }
+    }
+
+    pub fn line(&mut self) {
+        self.push(Doc::Line);
+    }
+
+    pub fn soft_line(&mut self) {
+        self.push(Doc::SoftLine);
+    }
+
+    pub fn hard_line(&mut self) {
+        self.push(Doc::HardLine);
+        self.stack.last_mut().unwrap().2 = true;
+    }
+
+    pub fn literal_line(&mut self) {
+        self.push(Doc::LiteralLine);
+        self.stack.last_mut().unwrap().2 = true;
+    }
+
+    pub fn mark(&mut self) {
+        self.push(Doc::Mark);
+    }
+
+    pub fn begin_indent(&mut self) {
+        self.stack.push((Frame::Indent, vec![], false));
+    }
+
+    pub fn end_indent(&mut self) {
+        let (contents, _) = self.pop(Frame::Indent);
+        self.push(Doc::Indent(contents));
+    }
+
+    pub fn begin_group(&mut self) {
+        self.stack.push((Frame::Group, vec![], false));
+    }
+
+    pub fn end_group(&mut self) {
+        let (contents, should_break) = self.pop(Frame::Group);
+        self.push(Doc::Group {
+            contents,
+            should_break,
+        });
+    }
+
+    /// Close the innermost container, which must be a `frame`,
+    /// and propagate its hard line breaks to the enclosing one.
+    fn pop(&mut self, frame: Frame) -> (Vec<Doc>, bool) {
+        let (kind, contents, has_hard_line) = self.stack.pop().unwrap();
+        debug_assert!(kind == frame, "Mismatched {:?}, expected {:?}", kind, frame);
+        self.stack.last_mut().unwrap().2 |= has_hard_line;
+        (contents, has_hard_line)
+    }
+
+    /// Return the finished document.
+    pub fn finish(mut self) -> Vec<Doc> {
+        debug_assert!(self.stack.len() == 1, "Unclosed indent or group");
+        self.stack.pop().unwrap().1
+    }
+}
+
+/// Options for laying out a document.
+#[derive(Debug, Copy, Clone)]
+pub struct LayoutOpt {
+    /// Maximum width of a line.
+    pub print_width: usize,
+    /// Number of columns in each level of indentation.
+    pub indent_width: usize,
+    /// Whether to indent with tabs instead of spaces.
+    pub use_tabs: bool,
+}
+
+/// Whether a group is printed on one line.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Lay out `docs` and print the result to `out`.
+/// Return the 0-based `(line, column)` of every `Doc::Mark`, in document order.
+pub fn print<W: Write>(docs: &[Doc], opt: LayoutOpt, out: &mut W) -> io::Result<Vec<(u32, u32)>> {
+    let mut marks = vec![];
+    let mut line = 0u32;
+    let mut col = 0usize;
+    // Indentation is only written before text, to avoid trailing whitespace.
+    let mut pending_indent = 0usize;
+
+    // Commands left to print, with their indent level, the last one first.
+    let mut stack: Vec<(usize, Mode, &Doc)> =
+        docs.iter().rev().map(|d| (0, Mode::Break, d)).collect();
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => {
+                if pending_indent > 0 {
+                    if opt.use_tabs {
+                        out.write_all("\t".repeat(pending_indent).as_bytes())?;
+                    } else {
+                        out.write_all(" ".repeat(pending_indent * opt.indent_width).as_bytes())?;
+                    }
+                    pending_indent = 0;
+                }
+                out.write_all(s.as_bytes())?;
+                col += s.chars().count();
+            }
+            Doc::Line | Doc::SoftLine if mode == Mode::Flat => {
+                if let Doc::Line = doc {
+                    out.write_all(b" ")?;
+                    col += 1;
+                }
+            }
+            Doc::Line | Doc::SoftLine | Doc::HardLine => {
+                out.write_all(b"\n")?;
+                line += 1;
+                col = indent * opt.indent_width;
+                pending_indent = indent;
+            }
+            Doc::LiteralLine => {
+                out.write_all(b"\n")?;
+                line += 1;
+                col = 0;
+                pending_indent = 0;
+            }
+            Doc::Mark => {
+                marks.push((line, col as u32));
+            }
+            Doc::Indent(contents) => {
+                stack.extend(contents.iter().rev().map(|d| (indent + 1, mode, d)));
+            }
+            Doc::Group {
+                contents,
+                should_break,
+            } => {
+                let mode = if mode == Mode::Flat
+                    || (!should_break
+                        && fits(contents, &stack, opt.print_width as isize - col as isize))
+                {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.extend(contents.iter().rev().map(|d| (indent, mode, d)));
+            }
+        }
+    }
+    Ok(marks)
+}
+
+/// Return whether `contents` fit in `width` columns when printed flat,
+/// along with the `rest` of the commands up to the next line break.
+fn fits(contents: &[Doc], rest: &[(usize, Mode, &Doc)], mut width: isize) -> bool {
+    let mut cmds: Vec<(Mode, &Doc)> = contents.iter().rev().map(|d| (Mode::Flat, d)).collect();
+    let mut rest = rest.iter().rev();
+    while width >= 0 {
+        let (mode, doc) = match cmds.pop() {
+            Some(cmd) => cmd,
+            None => match rest.next() {
+                Some(&(_, mode, doc)) => (mode, doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(s) => width -= s.chars().count() as isize,
+            Doc::Line if mode == Mode::Flat => width -= 1,
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine | Doc::LiteralLine => return true,
+            Doc::Mark => {}
+            Doc::Indent(contents) => cmds.extend(contents.iter().rev().map(|d| (mode, d))),
+            Doc::Group {
+                contents,
+                should_break,
+            } => {
+                let mode = if *should_break { Mode::Break } else { mode };
+                cmds.extend(contents.iter().rev().map(|d| (mode, d)));
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build the document for `f(<args>)`.
+    fn call(b: &mut DocBuilder, args: &[&str]) {
+        b.text("f(");
+        b.begin_group();
+        b.begin_indent();
+        b.soft_line();
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                b.text(",");
+                b.line();
+            }
+            b.text(arg);
+        }
+        b.end_indent();
+        b.soft_line();
+        b.end_group();
+        b.text(");");
+    }
+
+    fn layout(b: DocBuilder, print_width: usize, use_tabs: bool) -> String {
+        let mut out = vec![];
+        print(
+            &b.finish(),
+            LayoutOpt {
+                print_width,
+                indent_width: 2,
+                use_tabs,
+            },
+            &mut out,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_group() {
+        let mut b = DocBuilder::new();
+        call(&mut b, &["aaa", "bbb"]);
+        assert_eq!(layout(b, 80, false), "f(aaa, bbb);");
+
+        // The trailing `);` must fit as well.
+        let mut b = DocBuilder::new();
+        call(&mut b, &["aaa", "bbb"]);
+        assert_eq!(layout(b, 11, false), "f(\n  aaa,\n  bbb\n);");
+
+        let mut b = DocBuilder::new();
+        call(&mut b, &["aaa", "bbb"]);
+        assert_eq!(layout(b, 11, true), "f(\n\taaa,\n\tbbb\n);");
+    }
+
+    #[test]
+    fn test_hard_line() {
+        let mut b = DocBuilder::new();
+        b.begin_group();
+        b.text("{");
+        b.begin_indent();
+        b.hard_line();
+        b.text("x;");
+        b.end_indent();
+        b.hard_line();
+        b.text("}");
+        b.end_group();
+        assert_eq!(layout(b, 80, false), "{\n  x;\n}");
+    }
+}
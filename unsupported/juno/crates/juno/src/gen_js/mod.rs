@@ -5,6 +5,8 @@
  * LICENSE file in the root directory of this source tree.
  */
 
+mod doc;
+
 use crate::{
     ast::*,
     sema::{DeclKind, Resolution, SemContext},
@@ -33,6 +35,17 @@ pub struct Opt<'s> {
 
     /// Delimiter to use for string literals.
     pub quote: QuoteChar,
+
+    /// Maximum width of a line when pretty-printing.
+    /// Expressions which don't fit are broken across lines.
+    /// If `None`, lines are never broken to fit.
+    pub print_width: Option<usize>,
+
+    /// Number of columns in each level of indentation when pretty-printing.
+    pub indent_width: usize,
+
+    /// Whether to indent with tabs instead of spaces when pretty-printing.
+    pub use_tabs: bool,
 }
 
 impl Default for Opt<'_> {
@@ -43,6 +56,9 @@ impl Default for Opt<'_> {
             force_async_arrow_space: true,
             doc_block: None,
             quote: QuoteChar::Single,
+            print_width: None,
+            indent_width: 2,
+            use_tabs: false,
         }
     }
 }
@@ -54,12 +70,15 @@ impl Opt<'_> {
 }
 
 /// Whether to pretty-print the generated JS.
-/// Does not do full formatting of the source, but does add indentation and
-/// some extra spaces to make source more readable.
+/// Adds indentation and some extra spaces to make source more readable.
+/// Long lines are only broken if [`Opt::print_width`] is set.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Pretty {
     No,
     Yes,
+    /// Generate the shortest JS possible: omit every optional space, semicolon and comment,
+    /// and print literals in their shortest form.
+    Minify,
 }
 
 /// Delimiter to use for string literals.
@@ -81,6 +100,7 @@ impl QuoteChar {
 }
 
 /// Generate JS for `root` and print it to `out`.
+/// Comments attached to the nodes are printed next to them, unless minifying.
 /// FIXME: This currently only returns an empty SourceMap.
 pub fn generate<W: Write>(
     out: W,
@@ -217,7 +237,6 @@ struct GenJS<'s, W: Write> {
     opt: Opt<'s>,
 
     /// Size of the indentation step.
-    /// May be configurable in the future.
     indent_step: usize,
 
     /// Current indentation level, used in pretty mode.
@@ -229,11 +248,42 @@ struct GenJS<'s, W: Write> {
     /// Raw token tracking the most recent node.
     cur_token: Option<RawToken>,
 
+    /// Last character written on the current line, used in minify mode.
+    last_char: Option<char>,
+
+    /// Whether a space has been requested and will be written before the next token
+    /// if it is needed to separate the tokens. Only used in minify mode.
+    pending_space: bool,
+
+    /// Whether a semicolon terminating a statement will be written before the next token,
+    /// unless it closes a block. Only used in minify mode.
+    pending_semi: bool,
+
+    /// Whether a line break will be written before the next token,
+    /// because the last thing written was a line comment.
+    pending_newline: bool,
+
+    /// Whether the trailing comments of the next visited node are printed by the caller,
+    /// after the semicolon terminating the statement.
+    defer_trailing_comments: bool,
+
     /// Build a source map as we go along.
     sourcemap: SourceMapBuilder,
 
+    /// ID in `sourcemap` of each source, indexed by `SourceId`.
+    /// Sources with the same name share an ID.
+    sourcemap_ids: Vec<u32>,
+
     /// Some(err) if an error has occurred when writing, else None.
     error: Option<io::Error>,
+
+    /// Document being built instead of writing directly to `out`,
+    /// when lines are broken to fit in the print width.
+    doc: Option<doc::DocBuilder>,
+
+    /// Source map tokens for every `Doc::Mark` in `doc`,
+    /// whose destinations are only known after layout.
+    doc_tokens: Vec<RawToken>,
 }
 
 /// Print to the output stream if no errors have been seen so far.
@@ -265,21 +315,35 @@ impl<W: Write> GenJS<'_, W> {
         root: &'gc Node<'gc>,
         opt: Opt,
     ) -> io::Result<SourceMap> {
+        let doc = match (opt.pretty, opt.print_width) {
+            (Pretty::Yes, Some(_)) => Some(doc::DocBuilder::new()),
+            _ => None,
+        };
+        let indent_step = opt.indent_width;
         let mut gen_js = GenJS {
             out: BufWriter::new(writer),
             opt,
-            indent_step: 2,
+            indent_step,
             indent: 0,
             position: SourceLoc { line: 1, col: 1 },
             cur_token: None,
+            last_char: None,
+            pending_space: false,
+            pending_semi: false,
+            pending_newline: false,
+            defer_trailing_comments: false,
             // FIXME: Pass in file name here.
             sourcemap: SourceMapBuilder::new(None),
+            sourcemap_ids: vec![],
             error: None,
+            doc,
+            doc_tokens: vec![],
         };
         for i in 0..ctx.sm().num_sources() {
-            gen_js
+            let id = gen_js
                 .sourcemap
                 .add_source(ctx.sm().source_name(SourceId(i as u32)));
+            gen_js.sourcemap_ids.push(id);
         }
 
         if let Some(doc_block) = gen_js.opt.doc_block.clone() {
@@ -294,7 +358,12 @@ impl<W: Write> GenJS<'_, W> {
         }
 
         root.visit(ctx, &mut gen_js, None);
+        // The last statement never needs a semicolon.
+        gen_js.pending_semi = false;
         gen_js.force_newline();
+        if let Some(doc) = gen_js.doc.take() {
+            gen_js.print_doc(doc);
+        }
 
         gen_js.flush_cur_token();
         match gen_js.error {
@@ -315,10 +384,17 @@ impl<W: Write> GenJS<'_, W> {
             let buf = format!("{}", args);
             debug_assert!(buf.is_ascii(), "Output must be ASCII");
             debug_assert!(!buf.contains('\n'), "Output must have no newlines");
-            if let Err(e) = self.out.write_all(buf.as_bytes()) {
-                self.error = Some(e);
+            if self.opt.pretty == Pretty::Minify {
+                // Spaces are only written if they're necessary to separate tokens.
+                for (i, part) in buf.split(' ').enumerate() {
+                    if i > 0 {
+                        self.pending_space = true;
+                    }
+                    self.write_str(part);
+                }
+            } else {
+                self.write_str(&buf);
             }
-            self.position.col += buf.len() as u32;
         }
     }
 
@@ -329,10 +405,7 @@ impl<W: Write> GenJS<'_, W> {
     fn write_char(&mut self, ch: char, dst: &mut [u8]) {
         debug_assert!(ch != '\n', "Output must not contain newlines");
         if self.error.is_none() {
-            if let Err(e) = self.out.write_all(ch.encode_utf8(dst).as_bytes()) {
-                self.error = Some(e);
-            }
-            self.position.col += 1;
+            self.write_str(ch.encode_utf8(dst));
         }
     }
 
@@ -344,12 +417,54 @@ impl<W: Write> GenJS<'_, W> {
             !s.chars().any(|c| c == '\n'),
             "Output must not contain newlines"
         );
+        self.write_str(s);
+    }
+
+    /// Write `s` to the `out` writer, preceded by the pending semicolon and space if they're
+    /// still needed.
+    /// If we have seen any errors, do nothing.
+    /// The output must contain no newlines.
+    fn write_str(&mut self, s: &str) {
+        let first = match s.chars().next() {
+            Some(first) => first,
+            None => return,
+        };
+        if self.pending_newline {
+            self.force_newline();
+        }
+        let start_col = self.position.col;
+        if self.pending_semi {
+            self.pending_semi = false;
+            // Blocks can be closed without terminating the last statement.
+            if first != '}' {
+                self.write_str(";");
+            }
+        }
+        if self.pending_space {
+            self.pending_space = false;
+            if matches!(self.last_char, Some(last) if need_separator(last, first)) {
+                self.write_str(" ");
+            }
+        }
+        if let Some(doc) = &mut self.doc {
+            doc.text(s);
+            return;
+        }
+        if self.position.col != start_col {
+            // A source map token added for `s` must point past the separators.
+            if let Some(token) = &mut self.cur_token {
+                if token.dst_col == start_col.wrapping_sub(1) {
+                    token.dst_col = self.position.col.wrapping_sub(1);
+                }
+            }
+        }
         if self.error.is_none() {
             if let Err(e) = self.out.write_all(s.as_bytes()) {
                 self.error = Some(e);
             }
         }
         self.position.col += s.chars().count() as u32;
+        self.last_char = s.chars().last();
     }
 
     /// Generate the JS for each node kind.
@@ -360,6 +475,7 @@ impl<W: Write> GenJS<'_, W> {
 
             Node::Program(Program { metadata: _, body }) => {
                 self.visit_stmt_list(ctx, body, Path::new(node, NodeField::body));
+                self.print_inner_comments(node, body.is_empty());
             }
             Node::Module(Module { metadata: _, body }) => {
                 self.visit_stmt_list(ctx, body, Path::new(node, NodeField::body));
@@ -458,7 +574,7 @@ impl<W: Write> GenJS<'_, W> {
                             ..
                         })
                     )
-                    && (*expression || self.opt.pretty == Pretty::No)
+                    && (*expression || self.opt.pretty != Pretty::Yes)
                 {
                     if need_sep {
                         out!(self, " ");
@@ -624,13 +740,14 @@ impl<W: Write> GenJS<'_, W> {
             Node::EmptyStatement(_) => {}
 
             Node::BlockStatement(BlockStatement { metadata: _, body }) => {
-                if body.is_empty() {
+                if body.is_empty() && !self.has_inner_comments(node) {
                     out!(self, "{{}}");
                 } else {
                     out!(self, "{{");
                     self.inc_indent();
                     self.newline();
                     self.visit_stmt_list(ctx, body, Path::new(node, NodeField::body));
+                    self.print_inner_comments(node, body.is_empty());
                     self.dec_indent();
                     self.newline();
                     out!(self, "}}");
@@ -657,7 +774,7 @@ impl<W: Write> GenJS<'_, W> {
                 argument,
             }) => {
                 out_token!(self, node, "throw ");
-                argument.visit(ctx, self, Some(Path::new(node, NodeField::argument)));
+                self.visit_restricted_argument(ctx, argument, Path::new(node, NodeField::argument));
             }
             Node::ReturnStatement(ReturnStatement {
                 metadata: _,
@@ -666,7 +783,11 @@ impl<W: Write> GenJS<'_, W> {
                 out_token!(self, node, "return");
                 if let Some(argument) = argument {
                     out!(self, " ");
-                    argument.visit(ctx, self, Some(Path::new(node, NodeField::argument)));
+                    self.visit_restricted_argument(
+                        ctx,
+                        argument,
+                        Path::new(node, NodeField::argument),
+                    );
                 }
             }
             Node::WithStatement(WithStatement {
@@ -826,12 +947,13 @@ impl<W: Write> GenJS<'_, W> {
                 out_token!(self, node, "null");
             }
             Node::StringLiteral(StringLiteral { metadata: _, value }) => {
-                out_token!(self, node, "{}", self.opt.quote.as_char());
-                self.print_escaped_string_literal(ctx, *value, self.opt.quote.as_char());
-                out!(self, "{}", self.opt.quote.as_char());
+                let quote = self.string_quote(ctx, *value);
+                out_token!(self, node, "{}", quote);
+                self.print_escaped_string_literal(ctx, *value, quote);
+                out!(self, "{}", quote);
             }
             Node::NumericLiteral(NumericLiteral { metadata: _, value }) => {
-                out_token!(self, node, "{}", convert::number_to_string(*value));
+                out_token!(self, node, "{}", self.number_to_string(*value));
             }
             Node::BigIntLiteral(BigIntLiteral {
                 metadata: _,
@@ -894,9 +1016,10 @@ impl<W: Write> GenJS<'_, W> {
                 trailing_comma,
             }) => {
                 out_token!(self, node, "[");
+                self.begin_list();
                 for (i, elem) in elements.iter().enumerate() {
                     if i > 0 {
-                        self.comma();
+                        self.list_separator();
                     }
                     match elem {
                         Node::SpreadElement(_) => {
@@ -915,6 +1038,7 @@ impl<W: Write> GenJS<'_, W> {
                 if *trailing_comma {
                     self.comma();
                 }
+                self.end_list();
                 out!(self, "]");
             }
 
@@ -946,19 +1070,11 @@ impl<W: Write> GenJS<'_, W> {
                         Some(Path::new(node, NodeField::type_arguments)),
                     );
                 }
-                out!(self, "(");
-                for (i, arg) in arguments.iter().enumerate() {
-                    if i > 0 {
-                        self.comma();
-                    }
-                    self.print_child(
-                        ctx,
-                        Some(arg),
-                        Path::new(node, NodeField::arguments),
-                        ChildPos::Anywhere,
-                    );
+                // `get_precedence` accounts for the missing parens.
+                if self.opt.pretty == Pretty::Minify && arguments.is_empty() {
+                    return;
                 }
-                out!(self, ")");
+                self.visit_args(ctx, arguments, Path::new(node, NodeField::arguments));
             }
             Node::YieldExpression(YieldExpression {
                 metadata: _,
@@ -1026,19 +1142,7 @@ impl<W: Write> GenJS<'_, W> {
                         Some(Path::new(node, NodeField::type_arguments)),
                     );
                 }
-                out!(self, "(");
-                for (i, arg) in arguments.iter().enumerate() {
-                    if i > 0 {
-                        self.comma();
-                    }
-                    self.print_child(
-                        ctx,
-                        Some(arg),
-                        Path::new(node, NodeField::arguments),
-                        ChildPos::Anywhere,
-                    );
-                }
-                out!(self, ")");
+                self.visit_args(ctx, arguments, Path::new(node, NodeField::arguments));
             }
             Node::OptionalCallExpression(OptionalCallExpression {
                 metadata: _,
@@ -1060,19 +1164,10 @@ impl<W: Write> GenJS<'_, W> {
                         Some(Path::new(node, NodeField::type_arguments)),
                     );
                 }
-                out!(self, "{}(", if *optional { "?." } else { "" });
-                for (i, arg) in arguments.iter().enumerate() {
-                    if i > 0 {
-                        self.comma();
-                    }
-                    self.print_child(
-                        ctx,
-                        Some(arg),
-                        Path::new(node, NodeField::arguments),
-                        ChildPos::Anywhere,
-                    );
+                if *optional {
+                    out!(self, "?.");
                 }
-                out!(self, ")");
+                self.visit_args(ctx, arguments, Path::new(node, NodeField::arguments));
             }
 
             Node::AssignmentExpression(AssignmentExpression {
@@ -1161,9 +1256,14 @@ impl<W: Write> GenJS<'_, W> {
                 match object {
                     Node::NumericLiteral(NumericLiteral { value, .. }) => {
                         // Account for possible `50..toString()`.
-                        let string = convert::number_to_string(*value);
-                        // If there is an `e` or a decimal point, no need for an extra `.`.
-                        let suffix = string.find::<&[char]>(&['E', 'e', '.']).map_or(".", |_| "");
+                        let string = self.number_to_string(*value);
+                        // If there is an `e`, a decimal point or a hex prefix,
+                        // no need for an extra `.`.
+                        let suffix = if string.bytes().all(|c| c.is_ascii_digit()) {
+                            "."
+                        } else {
+                            ""
+                        };
                         out_token!(self, node, "{}{}", string, suffix);
                     }
                     _ => {
@@ -1178,6 +1278,9 @@ impl<W: Write> GenJS<'_, W> {
                 if *computed {
                     out!(self, "[");
                 } else {
+                    if is_call(object) {
+                        self.soft_line();
+                    }
                     out!(self, ".");
                 }
                 self.print_child(
@@ -1206,6 +1309,9 @@ impl<W: Write> GenJS<'_, W> {
                 if *computed {
                     out!(self, "{}[", if *optional { "?." } else { "" });
                 } else {
+                    if is_call(object) {
+                        self.soft_line();
+                    }
                     out!(self, "{}.", if *optional { "?" } else { "" });
                 }
                 self.print_child(
@@ -1238,7 +1344,7 @@ impl<W: Write> GenJS<'_, W> {
                     ForceSpace::No
                 });
                 out!(self, "{}", operator.as_str());
-                self.space(if ident {
+                self.line(if ident {
                     ForceSpace::Yes
                 } else {
                     ForceSpace::No
@@ -1372,7 +1478,7 @@ impl<W: Write> GenJS<'_, W> {
                         "{}",
                         match self.opt.pretty {
                             Pretty::Yes => " = ",
-                            Pretty::No => "=",
+                            Pretty::No | Pretty::Minify => "=",
                         }
                     );
                     init.visit(ctx, self, Some(Path::new(node, NodeField::init)));
@@ -1535,7 +1641,7 @@ impl<W: Write> GenJS<'_, W> {
                 );
                 self.space(ForceSpace::No);
                 out!(self, "{}", operator.as_str());
-                self.space(ForceSpace::No);
+                self.line(ForceSpace::No);
                 self.print_child(
                     ctx,
                     Some(*right),
@@ -2036,7 +2142,8 @@ impl<W: Write> GenJS<'_, W> {
                 out!(self, "<");
                 name.visit(ctx, self, Some(Path::new(node, NodeField::name)));
                 for attr in attributes.iter() {
-                    self.space(ForceSpace::Yes);
+                    // Always separate attributes, even when minifying.
+                    self.write_str(" ");
                     attr.visit(ctx, self, Some(Path::new(node, NodeField::attributes)));
                 }
                 if *self_closing {
@@ -3155,7 +3262,7 @@ impl<W: Write> GenJS<'_, W> {
                     "{}",
                     match self.opt.pretty {
                         Pretty::Yes => " = ",
-                        Pretty::No => "=",
+                        Pretty::No | Pretty::Minify => "=",
                     }
                 );
                 init.visit(ctx, self, Some(Path::new(node, NodeField::init)));
@@ -3170,11 +3277,101 @@ impl<W: Write> GenJS<'_, W> {
     /// Increase the indent level.
     fn inc_indent(&mut self) {
         self.indent += self.indent_step;
+        if let Some(doc) = &mut self.doc {
+            doc.begin_indent();
+        }
     }
 
     /// Decrease the indent level.
     fn dec_indent(&mut self) {
         self.indent -= self.indent_step;
+        if let Some(doc) = &mut self.doc {
+            doc.end_indent();
+        }
+    }
+
+    /// Begin a group of output which is broken across indented lines if it doesn't fit in the
+    /// print width, at the points marked by `line` and `soft_line`.
+    fn begin_group(&mut self) {
+        if let Some(doc) = &mut self.doc {
+            doc.begin_group();
+            doc.begin_indent();
+        }
+    }
+
+    /// End the group started by the matching `begin_group`.
+    fn end_group(&mut self) {
+        if let Some(doc) = &mut self.doc {
+            doc.end_indent();
+            doc.end_group();
+        }
+    }
+
+    /// Print a line break if the enclosing group is broken, otherwise a space like `space`.
+    fn line(&mut self, force: ForceSpace) {
+        match &mut self.doc {
+            Some(doc) => doc.line(),
+            None => self.space(force),
+        }
+    }
+
+    /// Print a line break if the enclosing group is broken.
+    fn soft_line(&mut self) {
+        if let Some(doc) = &mut self.doc {
+            doc.soft_line();
+        }
+    }
+
+    /// Begin a comma-separated list, which is printed with one element per line
+    /// if it doesn't fit in the print width.
+    fn begin_list(&mut self) {
+        self.begin_group();
+        self.soft_line();
+    }
+
+    /// Print the separator between two elements of a list started by `begin_list`.
+    fn list_separator(&mut self) {
+        match &mut self.doc {
+            Some(doc) => {
+                doc.text(",");
+                doc.line();
+            }
+            None => self.comma(),
+        }
+    }
+
+    /// End the list started by the matching `begin_list`.
+    fn end_list(&mut self) {
+        if let Some(doc) = &mut self.doc {
+            doc.end_indent();
+            doc.soft_line();
+            doc.end_group();
+        }
+    }
+
+    /// Lay out the finished document `doc` and write it to `out`,
+    /// then add its source map tokens at their final positions.
+    fn print_doc(&mut self, doc: doc::DocBuilder) {
+        let opt = doc::LayoutOpt {
+            print_width: self.opt.print_width.unwrap_or(usize::MAX),
+            indent_width: self.indent_step,
+            use_tabs: self.opt.use_tabs,
+        };
+        match doc::print(&doc.finish(), opt, &mut self.out) {
+            Ok(marks) => {
+                for (token, (line, col)) in
+                    std::mem::take(&mut self.doc_tokens).into_iter().zip(marks)
+                {
+                    self.cur_token = Some(RawToken {
+                        dst_line: line,
+                        dst_col: col,
+                        ..token
+                    });
+                    self.flush_cur_token();
+                }
+            }
+            Err(e) => self.error = Some(e),
+        }
     }
 
     /// Print a ',', with a trailing space in pretty mode.
@@ -3183,7 +3380,7 @@ impl<W: Write> GenJS<'_, W> {
             self,
             "{}",
             match self.opt.pretty {
-                Pretty::No => ",",
+                Pretty::No | Pretty::Minify => ",",
                 Pretty::Yes => ", ",
             }
         )
@@ -3205,12 +3402,27 @@ impl<W: Write> GenJS<'_, W> {
 
     /// Print a newline and indent.
     fn force_newline(&mut self) {
+        self.pending_newline = false;
+        if let Some(doc) = &mut self.doc {
+            doc.hard_line();
+            return;
+        }
         self.force_newline_without_indent();
-        out!(self, "{:indent$}", "", indent = self.indent as usize);
+        if self.opt.use_tabs {
+            let tabs = self.indent / self.indent_step.max(1);
+            out!(self, "{:\t<tabs$}", "", tabs = tabs);
+        } else {
+            out!(self, "{:indent$}", "", indent = self.indent as usize);
+        }
     }
 
     /// Print a newline without any indent after.
     fn force_newline_without_indent(&mut self) {
+        self.pending_newline = false;
+        if let Some(doc) = &mut self.doc {
+            doc.literal_line();
+            return;
+        }
         if self.error.is_none() {
             if let Err(e) = self.out.write(&[b'\n']) {
                 self.error = Some(e);
@@ -3218,6 +3430,91 @@ impl<W: Write> GenJS<'_, W> {
         }
         self.position.line += 1;
         self.position.col = 1;
+        self.last_char = None;
+        self.pending_space = false;
+    }
+
+    /// Return the comments to print for `node`, which are omitted when minifying.
+    fn comments<'gc>(&self, node: &'gc Node<'gc>) -> Option<&'gc NodeComments> {
+        match self.opt.pretty {
+            Pretty::Minify => None,
+            Pretty::Yes | Pretty::No => node.comments(),
+        }
+    }
+
+    /// Whether `node` has inner comments, which must be printed inside it.
+    fn has_inner_comments<'gc>(&self, node: &'gc Node<'gc>) -> bool {
+        self.comments(node).is_some_and(|c| !c.inner.is_empty())
+    }
+
+    /// Print a single comment. Comments which end the line are followed by a line break
+    /// before the next token.
+    fn print_comment(&mut self, comment: &Comment) {
+        match comment.kind {
+            CommentKind::Line => {
+                out!(self, "//");
+                self.write_utf8(&comment.value);
+            }
+            CommentKind::Hashbang => {
+                out!(self, "#!");
+                self.write_utf8(&comment.value);
+            }
+            CommentKind::Block => {
+                out!(self, "/*");
+                for (i, line) in comment.value.split('\n').enumerate() {
+                    if i > 0 {
+                        self.force_newline_without_indent();
+                    }
+                    self.write_utf8(line);
+                }
+                out!(self, "*/");
+            }
+        }
+        if comment.ends_line() {
+            self.pending_newline = true;
+        }
+    }
+
+    /// Print the comments preceding `node`.
+    /// Block comments stay on the same line as the next token, unless they weren't in the source.
+    fn print_leading_comments(&mut self, node: &Node, comments: &[Comment]) {
+        for (i, comment) in comments.iter().enumerate() {
+            self.print_comment(comment);
+            if !comment.ends_line() {
+                let next_line = match comments.get(i + 1) {
+                    Some(next) => next.range.start.line,
+                    None => node.range().start.line,
+                };
+                if comment.range.end.line < next_line {
+                    self.pending_newline = true;
+                } else {
+                    out!(self, " ");
+                }
+            }
+        }
+    }
+
+    /// Print the comments following a node on the same line.
+    fn print_trailing_comments(&mut self, comments: &[Comment]) {
+        for comment in comments {
+            if !self.pending_newline {
+                out!(self, " ");
+            }
+            self.print_comment(comment);
+        }
+    }
+
+    /// Print the inner comments of the block-like `node` after its body,
+    /// each on its own line.
+    fn print_inner_comments<'gc>(&mut self, node: &'gc Node<'gc>, body_is_empty: bool) {
+        if let Some(comments) = self.comments(node) {
+            for (i, comment) in comments.inner.iter().enumerate() {
+                if i > 0 || !body_is_empty {
+                    self.newline();
+                }
+                self.print_comment(comment);
+            }
+        }
     }
 
     /// Print the child of a `path` node at the position `child_pos`.
@@ -3279,6 +3576,7 @@ impl<W: Write> GenJS<'_, W> {
         esc: char,
     ) {
         let str = ctx.str_u16(value);
+        let mut buf = [0u8; 4];
         for &c in str {
             if c <= u8::MAX as u16 {
                 match char::from(c as u8) {
@@ -3317,8 +3615,8 @@ impl<W: Write> GenJS<'_, W> {
                 out!(self, "\\");
             }
             if (0x20..=0x7f).contains(&c) {
-                // Printable.
-                out!(self, "{}", char::from(c as u8));
+                // Printable. Written directly, because `out!` may drop spaces.
+                self.write_char(char::from(c as u8), &mut buf);
             } else {
                 out!(self, "\\u{:04x}", c);
             }
@@ -3327,15 +3625,55 @@ impl<W: Write> GenJS<'_, W> {
 
     fn visit_props<'gc>(&mut self, ctx: &'gc GCLock, props: &'gc NodeList<'gc>, path: Path<'gc>) {
         out!(self, "{{");
+        self.begin_list();
         for (i, prop) in props.iter().enumerate() {
             if i > 0 {
-                self.comma();
+                self.list_separator();
             }
             prop.visit(ctx, self, Some(path));
         }
+        self.end_list();
         out!(self, "}}");
     }
 
+    /// Print the parenthesized arguments of a call.
+    fn visit_args<'gc>(&mut self, ctx: &'gc GCLock, args: &'gc NodeList<'gc>, path: Path<'gc>) {
+        let is_function = |arg: &Node| {
+            matches!(
+                arg,
+                Node::FunctionExpression(_) | Node::ArrowFunctionExpression(_)
+            )
+        };
+        // A trailing callback or literal stays on the line of the call, `foo(a, () => {`,
+        // and only its own contents are broken across lines.
+        let hug = match args.iter().last() {
+            Some(last) => {
+                (is_function(last)
+                    || matches!(last, Node::ObjectExpression(_) | Node::ArrayExpression(_)))
+                    && !args.iter().take(args.len() - 1).any(is_function)
+            }
+            None => false,
+        };
+        out!(self, "(");
+        if !hug {
+            self.begin_list();
+        }
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                if hug {
+                    self.comma();
+                } else {
+                    self.list_separator();
+                }
+            }
+            self.print_child(ctx, Some(arg), path, ChildPos::Anywhere);
+        }
+        if !hug {
+            self.end_list();
+        }
+        out!(self, ")");
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn visit_func_params_body<'gc>(
         &mut self,
@@ -3504,20 +3842,9 @@ impl<W: Write> GenJS<'_, W> {
         force_block: ForceBlock,
         path: Path<'gc>,
     ) -> bool {
-        if let Node::BlockStatement(BlockStatement { metadata: _, body }) = &node {
-            if body.is_empty() {
-                self.space(ForceSpace::No);
-                out!(self, "{{}}");
-                return true;
-            }
+        if let Node::BlockStatement(_) = &node {
             self.space(ForceSpace::No);
-            out!(self, "{{");
-            self.inc_indent();
-            self.newline();
-            self.visit_stmt_list(ctx, body, Path::new(node, NodeField::body));
-            self.dec_indent();
-            self.newline();
-            out!(self, "}}");
+            node.visit(ctx, self, Some(path));
             return true;
         }
         if force_block == ForceBlock::Yes {
@@ -3555,9 +3882,68 @@ impl<W: Write> GenJS<'_, W> {
         stmt: &'gc Node<'gc>,
         path: Path<'gc>,
     ) {
+        self.defer_trailing_comments = true;
         stmt.visit(ctx, self, Some(path));
         if !stmt_skip_semi(ctx, Some(stmt)) {
-            out!(self, ";");
+            if self.opt.pretty == Pretty::Minify && !is_empty_stmt(stmt) {
+                self.pending_semi = true;
+            } else {
+                out!(self, ";");
+            }
+        }
+        if let Some(comments) = self.comments(stmt) {
+            self.print_trailing_comments(&comments.trailing);
+        }
+    }
+
+    /// Visit the `argument` of a `return` or `throw`, which must start on the same line.
+    fn visit_restricted_argument<'gc>(
+        &mut self,
+        ctx: &'gc GCLock,
+        argument: &'gc Node<'gc>,
+        path: Path<'gc>,
+    ) {
+        // A line break after a leading comment would terminate the statement early.
+        if self
+            .comments(argument)
+            .is_some_and(|c| !c.leading.is_empty())
+        {
+            out!(self, "(");
+            argument.visit(ctx, self, Some(path));
+            out!(self, ")");
+        } else {
+            argument.visit(ctx, self, Some(path));
+        }
+    }
+
+    /// Return the quote character to delimit the string `value` with.
+    /// In minify mode, this is the one which requires fewer escapes.
+    fn string_quote(&self, ctx: &GCLock, value: NodeString) -> char {
+        let quote = self.opt.quote.as_char();
+        if self.opt.pretty != Pretty::Minify {
+            return quote;
+        }
+        let other = match self.opt.quote {
+            QuoteChar::Single => QuoteChar::Double,
+            QuoteChar::Double => QuoteChar::Single,
+        }
+        .as_char();
+        let str = ctx.str_u16(value);
+        let count = |c: char| str.iter().filter(|&&x| x == c as u16).count();
+        if count(other) < count(quote) {
+            other
+        } else {
+            quote
+        }
+    }
+
+    /// Convert `value` to the string used to print it as a numeric literal.
+    fn number_to_string(&self, value: f64) -> String {
+        let string = convert::number_to_string(value);
+        if self.opt.pretty == Pretty::Minify {
+            shortest_number(&string, value)
+        } else {
+            string
         }
     }
 
@@ -3663,6 +4049,12 @@ impl<W: Write> GenJS<'_, W> {
         child: &'gc Node<'gc>,
         child_pos: ChildPos,
     ) -> NeedParens {
+        if matches!(path.parent, Node::YieldExpression(_))
+            && self.comments(child).is_some_and(|c| !c.leading.is_empty())
+        {
+            // A line break after a leading comment would terminate the `yield` early.
+            return NeedParens::Yes;
+        }
         #[allow(clippy::if_same_then_else)]
         if matches!(path.parent, Node::ArrowFunctionExpression(_)) {
             // (x) => ({x: 10}) needs parens to avoid confusing it with a block and a
@@ -3907,9 +4299,19 @@ impl<W: Write> GenJS<'_, W> {
             dst_col: self.position.col.wrapping_sub(1),
             src_line: node.range().start.line.wrapping_sub(1),
             src_col: node.range().start.col.wrapping_sub(1),
-            src_id: 0,
+            src_id: self
+                .sourcemap_ids
+                .get(node.range().file.0 as usize)
+                .copied()
+                .unwrap_or(!0),
             name_id: !0,
         });
+        if let Some(doc) = &mut self.doc {
+            // The destination is filled in after layout.
+            doc.mark();
+            self.doc_tokens.extend(new_token);
+            return;
+        }
         self.flush_cur_token();
         self.cur_token = new_token;
     }
@@ -3973,7 +4375,71 @@ impl<W: Write> GenJS<'_, W> {
 
 impl<'gc, W: Write> Visitor<'gc> for GenJS<'_, W> {
     fn call(&mut self, ctx: &'gc GCLock, node: &'gc Node<'gc>, path: Option<Path<'gc>>) {
+        let defer_trailing_comments = std::mem::take(&mut self.defer_trailing_comments);
+        let comments = self.comments(node);
+        if let Some(comments) = comments {
+            self.print_leading_comments(node, &comments.leading);
+        }
+        // Binary expressions and member chains can be broken across lines as a whole.
+        let group =
+            self.doc.is_some() && (is_binary_root(node, path) || is_member_chain_root(node, path));
+        if group {
+            self.begin_group();
+        }
         self.gen_node(ctx, node, path);
+        if group {
+            self.end_group();
+        }
+        if let Some(comments) = comments {
+            // Blocks print their inner comments before the closing brace,
+            // other nodes print them after the node.
+            if !matches!(node, Node::Program(_) | Node::BlockStatement(_)) {
+                self.print_trailing_comments(&comments.inner);
+            }
+            if !defer_trailing_comments {
+                self.print_trailing_comments(&comments.trailing);
+            }
+        }
+    }
+}
+
+/// Whether `node` is a binary expression which isn't an operand of another one.
+fn is_binary_root(node: &Node, path: Option<Path>) -> bool {
+    let is_binary = |n: &Node| matches!(n, Node::BinaryExpression(_) | Node::LogicalExpression(_));
+    is_binary(node) && !path.is_some_and(|path| is_binary(path.parent))
+}
+
+/// Whether `node` is the outermost call or member expression in a chain
+/// which contains calls followed by member accesses, like `a.b().c()`.
+fn is_member_chain_root(node: &Node, path: Option<Path>) -> bool {
+    if let Some(Path { parent, field }) = path {
+        if matches!(
+            (parent, field),
+            (
+                Node::MemberExpression(_) | Node::OptionalMemberExpression(_),
+                NodeField::object
+            ) | (
+                Node::CallExpression(_) | Node::OptionalCallExpression(_),
+                NodeField::callee
+            )
+        ) {
+            return false;
+        }
+    }
+    let mut cur = node;
+    loop {
+        cur = match cur {
+            Node::MemberExpression(MemberExpression { object, .. })
+            | Node::OptionalMemberExpression(OptionalMemberExpression { object, .. }) => {
+                if is_call(object) {
+                    return true;
+                }
+                object
+            }
+            Node::CallExpression(CallExpression { callee, .. })
+            | Node::OptionalCallExpression(OptionalCallExpression { callee, .. }) => callee,
+            _ => return false,
+        };
     }
 }
 
@@ -4020,6 +4486,22 @@ fn is_binary_op(node: &Node, op: BinaryExpressionOperator) -> bool {
     }
 }
 
+/// Whether `node` ends in an empty statement, which must always be terminated by a semicolon.
+fn is_empty_stmt(node: &Node) -> bool {
+    match node {
+        Node::EmptyStatement(_) => true,
+        Node::LabeledStatement(LabeledStatement { body, .. }) => is_empty_stmt(body),
+        _ => false,
+    }
+}
+
+fn is_call(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::CallExpression(_) | Node::OptionalCallExpression(_)
+    )
+}
+
 fn is_if_without_else(node: &Node) -> bool {
     match node {
         Node::IfStatement(IfStatement {
@@ -4146,3 +4628,72 @@ fn contains_call<'gc>(gc: &'gc GCLock, node: &'gc Node<'gc>) -> bool {
     node.visit(gc, &mut finder, None);
     finder.found
 }
+
+/// Whether a space is needed between the characters `last` and `next` to prevent
+/// the tokens they end and start from being joined.
+fn need_separator(last: char, next: char) -> bool {
+    let is_ident =
+        |c: char| c.is_ascii_alphanumeric() || matches!(c, '_' | '$' | '\\') || !c.is_ascii();
+    (is_ident(last) && is_ident(next))
+        // `a+ +b`, `a- -b`, `a/ /b/`.
+        || (last == next && matches!(last, '+' | '-' | '/'))
+        // Avoid creating HTML-like comments: `a<!--b` and `a-->b`.
+        || (last == '<' && next == '!')
+        || (last == '-' && next == '>')
+}
+
+/// Return the shortest numeric literal with the same value as `string`,
+/// which is the result of converting the number `value` to a string.
+/// Considers decimal, exponential (`1e3`) and hexadecimal (`0xff`) forms.
+fn shortest_number(string: &str, value: f64) -> String {
+    let (sign, unsigned) = match string.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", string),
+    };
+    if !unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+        // `NaN` and `Infinity`.
+        return string.to_string();
+    }
+
+    // Decompose the number into `digits * 10^exp`, without leading or trailing zeros.
+    let (base, mut exp) = match unsigned.split_once(['e', 'E']) {
+        Some((base, exp)) => (base, exp.parse::<i32>().unwrap_or(0)),
+        None => (unsigned, 0),
+    };
+    let (int_part, frac_part) = base.split_once('.').unwrap_or((base, ""));
+    exp -= frac_part.len() as i32;
+    let mut digits = format!("{}{}", int_part, frac_part);
+    while digits.ends_with('0') {
+        digits.pop();
+        exp += 1;
+    }
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return format!("{}0", sign);
+    }
+
+    let len = digits.len() as i32;
+    let decimal = if exp >= 0 {
+        format!("{}{}", digits, "0".repeat(exp as usize))
+    } else if len > -exp {
+        let point = (len + exp) as usize;
+        format!("{}.{}", &digits[..point], &digits[point..])
+    } else {
+        format!(".{}{}", "0".repeat((-exp - len) as usize), digits)
+    };
+    let mut best = decimal;
+    if exp != 0 {
+        let exponential = format!("{}e{}", digits, exp);
+        if exponential.len() < best.len() {
+            best = exponential;
+        }
+    }
+    let abs = value.abs();
+    if exp >= 0 && abs <= (1u64 << 53) as f64 {
+        let hex = format!("0x{:x}", abs as u64);
+        if hex.len() < best.len() {
+            best = hex;
+        }
+    }
+    format!("{}{}", sign, best)
+}
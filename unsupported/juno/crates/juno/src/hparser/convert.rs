@@ -9,15 +9,17 @@ use super::generated_cvt::cvt_node_ptr;
 use crate::ast;
 use crate::ast::SourceId;
 use hermes::parser::{
-    DataRef, HermesParser, NodeLabel, NodeLabelOpt, NodeListOptRef, NodeListRef, NodePtr,
-    NodePtrOpt, NodeString, NodeStringOpt, SMLoc,
+    CommentKind, DataRef, HermesParser, NodeLabel, NodeLabelOpt, NodeListOptRef, NodeListRef,
+    NodePtr, NodePtrOpt, NodeString, NodeStringOpt, SMLoc, SMRange, StoredComment,
 };
 use hermes::utf::{
-    is_utf8_continuation, utf8_with_surrogates_to_string, utf8_with_surrogates_to_utf16,
+    is_utf8_continuation, utf8_with_surrogates_to_string, utf8_with_surrogates_to_string_lossy,
+    utf8_with_surrogates_to_utf16,
 };
 use juno_support::atom_table;
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::ops::Range;
 use std::str::FromStr;
 
 /// A cache to speed up finding locations. The assumption is that most lookups
@@ -56,6 +58,15 @@ pub struct Converter<'parser> {
     /// ast::Identifier. This allows us to avoid repeated conversion of the same
     /// NodeLabel.
     atom_tab_u16: HashMap<NodeString, atom_table::AtomU16>,
+
+    /// All comments in the source, in source order.
+    comments: &'parser [StoredComment],
+
+    /// Index of the first comment in `comments` which hasn't been attached to a node yet.
+    next_comment: usize,
+
+    /// End locations of the nodes which are being converted, from the outermost to the innermost.
+    node_ends: Vec<SMLoc>,
 }
 
 /// Adjust the source location backwards making sure it doesn't point to \r or
@@ -100,6 +111,9 @@ impl<'parser> Converter<'parser> {
             line_cache: Default::default(),
             atom_tab: Default::default(),
             atom_tab_u16: Default::default(),
+            comments: hparser.comments(),
+            next_comment: 0,
+            node_ends: vec![],
         }
     }
 
@@ -179,6 +193,98 @@ impl<'parser> Converter<'parser> {
         u.as_node_string().map(|u| self.cvt_string(ctx, u))
     }
 
+    /// Start converting the node with the source range `range`.
+    /// Return the indices of the comments preceding it which haven't been attached to
+    /// an enclosing node, which become its leading comments.
+    /// Every call must be matched by a call to `exit_node` once the children are converted.
+    pub fn enter_node(&mut self, range: SMRange) -> Range<usize> {
+        if self.comments.is_empty() {
+            return 0..0;
+        }
+        let start = self.next_comment;
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.range.end.as_ptr() > range.start.as_ptr() {
+                break;
+            }
+            self.next_comment += 1;
+        }
+        self.node_ends.push(range.end);
+        start..self.next_comment
+    }
+
+    /// Finish converting the node with the source range `range`, after its children have
+    /// been converted, and return all the comments which must be attached to it.
+    ///
+    /// Comments inside the node which weren't attached to any of its children become its
+    /// inner comments. Comments which follow it on the same line become its trailing comments,
+    /// unless its parent ends at the same location, in which case they are attached to the parent.
+    /// The root node takes every remaining comment.
+    pub fn exit_node<'gc>(
+        &mut self,
+        gc: &'gc ast::GCLock,
+        leading: Range<usize>,
+        range: SMRange,
+    ) -> Option<&'gc ast::NodeComments> {
+        if self.comments.is_empty() {
+            return None;
+        }
+        self.node_ends.pop();
+        let parent_end = self.node_ends.last().map(|end| end.as_ptr());
+
+        let inner_start = self.next_comment;
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if parent_end.is_some() && comment.range.end.as_ptr() > range.end.as_ptr() {
+                break;
+            }
+            self.next_comment += 1;
+        }
+        let inner = inner_start..self.next_comment;
+
+        let trailing_start = self.next_comment;
+        if parent_end != Some(range.end.as_ptr()) {
+            let mut prev_end = range.end;
+            while let Some(comment) = self.comments.get(self.next_comment) {
+                if !is_same_line(prev_end, comment.range.start) {
+                    break;
+                }
+                prev_end = comment.range.end;
+                self.next_comment += 1;
+            }
+        }
+        let trailing = trailing_start..self.next_comment;
+
+        if leading.is_empty() && inner.is_empty() && trailing.is_empty() {
+            return None;
+        }
+        let comments = ast::NodeComments {
+            leading: self.cvt_comments(leading),
+            inner: self.cvt_comments(inner),
+            trailing: self.cvt_comments(trailing),
+        };
+        Some(gc.alloc_comments(comments))
+    }
+
+    /// Convert the comments with the specified indices.
+    fn cvt_comments(&mut self, indices: Range<usize>) -> Vec<ast::Comment> {
+        let comments = self.comments;
+        comments[indices]
+            .iter()
+            .map(|comment| ast::Comment {
+                kind: match comment.kind {
+                    CommentKind::Line => ast::CommentKind::Line,
+                    CommentKind::Block => ast::CommentKind::Block,
+                    CommentKind::Hashbang => ast::CommentKind::Hashbang,
+                },
+                value: utf8_with_surrogates_to_string_lossy(comment.value()),
+                range: ast::SourceRange {
+                    file: self.file_id,
+                    start: self.cvt_smloc(comment.range.start),
+                    end: self.cvt_smloc(comment.range.end.pred()),
+                },
+            })
+            .collect()
+    }
+
     /// Report an invalid node kind for conversion via the SourceManager.
     pub fn report_invalid_node(&self, lock: &ast::GCLock, node: NodePtr, range: ast::SourceRange) {
         use hermes::parser::NodeKind::*;
@@ -205,6 +311,21 @@ impl<'parser> Converter<'parser> {
     }
 }
 
+/// Whether there is only horizontal whitespace between `start` and `end`,
+/// which must point into the same buffer.
+fn is_same_line(start: SMLoc, end: SMLoc) -> bool {
+    if end.as_ptr() < start.as_ptr() {
+        return false;
+    }
+    let between = unsafe {
+        std::slice::from_raw_parts(
+            start.as_ptr(),
+            end.as_ptr().offset_from(start.as_ptr()) as usize,
+        )
+    };
+    between.iter().all(|&c| c == b' ' || c == b'\t')
+}
+
 /// # Safety
 /// `n` must be valid.
 pub unsafe fn cvt_node_ptr_opt<'gc, 'ast: 'gc>(
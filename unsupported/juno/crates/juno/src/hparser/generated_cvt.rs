@@ -22,6 +22,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
         start: cvt.cvt_smloc(nr.source_range.start),
         end: ast::SourceLoc::invalid(),
     };
+    let leading_comments = cvt.enter_node(nr.source_range);
 
     let res = match nr.kind {
         NodeKind::Empty => {
@@ -29,6 +30,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::Empty::build_template(gc, template)
         }
         NodeKind::Metadata => {
@@ -36,6 +38,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::Metadata::build_template(gc, template)
         }
         NodeKind::Program => {
@@ -45,6 +48,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::Program::build_template(gc, template)
         }
         NodeKind::FunctionExpression => {
@@ -68,6 +72,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   is_async,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::FunctionExpression::build_template(gc, template)
         }
         NodeKind::ArrowFunctionExpression => {
@@ -91,6 +96,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   is_async,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ArrowFunctionExpression::build_template(gc, template)
         }
         NodeKind::FunctionDeclaration => {
@@ -114,6 +120,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   is_async,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::FunctionDeclaration::build_template(gc, template)
         }
         NodeKind::WhileStatement => {
@@ -125,6 +132,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   test,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::WhileStatement::build_template(gc, template)
         }
         NodeKind::DoWhileStatement => {
@@ -136,6 +144,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   test,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DoWhileStatement::build_template(gc, template)
         }
         NodeKind::ForInStatement => {
@@ -149,6 +158,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ForInStatement::build_template(gc, template)
         }
         NodeKind::ForOfStatement => {
@@ -164,6 +174,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   is_await,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ForOfStatement::build_template(gc, template)
         }
         NodeKind::ForStatement => {
@@ -179,6 +190,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ForStatement::build_template(gc, template)
         }
         NodeKind::DebuggerStatement => {
@@ -186,6 +198,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DebuggerStatement::build_template(gc, template)
         }
         NodeKind::EmptyStatement => {
@@ -193,6 +206,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EmptyStatement::build_template(gc, template)
         }
         NodeKind::BlockStatement => {
@@ -202,6 +216,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::BlockStatement::build_template(gc, template)
         }
         NodeKind::BreakStatement => {
@@ -211,6 +226,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   label,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::BreakStatement::build_template(gc, template)
         }
         NodeKind::ContinueStatement => {
@@ -220,6 +236,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   label,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ContinueStatement::build_template(gc, template)
         }
         NodeKind::ThrowStatement => {
@@ -229,6 +246,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   argument,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ThrowStatement::build_template(gc, template)
         }
         NodeKind::ReturnStatement => {
@@ -238,6 +256,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   argument,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ReturnStatement::build_template(gc, template)
         }
         NodeKind::WithStatement => {
@@ -249,6 +268,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::WithStatement::build_template(gc, template)
         }
         NodeKind::SwitchStatement => {
@@ -260,6 +280,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   cases,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::SwitchStatement::build_template(gc, template)
         }
         NodeKind::LabeledStatement => {
@@ -271,6 +292,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::LabeledStatement::build_template(gc, template)
         }
         NodeKind::ExpressionStatement => {
@@ -282,6 +304,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   directive,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ExpressionStatement::build_template(gc, template)
         }
         NodeKind::TryStatement => {
@@ -295,6 +318,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   finalizer,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TryStatement::build_template(gc, template)
         }
         NodeKind::IfStatement => {
@@ -308,6 +332,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   alternate,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::IfStatement::build_template(gc, template)
         }
         NodeKind::NullLiteral => {
@@ -315,6 +340,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::NullLiteral::build_template(gc, template)
         }
         NodeKind::BooleanLiteral => {
@@ -324,6 +350,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   value,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::BooleanLiteral::build_template(gc, template)
         }
         NodeKind::StringLiteral => {
@@ -333,6 +360,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   value,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::StringLiteral::build_template(gc, template)
         }
         NodeKind::NumericLiteral => {
@@ -342,6 +370,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   value,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::NumericLiteral::build_template(gc, template)
         }
         NodeKind::RegExpLiteral => {
@@ -353,6 +382,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   flags,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::RegExpLiteral::build_template(gc, template)
         }
         NodeKind::BigIntLiteral => {
@@ -362,6 +392,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   bigint,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::BigIntLiteral::build_template(gc, template)
         }
         NodeKind::ThisExpression => {
@@ -369,6 +400,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ThisExpression::build_template(gc, template)
         }
         NodeKind::Super => {
@@ -376,6 +408,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::Super::build_template(gc, template)
         }
         NodeKind::SequenceExpression => {
@@ -385,6 +418,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   expressions,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::SequenceExpression::build_template(gc, template)
         }
         NodeKind::ObjectExpression => {
@@ -394,6 +428,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   properties,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ObjectExpression::build_template(gc, template)
         }
         NodeKind::ArrayExpression => {
@@ -405,6 +440,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   trailing_comma,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ArrayExpression::build_template(gc, template)
         }
         NodeKind::SpreadElement => {
@@ -414,6 +450,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   argument,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::SpreadElement::build_template(gc, template)
         }
         NodeKind::NewExpression => {
@@ -427,6 +464,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   arguments,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::NewExpression::build_template(gc, template)
         }
         NodeKind::YieldExpression => {
@@ -438,6 +476,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   delegate,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::YieldExpression::build_template(gc, template)
         }
         NodeKind::AwaitExpression => {
@@ -447,6 +486,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   argument,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::AwaitExpression::build_template(gc, template)
         }
         NodeKind::ImportExpression => {
@@ -458,6 +498,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   attributes,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ImportExpression::build_template(gc, template)
         }
         NodeKind::CallExpression => {
@@ -471,6 +512,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   arguments,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::CallExpression::build_template(gc, template)
         }
         NodeKind::OptionalCallExpression => {
@@ -486,6 +528,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   optional,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::OptionalCallExpression::build_template(gc, template)
         }
         NodeKind::AssignmentExpression => {
@@ -499,6 +542,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   right,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::AssignmentExpression::build_template(gc, template)
         }
         NodeKind::UnaryExpression => {
@@ -512,6 +556,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   prefix,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::UnaryExpression::build_template(gc, template)
         }
         NodeKind::UpdateExpression => {
@@ -525,6 +570,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   prefix,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::UpdateExpression::build_template(gc, template)
         }
         NodeKind::MemberExpression => {
@@ -538,6 +584,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   computed,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::MemberExpression::build_template(gc, template)
         }
         NodeKind::OptionalMemberExpression => {
@@ -553,6 +600,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   optional,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::OptionalMemberExpression::build_template(gc, template)
         }
         NodeKind::LogicalExpression => {
@@ -566,6 +614,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   operator,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::LogicalExpression::build_template(gc, template)
         }
         NodeKind::ConditionalExpression => {
@@ -579,6 +628,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   consequent,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ConditionalExpression::build_template(gc, template)
         }
         NodeKind::BinaryExpression => {
@@ -592,6 +642,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   operator,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::BinaryExpression::build_template(gc, template)
         }
         NodeKind::Directive => {
@@ -601,6 +652,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   value,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::Directive::build_template(gc, template)
         }
         NodeKind::DirectiveLiteral => {
@@ -610,6 +662,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   value,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DirectiveLiteral::build_template(gc, template)
         }
         NodeKind::Identifier => {
@@ -623,6 +676,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   optional,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::Identifier::build_template(gc, template)
         }
         NodeKind::PrivateName => {
@@ -632,6 +686,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   id,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::PrivateName::build_template(gc, template)
         }
         NodeKind::MetaProperty => {
@@ -643,6 +698,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   property,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::MetaProperty::build_template(gc, template)
         }
         NodeKind::SwitchCase => {
@@ -654,6 +710,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   consequent,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::SwitchCase::build_template(gc, template)
         }
         NodeKind::CatchClause => {
@@ -665,6 +722,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::CatchClause::build_template(gc, template)
         }
         NodeKind::VariableDeclarator => {
@@ -676,6 +734,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   id,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::VariableDeclarator::build_template(gc, template)
         }
         NodeKind::VariableDeclaration => {
@@ -687,6 +746,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   declarations,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::VariableDeclaration::build_template(gc, template)
         }
         NodeKind::TemplateLiteral => {
@@ -698,6 +758,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   expressions,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TemplateLiteral::build_template(gc, template)
         }
         NodeKind::TaggedTemplateExpression => {
@@ -709,6 +770,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   quasi,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TaggedTemplateExpression::build_template(gc, template)
         }
         NodeKind::TemplateElement => {
@@ -722,6 +784,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   raw,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TemplateElement::build_template(gc, template)
         }
         NodeKind::Property => {
@@ -741,6 +804,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   shorthand,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::Property::build_template(gc, template)
         }
         NodeKind::ClassDeclaration => {
@@ -762,6 +826,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ClassDeclaration::build_template(gc, template)
         }
         NodeKind::ClassExpression => {
@@ -783,6 +848,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ClassExpression::build_template(gc, template)
         }
         NodeKind::ClassBody => {
@@ -792,6 +858,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ClassBody::build_template(gc, template)
         }
         NodeKind::ClassProperty => {
@@ -815,6 +882,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ClassProperty::build_template(gc, template)
         }
         NodeKind::ClassPrivateProperty => {
@@ -836,6 +904,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ClassPrivateProperty::build_template(gc, template)
         }
         NodeKind::MethodDefinition => {
@@ -853,6 +922,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   is_static,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::MethodDefinition::build_template(gc, template)
         }
         NodeKind::ImportDeclaration => {
@@ -868,6 +938,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   import_kind,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ImportDeclaration::build_template(gc, template)
         }
         NodeKind::ImportSpecifier => {
@@ -881,6 +952,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   import_kind,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ImportSpecifier::build_template(gc, template)
         }
         NodeKind::ImportDefaultSpecifier => {
@@ -890,6 +962,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   local,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ImportDefaultSpecifier::build_template(gc, template)
         }
         NodeKind::ImportNamespaceSpecifier => {
@@ -899,6 +972,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   local,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ImportNamespaceSpecifier::build_template(gc, template)
         }
         NodeKind::ImportAttribute => {
@@ -910,6 +984,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   value,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ImportAttribute::build_template(gc, template)
         }
         NodeKind::ExportNamedDeclaration => {
@@ -925,6 +1000,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   export_kind,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ExportNamedDeclaration::build_template(gc, template)
         }
         NodeKind::ExportSpecifier => {
@@ -936,6 +1012,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   local,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ExportSpecifier::build_template(gc, template)
         }
         NodeKind::ExportNamespaceSpecifier => {
@@ -945,6 +1022,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   exported,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ExportNamespaceSpecifier::build_template(gc, template)
         }
         NodeKind::ExportDefaultDeclaration => {
@@ -954,6 +1032,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   declaration,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ExportDefaultDeclaration::build_template(gc, template)
         }
         NodeKind::ExportAllDeclaration => {
@@ -965,6 +1044,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   export_kind,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ExportAllDeclaration::build_template(gc, template)
         }
         NodeKind::ObjectPattern => {
@@ -976,6 +1056,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ObjectPattern::build_template(gc, template)
         }
         NodeKind::ArrayPattern => {
@@ -987,6 +1068,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ArrayPattern::build_template(gc, template)
         }
         NodeKind::RestElement => {
@@ -996,6 +1078,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   argument,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::RestElement::build_template(gc, template)
         }
         NodeKind::AssignmentPattern => {
@@ -1007,6 +1090,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   right,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::AssignmentPattern::build_template(gc, template)
         }
         NodeKind::JSXIdentifier => {
@@ -1016,6 +1100,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   name,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXIdentifier::build_template(gc, template)
         }
         NodeKind::JSXMemberExpression => {
@@ -1027,6 +1112,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   property,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXMemberExpression::build_template(gc, template)
         }
         NodeKind::JSXNamespacedName => {
@@ -1038,6 +1124,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   name,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXNamespacedName::build_template(gc, template)
         }
         NodeKind::JSXEmptyExpression => {
@@ -1045,6 +1132,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXEmptyExpression::build_template(gc, template)
         }
         NodeKind::JSXExpressionContainer => {
@@ -1054,6 +1142,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   expression,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXExpressionContainer::build_template(gc, template)
         }
         NodeKind::JSXSpreadChild => {
@@ -1063,6 +1152,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   expression,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXSpreadChild::build_template(gc, template)
         }
         NodeKind::JSXOpeningElement => {
@@ -1076,6 +1166,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   self_closing,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXOpeningElement::build_template(gc, template)
         }
         NodeKind::JSXClosingElement => {
@@ -1085,6 +1176,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   name,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXClosingElement::build_template(gc, template)
         }
         NodeKind::JSXAttribute => {
@@ -1096,6 +1188,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   value,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXAttribute::build_template(gc, template)
         }
         NodeKind::JSXSpreadAttribute => {
@@ -1105,6 +1198,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   argument,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXSpreadAttribute::build_template(gc, template)
         }
         NodeKind::JSXStringLiteral => {
@@ -1116,6 +1210,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   raw,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXStringLiteral::build_template(gc, template)
         }
         NodeKind::JSXText => {
@@ -1127,6 +1222,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   raw,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXText::build_template(gc, template)
         }
         NodeKind::JSXElement => {
@@ -1140,6 +1236,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   closing_element,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXElement::build_template(gc, template)
         }
         NodeKind::JSXFragment => {
@@ -1153,6 +1250,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   closing_fragment,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXFragment::build_template(gc, template)
         }
         NodeKind::JSXOpeningFragment => {
@@ -1160,6 +1258,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXOpeningFragment::build_template(gc, template)
         }
         NodeKind::JSXClosingFragment => {
@@ -1167,6 +1266,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::JSXClosingFragment::build_template(gc, template)
         }
         NodeKind::ExistsTypeAnnotation => {
@@ -1174,6 +1274,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ExistsTypeAnnotation::build_template(gc, template)
         }
         NodeKind::EmptyTypeAnnotation => {
@@ -1181,6 +1282,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EmptyTypeAnnotation::build_template(gc, template)
         }
         NodeKind::StringTypeAnnotation => {
@@ -1188,6 +1290,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::StringTypeAnnotation::build_template(gc, template)
         }
         NodeKind::NumberTypeAnnotation => {
@@ -1195,6 +1298,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::NumberTypeAnnotation::build_template(gc, template)
         }
         NodeKind::StringLiteralTypeAnnotation => {
@@ -1206,6 +1310,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   raw,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::StringLiteralTypeAnnotation::build_template(gc, template)
         }
         NodeKind::NumberLiteralTypeAnnotation => {
@@ -1217,6 +1322,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   raw,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::NumberLiteralTypeAnnotation::build_template(gc, template)
         }
         NodeKind::BigIntLiteralTypeAnnotation => {
@@ -1226,6 +1332,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   raw,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::BigIntLiteralTypeAnnotation::build_template(gc, template)
         }
         NodeKind::BooleanTypeAnnotation => {
@@ -1233,6 +1340,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::BooleanTypeAnnotation::build_template(gc, template)
         }
         NodeKind::BooleanLiteralTypeAnnotation => {
@@ -1244,6 +1352,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   raw,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::BooleanLiteralTypeAnnotation::build_template(gc, template)
         }
         NodeKind::NullLiteralTypeAnnotation => {
@@ -1251,6 +1360,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::NullLiteralTypeAnnotation::build_template(gc, template)
         }
         NodeKind::SymbolTypeAnnotation => {
@@ -1258,6 +1368,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::SymbolTypeAnnotation::build_template(gc, template)
         }
         NodeKind::AnyTypeAnnotation => {
@@ -1265,6 +1376,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::AnyTypeAnnotation::build_template(gc, template)
         }
         NodeKind::MixedTypeAnnotation => {
@@ -1272,6 +1384,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::MixedTypeAnnotation::build_template(gc, template)
         }
         NodeKind::VoidTypeAnnotation => {
@@ -1279,6 +1392,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::VoidTypeAnnotation::build_template(gc, template)
         }
         NodeKind::FunctionTypeAnnotation => {
@@ -1296,6 +1410,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_parameters,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::FunctionTypeAnnotation::build_template(gc, template)
         }
         NodeKind::FunctionTypeParam => {
@@ -1309,6 +1424,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   optional,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::FunctionTypeParam::build_template(gc, template)
         }
         NodeKind::NullableTypeAnnotation => {
@@ -1318,6 +1434,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::NullableTypeAnnotation::build_template(gc, template)
         }
         NodeKind::QualifiedTypeIdentifier => {
@@ -1329,6 +1446,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   id,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::QualifiedTypeIdentifier::build_template(gc, template)
         }
         NodeKind::TypeofTypeAnnotation => {
@@ -1338,6 +1456,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   argument,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TypeofTypeAnnotation::build_template(gc, template)
         }
         NodeKind::TupleTypeAnnotation => {
@@ -1347,6 +1466,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   types,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TupleTypeAnnotation::build_template(gc, template)
         }
         NodeKind::ArrayTypeAnnotation => {
@@ -1356,6 +1476,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   element_type,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ArrayTypeAnnotation::build_template(gc, template)
         }
         NodeKind::UnionTypeAnnotation => {
@@ -1365,6 +1486,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   types,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::UnionTypeAnnotation::build_template(gc, template)
         }
         NodeKind::IntersectionTypeAnnotation => {
@@ -1374,6 +1496,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   types,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::IntersectionTypeAnnotation::build_template(gc, template)
         }
         NodeKind::GenericTypeAnnotation => {
@@ -1385,6 +1508,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_parameters,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::GenericTypeAnnotation::build_template(gc, template)
         }
         NodeKind::IndexedAccessType => {
@@ -1396,6 +1520,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   index_type,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::IndexedAccessType::build_template(gc, template)
         }
         NodeKind::OptionalIndexedAccessType => {
@@ -1409,6 +1534,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   optional,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::OptionalIndexedAccessType::build_template(gc, template)
         }
         NodeKind::InterfaceTypeAnnotation => {
@@ -1420,6 +1546,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::InterfaceTypeAnnotation::build_template(gc, template)
         }
         NodeKind::TypeAlias => {
@@ -1433,6 +1560,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   right,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TypeAlias::build_template(gc, template)
         }
         NodeKind::OpaqueType => {
@@ -1448,6 +1576,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   supertype,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::OpaqueType::build_template(gc, template)
         }
         NodeKind::InterfaceDeclaration => {
@@ -1463,6 +1592,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::InterfaceDeclaration::build_template(gc, template)
         }
         NodeKind::DeclareTypeAlias => {
@@ -1476,6 +1606,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   right,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclareTypeAlias::build_template(gc, template)
         }
         NodeKind::DeclareOpaqueType => {
@@ -1491,6 +1622,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   supertype,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclareOpaqueType::build_template(gc, template)
         }
         NodeKind::DeclareInterface => {
@@ -1506,6 +1638,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclareInterface::build_template(gc, template)
         }
         NodeKind::DeclareClass => {
@@ -1525,6 +1658,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclareClass::build_template(gc, template)
         }
         NodeKind::DeclareFunction => {
@@ -1536,6 +1670,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   predicate,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclareFunction::build_template(gc, template)
         }
         NodeKind::DeclareVariable => {
@@ -1545,6 +1680,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   id,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclareVariable::build_template(gc, template)
         }
         NodeKind::DeclareExportDeclaration => {
@@ -1560,6 +1696,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   default,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclareExportDeclaration::build_template(gc, template)
         }
         NodeKind::DeclareExportAllDeclaration => {
@@ -1569,6 +1706,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   source,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclareExportAllDeclaration::build_template(gc, template)
         }
         NodeKind::DeclareModule => {
@@ -1582,6 +1720,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   kind,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclareModule::build_template(gc, template)
         }
         NodeKind::DeclareModuleExports => {
@@ -1591,6 +1730,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclareModuleExports::build_template(gc, template)
         }
         NodeKind::InterfaceExtends => {
@@ -1602,6 +1742,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_parameters,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::InterfaceExtends::build_template(gc, template)
         }
         NodeKind::ClassImplements => {
@@ -1613,6 +1754,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_parameters,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ClassImplements::build_template(gc, template)
         }
         NodeKind::TypeAnnotation => {
@@ -1622,6 +1764,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TypeAnnotation::build_template(gc, template)
         }
         NodeKind::ObjectTypeAnnotation => {
@@ -1641,6 +1784,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   exact,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ObjectTypeAnnotation::build_template(gc, template)
         }
         NodeKind::ObjectTypeProperty => {
@@ -1664,6 +1808,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   kind,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ObjectTypeProperty::build_template(gc, template)
         }
         NodeKind::ObjectTypeSpreadProperty => {
@@ -1673,6 +1818,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   argument,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ObjectTypeSpreadProperty::build_template(gc, template)
         }
         NodeKind::ObjectTypeInternalSlot => {
@@ -1690,6 +1836,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   method,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ObjectTypeInternalSlot::build_template(gc, template)
         }
         NodeKind::ObjectTypeCallProperty => {
@@ -1701,6 +1848,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   is_static,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ObjectTypeCallProperty::build_template(gc, template)
         }
         NodeKind::ObjectTypeIndexer => {
@@ -1718,6 +1866,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   variance,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::ObjectTypeIndexer::build_template(gc, template)
         }
         NodeKind::Variance => {
@@ -1727,6 +1876,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   kind,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::Variance::build_template(gc, template)
         }
         NodeKind::TypeParameterDeclaration => {
@@ -1736,6 +1886,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   params,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TypeParameterDeclaration::build_template(gc, template)
         }
         NodeKind::TypeParameter => {
@@ -1751,6 +1902,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   default,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TypeParameter::build_template(gc, template)
         }
         NodeKind::TypeParameterInstantiation => {
@@ -1760,6 +1912,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   params,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TypeParameterInstantiation::build_template(gc, template)
         }
         NodeKind::TypeCastExpression => {
@@ -1771,6 +1924,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TypeCastExpression::build_template(gc, template)
         }
         NodeKind::InferredPredicate => {
@@ -1778,6 +1932,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::InferredPredicate::build_template(gc, template)
         }
         NodeKind::DeclaredPredicate => {
@@ -1787,6 +1942,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   value,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::DeclaredPredicate::build_template(gc, template)
         }
         NodeKind::EnumDeclaration => {
@@ -1798,6 +1954,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EnumDeclaration::build_template(gc, template)
         }
         NodeKind::EnumStringBody => {
@@ -1811,6 +1968,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   has_unknown_members,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EnumStringBody::build_template(gc, template)
         }
         NodeKind::EnumNumberBody => {
@@ -1824,6 +1982,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   has_unknown_members,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EnumNumberBody::build_template(gc, template)
         }
         NodeKind::EnumBooleanBody => {
@@ -1837,6 +1996,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   has_unknown_members,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EnumBooleanBody::build_template(gc, template)
         }
         NodeKind::EnumSymbolBody => {
@@ -1848,6 +2008,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   has_unknown_members,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EnumSymbolBody::build_template(gc, template)
         }
         NodeKind::EnumDefaultedMember => {
@@ -1857,6 +2018,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   id,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EnumDefaultedMember::build_template(gc, template)
         }
         NodeKind::EnumStringMember => {
@@ -1868,6 +2030,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   init,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EnumStringMember::build_template(gc, template)
         }
         NodeKind::EnumNumberMember => {
@@ -1879,6 +2042,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   init,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EnumNumberMember::build_template(gc, template)
         }
         NodeKind::EnumBooleanMember => {
@@ -1890,6 +2054,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   init,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::EnumBooleanMember::build_template(gc, template)
         }
         NodeKind::TSTypeAnnotation => {
@@ -1899,6 +2064,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTypeAnnotation::build_template(gc, template)
         }
         NodeKind::TSAnyKeyword => {
@@ -1906,6 +2072,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSAnyKeyword::build_template(gc, template)
         }
         NodeKind::TSNumberKeyword => {
@@ -1913,6 +2080,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSNumberKeyword::build_template(gc, template)
         }
         NodeKind::TSBooleanKeyword => {
@@ -1920,6 +2088,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSBooleanKeyword::build_template(gc, template)
         }
         NodeKind::TSStringKeyword => {
@@ -1927,6 +2096,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSStringKeyword::build_template(gc, template)
         }
         NodeKind::TSSymbolKeyword => {
@@ -1934,6 +2104,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSSymbolKeyword::build_template(gc, template)
         }
         NodeKind::TSVoidKeyword => {
@@ -1941,6 +2112,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSVoidKeyword::build_template(gc, template)
         }
         NodeKind::TSThisType => {
@@ -1948,6 +2120,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
               metadata: ast::TemplateMetadata {range, ..Default::default()},
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSThisType::build_template(gc, template)
         }
         NodeKind::TSLiteralType => {
@@ -1957,6 +2130,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   literal,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSLiteralType::build_template(gc, template)
         }
         NodeKind::TSIndexedAccessType => {
@@ -1968,6 +2142,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   index_type,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSIndexedAccessType::build_template(gc, template)
         }
         NodeKind::TSArrayType => {
@@ -1977,6 +2152,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   element_type,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSArrayType::build_template(gc, template)
         }
         NodeKind::TSTypeReference => {
@@ -1988,6 +2164,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_parameters,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTypeReference::build_template(gc, template)
         }
         NodeKind::TSQualifiedName => {
@@ -1999,6 +2176,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   right,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSQualifiedName::build_template(gc, template)
         }
         NodeKind::TSFunctionType => {
@@ -2012,6 +2190,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_parameters,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSFunctionType::build_template(gc, template)
         }
         NodeKind::TSConstructorType => {
@@ -2025,6 +2204,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_parameters,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSConstructorType::build_template(gc, template)
         }
         NodeKind::TSTypePredicate => {
@@ -2036,6 +2216,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTypePredicate::build_template(gc, template)
         }
         NodeKind::TSTupleType => {
@@ -2045,6 +2226,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   element_types,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTupleType::build_template(gc, template)
         }
         NodeKind::TSTypeAssertion => {
@@ -2056,6 +2238,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   expression,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTypeAssertion::build_template(gc, template)
         }
         NodeKind::TSAsExpression => {
@@ -2067,6 +2250,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSAsExpression::build_template(gc, template)
         }
         NodeKind::TSParameterProperty => {
@@ -2084,6 +2268,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   export,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSParameterProperty::build_template(gc, template)
         }
         NodeKind::TSTypeAliasDeclaration => {
@@ -2097,6 +2282,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTypeAliasDeclaration::build_template(gc, template)
         }
         NodeKind::TSInterfaceDeclaration => {
@@ -2112,6 +2298,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_parameters,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSInterfaceDeclaration::build_template(gc, template)
         }
         NodeKind::TSInterfaceHeritage => {
@@ -2123,6 +2310,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_parameters,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSInterfaceHeritage::build_template(gc, template)
         }
         NodeKind::TSInterfaceBody => {
@@ -2132,6 +2320,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSInterfaceBody::build_template(gc, template)
         }
         NodeKind::TSEnumDeclaration => {
@@ -2143,6 +2332,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   members,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSEnumDeclaration::build_template(gc, template)
         }
         NodeKind::TSEnumMember => {
@@ -2154,6 +2344,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   initializer,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSEnumMember::build_template(gc, template)
         }
         NodeKind::TSModuleDeclaration => {
@@ -2165,6 +2356,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSModuleDeclaration::build_template(gc, template)
         }
         NodeKind::TSModuleBlock => {
@@ -2174,6 +2366,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   body,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSModuleBlock::build_template(gc, template)
         }
         NodeKind::TSModuleMember => {
@@ -2185,6 +2378,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   initializer,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSModuleMember::build_template(gc, template)
         }
         NodeKind::TSTypeParameterDeclaration => {
@@ -2194,6 +2388,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   params,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTypeParameterDeclaration::build_template(gc, template)
         }
         NodeKind::TSTypeParameter => {
@@ -2207,6 +2402,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   default,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTypeParameter::build_template(gc, template)
         }
         NodeKind::TSTypeParameterInstantiation => {
@@ -2216,6 +2412,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   params,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTypeParameterInstantiation::build_template(gc, template)
         }
         NodeKind::TSUnionType => {
@@ -2225,6 +2422,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   types,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSUnionType::build_template(gc, template)
         }
         NodeKind::TSIntersectionType => {
@@ -2234,6 +2432,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   types,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSIntersectionType::build_template(gc, template)
         }
         NodeKind::TSTypeQuery => {
@@ -2243,6 +2442,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   expr_name,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTypeQuery::build_template(gc, template)
         }
         NodeKind::TSConditionalType => {
@@ -2258,6 +2458,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   false_t_ype,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSConditionalType::build_template(gc, template)
         }
         NodeKind::TSTypeLiteral => {
@@ -2267,6 +2468,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   members,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSTypeLiteral::build_template(gc, template)
         }
         NodeKind::TSPropertySignature => {
@@ -2290,6 +2492,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   export,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSPropertySignature::build_template(gc, template)
         }
         NodeKind::TSMethodSignature => {
@@ -2305,6 +2508,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   computed,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSMethodSignature::build_template(gc, template)
         }
         NodeKind::TSIndexSignature => {
@@ -2316,6 +2520,7 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   type_annotation,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSIndexSignature::build_template(gc, template)
         }
         NodeKind::TSCallSignatureDeclaration => {
@@ -2327,10 +2532,12 @@ pub unsafe fn cvt_node_ptr<'parser, 'gc>(
                   return_type,
           };
           template.metadata.range.end = if nr.source_range.is_empty() { template.metadata.range.start } else { cvt.cvt_smloc(nr.source_range.end.pred()) };
+          template.metadata.comments = cvt.exit_node(gc, leading_comments, nr.source_range);
           ast::builder::TSCallSignatureDeclaration::build_template(gc, template)
         }
         _ => {
           cvt.report_invalid_node(gc, n, range);
+          cvt.exit_node(gc, leading_comments, nr.source_range);
           let template = ast::template::Empty {
             metadata: ast::TemplateMetadata {range, ..Default::default()}
           };
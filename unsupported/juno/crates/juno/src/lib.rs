@@ -7,6 +7,7 @@
 
 pub use juno_ast as ast;
 
+pub mod dependency_graph;
 pub mod gen_js;
 pub mod hparser;
 pub mod resolve_dependency;
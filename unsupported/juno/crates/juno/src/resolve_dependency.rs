@@ -6,16 +6,58 @@
  */
 
 use crate::ast::GCLock;
-use juno_support::source_manager::{SourceId, SourceManager};
+use juno_support::source_manager::{SourceId, SourceLoc, SourceManager, SourceRange};
+use juno_support::NullTerminatedBuf;
+use serde_json::Value;
 use std::{
-    collections::HashMap,
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fs::{self, File},
     path::{Component, Path, PathBuf},
+    rc::Rc,
+    time::UNIX_EPOCH,
 };
+use thiserror::Error;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum DependencyKind {
+    /// `require('x')`.
     Require,
+    /// `import ... from 'x'` and `export ... from 'x'`.
     Import,
+    /// `import('x')`, which loads the module asynchronously.
+    DynamicImport,
+    /// `require.resolve('x')`, which only gets the ID of the module without running it.
+    RequireResolve,
+    /// `require.async('x')`, which loads the module asynchronously.
+    AsyncRequire,
+}
+
+impl DependencyKind {
+    /// Whether the module is loaded after the dependent one runs, so it may be split into
+    /// another bundle.
+    pub fn is_lazy(self) -> bool {
+        matches!(
+            self,
+            DependencyKind::DynamicImport | DependencyKind::AsyncRequire
+        )
+    }
+
+    /// Whether the dependency is on an ES module rather than a CommonJS one.
+    pub fn is_import(self) -> bool {
+        matches!(self, DependencyKind::Import | DependencyKind::DynamicImport)
+    }
+
+    /// Name of the kind used in messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            DependencyKind::Require => "require",
+            DependencyKind::Import => "import",
+            DependencyKind::DynamicImport => "dynamic import",
+            DependencyKind::RequireResolve => "require.resolve",
+            DependencyKind::AsyncRequire => "async require",
+        }
+    }
 }
 
 /// Function used for resolving dependencies.
@@ -29,6 +71,18 @@ pub trait DependencyResolver {
         path: &str,
         kind: DependencyKind,
     ) -> Option<SourceId>;
+
+    /// The paths which were tried when resolving `path` from `file`, to explain why it
+    /// couldn't be resolved. Empty if the resolver doesn't keep track of them.
+    fn tried_candidates(
+        &self,
+        _lock: &GCLock,
+        _file: SourceId,
+        _path: &str,
+        _kind: DependencyKind,
+    ) -> Vec<String> {
+        vec![]
+    }
 }
 
 #[derive(Debug, Default)]
@@ -86,6 +140,1015 @@ impl DependencyResolver for DefaultResolver {
     }
 }
 
+/// Options for [`NodeResolver`].
+#[derive(Debug, Clone)]
+pub struct NodeResolverOptions {
+    /// Extensions tried in order on paths which don't name a file.
+    pub extensions: Vec<String>,
+    /// Conditions of the `exports` field of `package.json` which apply besides `default`, and
+    /// `import` or `require` depending on the kind of dependency. e.g. `react-native`.
+    pub conditions: Vec<String>,
+    /// Whether to use the `browser` field of `package.json` and the `browser` condition.
+    pub browser: bool,
+}
+
+impl Default for NodeResolverOptions {
+    fn default() -> Self {
+        NodeResolverOptions {
+            extensions: vec![".js".to_string(), ".mjs".to_string(), ".cjs".to_string()],
+            conditions: vec![],
+            browser: false,
+        }
+    }
+}
+
+/// The fields of a `package.json` which are used for resolution.
+#[derive(Debug)]
+struct Package {
+    /// Directory containing the `package.json`.
+    dir: PathBuf,
+    main: Option<String>,
+    exports: Option<Value>,
+    browser: Option<Value>,
+}
+
+/// Resolves dependencies like Node.js, searching `node_modules` directories for packages and
+/// following the fields of their `package.json`.
+/// Files which haven't been loaded yet are added to the `SourceManager` when they're found,
+/// named by their path relative to the importing file's name.
+#[derive(Debug, Default)]
+pub struct NodeResolver {
+    options: NodeResolverOptions,
+    /// The package of each directory which has been looked up,
+    /// `None` if it has no `package.json` or it couldn't be parsed.
+    packages: RefCell<HashMap<PathBuf, Option<Rc<Package>>>>,
+}
+
+impl NodeResolver {
+    pub fn new(options: NodeResolverOptions) -> Self {
+        NodeResolver {
+            options,
+            packages: Default::default(),
+        }
+    }
+
+    /// Resolve `specifier` imported from the file at `importer` to the path of a file.
+    fn resolve(
+        &self,
+        sm: &SourceManager,
+        importer: &Path,
+        specifier: &str,
+        kind: DependencyKind,
+    ) -> Option<PathBuf> {
+        let dir = importer.parent().unwrap_or_else(|| Path::new(""));
+        if is_relative(specifier) || Path::new(specifier).is_absolute() {
+            let resolved = self.resolve_path(sm, &join_path(dir, specifier))?;
+            return self.browser_replacement(sm, resolved);
+        }
+        if self.options.browser {
+            // The package of the importer may replace or ignore other packages.
+            if let Some(package) = self.package_scope(dir) {
+                if let Some(Value::Object(map)) = &package.browser {
+                    match map.get(specifier) {
+                        Some(Value::String(replacement)) if is_relative(replacement) => {
+                            return self.resolve_path(sm, &join_path(&package.dir, replacement));
+                        }
+                        Some(Value::String(replacement)) => {
+                            return self.resolve_package(sm, dir, replacement, kind);
+                        }
+                        Some(Value::Bool(false)) => return None,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        self.resolve_package(sm, dir, specifier, kind)
+    }
+
+    /// Resolve the bare `specifier`, which names a package and optionally a path within it,
+    /// by searching the `node_modules` directories of `dir` and its ancestors.
+    fn resolve_package(
+        &self,
+        sm: &SourceManager,
+        dir: &Path,
+        specifier: &str,
+        kind: DependencyKind,
+    ) -> Option<PathBuf> {
+        let (name, subpath) = split_package(specifier)?;
+        for ancestor in dir.ancestors() {
+            if ancestor
+                .file_name()
+                .is_some_and(|file_name| file_name == "node_modules")
+            {
+                continue;
+            }
+            let package_dir = ancestor.join("node_modules").join(name);
+            if !package_dir.is_dir() {
+                continue;
+            }
+            if let Some(package) = self.package(&package_dir) {
+                if let Some(exports) = &package.exports {
+                    // Only the exported paths of the package may be imported.
+                    return self
+                        .resolve_exports(&package, exports, &subpath, kind)
+                        .filter(|path| is_file(sm, path));
+                }
+            }
+            let resolved = self
+                .resolve_path(sm, &join_path(&package_dir, &subpath))
+                .and_then(|resolved| self.browser_replacement(sm, resolved));
+            if resolved.is_some() {
+                return resolved;
+            }
+        }
+        None
+    }
+
+    /// Resolve `subpath` of `package` using its `exports`.
+    fn resolve_exports(
+        &self,
+        package: &Package,
+        exports: &Value,
+        subpath: &str,
+        kind: DependencyKind,
+    ) -> Option<PathBuf> {
+        let map = match exports {
+            Value::Object(map) if map.keys().any(|key| key.starts_with('.')) => map,
+            // Exports without subpaths are the targets of the main export.
+            _ if subpath == "." => return self.export_target(package, exports, None, kind),
+            _ => return None,
+        };
+        if let Some(target) = map.get(subpath) {
+            return self.export_target(package, target, None, kind);
+        }
+        // The pattern with the longest prefix before its `*` matching `subpath` applies.
+        let mut best: Option<(&str, &str, &Value)> = None;
+        for (key, target) in map {
+            if let Some((prefix, suffix)) = split_pattern(key) {
+                if subpath.len() >= prefix.len() + suffix.len()
+                    && subpath.starts_with(prefix)
+                    && subpath.ends_with(suffix)
+                    && best.is_none_or(|(best, _, _)| prefix.len() > best.len())
+                {
+                    best = Some((prefix, suffix, target));
+                }
+            }
+        }
+        let (prefix, suffix, target) = best?;
+        let star = &subpath[prefix.len()..subpath.len() - suffix.len()];
+        self.export_target(package, target, Some(star), kind)
+    }
+
+    /// The path of the export `target` of `package`, with `*` replaced by `star` for patterns.
+    fn export_target(
+        &self,
+        package: &Package,
+        target: &Value,
+        star: Option<&str>,
+        kind: DependencyKind,
+    ) -> Option<PathBuf> {
+        match target {
+            Value::String(target) if target.starts_with("./") => {
+                let target = match star {
+                    Some(star) => target.replace('*', star),
+                    None => target.clone(),
+                };
+                Some(join_path(&package.dir, target))
+            }
+            // Conditions apply in the order they're listed.
+            Value::Object(conditions) => conditions
+                .iter()
+                .filter(|(condition, _)| self.condition_applies(condition, kind))
+                .find_map(|(_, target)| self.export_target(package, target, star, kind)),
+            Value::Array(targets) => targets
+                .iter()
+                .find_map(|target| self.export_target(package, target, star, kind)),
+            _ => None,
+        }
+    }
+
+    /// Whether the targets of `condition` in `exports` apply to dependencies of `kind`.
+    fn condition_applies(&self, condition: &str, kind: DependencyKind) -> bool {
+        match condition {
+            "default" => true,
+            "import" => kind.is_import(),
+            "require" => !kind.is_import(),
+            "browser" => self.options.browser,
+            _ => self.options.conditions.iter().any(|c| c == condition),
+        }
+    }
+
+    /// Resolve `path` to a file, trying the extensions and then treating it as a directory.
+    fn resolve_path(&self, sm: &SourceManager, path: &Path) -> Option<PathBuf> {
+        self.resolve_file(sm, path)
+            .or_else(|| self.resolve_directory(sm, path))
+    }
+
+    /// Resolve `path` to a file, trying it with each of the extensions if it isn't one.
+    fn resolve_file(&self, sm: &SourceManager, path: &Path) -> Option<PathBuf> {
+        if is_file(sm, path) {
+            return Some(path.to_path_buf());
+        }
+        self.options.extensions.iter().find_map(|extension| {
+            let mut with_extension = path.as_os_str().to_owned();
+            with_extension.push(extension);
+            let with_extension = PathBuf::from(with_extension);
+            if is_file(sm, &with_extension) {
+                Some(with_extension)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Resolve the directory `dir` to its main file, or else its `index` file.
+    fn resolve_directory(&self, sm: &SourceManager, dir: &Path) -> Option<PathBuf> {
+        if let Some(package) = self.package(dir) {
+            let main = match &package.browser {
+                Some(Value::String(browser)) if self.options.browser => Some(browser),
+                _ => package.main.as_ref(),
+            };
+            if let Some(main) = main {
+                let main = join_path(dir, main);
+                let resolved = self
+                    .resolve_file(sm, &main)
+                    .or_else(|| self.resolve_file(sm, &main.join("index")));
+                if resolved.is_some() {
+                    return resolved;
+                }
+            }
+        }
+        self.resolve_file(sm, &dir.join("index"))
+    }
+
+    /// Apply the replacements in the `browser` field of the package containing `resolved`.
+    /// Files which are replaced with `false` resolve to nothing.
+    fn browser_replacement(&self, sm: &SourceManager, resolved: PathBuf) -> Option<PathBuf> {
+        if !self.options.browser {
+            return Some(resolved);
+        }
+        let package = match self.package_scope(resolved.parent()?) {
+            Some(package) => package,
+            None => return Some(resolved),
+        };
+        if let Some(Value::Object(map)) = &package.browser {
+            for (key, replacement) in map {
+                if is_relative(key)
+                    && self.resolve_file(sm, &join_path(&package.dir, key))
+                        == Some(resolved.clone())
+                {
+                    return match replacement {
+                        Value::String(replacement) => {
+                            self.resolve_path(sm, &join_path(&package.dir, replacement))
+                        }
+                        _ => None,
+                    };
+                }
+            }
+        }
+        Some(resolved)
+    }
+
+    /// The package of the closest directory to `dir` which has a `package.json`.
+    fn package_scope(&self, dir: &Path) -> Option<Rc<Package>> {
+        dir.ancestors().find_map(|ancestor| self.package(ancestor))
+    }
+
+    /// The package whose `package.json` is in `dir`, if any.
+    fn package(&self, dir: &Path) -> Option<Rc<Package>> {
+        if let Some(package) = self.packages.borrow().get(dir) {
+            return package.clone();
+        }
+        let package = fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str::<Value>(&json).ok())
+            .and_then(|json| match json {
+                Value::Object(mut fields) => Some(Rc::new(Package {
+                    dir: dir.to_path_buf(),
+                    main: match fields.remove("main") {
+                        Some(Value::String(main)) => Some(main),
+                        _ => None,
+                    },
+                    exports: fields.remove("exports"),
+                    browser: fields.remove("browser"),
+                })),
+                _ => None,
+            });
+        self.packages
+            .borrow_mut()
+            .insert(dir.to_path_buf(), package.clone());
+        package
+    }
+}
+
+impl DependencyResolver for NodeResolver {
+    /// Resolve a dependency on another file from source file `file` like Node.js,
+    /// loading the file it resolves to if it hasn't been loaded yet.
+    /// Return the ID of the resolved file if it could be resolved, else `None`.
+    fn resolve_dependency(
+        &self,
+        lock: &GCLock,
+        file: SourceId,
+        path: &str,
+        kind: DependencyKind,
+    ) -> Option<SourceId> {
+        let sm = lock.sm();
+        let resolved = self.resolve(sm, Path::new(sm.source_name(file)), path, kind)?;
+        load(sm, &resolved)
+    }
+}
+
+/// Options for [`HasteResolver`].
+#[derive(Debug, Clone, Default)]
+pub struct HasteResolverOptions {
+    /// Directories whose JS files are modules, besides the sources which have been loaded.
+    pub roots: Vec<PathBuf>,
+    /// Platform whose variants of modules are preferred, like `Foo.ios.js` for `ios`.
+    /// Variants for `native` are preferred next for all platforms but `web`.
+    pub platform: Option<String>,
+    /// File caching the names provided by the doc blocks of files, which are only scanned
+    /// again when they've been modified since.
+    pub cache_path: Option<PathBuf>,
+}
+
+/// Platforms which may be part of the file names of modules, like `Foo.ios.js`.
+const HASTE_PLATFORMS: &[&str] = &["ios", "android", "native", "web"];
+
+/// Resolves dependencies by the globally unique names of modules, like Haste.
+/// Each module is named by the `@providesModule` tag of its doc block, or else by its file
+/// name without extensions, so `Foo.js` and its `ios` variant `Foo.ios.js` are both `Foo`.
+/// Relative paths are resolved against the importing file.
+#[derive(Debug, Default)]
+pub struct HasteResolver {
+    platform: Option<String>,
+    /// The file providing each module for each platform, or `None` for all platforms.
+    modules: HashMap<String, HashMap<Option<String>, PathBuf>>,
+}
+
+impl HasteResolver {
+    /// Build the map of modules from the sources in `source_manager` and the JS files in the
+    /// roots, reporting an error for each module with the same name and platform as another.
+    pub fn new(source_manager: &SourceManager, options: HasteResolverOptions) -> Self {
+        let mut files: Vec<PathBuf> = (0..source_manager.num_sources())
+            .map(|i| PathBuf::from(source_manager.source_name(SourceId(i as u32))))
+            .collect();
+        for root in &options.roots {
+            collect_js_files(root, &mut files);
+        }
+
+        let mut cache = match &options.cache_path {
+            Some(path) => HasteCache::load(path),
+            None => Default::default(),
+        };
+        let mut modules: HashMap<String, HashMap<Option<String>, PathBuf>> = HashMap::new();
+        let mut seen = HashSet::new();
+        for file in files {
+            if !seen.insert(file.clone()) {
+                continue;
+            }
+            let (stem, platform) = match haste_name(&file) {
+                Some(name) => name,
+                None => continue,
+            };
+            let name = cache.provides_module(source_manager, &file).unwrap_or(stem);
+            let variants = modules.entry(name.clone()).or_default();
+            match variants.get(&platform) {
+                Some(previous) => {
+                    report_duplicate(source_manager, &name, platform.as_deref(), previous, &file)
+                }
+                None => {
+                    variants.insert(platform, file);
+                }
+            }
+        }
+        if let Some(path) = &options.cache_path {
+            cache.save(path);
+        }
+
+        HasteResolver {
+            platform: options.platform,
+            modules,
+        }
+    }
+}
+
+impl DependencyResolver for HasteResolver {
+    /// Resolve a dependency on another file from source file `file` by the name of the module,
+    /// preferring the variant for the platform, or else by its path if it's relative.
+    /// Return the ID of the resolved file if it could be resolved, else `None`.
+    fn resolve_dependency(
+        &self,
+        lock: &GCLock,
+        file: SourceId,
+        path: &str,
+        _kind: DependencyKind,
+    ) -> Option<SourceId> {
+        let sm = lock.sm();
+        if is_relative(path) {
+            let dir = Path::new(sm.source_name(file))
+                .parent()
+                .unwrap_or_else(|| Path::new(""));
+            let resolved = join_path(dir, path);
+            let mut with_extension = resolved.clone().into_os_string();
+            with_extension.push(".js");
+            return [resolved, PathBuf::from(with_extension)]
+                .iter()
+                .find(|path| is_file(sm, path))
+                .and_then(|path| load(sm, path));
+        }
+        let variants = self.modules.get(path)?;
+        let mut platforms = Vec::new();
+        if let Some(platform) = &self.platform {
+            platforms.push(Some(platform.clone()));
+            if platform != "web" && platform != "native" {
+                platforms.push(Some("native".to_string()));
+            }
+        }
+        platforms.push(None);
+        let resolved = platforms
+            .iter()
+            .find_map(|platform| variants.get(platform))?;
+        load(sm, resolved)
+    }
+}
+
+/// The names provided by the doc blocks of files, saved between runs.
+#[derive(Debug, Default)]
+struct HasteCache {
+    /// The modification time of each file when it was scanned, and the name it provides.
+    loaded: HashMap<PathBuf, (u64, Option<String>)>,
+    /// The entries for the files looked up since the cache was loaded.
+    current: HashMap<PathBuf, (u64, Option<String>)>,
+}
+
+impl HasteCache {
+    /// Load the cache from the JSON file at `path`, or start an empty one if it can't be read.
+    fn load(path: &Path) -> Self {
+        let mut loaded = HashMap::new();
+        let json = fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<Value>(&json).ok());
+        if let Some(Value::Object(files)) = json.as_ref().and_then(|json| json.get("files")) {
+            for (file, entry) in files {
+                if let Some(mtime) = entry.get("mtime").and_then(Value::as_u64) {
+                    let name = entry
+                        .get("providesModule")
+                        .and_then(Value::as_str)
+                        .map(str::to_string);
+                    loaded.insert(PathBuf::from(file), (mtime, name));
+                }
+            }
+        }
+        HasteCache {
+            loaded,
+            current: HashMap::new(),
+        }
+    }
+
+    /// Save the entries of the files which were looked up to `path`, if they changed.
+    /// The cache only saves time, so it isn't an error if it can't be written.
+    fn save(&self, path: &Path) {
+        if self.current == self.loaded {
+            return;
+        }
+        let mut files = serde_json::Map::new();
+        let mut entries: Vec<_> = self.current.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        for (file, (mtime, name)) in entries {
+            let mut entry = serde_json::Map::new();
+            entry.insert("mtime".to_string(), Value::from(*mtime));
+            entry.insert("providesModule".to_string(), Value::from(name.clone()));
+            files.insert(file.display().to_string(), Value::Object(entry));
+        }
+        let mut json = serde_json::Map::new();
+        json.insert("files".to_string(), Value::Object(files));
+        let _ = fs::write(path, Value::Object(json).to_string());
+    }
+
+    /// The name provided by the `@providesModule` tag of the doc block of `file`, which is only
+    /// scanned if it was modified since it was cached.
+    fn provides_module(&mut self, sm: &SourceManager, file: &Path) -> Option<String> {
+        let mtime = fs::metadata(file)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since_epoch| since_epoch.as_nanos() as u64);
+        let name = match (mtime, self.loaded.get(file)) {
+            (Some(mtime), Some((cached, name))) if *cached == mtime => name.clone(),
+            _ => match sm.lookup_name(file.display().to_string()) {
+                Some(id) => doc_block_provides_module(sm.source_buffer(id).as_bytes()),
+                None => fs::read(file)
+                    .ok()
+                    .and_then(|source| doc_block_provides_module(&source)),
+            },
+        };
+        if let Some(mtime) = mtime {
+            self.current
+                .insert(file.to_path_buf(), (mtime, name.clone()));
+        }
+        name
+    }
+}
+
+/// The name provided by the `@providesModule` tag in the doc block of `source`, if any.
+fn doc_block_provides_module(source: &[u8]) -> Option<String> {
+    doc_block_comments(source).into_iter().find_map(|comment| {
+        let comment = std::str::from_utf8(comment).ok()?;
+        let (_, rest) = comment.split_once("@providesModule")?;
+        rest.split_whitespace()
+            .next()
+            .map(|name| name.trim_end_matches("*/"))
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+    })
+}
+
+/// The comments before the first token of `source` which isn't part of a directive, which
+/// make up its doc block. Only the start of the file is scanned, so it doesn't need to parse.
+fn doc_block_comments(source: &[u8]) -> Vec<&[u8]> {
+    let mut comments = Vec::new();
+    let mut rest = source;
+    if rest.starts_with(b"#!") {
+        rest = skip_line(rest);
+    }
+    loop {
+        while let Some((c, tail)) = rest.split_first() {
+            if !c.is_ascii_whitespace() {
+                break;
+            }
+            rest = tail;
+        }
+        if rest.starts_with(b"//") {
+            let tail = skip_line(rest);
+            comments.push(&rest[..rest.len() - tail.len()]);
+            rest = tail;
+        } else if rest.starts_with(b"/*") {
+            let end = match rest[2..].windows(2).position(|w| w == b"*/") {
+                Some(end) => end + 4,
+                None => break,
+            };
+            comments.push(&rest[..end]);
+            rest = &rest[end..];
+        } else if let Some(&quote @ (b'\'' | b'"')) = rest.first() {
+            // A directive like `'use strict';`, which may be followed by more of the doc block.
+            let mut end = 1;
+            while end < rest.len() && rest[end] != quote && rest[end] != b'\n' {
+                end += if rest[end] == b'\\' { 2 } else { 1 };
+            }
+            if rest.get(end) != Some(&quote) {
+                break;
+            }
+            rest = &rest[end + 1..];
+            if let Some(tail) = rest.strip_prefix(b";") {
+                rest = tail;
+            }
+        } else {
+            break;
+        }
+    }
+    comments
+}
+
+/// The rest of `source` after the end of its first line.
+fn skip_line(source: &[u8]) -> &[u8] {
+    match source.iter().position(|&c| c == b'\n') {
+        Some(end) => &source[end..],
+        None => &[],
+    }
+}
+
+/// The name of the module in `file` without the extensions, and the platform it's for if its
+/// name has one, like `Foo` and `ios` for `Foo.ios.js`.
+fn haste_name(file: &Path) -> Option<(String, Option<String>)> {
+    let file_name = file.file_name()?.to_str()?;
+    let mut parts: Vec<&str> = file_name.split('.').collect();
+    if parts.len() > 1 {
+        parts.pop();
+    }
+    let platform = match parts.last() {
+        Some(last) if parts.len() > 1 && HASTE_PLATFORMS.contains(last) => parts.pop(),
+        _ => None,
+    };
+    Some((parts.join("."), platform.map(str::to_string)))
+}
+
+/// Report that `file` provides the module `name` for `platform` like `previous` does.
+fn report_duplicate(
+    sm: &SourceManager,
+    name: &str,
+    platform: Option<&str>,
+    previous: &Path,
+    file: &Path,
+) {
+    let start = SourceLoc { line: 1, col: 1 };
+    if let (Some(previous), Some(file)) = (load(sm, previous), load(sm, file)) {
+        let message = match platform {
+            Some(platform) => format!("Duplicate module {} for platform {}", name, platform),
+            None => format!("Duplicate module {}", name),
+        };
+        sm.error(SourceRange::from_loc(file, start), message);
+        sm.note(
+            SourceRange::from_loc(previous, start),
+            "previous module with the same name",
+        );
+    }
+}
+
+/// Append the JS files in `dir` and its subdirectories to `files`, skipping `node_modules`
+/// and hidden directories.
+fn collect_js_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut entries: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .collect(),
+        Err(_) => return,
+    };
+    entries.sort();
+    for path in entries {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if path.is_dir() {
+            if name != "node_modules" && !name.starts_with('.') {
+                collect_js_files(&path, files);
+            }
+        } else if path.extension().is_some_and(|extension| extension == "js") {
+            files.push(path);
+        }
+    }
+}
+
+/// Options for [`MetroResolver`].
+#[derive(Debug, Clone)]
+pub struct MetroResolverOptions {
+    /// Platform whose variants of files are preferred, like `foo.ios.js` for `ios`.
+    /// Variants for `native` are preferred next for all platforms but `web`.
+    pub platform: Option<String>,
+    /// Extensions without the leading `.` tried in order on paths which don't name a file.
+    pub source_exts: Vec<String>,
+    /// Fields of `package.json` naming the main file of a package, tried in order.
+    pub main_fields: Vec<String>,
+    /// Specifiers which are replaced before resolving them, tried in order. A `*` in the
+    /// specifier matches anything and is substituted for the `*` in its replacement.
+    pub aliases: Vec<(String, String)>,
+    /// Directory which relative replacements of aliases are relative to.
+    pub root: PathBuf,
+}
+
+impl Default for MetroResolverOptions {
+    fn default() -> Self {
+        MetroResolverOptions {
+            platform: None,
+            source_exts: vec!["js".to_string(), "jsx".to_string()],
+            main_fields: vec![
+                "react-native".to_string(),
+                "browser".to_string(),
+                "main".to_string(),
+            ],
+            aliases: vec![],
+            root: PathBuf::new(),
+        }
+    }
+}
+
+/// Error reading the config file of a resolver.
+#[derive(Debug, Error)]
+pub enum ResolverConfigError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+impl MetroResolverOptions {
+    /// Read the options from the JSON config file at `path`, like
+    /// `{"platform": "ios", "sourceExts": ["js"], "alias": {"@app/*": "./src/*"}}`.
+    /// Fields which are missing keep their defaults, and the root is the directory
+    /// containing the config file.
+    pub fn from_config(path: &Path) -> Result<Self, ResolverConfigError> {
+        let json: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let fields = match json {
+            Value::Object(fields) => fields,
+            _ => {
+                return Err(ResolverConfigError::Invalid(
+                    "config must be an object".to_string(),
+                ))
+            }
+        };
+        let mut options = MetroResolverOptions {
+            root: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            ..Default::default()
+        };
+        for (field, value) in fields {
+            match (field.as_str(), value) {
+                ("platform", Value::String(platform)) => options.platform = Some(platform),
+                ("platform", Value::Null) => options.platform = None,
+                ("sourceExts", value) => options.source_exts = config_strings(&field, value)?,
+                ("mainFields", value) => options.main_fields = config_strings(&field, value)?,
+                ("alias", Value::Object(aliases)) => {
+                    for (pattern, replacement) in aliases {
+                        match replacement {
+                            Value::String(replacement) => {
+                                options.aliases.push((pattern, replacement))
+                            }
+                            _ => {
+                                return Err(ResolverConfigError::Invalid(format!(
+                                    "alias {} must be a string",
+                                    pattern
+                                )))
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    return Err(ResolverConfigError::Invalid(format!(
+                        "invalid config field {}",
+                        field
+                    )))
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// The strings in the array `value` of the config `field`.
+fn config_strings(field: &str, value: Value) -> Result<Vec<String>, ResolverConfigError> {
+    match value {
+        Value::Array(values) => values
+            .into_iter()
+            .map(|value| match value {
+                Value::String(value) => Ok(value),
+                _ => Err(ResolverConfigError::Invalid(format!(
+                    "{} must only contain strings",
+                    field
+                ))),
+            })
+            .collect(),
+        _ => Err(ResolverConfigError::Invalid(format!(
+            "{} must be an array",
+            field
+        ))),
+    }
+}
+
+/// Resolves dependencies like Metro, the bundler of React Native.
+/// Paths are tried with each of the source extensions, preferring the variant for the
+/// platform, like `foo.ios.js`, then `foo.native.js`, then `foo.js`.
+/// Bare specifiers are replaced by the first matching alias, or else name packages in
+/// `node_modules` directories whose main file is given by the main fields.
+/// Files which haven't been loaded yet are added to the `SourceManager` when they're found.
+#[derive(Debug, Default)]
+pub struct MetroResolver {
+    options: MetroResolverOptions,
+}
+
+impl MetroResolver {
+    pub fn new(options: MetroResolverOptions) -> Self {
+        MetroResolver { options }
+    }
+
+    /// Resolve `specifier` imported from the file at `importer` to the path of a file,
+    /// appending each path which is tried to `tried`.
+    fn resolve(
+        &self,
+        sm: &SourceManager,
+        importer: &Path,
+        specifier: &str,
+        tried: &mut Vec<PathBuf>,
+    ) -> Option<PathBuf> {
+        let dir = importer.parent().unwrap_or_else(|| Path::new(""));
+        if is_relative(specifier) || Path::new(specifier).is_absolute() {
+            return self.resolve_path(sm, &join_path(dir, specifier), tried);
+        }
+        let aliased = self.alias(specifier);
+        let specifier = match &aliased {
+            Some(replacement) if is_relative(replacement) => {
+                return self.resolve_path(sm, &join_path(&self.options.root, replacement), tried);
+            }
+            Some(replacement) if Path::new(replacement).is_absolute() => {
+                return self.resolve_path(sm, Path::new(replacement), tried);
+            }
+            Some(replacement) => replacement.as_str(),
+            None => specifier,
+        };
+        let (name, subpath) = split_package(specifier)?;
+        for ancestor in dir.ancestors() {
+            if ancestor
+                .file_name()
+                .is_some_and(|file_name| file_name == "node_modules")
+            {
+                continue;
+            }
+            let package_dir = ancestor.join("node_modules").join(name);
+            if !package_dir.is_dir() {
+                continue;
+            }
+            let resolved = self.resolve_path(sm, &join_path(&package_dir, &subpath), tried);
+            if resolved.is_some() {
+                return resolved;
+            }
+        }
+        None
+    }
+
+    /// The replacement of `specifier` by the first alias matching it, if any.
+    fn alias(&self, specifier: &str) -> Option<String> {
+        self.options
+            .aliases
+            .iter()
+            .find_map(|(pattern, replacement)| match split_pattern(pattern) {
+                Some((prefix, suffix)) => {
+                    if specifier.len() >= prefix.len() + suffix.len()
+                        && specifier.starts_with(prefix)
+                        && specifier.ends_with(suffix)
+                    {
+                        let star = &specifier[prefix.len()..specifier.len() - suffix.len()];
+                        Some(replacement.replace('*', star))
+                    } else {
+                        None
+                    }
+                }
+                None if pattern == specifier => Some(replacement.clone()),
+                None => None,
+            })
+    }
+
+    /// Resolve `path` to a file, trying its variants and then treating it as a directory.
+    fn resolve_path(
+        &self,
+        sm: &SourceManager,
+        path: &Path,
+        tried: &mut Vec<PathBuf>,
+    ) -> Option<PathBuf> {
+        self.resolve_file(sm, path, tried)
+            .or_else(|| self.resolve_directory(sm, path, tried))
+    }
+
+    /// Resolve `path` to a file, trying it with the variants for the platform and each of the
+    /// source extensions if it isn't one.
+    fn resolve_file(
+        &self,
+        sm: &SourceManager,
+        path: &Path,
+        tried: &mut Vec<PathBuf>,
+    ) -> Option<PathBuf> {
+        tried.push(path.to_path_buf());
+        if is_file(sm, path) {
+            return Some(path.to_path_buf());
+        }
+        let mut variants = vec![];
+        if let Some(platform) = &self.options.platform {
+            variants.push(format!(".{}", platform));
+            if platform != "web" && platform != "native" {
+                variants.push(".native".to_string());
+            }
+        }
+        variants.push(String::new());
+        for ext in &self.options.source_exts {
+            for variant in &variants {
+                let mut candidate = path.as_os_str().to_owned();
+                candidate.push(format!("{}.{}", variant, ext));
+                let candidate = PathBuf::from(candidate);
+                tried.push(candidate.clone());
+                if is_file(sm, &candidate) {
+                    return Some(candidate);
+                }
+            }
+        }
+        None
+    }
+
+    /// Resolve the directory `dir` to the file named by the first main field of its
+    /// `package.json` which it has, or else its `index` file.
+    fn resolve_directory(
+        &self,
+        sm: &SourceManager,
+        dir: &Path,
+        tried: &mut Vec<PathBuf>,
+    ) -> Option<PathBuf> {
+        if !dir.is_dir() {
+            return None;
+        }
+        let package = fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|json| serde_json::from_str::<Value>(&json).ok());
+        let main = package.as_ref().and_then(|package| {
+            self.options
+                .main_fields
+                .iter()
+                .find_map(|field| package.get(field)?.as_str())
+        });
+        if let Some(main) = main {
+            let main = join_path(dir, main);
+            let resolved = self
+                .resolve_file(sm, &main, tried)
+                .or_else(|| self.resolve_file(sm, &main.join("index"), tried));
+            if resolved.is_some() {
+                return resolved;
+            }
+        }
+        self.resolve_file(sm, &dir.join("index"), tried)
+    }
+}
+
+impl DependencyResolver for MetroResolver {
+    /// Resolve a dependency on another file from source file `file` like Metro,
+    /// loading the file it resolves to if it hasn't been loaded yet.
+    /// Return the ID of the resolved file if it could be resolved, else `None`.
+    fn resolve_dependency(
+        &self,
+        lock: &GCLock,
+        file: SourceId,
+        path: &str,
+        _kind: DependencyKind,
+    ) -> Option<SourceId> {
+        let sm = lock.sm();
+        let resolved = self.resolve(sm, Path::new(sm.source_name(file)), path, &mut vec![])?;
+        load(sm, &resolved)
+    }
+
+    fn tried_candidates(
+        &self,
+        lock: &GCLock,
+        file: SourceId,
+        path: &str,
+        _kind: DependencyKind,
+    ) -> Vec<String> {
+        let sm = lock.sm();
+        let mut tried = vec![];
+        self.resolve(sm, Path::new(sm.source_name(file)), path, &mut tried);
+        tried
+            .into_iter()
+            .map(|path| path.display().to_string())
+            .collect()
+    }
+}
+
+/// The ID of the file at `path`, loading it if it hasn't been loaded yet.
+fn load(sm: &SourceManager, path: &Path) -> Option<SourceId> {
+    let name = path.display().to_string();
+    sm.lookup_name(&name).or_else(|| {
+        let buf = NullTerminatedBuf::from_file(&mut File::open(path).ok()?).ok()?;
+        Some(sm.add_source_on_demand(name, buf))
+    })
+}
+
+/// Whether `specifier` is a path relative to the importing file.
+fn is_relative(specifier: &str) -> bool {
+    specifier == "."
+        || specifier == ".."
+        || specifier.starts_with("./")
+        || specifier.starts_with("../")
+}
+
+/// Whether `path` is a file which has been loaded or exists.
+fn is_file(sm: &SourceManager, path: &Path) -> bool {
+    sm.lookup_name(path.display().to_string()).is_some() || path.is_file()
+}
+
+/// Split the bare `specifier` into the name of the package, which may be scoped like
+/// `@scope/name`, and the subpath within it, like `./sub/path` or `.` for the package itself.
+fn split_package(specifier: &str) -> Option<(&str, String)> {
+    let name_len = if specifier.starts_with('@') {
+        let scope_len = specifier.find('/')?;
+        specifier[scope_len + 1..]
+            .find('/')
+            .map_or(specifier.len(), |i| scope_len + 1 + i)
+    } else {
+        specifier.find('/').unwrap_or(specifier.len())
+    };
+    let (name, rest) = specifier.split_at(name_len);
+    if name.is_empty() {
+        None
+    } else {
+        Some((name, format!(".{}", rest)))
+    }
+}
+
+/// Split the `exports` subpath pattern `key` at its `*`.
+fn split_pattern(key: &str) -> Option<(&str, &str)> {
+    let star = key.find('*')?;
+    Some((&key[..star], &key[star + 1..]))
+}
+
+/// Join `path` onto `dir` without `.` and `..`, so each file found has a single name.
+fn join_path<P: AsRef<Path>>(dir: &Path, path: P) -> PathBuf {
+    let joined = remove_dots(dir.join(path));
+    if dir.as_os_str().is_empty() {
+        // Files in the working directory are named without a leading `./`.
+        joined
+            .strip_prefix(".")
+            .map(Path::to_path_buf)
+            .unwrap_or(joined)
+    } else {
+        joined
+    }
+}
+
 /// Remove all non-leading `.` and `..` from the path, treating `..` as a parent directory.
 fn remove_dots(buf: PathBuf) -> PathBuf {
     let mut result = PathBuf::new();
@@ -14,6 +14,8 @@ pub(super) struct Keywords {
     pub ident_new: Atom,
     pub ident_target: Atom,
     pub ident_require: Atom,
+    pub ident_resolve: Atom,
+    pub ident_async: Atom,
 }
 
 impl Keywords {
@@ -25,6 +27,8 @@ impl Keywords {
             ident_new: tab.atom("new"),
             ident_target: tab.atom("target"),
             ident_require: tab.atom("require"),
+            ident_resolve: tab.atom("resolve"),
+            ident_async: tab.atom("async"),
         }
     }
 }
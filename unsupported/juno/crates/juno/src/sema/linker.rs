@@ -0,0 +1,501 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Linking of the ES modules of a program, after each was resolved by [`resolve_module`].
+//!
+//! [`link_modules`] builds the table of exports of each module, including the names it
+//! re-exports with `export ... from` and `export *`, and resolves every import binding to the
+//! declaration of the module which originally exports it. Imports of names which a linked
+//! module doesn't export, and names which reach a module through several `export *` from
+//! different declarations, are reported as errors.
+//!
+//! Only modules which contain `import` or `export` declarations are linked. The exports of
+//! other modules, like CommonJS modules, and of specifiers which couldn't be resolved aren't
+//! known, so imports from them resolve to [`Binding::External`].
+//!
+//! [`resolve_module`]: crate::sema::resolve_module
+
+use crate::ast::{
+    node_cast, ClassDeclaration, ExportAllDeclaration, ExportDefaultDeclaration, ExportKind,
+    ExportNamedDeclaration, ExportNamespaceSpecifier, ExportSpecifier, FunctionDeclaration, GCLock,
+    ImportDeclaration, ImportDefaultSpecifier, ImportKind, ImportNamespaceSpecifier,
+    ImportSpecifier, Module, Node, NodeRc, VariableDeclaration, VariableDeclarator,
+};
+use crate::sema::resolver::extract_assigned_idents;
+use crate::sema::{DeclId, LexicalScopeId, Resolution, SemContext};
+use juno_support::atom_table::Atom;
+use juno_support::source_manager::{SourceId, SourceRange};
+use smallvec::SmallVec;
+use std::collections::{HashMap, HashSet};
+
+/// A module which has been resolved by [`resolve_module`](crate::sema::resolve_module).
+pub struct LinkModule<'a, 'gc> {
+    pub id: SourceId,
+    pub ast: &'gc Node<'gc>,
+    pub sem: &'a SemContext,
+}
+
+/// Which export of a module an import or re-export refers to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Imported {
+    Named(Atom),
+    /// The namespace object, imported with `import * as` or `export * as`.
+    Namespace,
+}
+
+/// The binding which an import or export refers to, after following re-exports.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Binding {
+    /// A top-level declaration of a linked module.
+    Decl(SourceId, DeclId),
+    /// The value of an `export default` of a linked module which doesn't declare a name.
+    Default(SourceId),
+    /// The namespace object of a module.
+    Namespace(SourceId),
+    /// An export of a module whose exports aren't known, or of a specifier which couldn't be
+    /// resolved.
+    External(Option<SourceId>, Imported),
+}
+
+impl Binding {
+    /// The module which declares the binding, if it is known.
+    pub fn module(self) -> Option<SourceId> {
+        match self {
+            Binding::Decl(id, _) | Binding::Default(id) | Binding::Namespace(id) => Some(id),
+            Binding::External(id, _) => id,
+        }
+    }
+}
+
+/// Why an export couldn't be resolved.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Unresolved {
+    /// The module doesn't export the name.
+    Missing,
+    /// The name is re-exported in a cycle which never reaches a declaration.
+    Circular,
+    /// The name reaches the module through `export *` from two different bindings.
+    Ambiguous(Binding, Binding),
+}
+
+/// What an export of a module refers to, before following re-exports.
+#[derive(Debug, Clone)]
+enum Export {
+    /// A top-level declaration, which may be an import.
+    Decl(DeclId),
+    /// The value of an `export default` which doesn't declare a name.
+    Default,
+    /// An export of another module.
+    Reexport(Option<SourceId>, Imported),
+}
+
+/// An import or re-export of a name, which must be exported by the module it refers to.
+struct Request {
+    source: Option<SourceId>,
+    specifier: String,
+    name: Atom,
+    /// The range of the specifier of the module, where errors are reported.
+    range: SourceRange,
+}
+
+/// The import and export declarations of a module.
+#[derive(Default)]
+struct ModuleInfo {
+    /// The top-level scope of the module.
+    scope: Option<LexicalScopeId>,
+    /// Whether the module contains `import` or `export` declarations, so its exports are known.
+    linked: bool,
+    /// The exported names, in the order they are exported.
+    exports: Vec<(Atom, Export)>,
+    /// The modules whose exports are re-exported with `export *`.
+    star_exports: Vec<Option<SourceId>>,
+    /// The import declarations and the exports they import.
+    imports: Vec<(DeclId, Option<SourceId>, Imported)>,
+    requests: Vec<Request>,
+}
+
+impl ModuleInfo {
+    fn new(gc: &GCLock, module: &LinkModule) -> ModuleInfo {
+        let mut info = ModuleInfo {
+            scope: module.sem.node_scope(NodeRc::from_node(gc, module.ast)),
+            ..Default::default()
+        };
+        if let Node::Module(Module { body, .. }) = module.ast {
+            for stmt in body.iter() {
+                info.collect_statement(gc, module.sem, stmt);
+            }
+        }
+        info
+    }
+
+    /// Record the imports and exports declared by the top-level statement `stmt`.
+    fn collect_statement<'gc>(&mut self, gc: &'gc GCLock, sem: &SemContext, stmt: &'gc Node<'gc>) {
+        match stmt {
+            Node::ImportDeclaration(ImportDeclaration {
+                specifiers,
+                source,
+                import_kind: ImportKind::Value,
+                ..
+            }) => {
+                self.linked = true;
+                let target = dependency_target(gc, sem, stmt);
+                for specifier in specifiers.iter() {
+                    let (local, imported) = match specifier {
+                        Node::ImportSpecifier(ImportSpecifier {
+                            import_kind: ImportKind::Value,
+                            imported,
+                            local,
+                            ..
+                        }) => (*local, Imported::Named(ident_name(imported))),
+                        Node::ImportDefaultSpecifier(ImportDefaultSpecifier { local, .. }) => {
+                            (*local, Imported::Named(gc.atom("default")))
+                        }
+                        Node::ImportNamespaceSpecifier(ImportNamespaceSpecifier {
+                            local, ..
+                        }) => (*local, Imported::Namespace),
+                        _ => continue,
+                    };
+                    self.add_request(gc, target, source, imported);
+                    if let Some(decl) = self.top_level_decl(gc, sem, local) {
+                        self.imports.push((decl, target, imported));
+                    }
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                specifiers,
+                source: Some(source),
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                self.linked = true;
+                let target = dependency_target(gc, sem, stmt);
+                for specifier in specifiers.iter() {
+                    let (exported, imported) = match specifier {
+                        Node::ExportSpecifier(ExportSpecifier {
+                            exported, local, ..
+                        }) => (ident_name(exported), Imported::Named(ident_name(local))),
+                        Node::ExportNamespaceSpecifier(ExportNamespaceSpecifier {
+                            exported,
+                            ..
+                        }) => (ident_name(exported), Imported::Namespace),
+                        _ => continue,
+                    };
+                    self.add_request(gc, target, source, imported);
+                    self.exports
+                        .push((exported, Export::Reexport(target, imported)));
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                declaration: Some(declaration),
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                self.linked = true;
+                let mut ids = SmallVec::<[&Node; 4]>::new();
+                match declaration {
+                    Node::VariableDeclaration(VariableDeclaration { declarations, .. }) => {
+                        for declarator in declarations.iter() {
+                            let VariableDeclarator { id, .. } =
+                                node_cast!(Node::VariableDeclarator, declarator);
+                            extract_assigned_idents(id, &mut ids);
+                        }
+                    }
+                    Node::FunctionDeclaration(FunctionDeclaration { id: Some(id), .. })
+                    | Node::ClassDeclaration(ClassDeclaration { id: Some(id), .. }) => {
+                        ids.push(*id)
+                    }
+                    _ => {}
+                }
+                for id in ids {
+                    if let Some(decl) = self.top_level_decl(gc, sem, id) {
+                        self.exports.push((ident_name(id), Export::Decl(decl)));
+                    }
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                specifiers,
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                self.linked = true;
+                for specifier in specifiers.iter() {
+                    if let Node::ExportSpecifier(ExportSpecifier {
+                        exported, local, ..
+                    }) = specifier
+                    {
+                        if let Some(decl) = self.top_level_decl(gc, sem, local) {
+                            self.exports
+                                .push((ident_name(exported), Export::Decl(decl)));
+                        }
+                    }
+                }
+            }
+            Node::ExportDefaultDeclaration(ExportDefaultDeclaration { declaration, .. }) => {
+                self.linked = true;
+                let decl = match declaration {
+                    Node::FunctionDeclaration(FunctionDeclaration { id: Some(id), .. })
+                    | Node::ClassDeclaration(ClassDeclaration { id: Some(id), .. }) => {
+                        self.top_level_decl(gc, sem, id)
+                    }
+                    _ => None,
+                };
+                self.exports.push((
+                    gc.atom("default"),
+                    decl.map_or(Export::Default, Export::Decl),
+                ));
+            }
+            Node::ExportAllDeclaration(ExportAllDeclaration {
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                self.linked = true;
+                self.star_exports.push(dependency_target(gc, sem, stmt));
+            }
+            _ => {}
+        }
+    }
+
+    /// Record that the module imports `imported` from the module `source` resolved to.
+    fn add_request(
+        &mut self,
+        gc: &GCLock,
+        target: Option<SourceId>,
+        source: &Node,
+        imported: Imported,
+    ) {
+        if let Imported::Named(name) = imported {
+            let specifier =
+                String::from_utf16_lossy(gc.str_u16(node_cast!(Node::StringLiteral, source).value));
+            self.requests.push(Request {
+                source: target,
+                specifier,
+                name,
+                range: *source.range(),
+            });
+        }
+    }
+
+    /// The declaration of the identifier `id` if it is declared in the top-level scope.
+    fn top_level_decl(&self, gc: &GCLock, sem: &SemContext, id: &Node) -> Option<DeclId> {
+        match sem.ident_decl(&NodeRc::from_node(gc, id)) {
+            Some(Resolution::Decl(decl)) if Some(sem.decl(decl).scope) == self.scope => Some(decl),
+            _ => None,
+        }
+    }
+}
+
+/// The exports of the linked modules and the bindings their imports refer to.
+#[derive(Debug, Default)]
+pub struct LinkedModules {
+    /// The exported names of each linked module and their bindings, in the order they are
+    /// exported, followed by the names re-exported with `export *`.
+    exports: HashMap<SourceId, Vec<(Atom, Binding)>>,
+    /// The binding each import declaration of each module refers to.
+    imports: HashMap<(SourceId, DeclId), Binding>,
+}
+
+impl LinkedModules {
+    /// The exports of the module `id`, or `None` if it isn't a linked module. Names which are
+    /// ambiguous or can't be resolved aren't included.
+    pub fn exports(&self, id: SourceId) -> Option<&[(Atom, Binding)]> {
+        self.exports.get(&id).map(|exports| exports.as_slice())
+    }
+
+    /// The binding which the export `name` of the module `id` refers to, or `None` if the
+    /// module is linked and doesn't export it.
+    pub fn resolve_export(&self, id: SourceId, name: Atom) -> Option<Binding> {
+        match self.exports.get(&id) {
+            Some(exports) => exports
+                .iter()
+                .find(|(exported, _)| *exported == name)
+                .map(|(_, binding)| *binding),
+            None => Some(Binding::External(Some(id), Imported::Named(name))),
+        }
+    }
+
+    /// The binding which the import declaration `decl` of the module `id` refers to, or
+    /// `None` if it isn't an import declaration or it couldn't be resolved.
+    pub fn import_binding(&self, id: SourceId, decl: DeclId) -> Option<Binding> {
+        self.imports.get(&(id, decl)).copied()
+    }
+}
+
+/// Build the export tables of `modules` and resolve their imports, reporting imports of
+/// names which aren't exported as errors.
+pub fn link_modules(gc: &GCLock, modules: &[LinkModule]) -> LinkedModules {
+    let linker = Linker {
+        infos: modules
+            .iter()
+            .map(|module| (module.id, ModuleInfo::new(gc, module)))
+            .filter(|(_, info)| info.linked)
+            .collect(),
+        default: gc.atom("default"),
+    };
+
+    let mut linked = LinkedModules::default();
+    for module in modules {
+        let info = match linker.infos.get(&module.id) {
+            Some(info) => info,
+            None => continue,
+        };
+        for request in &info.requests {
+            let result =
+                linker.resolve_import(request.source, Imported::Named(request.name), &mut vec![]);
+            let msg = match result {
+                Ok(_) => continue,
+                Err(Unresolved::Missing) => {
+                    format!(
+                        "'{}' is not exported by {}",
+                        gc.str(request.name),
+                        request.specifier
+                    )
+                }
+                Err(Unresolved::Circular) => format!(
+                    "'{}' of {} is re-exported in a cycle",
+                    gc.str(request.name),
+                    request.specifier
+                ),
+                Err(Unresolved::Ambiguous(first, second)) => format!(
+                    "'{}' is ambiguous in {}, which exports it from {} and {} with 'export *'",
+                    gc.str(request.name),
+                    request.specifier,
+                    module_name(gc, first),
+                    module_name(gc, second)
+                ),
+            };
+            gc.sm().error(request.range, msg);
+        }
+        for &(decl, source, imported) in &info.imports {
+            if let Ok(binding) = linker.resolve_import(source, imported, &mut vec![]) {
+                linked.imports.insert((module.id, decl), binding);
+            }
+        }
+    }
+    for &id in linker.infos.keys() {
+        let exports = linker
+            .exported_names(id, &mut HashSet::new())
+            .into_iter()
+            .filter_map(|name| Some((name, linker.resolve_export(id, name, &mut vec![]).ok()?)))
+            .collect();
+        linked.exports.insert(id, exports);
+    }
+    linked
+}
+
+/// The import and export declarations of the linked modules.
+struct Linker {
+    infos: HashMap<SourceId, ModuleInfo>,
+    default: Atom,
+}
+
+impl Linker {
+    /// The names exported by the module `id`, including the names of `export *`.
+    fn exported_names(&self, id: SourceId, visited: &mut HashSet<SourceId>) -> Vec<Atom> {
+        let info = match self.infos.get(&id) {
+            Some(info) if visited.insert(id) => info,
+            _ => return vec![],
+        };
+        let mut names: Vec<Atom> = info.exports.iter().map(|(name, _)| *name).collect();
+        for star in info.star_exports.iter().flatten() {
+            for name in self.exported_names(*star, visited) {
+                // `export *` doesn't re-export the default export.
+                if name != self.default && !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    /// Find the binding which the export `name` of the module `id` refers to. `visited` are
+    /// the exports which are already being resolved, to detect cycles.
+    fn resolve_export(
+        &self,
+        id: SourceId,
+        name: Atom,
+        visited: &mut Vec<(SourceId, Atom)>,
+    ) -> Result<Binding, Unresolved> {
+        let info = match self.infos.get(&id) {
+            Some(info) => info,
+            None => return Ok(Binding::External(Some(id), Imported::Named(name))),
+        };
+        if visited.contains(&(id, name)) {
+            return Err(Unresolved::Circular);
+        }
+        visited.push((id, name));
+        match info.exports.iter().find(|(exported, _)| *exported == name) {
+            Some((_, Export::Decl(decl))) => {
+                return match info.imports.iter().find(|(import, ..)| import == decl) {
+                    Some(&(_, source, imported)) => self.resolve_import(source, imported, visited),
+                    None => Ok(Binding::Decl(id, *decl)),
+                }
+            }
+            Some((_, Export::Default)) => return Ok(Binding::Default(id)),
+            Some(&(_, Export::Reexport(source, imported))) => {
+                return self.resolve_import(source, imported, visited)
+            }
+            None => {}
+        }
+        // `export *` doesn't re-export the default export.
+        if name == self.default {
+            return Err(Unresolved::Missing);
+        }
+        let mut found: Option<Binding> = None;
+        let mut external = None;
+        for &star in &info.star_exports {
+            match self.resolve_import(star, Imported::Named(name), visited) {
+                // The exports of the module aren't known, so it may provide the name.
+                Ok(binding @ Binding::External(..)) => {
+                    external.get_or_insert(binding);
+                }
+                Ok(binding) => match found {
+                    Some(first) if first != binding => {
+                        return Err(Unresolved::Ambiguous(first, binding))
+                    }
+                    _ => found = Some(binding),
+                },
+                Err(Unresolved::Ambiguous(first, second)) => {
+                    return Err(Unresolved::Ambiguous(first, second))
+                }
+                Err(Unresolved::Missing | Unresolved::Circular) => {}
+            }
+        }
+        found.or(external).ok_or(Unresolved::Missing)
+    }
+
+    /// Find the binding which `imported` from the module `source` resolved to refers to.
+    fn resolve_import(
+        &self,
+        source: Option<SourceId>,
+        imported: Imported,
+        visited: &mut Vec<(SourceId, Atom)>,
+    ) -> Result<Binding, Unresolved> {
+        match (source, imported) {
+            (Some(id), Imported::Named(name)) => self.resolve_export(id, name, visited),
+            (Some(id), Imported::Namespace) => Ok(Binding::Namespace(id)),
+            (None, _) => Ok(Binding::External(None, imported)),
+        }
+    }
+}
+
+/// The module which the import or export declaration `node` was resolved to.
+fn dependency_target(gc: &GCLock, sem: &SemContext, node: &Node) -> Option<SourceId> {
+    sem.dependency(&NodeRc::from_node(gc, node))
+        .and_then(|dependency| dependency.target)
+}
+
+/// The name of the module declaring `binding`, to show in errors.
+fn module_name(gc: &GCLock, binding: Binding) -> String {
+    match binding.module() {
+        Some(id) => gc.sm().source_name(id).to_string(),
+        None => "an unresolved module".to_string(),
+    }
+}
+
+fn ident_name(node: &Node) -> Atom {
+    node_cast!(Node::Identifier, node).name
+}
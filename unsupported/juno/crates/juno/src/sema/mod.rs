@@ -10,13 +10,16 @@
 //! This module performs semantic analysis of the AST. That includes resolving
 //! identifiers to corresponding declarations, resolving `break`/`continue`
 //! targets, and performing validation (things like assigning to a non-lvalue,
-//! names disallowed in strict mode, etc).
+//! names disallowed in strict mode, etc). Once all modules of a program are
+//! resolved, [`link_modules`] links their imports to the exports of other modules.
 
 mod decl_collector;
 mod keywords;
 mod known_globals;
+mod linker;
 mod resolver;
 mod sem_context;
 
+pub use linker::{link_modules, Binding, Imported, LinkModule, LinkedModules};
 pub use resolver::{resolve_module, resolve_program};
 pub use sem_context::*;
@@ -7,9 +7,9 @@
 
 use super::sem_context::*;
 use crate::ast::{
-    self, builder, node_cast, node_isa, template, GCLock, Identifier, Node, NodeField, NodeList,
-    NodePtr, NodeRc, NodeVariant, Path, TemplateMetadata, UnaryExpressionOperator,
-    VariableDeclarationKind, Visitor,
+    self, builder, node_cast, node_isa, template, AssignmentExpressionOperator, GCLock, Identifier,
+    Node, NodeField, NodeList, NodePtr, NodeRc, NodeVariant, Path, TemplateMetadata,
+    UnaryExpressionOperator, VariableDeclarationKind, Visitor,
 };
 use crate::resolve_dependency::{DependencyKind, DependencyResolver};
 use crate::sema::decl_collector::{DeclCollector, ScopeDecls};
@@ -76,6 +76,9 @@ struct Resolver<'gc, 'mode> {
     /// The depth of the global scope in ['binding_table'].
     /// It is None until we have actually entered the global scope.
     global_binding_scope_depth: Option<usize>,
+    /// How the identifiers which are about to be visited and aren't simply read access their
+    /// declaration, or `None` if they aren't references, like declaring identifiers.
+    ident_accesses: HashMap<NodePtr<'gc>, Option<(ReferenceKind, bool)>>,
     file_id: SourceId,
     mode: ResolverMode<'mode>,
 }
@@ -137,6 +140,7 @@ impl<'gc, 'mode> Resolver<'gc, 'mode> {
             binding_table: Default::default(),
             validating_formal_params: false,
             global_binding_scope_depth: None,
+            ident_accesses: Default::default(),
             file_id,
             mode,
         }
@@ -184,22 +188,101 @@ impl<'gc> Resolver<'gc, '_> {
         self.decl_scope(decl).parent_function == self.function_context().func_id
     }
 
-    /// Return `true` if the `callee` is the `require` function.
-    fn is_require(&mut self, lock: &'gc GCLock, node: &'gc ast::CallExpression<'gc>) -> bool {
+    /// Return the kind of dependency if `node` calls the `require` function with one argument,
+    /// as `require()`, `require.resolve()` or `require.async()`.
+    fn require_kind(
+        &mut self,
+        lock: &'gc GCLock,
+        node: &'gc ast::CallExpression<'gc>,
+    ) -> Option<DependencyKind> {
         debug_assert!(
             matches!(self.mode, ResolverMode::Module { .. }),
-            "is_require must only be called in module mode"
+            "require_kind must only be called in module mode"
         );
-        if let Node::Identifier(ident @ ast::Identifier { name, .. }) = node.callee {
-            if *name == self.kw.ident_require && node.arguments.len() == 1 {
+        if node.arguments.len() != 1 {
+            return None;
+        }
+        let (require, kind) = match node.callee {
+            require @ Node::Identifier(_) => (require, DependencyKind::Require),
+            Node::MemberExpression(ast::MemberExpression {
+                object,
+                property: Node::Identifier(ast::Identifier { name, .. }),
+                computed: false,
+                ..
+            }) => {
+                let kind = if *name == self.kw.ident_resolve {
+                    DependencyKind::RequireResolve
+                } else if *name == self.kw.ident_async {
+                    DependencyKind::AsyncRequire
+                } else {
+                    return None;
+                };
+                (*object, kind)
+            }
+            _ => return None,
+        };
+        match require {
+            Node::Identifier(ident @ ast::Identifier { name, .. })
+                if *name == self.kw.ident_require =>
+            {
                 // The identifier must not have a binding.
                 // It has been resolved as an ambient global property.
-                if let Some(id) = self.check_identifier_resolved(lock, ident, node.callee) {
-                    return self.sem.decl(id).kind == DeclKind::UndeclaredGlobalProperty;
+                let id = self.check_identifier_resolved(lock, ident, require)?;
+                if self.sem.decl(id).kind == DeclKind::UndeclaredGlobalProperty {
+                    Some(kind)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolve the dependency of `node` on the module named by `source` in module mode,
+    /// if it is a string literal. Warn at the range of `source` if it can't be resolved,
+    /// noting the candidates which the resolver tried.
+    fn resolve_dependency(
+        &mut self,
+        lock: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        source: &'gc Node<'gc>,
+        kind: DependencyKind,
+    ) {
+        let dependency_resolver = match self.mode {
+            ResolverMode::Module {
+                dependency_resolver,
+            } => dependency_resolver,
+            ResolverMode::Script => return,
+        };
+        if let Node::StringLiteral(ast::StringLiteral { value, .. }) = source {
+            let target = String::from_utf16_lossy(lock.str_u16(*value));
+            let resolved =
+                dependency_resolver.resolve_dependency(lock, self.file_id, &target, kind);
+            self.sem.add_dependency(Dependency {
+                node: NodeRc::from_node(lock, node),
+                kind,
+                specifier: target.clone(),
+                range: *source.range(),
+                target: resolved,
+            });
+            match resolved {
+                Some(file_id) => {
+                    self.sem.add_require(NodeRc::from_node(lock, node), file_id);
+                }
+                None => {
+                    lock.sm().warning(
+                        *source.range(),
+                        format!("Unable to resolve {} for {}", kind.name(), target),
+                    );
+                    let tried =
+                        dependency_resolver.tried_candidates(lock, self.file_id, &target, kind);
+                    if !tried.is_empty() {
+                        lock.sm()
+                            .note(*source.range(), format!("tried {}", tried.join(", ")));
+                    }
                 }
             }
         }
-        false
     }
 
     /// Create a new function, push a new function context, execute the callback
@@ -564,6 +647,7 @@ impl<'gc> Resolver<'gc, '_> {
                     pself
                         .sem
                         .set_ident_decl(NodeRc::from_node(lock, param_id_node), param_decl);
+                    pself.set_not_reference(param_id_node);
 
                     match pself.binding_table.get_mut(&param_id.name) {
                         // Check for parameter re-declaration.
@@ -663,14 +747,41 @@ impl<'gc> Resolver<'gc, '_> {
             if let Some(decls) = pself.function_context().decls.scope_decls_for_node(node) {
                 pself.in_new_scope(lock, node, |pself| {
                     pself.process_declarations(lock, decls.as_slice());
+                    pself.set_for_in_of_target_accesses(left);
                     node.visit_children(lock, pself);
                 });
             } else {
+                pself.set_for_in_of_target_accesses(left);
                 node.visit_children(lock, pself);
             }
         });
     }
 
+    /// Record that the loop variables of a for-in/for-of loop with the `left` target are
+    /// written on each iteration.
+    fn set_for_in_of_target_accesses(&mut self, left: &'gc Node<'gc>) {
+        let mut idents = SmallVec::<[&Node; 4]>::new();
+        let init = match left {
+            Node::VariableDeclaration(vd) => {
+                for declarator in vd.declarations {
+                    let declarator = node_cast!(Node::VariableDeclarator, declarator);
+                    extract_assigned_idents(declarator.id, &mut idents);
+                }
+                vd.kind != VariableDeclarationKind::Var
+            }
+            _ => {
+                extract_assigned_idents(left, &mut idents);
+                false
+            }
+        };
+        for id_node in idents {
+            self.ident_accesses.insert(
+                NodePtr::from_node(id_node),
+                Some((ReferenceKind::Write, init)),
+            );
+        }
+    }
+
     fn visit_function_expression(
         &mut self,
         lock: &'gc GCLock,
@@ -689,6 +800,7 @@ impl<'gc> Resolver<'gc, '_> {
                     pself
                         .sem
                         .set_ident_decl(NodeRc::from_node(lock, node_id), decl);
+                    pself.set_not_reference(node_id);
                     pself
                         .binding_table
                         .insert(ident.name, Binding { decl, ident });
@@ -724,17 +836,63 @@ impl<'gc> Resolver<'gc, '_> {
             | Node::BreakStatement(_)
             | Node::ContinueStatement(_)
             | Node::LabeledStatement(_) => return,
-            // typeof identifier
+            _ => {}
+        }
+        // typeof identifier
+        let in_typeof = matches!(
+            path.parent,
             Node::UnaryExpression(ast::UnaryExpression {
                 operator: UnaryExpressionOperator::Typeof,
                 ..
-            }) => {
-                self.resolve_identifier(lock, ident, node, true);
-                return;
-            }
-            _ => {}
+            })
+        );
+        self.resolve_identifier(lock, ident, node, in_typeof);
+
+        let access = match self.ident_accesses.get(&NodePtr::from_node(node)) {
+            Some(&access) => access,
+            // The names of exports and of imports from other modules aren't references.
+            None if matches!(
+                (path.parent, path.field),
+                (Node::ImportSpecifier(_), NodeField::imported)
+                    | (Node::ExportSpecifier(_), NodeField::exported)
+                    | (Node::ExportNamespaceSpecifier(_), NodeField::exported)
+            ) =>
+            {
+                None
+            }
+            None => Some((ReferenceKind::Read, false)),
+        };
+        if let Some((kind, init)) = access {
+            self.add_reference(lock, node, kind, init);
+        }
+    }
+
+    /// Record that the identifier `node` is not a reference to its declaration.
+    fn set_not_reference(&mut self, node: &'gc Node<'gc>) {
+        self.ident_accesses.insert(NodePtr::from_node(node), None);
+    }
+
+    /// Add the reference of the identifier `node` to the declaration it was resolved to.
+    fn add_reference(
+        &mut self,
+        lock: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        kind: ReferenceKind,
+        init: bool,
+    ) {
+        let node = NodeRc::from_node(lock, node);
+        if let Some(Resolution::Decl(decl)) = self.sem.ident_decl(&node) {
+            let function = self.function_context().func_id;
+            self.sem.add_reference(
+                decl,
+                Reference {
+                    node,
+                    kind,
+                    init,
+                    function,
+                },
+            );
         }
-        self.resolve_identifier(lock, ident, node, false);
     }
 
     fn visit_block_statement(&mut self, lock: &'gc GCLock, node: &'gc Node<'gc>, path: Path) {
@@ -876,6 +1034,7 @@ impl<'gc> Resolver<'gc, '_> {
         id_node: &'gc Node<'gc>,
     ) {
         let ident = node_cast!(Node::Identifier, id_node);
+        self.set_not_reference(id_node);
 
         if !self.validate_declaration_name(lock, decl_kind, ident) {
             return;
@@ -1282,6 +1441,21 @@ impl<'gc> Visitor<'gc> for Resolver<'gc, '_> {
 
             Node::AssignmentExpression(asgn) => {
                 self.validate_assignment_target(lock, asgn.left);
+                let mut idents = SmallVec::<[&Node; 4]>::new();
+                let kind = if asgn.operator == AssignmentExpressionOperator::Assign {
+                    extract_assigned_idents(asgn.left, &mut idents);
+                    ReferenceKind::Write
+                } else {
+                    // Compound assignments can only assign a single identifier.
+                    if let Node::Identifier(_) = asgn.left {
+                        idents.push(asgn.left);
+                    }
+                    ReferenceKind::ReadWrite
+                };
+                for id_node in idents {
+                    self.ident_accesses
+                        .insert(NodePtr::from_node(id_node), Some((kind, false)));
+                }
                 node.visit_children(lock, self);
             }
 
@@ -1292,6 +1466,34 @@ impl<'gc> Visitor<'gc> for Resolver<'gc, '_> {
                         "invalid operand in update operation",
                     );
                 }
+                if let Node::Identifier(_) = update.argument {
+                    self.ident_accesses.insert(
+                        NodePtr::from_node(update.argument),
+                        Some((ReferenceKind::ReadWrite, false)),
+                    );
+                }
+                node.visit_children(lock, self);
+            }
+
+            Node::VariableDeclarator(ast::VariableDeclarator {
+                id, init: Some(_), ..
+            }) => {
+                // `var` initializers are assignments, since the variable can be read before.
+                let init = !matches!(
+                    path.unwrap().parent,
+                    Node::VariableDeclaration(ast::VariableDeclaration {
+                        kind: VariableDeclarationKind::Var,
+                        ..
+                    })
+                );
+                let mut idents = SmallVec::<[&Node; 4]>::new();
+                extract_assigned_idents(id, &mut idents);
+                for id_node in idents {
+                    self.ident_accesses.insert(
+                        NodePtr::from_node(id_node),
+                        Some((ReferenceKind::Write, init)),
+                    );
+                }
                 node.visit_children(lock, self);
             }
 
@@ -1446,34 +1648,30 @@ impl<'gc> Visitor<'gc> for Resolver<'gc, '_> {
                 }
             }
 
-            Node::ImportDeclaration(ast::ImportDeclaration {
-                source: Node::StringLiteral(ast::StringLiteral { value, .. }),
+            Node::ImportDeclaration(ast::ImportDeclaration { source, .. })
+            | Node::ExportNamedDeclaration(ast::ExportNamedDeclaration {
+                source: Some(source),
                 ..
-            }) => {
-                node.visit_children(lock, self);
-                if let ResolverMode::Module {
-                    dependency_resolver,
-                } = self.mode
-                {
-                    // Resolve `import`.
-                    let target = String::from_utf16_lossy(lock.str_u16(*value));
-                    match dependency_resolver.resolve_dependency(
-                        lock,
-                        self.file_id,
-                        &target,
-                        DependencyKind::Import,
-                    ) {
-                        Some(file_id) => {
-                            self.sem.add_require(NodeRc::from_node(lock, node), file_id);
-                        }
-                        None => {
-                            lock.sm().warning(
-                                *node.range(),
-                                format!("Unable to resolve import for {}", target),
-                            );
+            })
+            | Node::ExportAllDeclaration(ast::ExportAllDeclaration { source, .. }) => {
+                if let Node::ExportNamedDeclaration(export) = node {
+                    // The local names of re-exports name exports of the other module.
+                    for specifier in export.specifiers.iter() {
+                        if let Node::ExportSpecifier(ast::ExportSpecifier { local, .. }) = specifier
+                        {
+                            self.set_not_reference(local);
                         }
                     }
                 }
+                node.visit_children(lock, self);
+                // Resolve `import` and `export ... from`.
+                self.resolve_dependency(lock, node, source, DependencyKind::Import);
+            }
+
+            Node::ImportExpression(ast::ImportExpression { source, .. }) => {
+                node.visit_children(lock, self);
+                // Resolve `import()`.
+                self.resolve_dependency(lock, node, source, DependencyKind::DynamicImport);
             }
 
             Node::ClassDeclaration(_) | Node::ClassExpression(_) => {
@@ -1501,32 +1699,11 @@ impl<'gc> Visitor<'gc> for Resolver<'gc, '_> {
                 }
 
                 node.visit_children(lock, self);
-                if let ResolverMode::Module {
-                    dependency_resolver,
-                } = self.mode
-                {
-                    if self.is_require(lock, call) {
-                        // Resolve `require()` call.
-                        if let Some(Node::StringLiteral(ast::StringLiteral { value, .. })) =
-                            arguments.head()
-                        {
-                            let target = String::from_utf16_lossy(lock.str_u16(*value));
-                            match dependency_resolver.resolve_dependency(
-                                lock,
-                                self.file_id,
-                                &target,
-                                DependencyKind::Require,
-                            ) {
-                                Some(file_id) => {
-                                    self.sem.add_require(NodeRc::from_node(lock, node), file_id);
-                                }
-                                None => {
-                                    lock.sm().warning(
-                                        *node.range(),
-                                        format!("Unable to resolve require for {}", target),
-                                    );
-                                }
-                            }
+                if matches!(self.mode, ResolverMode::Module { .. }) {
+                    // Resolve `require()`, `require.resolve()` and `require.async()` calls.
+                    if let Some(kind) = self.require_kind(lock, call) {
+                        if let Some(source) = arguments.head() {
+                            self.resolve_dependency(lock, node, source, kind);
                         }
                     }
                 }
@@ -1553,6 +1730,34 @@ impl<'gc> Visitor<'gc> for Resolver<'gc, '_> {
                 node.visit_children(lock, self);
             }
 
+            Node::ExportNamedDeclaration(ast::ExportNamedDeclaration {
+                declaration: Some(declaration),
+                ..
+            })
+            | Node::ExportDefaultDeclaration(ast::ExportDefaultDeclaration {
+                declaration, ..
+            }) => {
+                node.visit_children(lock, self);
+                // Exported declarations may be read by the modules importing them.
+                let mut idents = SmallVec::<[&Node; 4]>::new();
+                match declaration {
+                    Node::VariableDeclaration(vd) => {
+                        for declarator in vd.declarations {
+                            let declarator = node_cast!(Node::VariableDeclarator, declarator);
+                            extract_assigned_idents(declarator.id, &mut idents);
+                        }
+                    }
+                    Node::FunctionDeclaration(ast::FunctionDeclaration { id, .. })
+                    | Node::ClassDeclaration(ast::ClassDeclaration { id, .. }) => {
+                        idents.extend(*id);
+                    }
+                    _ => {}
+                }
+                for id_node in idents {
+                    self.add_reference(lock, id_node, ReferenceKind::Read, false);
+                }
+            }
+
             _ => {
                 node.visit_children(lock, self);
             }
@@ -1887,3 +2092,33 @@ impl<'gc> Visitor<'gc> for Unresolver<'_, 'gc, '_> {
         }
     }
 }
+
+/// Extract the identifiers assigned by the assignment target `node`, which may be a
+/// destructuring pattern, or the identifiers declared by the pattern `node`. Member expressions
+/// are skipped.
+pub(crate) fn extract_assigned_idents<'gc, A: smallvec::Array<Item = &'gc Node<'gc>>>(
+    node: &'gc Node<'gc>,
+    idents: &mut SmallVec<A>,
+) {
+    match node {
+        Node::Identifier(_) => idents.push(node),
+        Node::AssignmentPattern(ast::AssignmentPattern { left, .. }) => {
+            extract_assigned_idents(left, idents)
+        }
+        Node::ArrayPattern(ast::ArrayPattern { elements, .. }) => {
+            for element in elements.iter() {
+                extract_assigned_idents(element, idents);
+            }
+        }
+        Node::Property(ast::Property { value, .. }) => extract_assigned_idents(value, idents),
+        Node::ObjectPattern(ast::ObjectPattern { properties, .. }) => {
+            for property in properties.iter() {
+                extract_assigned_idents(property, idents);
+            }
+        }
+        Node::RestElement(ast::RestElement { argument, .. }) => {
+            extract_assigned_idents(argument, idents)
+        }
+        _ => {}
+    }
+}
@@ -6,10 +6,11 @@
  */
 
 use crate::ast::NodeRc;
+use crate::resolve_dependency::DependencyKind;
 use juno_ast::{node_cast, GCLock, Node};
 use juno_support::atom_table::Atom;
 use juno_support::declare_opaque_id;
-use juno_support::source_manager::SourceId;
+use juno_support::source_manager::{SourceId, SourceRange};
 use std::collections::HashMap;
 
 declare_opaque_id!(DeclId);
@@ -120,6 +121,9 @@ pub struct Decl {
     /// Whether the variable can be renamed.
     /// False when, e.g., it may be read/written by a local `eval` call.
     pub can_rename: bool,
+    /// Whether the variable is referenced by a function nested in the function declaring it,
+    /// so it must outlive the activation of its function.
+    pub captured: bool,
 }
 
 impl Decl {
@@ -263,6 +267,58 @@ pub enum Resolution {
     Decl(DeclId),
 }
 
+/// How a reference accesses the declaration it refers to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReferenceKind {
+    Read,
+    /// Assigns the variable without reading it, like `x = 1` or `[x] = a`.
+    Write,
+    /// Reads and assigns the variable, like `x += 1` or `x++`.
+    ReadWrite,
+}
+
+impl ReferenceKind {
+    pub fn is_read(self) -> bool {
+        matches!(self, ReferenceKind::Read | ReferenceKind::ReadWrite)
+    }
+    pub fn is_write(self) -> bool {
+        matches!(self, ReferenceKind::Write | ReferenceKind::ReadWrite)
+    }
+}
+
+/// A reference to a declaration by an identifier.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    /// The Identifier node.
+    pub node: NodeRc,
+    pub kind: ReferenceKind,
+    /// True if this is the initializer of a `let` or `const` declaration.
+    pub init: bool,
+    /// The function containing the reference.
+    pub function: FunctionInfoId,
+}
+
+/// A dependency of a module on another module, named by a string literal.
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    /// The `require` call, import or re-export declaration, or `import()` expression.
+    pub node: NodeRc,
+    pub kind: DependencyKind,
+    /// The specifier naming the other module.
+    pub specifier: String,
+    /// The range of the string literal of the specifier.
+    pub range: SourceRange,
+    /// The module the specifier was resolved to, if it could be resolved.
+    pub target: Option<SourceId>,
+}
+
+impl Dependency {
+    /// Whether the other module is loaded after this one runs.
+    pub fn is_lazy(&self) -> bool {
+        self.kind.is_lazy()
+    }
+}
+
 #[derive(Default)]
 pub struct SemContext {
     pub decls: DeclList,
@@ -275,6 +331,12 @@ pub struct SemContext {
     node_scopes: HashMap<NodeRc, LexicalScopeId>,
     /// Resolved `require` calls.
     requires: HashMap<NodeRc, SourceId>,
+    /// All dependencies on other modules in the order they appear.
+    dependencies: Vec<Dependency>,
+    /// The index in `dependencies` of the dependency of each AST node.
+    dependency_index: HashMap<NodeRc, usize>,
+    /// The references to each declaration in the order they appear.
+    references: HashMap<DeclId, Vec<Reference>>,
 }
 
 impl SemContext {
@@ -334,7 +396,9 @@ impl SemContext {
             special,
             function_in_scope: false,
             scope,
-            can_rename: false,
+            // Cleared later if a local `eval()` is found.
+            can_rename: special == Special::NotSpecial && !kind.is_global(),
+            captured: false,
         });
         let decl_id = DeclId::new(self.decls.0.len() - 1);
         self.scopes.get_mut(scope).decls.push(decl_id);
@@ -413,6 +477,69 @@ impl SemContext {
         self.requires.insert(call, file_id);
     }
 
+    /// All dependencies of the module on other modules, whether or not they were resolved,
+    /// in the order they appear.
+    pub fn dependencies(&self) -> &[Dependency] {
+        &self.dependencies
+    }
+    /// The dependencies of the module which are loaded after it runs, like `import()`.
+    pub fn lazy_dependencies(&self) -> impl Iterator<Item = &Dependency> {
+        self.dependencies.iter().filter(|dep| dep.is_lazy())
+    }
+    /// The dependency declared by `node`, if any.
+    pub fn dependency(&self, node: &NodeRc) -> Option<&Dependency> {
+        self.dependency_index
+            .get(node)
+            .map(|&index| &self.dependencies[index])
+    }
+    pub(super) fn add_dependency(&mut self, dependency: Dependency) {
+        self.dependency_index
+            .entry(dependency.node.clone())
+            .or_insert(self.dependencies.len());
+        self.dependencies.push(dependency);
+    }
+
+    /// The references to `decl` in the order they appear. Declaring a variable without an
+    /// initializer isn't a reference, and exporting a declaration is a read.
+    pub fn references(&self, decl: DeclId) -> &[Reference] {
+        self.references
+            .get(&decl)
+            .map_or(&[], |references| references.as_slice())
+    }
+    pub(super) fn add_reference(&mut self, decl: DeclId, reference: Reference) {
+        if self.scope(self.decl(decl).scope).parent_function != reference.function {
+            self.decl_mut(decl).captured = true;
+        }
+        self.references.entry(decl).or_default().push(reference);
+    }
+
+    /// Whether the value of `decl` is never read. Always false for variables which can't be
+    /// renamed, like globals and variables visible to a local `eval`.
+    pub fn is_never_read(&self, decl: DeclId) -> bool {
+        self.decl(decl).can_rename
+            && !self
+                .references(decl)
+                .iter()
+                .any(|reference| reference.kind.is_read())
+    }
+
+    /// Whether `decl` is never assigned after it is initialized: the only write is the
+    /// initializer of a `let` or `const`, if any. Parameters, functions, classes and imports
+    /// are initialized without a write. `var` initializers are assignments, since the
+    /// variable can be read before them. Always false for variables which can't be renamed.
+    pub fn is_const_after_init(&self, decl: DeclId) -> bool {
+        let mut writes = self
+            .references(decl)
+            .iter()
+            .filter(|reference| reference.kind.is_write());
+        self.decl(decl).can_rename
+            && match (writes.next(), writes.next()) {
+                (None, _) => true,
+                (Some(write), None) => write.init,
+                (Some(_), Some(_)) => false,
+            }
+    }
+
     /// Return the id of the global scope in the context. This may seem
     /// redundant, since the ID is constant. The idea here that the global scope
     /// may not have been created yet.
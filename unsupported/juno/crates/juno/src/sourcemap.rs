@@ -6,6 +6,7 @@
  */
 
 use sourcemap::{SourceMap, SourceMapBuilder};
+use std::collections::HashMap;
 
 /// Return a merged version of the `input` and `output` source maps.
 /// Currently this only supports a single input source map.
@@ -46,3 +47,59 @@ pub fn merge_sourcemaps(input: &SourceMap, output: &SourceMap) -> SourceMap {
     }
     merged.into_sourcemap()
 }
+
+/// Return a version of the `output` source map, which may have been generated from several
+/// sources, in which the tokens of each source with an entry in `inputs` are looked up in its
+/// input source map, like in [`merge_sourcemaps`].
+/// `inputs` maps the IDs of sources in `output` to their input source maps.
+pub fn merge_sourcemaps_by_source(
+    inputs: &HashMap<u32, SourceMap>,
+    output: &SourceMap,
+) -> SourceMap {
+    let mut merged = SourceMapBuilder::new(output.get_file());
+
+    let output_sources: Vec<u32> = output.sources().map(|src| merged.add_source(src)).collect();
+    let output_names: Vec<u32> = output.names().map(|name| merged.add_name(name)).collect();
+
+    // IDs in `merged` of the sources and names of each input source map.
+    let mut input_ids = HashMap::new();
+    let mut input_src_ids: Vec<u32> = inputs.keys().copied().collect();
+    input_src_ids.sort_unstable();
+    for src_id in input_src_ids {
+        let input = &inputs[&src_id];
+        let sources: Vec<u32> = input.sources().map(|src| merged.add_source(src)).collect();
+        let names: Vec<u32> = input.names().map(|name| merged.add_name(name)).collect();
+        input_ids.insert(src_id, (sources, names));
+    }
+
+    for output_token in output.tokens() {
+        let raw = output_token.get_raw_token();
+        let input_token = inputs
+            .get(&raw.src_id)
+            .and_then(|input| input.lookup_token(raw.src_line, raw.src_col));
+        match input_token {
+            Some(input_token) => {
+                let (sources, names) = &input_ids[&raw.src_id];
+                merged.add_raw(
+                    raw.dst_line,
+                    raw.dst_col,
+                    input_token.get_src_line(),
+                    input_token.get_src_col(),
+                    sources.get(input_token.get_src_id() as usize).copied(),
+                    names.get(input_token.get_name_id() as usize).copied(),
+                );
+            }
+            None => {
+                merged.add_raw(
+                    raw.dst_line,
+                    raw.dst_col,
+                    raw.src_line,
+                    raw.src_col,
+                    output_sources.get(raw.src_id as usize).copied(),
+                    output_names.get(raw.name_id as usize).copied(),
+                );
+            }
+        }
+    }
+    merged.into_sourcemap()
+}
@@ -501,3 +501,31 @@ fn test_store_node() {
         assert!(pass.n.is_some());
     }
 }
+
+#[test]
+fn test_reuse_list_elements() {
+    fn number<'gc>(gc: &'gc GCLock, value: f64) -> &'gc Node<'gc> {
+        builder::NumericLiteral::build_template(
+            gc,
+            template::NumericLiteral {
+                metadata: Default::default(),
+                value,
+            },
+        )
+    }
+
+    let mut ctx = Context::new();
+    {
+        let gc = GCLock::new(&mut ctx);
+        NodeList::from_iter(&gc, (0..3).map(|i| number(&gc, i as f64)));
+    }
+    ctx.gc();
+
+    // Every freed element is reused for the head of a new list,
+    // which must not keep the link to the rest of the old list.
+    let gc = GCLock::new(&mut ctx);
+    for i in 0..3 {
+        let list = NodeList::from_iter(&gc, [number(&gc, i as f64)]);
+        assert_eq!(list.len(), 1);
+    }
+}
@@ -0,0 +1,133 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use juno::ast::{builder, node_cast, template, Context, GCLock, Node};
+use juno::dependency_graph::DependencyGraph;
+use juno::hparser::ParsedJS;
+use juno::resolve_dependency::{DefaultResolver, DependencyKind};
+use juno::sema::{self, SemContext};
+use juno_support::json::Pretty;
+use juno_support::source_manager::SourceId;
+use juno_support::NullTerminatedBuf;
+
+/// Resolve the `(name, source)` modules and build their graph.
+fn build_graph(ctx: &mut Context, modules: &[(&str, &str)]) -> DependencyGraph {
+    let ids: Vec<SourceId> = modules
+        .iter()
+        .map(|(name, source)| {
+            ctx.sm_mut()
+                .add_source(*name, NullTerminatedBuf::from_str_copy(source))
+        })
+        .collect();
+    let resolver = DefaultResolver::new(ctx.sm());
+    let mut sems: Vec<SemContext> = vec![];
+    for &id in &ids {
+        let buf = ctx.sm().source_buffer_rc(id);
+        let parsed = ParsedJS::parse(Default::default(), &buf);
+        let lock = GCLock::new(ctx);
+        let program = node_cast!(Node::Program, parsed.to_ast(&lock, id).unwrap());
+        let module = builder::Module::build_template(
+            &lock,
+            template::Module {
+                metadata: Default::default(),
+                body: program.body,
+            },
+        );
+        sems.push(sema::resolve_module(&lock, module, id, &resolver));
+        assert_eq!(lock.sm().num_errors(), 0);
+    }
+    DependencyGraph::new(ids.into_iter().zip(&sems))
+}
+
+#[test]
+fn test_graph() {
+    let mut ctx = Context::new();
+    let graph = build_graph(
+        &mut ctx,
+        &[
+            (
+                "./a.js",
+                "import './b'; import('./c'); require('./missing');",
+            ),
+            ("./b.js", "import './c'; require('./c');"),
+            ("./c.js", "export * from './d';"),
+            ("./d.js", "import './b';"),
+            ("./e.js", "require('./e'); import './f';"),
+            ("./f.js", ""),
+        ],
+    );
+    let id = |i: u32| SourceId(i);
+
+    assert_eq!(graph.modules().len(), 6);
+    let edges: Vec<_> = graph
+        .edges()
+        .iter()
+        .filter(|edge| edge.from == id(0))
+        .map(|edge| (edge.to, edge.kind, edge.specifier.as_str()))
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            (Some(id(1)), DependencyKind::Import, "./b"),
+            (Some(id(2)), DependencyKind::DynamicImport, "./c"),
+            (None, DependencyKind::Require, "./missing"),
+        ]
+    );
+    assert_eq!(graph.dependencies(id(1)).collect::<Vec<_>>(), vec![id(2)]);
+    assert_eq!(graph.dependents(id(2)), vec![id(0), id(1)]);
+    assert_eq!(graph.transitive_closure_size(id(0)), 3);
+    assert_eq!(graph.transitive_closure_size(id(1)), 2);
+    assert_eq!(graph.transitive_closure_size(id(5)), 0);
+    let sizes: Vec<usize> = graph
+        .modules()
+        .iter()
+        .map(|&id| graph.transitive_closure_size(id))
+        .collect();
+    assert_eq!(sizes, vec![3, 2, 2, 2, 1, 0]);
+    assert_eq!(graph.transitive_closure_sizes(), sizes);
+    assert_eq!(
+        graph.cycles(),
+        vec![vec![id(1), id(2), id(3), id(1)], vec![id(4), id(4)]]
+    );
+}
+
+#[test]
+fn test_dump() {
+    let mut ctx = Context::new();
+    let graph = build_graph(
+        &mut ctx,
+        &[("./a.js", "import('./b');"), ("./b.js", "import './a';")],
+    );
+
+    let mut json = vec![];
+    graph.dump_json(&mut json, ctx.sm(), Pretty::No).unwrap();
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        concat!(
+            r#"{"modules":["#,
+            r#"{"id":0,"name":"./a.js","dependencies":1,"dependents":1,"transitiveDependencies":1},"#,
+            r#"{"id":1,"name":"./b.js","dependencies":1,"dependents":1,"transitiveDependencies":1}],"#,
+            r#""edges":["#,
+            r#"{"from":0,"to":1,"kind":"dynamic import","lazy":true,"specifier":"./b","resolved":true,"line":1,"column":8},"#,
+            r#"{"from":1,"to":0,"kind":"import","lazy":false,"specifier":"./a","resolved":true,"line":1,"column":8}],"#,
+            r#""cycles":[[0,1,0]]}"#,
+        )
+    );
+
+    let mut dot = vec![];
+    graph.dump_dot(&mut dot, ctx.sm()).unwrap();
+    assert_eq!(
+        String::from_utf8(dot).unwrap(),
+        r#"digraph dependencies {
+  m0 [label="./a.js"];
+  m1 [label="./b.js"];
+  m0 -> m1 [label="./b", style=dashed, color=red];
+  m1 -> m0 [label="./a", color=red];
+}
+"#
+    );
+}
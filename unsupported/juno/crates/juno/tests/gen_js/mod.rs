@@ -8,7 +8,9 @@
 use juno::ast::*;
 use juno::gen_js;
 use juno::hparser;
-use juno::sourcemap::merge_sourcemaps;
+use juno::sourcemap::{merge_sourcemaps, merge_sourcemaps_by_source};
+use juno_support::NullTerminatedBuf;
+use std::collections::HashMap;
 
 fn do_gen<'ast>(ctx: &mut Context<'ast>, node: &NodeRc, pretty: gen_js::Pretty) -> String {
     use juno::gen_js::*;
@@ -31,7 +33,11 @@ fn test_roundtrip_with_flags(flags: hparser::ParserFlags, src1: &str) {
 
     let mut ctx = Context::new();
 
-    for pretty in &[gen_js::Pretty::Yes, gen_js::Pretty::No] {
+    for pretty in &[
+        gen_js::Pretty::Yes,
+        gen_js::Pretty::No,
+        gen_js::Pretty::Minify,
+    ] {
         let ast1 = hparser::parse_with_flags(flags, src1, &mut ctx).unwrap();
         let mut dump: Vec<u8> = vec![];
         dump_json(&mut dump, &mut ctx, &ast1, juno::ast::Pretty::Yes).unwrap();
@@ -67,6 +73,7 @@ fn test_roundtrip_flow(src1: &str) {
             enable_jsx: false,
             dialect: hparser::ParserDialect::Flow,
             store_doc_block: false,
+            store_comments: false,
         },
         src1,
     );
@@ -79,6 +86,7 @@ fn test_roundtrip_jsx(src1: &str) {
             enable_jsx: true,
             dialect: hparser::ParserDialect::JavaScript,
             store_doc_block: false,
+            store_comments: false,
         },
         src1,
     )
@@ -644,6 +652,146 @@ fn test_jsx() {
     );
 }
 
+#[test]
+fn test_minify() {
+    let mut ctx = Context::new();
+    let mut minify = |src: &str| {
+        let ast = hparser::parse(&mut ctx, src).unwrap();
+        do_gen(&mut ctx, &ast, gen_js::Pretty::Minify)
+    };
+    assert_eq!(
+        minify("function foo(a, b) { return a + b; }"),
+        "function foo(a,b){return a+b}\n"
+    );
+    assert_eq!(
+        minify(r#"var x = 'it\'s', y = "say \"hi\"", z = 'a b';"#),
+        "var x=\"it's\",y='say \"hi\"',z='a b'\n"
+    );
+    assert_eq!(
+        minify("x = [1000, 0.5, 1e21, 255, 0.0001, 1.5e-7, 1099511627775, 100];"),
+        "x=[1e3,.5,1e21,255,1e-4,15e-8,0xffffffffff,100]\n"
+    );
+    assert_eq!(minify("x = 1000..toString();"), "x=1e3.toString()\n");
+    assert_eq!(
+        minify("if (a) { b(); } else { c(); }"),
+        "if(a){b()}else{c()}\n"
+    );
+    assert_eq!(minify("if (a) b(); else c();"), "if(a)b();else c()\n");
+    assert_eq!(minify("do x(); while (y)"), "do x();while(y)\n");
+    assert_eq!(
+        minify("function f() { for (;;); }"),
+        "function f(){for(;;);}\n"
+    );
+    assert_eq!(
+        minify("function g() { return [1]; }"),
+        "function g(){return[1]}\n"
+    );
+    assert_eq!(
+        minify("x = new Foo(); z = - -a; w = a + +b; typeof a;"),
+        "x=new Foo;z=- -a;w=a+ +b;typeof a\n"
+    );
+}
+
+#[test]
+fn test_print_width() {
+    let layout = |src: &str, print_width: usize, use_tabs: bool| {
+        let mut ctx = Context::new();
+        let ast = hparser::parse(&mut ctx, src).unwrap();
+        let mut out: Vec<u8> = vec![];
+        gen_js::generate(
+            &mut out,
+            &mut ctx,
+            &ast,
+            gen_js::Opt {
+                print_width: Some(print_width),
+                use_tabs,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        String::from_utf8(out).expect("Invalid UTF-8 output in test")
+    };
+    assert_eq!(
+        layout("foo(aaaaaaaaaa, bbbbbbbbbb, cccccccccc);", 80, false),
+        "foo(aaaaaaaaaa, bbbbbbbbbb, cccccccccc);\n"
+    );
+    assert_eq!(
+        layout("foo(aaaaaaaaaa, bbbbbbbbbb, cccccccccc);", 20, false),
+        "foo(\n  aaaaaaaaaa,\n  bbbbbbbbbb,\n  cccccccccc\n);\n"
+    );
+    assert_eq!(
+        layout("x = {a: 1, b: 2};", 80, false),
+        "x = {a: 1, b: 2};\n"
+    );
+    assert_eq!(
+        layout("x = {a: 1, b: 2};", 10, false),
+        "x = {\n  a: 1,\n  b: 2\n};\n"
+    );
+    assert_eq!(
+        layout("x = [1, 2, 3];", 10, true),
+        "x = [\n\t1,\n\t2,\n\t3\n];\n"
+    );
+    assert_eq!(
+        layout("x = aaaaaaaaaa + bbbbbbbbbb + cccccccccc;", 20, false),
+        "x = aaaaaaaaaa +\n  bbbbbbbbbb +\n  cccccccccc;\n"
+    );
+    assert_eq!(
+        layout("promise.then(a).catch(b).finally(c);", 20, false),
+        "promise.then(a)\n  .catch(b)\n  .finally(c);\n"
+    );
+    assert_eq!(
+        layout("foo(a, function () { return 1; });", 10, false),
+        "foo(a, function() {\n  return 1;\n});\n"
+    );
+}
+
+#[test]
+fn test_comments() {
+    let mut ctx = Context::new();
+    let mut gen = |src: &str, pretty: gen_js::Pretty| {
+        let ast = hparser::parse_with_flags(
+            hparser::ParserFlags {
+                store_comments: true,
+                ..Default::default()
+            },
+            src,
+            &mut ctx,
+        )
+        .unwrap();
+        do_gen(&mut ctx, &ast, pretty)
+    };
+    let src = "// header
+function foo(a /* first */, b) {
+  // body
+  return a; // done
+}
+";
+    assert_eq!(gen(src, gen_js::Pretty::Yes), src);
+    assert_eq!(
+        gen(src, gen_js::Pretty::Minify),
+        "function foo(a,b){return a}\n"
+    );
+    let src = "/**
+ * Doc.
+ */
+function f() {}
+";
+    assert_eq!(gen(src, gen_js::Pretty::Yes), src);
+    let src = "if (x) {
+  // nothing
+}
+";
+    assert_eq!(gen(src, gen_js::Pretty::Yes), src);
+    // The comment can't end the line after `return`.
+    assert_eq!(
+        gen(
+            "function f() {\n  return (\n    // why\n    x\n  );\n}",
+            gen_js::Pretty::Yes
+        ),
+        "function f() {\n  return (// why\n  x);\n}\n"
+    );
+}
+
 #[test]
 fn test_sourcemap() {
     use juno::gen_js::*;
@@ -743,3 +891,65 @@ fn test_sourcemap_merged() {
     assert_eq!(input_token.get_source().unwrap(), "test.ts");
     assert_eq!(input_token.get_src(), (1, 17));
 }
+
+#[test]
+fn test_sourcemap_multiple_sources() {
+    use juno::gen_js::*;
+    let mut context = Context::new();
+    let ctx = &mut context;
+
+    // Generate a single program from the statements of two files.
+    let mut body = vec![];
+    for (name, src) in [("a.js", "a();"), ("b.js", "\n  b();")] {
+        let file_id = ctx
+            .sm_mut()
+            .add_source(name, NullTerminatedBuf::from_str_copy(src));
+        let buf = ctx.sm().source_buffer_rc(file_id);
+        let parsed = hparser::ParsedJS::parse(Default::default(), &buf);
+        let gc = GCLock::new(ctx);
+        let program = node_cast!(Node::Program, parsed.to_ast(&gc, file_id).unwrap());
+        body.extend(program.body.iter().map(|stmt| NodeRc::from_node(&gc, stmt)));
+    }
+    let node = {
+        let gc = GCLock::new(ctx);
+        let program = builder::Program::build_template(
+            &gc,
+            template::Program {
+                metadata: Default::default(),
+                body: NodeList::from_iter(&gc, body.iter().map(|stmt| stmt.node(&gc))),
+            },
+        );
+        NodeRc::from_node(&gc, program)
+    };
+    let mut out: Vec<u8> = vec![];
+    let output_map = generate(&mut out, ctx, &node, gen_js::Opt::new()).unwrap();
+    let output = String::from_utf8(out).expect("Invalid UTF-8 output in test");
+    assert_eq!(output, "a();\nb();\n");
+
+    let token = output_map.lookup_token(0, 0).unwrap();
+    assert_eq!(token.get_source(), Some("a.js"));
+    assert_eq!(token.get_src(), (0, 0));
+    let token = output_map.lookup_token(1, 0).unwrap();
+    assert_eq!(token.get_source(), Some("b.js"));
+    assert_eq!(token.get_src(), (1, 2));
+
+    // Only the tokens from b.js are looked up in its input source map.
+    let input_map = sourcemap::SourceMap::from_slice(
+        br#"{
+            "version":3,
+            "sources":["b.ts"],
+            "names":[],
+            "mappings":";EAAA"
+        }"#,
+    )
+    .unwrap();
+    let b_id = ctx.sm().lookup_name("b.js").unwrap();
+    let merged = merge_sourcemaps_by_source(&HashMap::from([(b_id.0, input_map)]), &output_map);
+
+    let token = merged.lookup_token(0, 0).unwrap();
+    assert_eq!(token.get_source(), Some("a.js"));
+    assert_eq!(token.get_src(), (0, 0));
+    let token = merged.lookup_token(1, 0).unwrap();
+    assert_eq!(token.get_source(), Some("b.ts"));
+    assert_eq!(token.get_src(), (0, 0));
+}
@@ -8,4 +8,8 @@
 #[macro_use]
 mod ast;
 
+mod dependency_graph;
 mod gen_js;
+mod linker;
+mod references;
+mod resolve_dependency;
@@ -0,0 +1,216 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use juno::ast::{builder, node_cast, template, Context, GCLock, Node, NodeRc};
+use juno::hparser::ParsedJS;
+use juno::resolve_dependency::DefaultResolver;
+use juno::sema::{self, Binding, Imported, LinkModule, LinkedModules, Resolution, SemContext};
+use juno_support::source_manager::SourceId;
+use juno_support::NullTerminatedBuf;
+
+/// Resolve the `(name, source)` modules, returning their ASTs and semantic contexts.
+fn resolve(ctx: &mut Context, modules: &[(&str, &str)]) -> Vec<(SourceId, NodeRc, SemContext)> {
+    for (name, source) in modules {
+        ctx.sm_mut()
+            .add_source(*name, NullTerminatedBuf::from_str_copy(source));
+    }
+    let resolver = DefaultResolver::new(ctx.sm());
+    (0..modules.len() as u32)
+        .map(|i| {
+            let id = SourceId(i);
+            let buf = ctx.sm().source_buffer_rc(id);
+            let parsed = ParsedJS::parse(Default::default(), &buf);
+            let lock = GCLock::new(ctx);
+            let program = node_cast!(Node::Program, parsed.to_ast(&lock, id).unwrap());
+            let module = builder::Module::build_template(
+                &lock,
+                template::Module {
+                    metadata: Default::default(),
+                    body: program.body,
+                },
+            );
+            let sem = sema::resolve_module(&lock, module, id, &resolver);
+            assert_eq!(lock.sm().num_errors(), 0);
+            (id, NodeRc::from_node(&lock, module), sem)
+        })
+        .collect()
+}
+
+/// Link the resolved `modules`.
+fn link(lock: &GCLock, modules: &[(SourceId, NodeRc, SemContext)]) -> LinkedModules {
+    let link_modules: Vec<LinkModule> = modules
+        .iter()
+        .map(|(id, ast, sem)| LinkModule {
+            id: *id,
+            ast: ast.node(lock),
+            sem,
+        })
+        .collect();
+    sema::link_modules(lock, &link_modules)
+}
+
+/// Describe `binding`, naming its declaration.
+fn describe(
+    lock: &GCLock,
+    modules: &[(SourceId, NodeRc, SemContext)],
+    binding: Option<Binding>,
+) -> String {
+    match binding {
+        Some(Binding::Decl(id, decl)) => format!(
+            "{}:{}",
+            id.0,
+            lock.str(modules[id.0 as usize].2.decl(decl).name)
+        ),
+        Some(Binding::Default(id)) => format!("{}:default", id.0),
+        Some(Binding::Namespace(id)) => format!("{}:*", id.0),
+        Some(Binding::External(Some(id), _)) => format!("{}:external", id.0),
+        Some(Binding::External(None, _)) => "external".to_string(),
+        None => "none".to_string(),
+    }
+}
+
+/// The local names of the imports of the first module and the bindings they refer to.
+fn imports(
+    lock: &GCLock,
+    modules: &[(SourceId, NodeRc, SemContext)],
+    linked: &LinkedModules,
+) -> Vec<(String, String)> {
+    let (id, ast, sem) = &modules[0];
+    let mut result = vec![];
+    for stmt in node_cast!(Node::Module, ast.node(lock)).body.iter() {
+        if let Node::ImportDeclaration(decl) = stmt {
+            for specifier in decl.specifiers.iter() {
+                let local = match specifier {
+                    Node::ImportSpecifier(s) => s.local,
+                    Node::ImportDefaultSpecifier(s) => s.local,
+                    Node::ImportNamespaceSpecifier(s) => s.local,
+                    _ => continue,
+                };
+                let binding = match sem.ident_decl(&NodeRc::from_node(lock, local)) {
+                    Some(Resolution::Decl(decl)) => linked.import_binding(*id, decl),
+                    _ => None,
+                };
+                result.push((
+                    lock.str(node_cast!(Node::Identifier, local).name)
+                        .to_string(),
+                    describe(lock, modules, binding),
+                ));
+            }
+        }
+    }
+    result
+}
+
+#[test]
+fn test_link() {
+    let mut ctx = Context::new();
+    let modules = resolve(
+        &mut ctx,
+        &[
+            (
+                "./a.js",
+                "import {x, y, ns} from './b'; import d from './b'; \
+                 import * as c from './c'; import q from './e'; import r from './missing';",
+            ),
+            (
+                "./b.js",
+                "export * from './c'; export {w as y} from './d'; \
+                 export * as ns from './d'; export default 1;",
+            ),
+            (
+                "./c.js",
+                "import {w} from './d'; export const x = 1; export {w};",
+            ),
+            ("./d.js", "export function w() {}"),
+            ("./e.js", "module.exports = 1;"),
+        ],
+    );
+    let lock = GCLock::new(&mut ctx);
+    let linked = link(&lock, &modules);
+    assert_eq!(lock.sm().num_errors(), 0);
+
+    let expected = [
+        ("x", "2:x"),
+        ("y", "3:w"),
+        ("ns", "3:*"),
+        ("d", "1:default"),
+        ("c", "2:*"),
+        ("q", "4:external"),
+        ("r", "external"),
+    ];
+    assert_eq!(
+        imports(&lock, &modules, &linked),
+        expected
+            .iter()
+            .map(|(local, binding)| (local.to_string(), binding.to_string()))
+            .collect::<Vec<_>>()
+    );
+
+    let exports: Vec<(&str, String)> = linked
+        .exports(SourceId(1))
+        .unwrap()
+        .iter()
+        .map(|(name, binding)| (lock.str(*name), describe(&lock, &modules, Some(*binding))))
+        .collect();
+    assert_eq!(
+        exports,
+        vec![
+            ("y", "3:w".to_string()),
+            ("ns", "3:*".to_string()),
+            ("default", "1:default".to_string()),
+            ("x", "2:x".to_string()),
+            ("w", "3:w".to_string()),
+        ]
+    );
+    assert!(linked.exports(SourceId(4)).is_none());
+    assert_eq!(
+        linked.resolve_export(SourceId(4), lock.atom("default")),
+        Some(Binding::External(
+            Some(SourceId(4)),
+            Imported::Named(lock.atom("default"))
+        ))
+    );
+    assert_eq!(linked.resolve_export(SourceId(1), lock.atom("z")), None);
+}
+
+#[test]
+fn test_link_errors() {
+    let mut ctx = Context::new();
+    let modules = resolve(
+        &mut ctx,
+        &[
+            (
+                "./a.js",
+                "import {missing} from './b'; import {dup} from './b'; \
+                 import {dup as same} from './f'; import {loop} from './h';",
+            ),
+            ("./b.js", "export * from './c'; export * from './d';"),
+            ("./c.js", "export const dup = 1;"),
+            ("./d.js", "export const dup = 2;"),
+            // Reaching the same declaration through several `export *` isn't ambiguous.
+            ("./e.js", "export * from './c';"),
+            ("./f.js", "export * from './c'; export * from './e';"),
+            ("./g.js", "export {loop} from './h';"),
+            ("./h.js", "export {loop} from './g';"),
+        ],
+    );
+    let lock = GCLock::new(&mut ctx);
+    let linked = link(&lock, &modules);
+    // `missing` and `dup` in a.js, and `loop` in a.js, g.js and h.js.
+    assert_eq!(lock.sm().num_errors(), 5);
+
+    assert_eq!(
+        imports(&lock, &modules, &linked),
+        vec![
+            ("missing".to_string(), "none".to_string()),
+            ("dup".to_string(), "none".to_string()),
+            ("same".to_string(), "2:dup".to_string()),
+            ("loop".to_string(), "none".to_string()),
+        ]
+    );
+    assert!(linked.exports(SourceId(1)).unwrap().is_empty());
+}
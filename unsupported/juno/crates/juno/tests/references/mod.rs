@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use juno::ast::{builder, node_cast, template, Context, GCLock, Node};
+use juno::hparser::ParsedJS;
+use juno::resolve_dependency::DefaultResolver;
+use juno::sema::{self, DeclId, ReferenceKind, Resolution, SemContext};
+use juno_support::NullTerminatedBuf;
+
+/// Resolve `source` as a module.
+fn resolve(ctx: &mut Context, source: &str) -> SemContext {
+    let id = ctx
+        .sm_mut()
+        .add_source("./main.js", NullTerminatedBuf::from_str_copy(source));
+    let resolver = DefaultResolver::new(ctx.sm());
+    let buf = ctx.sm().source_buffer_rc(id);
+    let parsed = ParsedJS::parse(Default::default(), &buf);
+    let lock = GCLock::new(ctx);
+    let program = node_cast!(Node::Program, parsed.to_ast(&lock, id).unwrap());
+    let module = builder::Module::build_template(
+        &lock,
+        template::Module {
+            metadata: Default::default(),
+            body: program.body,
+        },
+    );
+    let sem = sema::resolve_module(&lock, module, id, &resolver);
+    assert_eq!(lock.sm().num_errors(), 0);
+    sem
+}
+
+/// The declaration named `name`, which must be declared by an identifier.
+fn decl(ctx: &mut Context, sem: &SemContext, name: &str) -> DeclId {
+    let lock = GCLock::new(ctx);
+    let name = lock.atom(name);
+    sem.all_ident_decls()
+        .values()
+        .find_map(|resolution| match resolution {
+            Resolution::Decl(decl) if sem.decl(*decl).name == name => Some(*decl),
+            _ => None,
+        })
+        .unwrap()
+}
+
+/// The kinds of the references to `decl` and whether they initialize it.
+fn references(sem: &SemContext, decl: DeclId) -> Vec<(ReferenceKind, bool)> {
+    sem.references(decl)
+        .iter()
+        .map(|reference| (reference.kind, reference.init))
+        .collect()
+}
+
+#[test]
+fn test_references() {
+    use ReferenceKind::*;
+    let mut ctx = Context::new();
+    let sem = resolve(
+        &mut ctx,
+        "let a = 1; let b; b = 2; var c = 3; let d = 0; d += 1; d++; \
+         let [h, {x: i}] = []; [h] = [1]; for (const j of []) {} typeof a;",
+    );
+
+    let a = decl(&mut ctx, &sem, "a");
+    assert_eq!(references(&sem, a), [(Write, true), (Read, false)]);
+    assert!(sem.is_const_after_init(a));
+    assert!(!sem.is_never_read(a));
+
+    let b = decl(&mut ctx, &sem, "b");
+    assert_eq!(references(&sem, b), [(Write, false)]);
+    assert!(!sem.is_const_after_init(b));
+    assert!(sem.is_never_read(b));
+
+    // `var` initializers are assignments.
+    let c = decl(&mut ctx, &sem, "c");
+    assert_eq!(references(&sem, c), [(Write, false)]);
+    assert!(!sem.is_const_after_init(c));
+
+    let d = decl(&mut ctx, &sem, "d");
+    assert_eq!(
+        references(&sem, d),
+        [(Write, true), (ReadWrite, false), (ReadWrite, false)]
+    );
+    assert!(!sem.is_const_after_init(d));
+    assert!(!sem.is_never_read(d));
+
+    let h = decl(&mut ctx, &sem, "h");
+    assert_eq!(references(&sem, h), [(Write, true), (Write, false)]);
+    assert!(!sem.is_const_after_init(h));
+    let i = decl(&mut ctx, &sem, "i");
+    assert_eq!(references(&sem, i), [(Write, true)]);
+    assert!(sem.is_const_after_init(i));
+    assert!(sem.is_never_read(i));
+
+    let j = decl(&mut ctx, &sem, "j");
+    assert_eq!(references(&sem, j), [(Write, true)]);
+    assert!(sem.is_const_after_init(j));
+}
+
+#[test]
+fn test_captured() {
+    use ReferenceKind::*;
+    let mut ctx = Context::new();
+    let sem = resolve(
+        &mut ctx,
+        "let a = 1; let b = 2; function f(p) { let q = p; return () => a + q; } b; \
+         function g() {} export const e = 3; let k; export {k};",
+    );
+
+    let a = decl(&mut ctx, &sem, "a");
+    assert!(sem.decl(a).captured);
+    let b = decl(&mut ctx, &sem, "b");
+    assert!(!sem.decl(b).captured);
+    let q = decl(&mut ctx, &sem, "q");
+    assert!(sem.decl(q).captured);
+    assert!(sem.is_const_after_init(q));
+
+    // Parameters and functions are initialized without a write.
+    let p = decl(&mut ctx, &sem, "p");
+    assert_eq!(references(&sem, p), [(Read, false)]);
+    assert!(!sem.decl(p).captured);
+    assert!(sem.is_const_after_init(p));
+    let g = decl(&mut ctx, &sem, "g");
+    assert!(references(&sem, g).is_empty());
+    assert!(sem.is_never_read(g));
+    assert!(sem.is_const_after_init(g));
+
+    // Exports are reads.
+    let e = decl(&mut ctx, &sem, "e");
+    assert_eq!(references(&sem, e), [(Write, true), (Read, false)]);
+    assert!(!sem.is_never_read(e));
+    let k = decl(&mut ctx, &sem, "k");
+    assert_eq!(references(&sem, k), [(Read, false)]);
+    assert!(!sem.is_never_read(k));
+}
@@ -0,0 +1,569 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use juno::ast::{builder, node_cast, template, Context, GCLock, Node};
+use juno::hparser::ParsedJS;
+use juno::resolve_dependency::{
+    DefaultResolver, DependencyKind, DependencyResolver, HasteResolver, HasteResolverOptions,
+    MetroResolver, MetroResolverOptions, NodeResolver, NodeResolverOptions,
+};
+use juno::sema;
+use juno_support::source_manager::SourceManager;
+use juno_support::NullTerminatedBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Create a directory named `name` in the temporary directory, containing the
+/// `(path, contents)` files.
+fn create_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("juno_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for (path, contents) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    root
+}
+
+/// Resolve each `(specifier, kind, expected)` from `src/main.js` in `root` with the resolver
+/// created by `new_resolver`, where `expected` is the path of the resolved file relative to
+/// `root`.
+fn assert_resolve<R: DependencyResolver>(
+    root: &Path,
+    new_resolver: impl FnOnce(&SourceManager) -> R,
+    cases: &[(&str, DependencyKind, Option<&str>)],
+) {
+    let mut ctx = Context::new();
+    let main = ctx.sm_mut().add_source(
+        root.join("src/main.js").display().to_string(),
+        NullTerminatedBuf::from_str_copy(""),
+    );
+    let resolver = new_resolver(ctx.sm());
+    assert_eq!(ctx.sm().num_errors(), 0);
+    let lock = GCLock::new(&mut ctx);
+    for &(specifier, kind, expected) in cases {
+        let resolved = resolver
+            .resolve_dependency(&lock, main, specifier, kind)
+            .map(|id| PathBuf::from(lock.sm().source_name(id)));
+        assert_eq!(
+            resolved,
+            expected.map(|path| root.join(path)),
+            "{}",
+            specifier
+        );
+    }
+}
+
+#[test]
+fn test_relative() {
+    let root = create_files(
+        "relative",
+        &[
+            ("src/main.js", ""),
+            ("src/lib.js", ""),
+            ("src/dir/index.js", ""),
+            ("other.mjs", ""),
+        ],
+    );
+    assert_resolve(
+        &root,
+        |_| NodeResolver::new(Default::default()),
+        &[
+            ("./lib", DependencyKind::Import, Some("src/lib.js")),
+            ("./lib.js", DependencyKind::Require, Some("src/lib.js")),
+            ("./dir", DependencyKind::Import, Some("src/dir/index.js")),
+            ("../other", DependencyKind::Import, Some("other.mjs")),
+            ("./missing", DependencyKind::Import, None),
+        ],
+    );
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_packages() {
+    let root = create_files(
+        "packages",
+        &[
+            ("src/main.js", ""),
+            ("node_modules/plain/package.json", r#"{"main": "lib/main"}"#),
+            ("node_modules/plain/lib/main.js", ""),
+            ("node_modules/plain/sub.js", ""),
+            ("node_modules/@scope/pkg/index.js", ""),
+            (
+                "node_modules/exp/package.json",
+                r#"{
+                    "exports": {
+                        ".": {
+                            "react-native": "./src/native.js",
+                            "import": "./src/module.js",
+                            "require": "./src/common.js"
+                        },
+                        "./features/*": "./src/features/*.js"
+                    }
+                }"#,
+            ),
+            ("node_modules/exp/src/native.js", ""),
+            ("node_modules/exp/src/module.js", ""),
+            ("node_modules/exp/src/common.js", ""),
+            ("node_modules/exp/src/features/a.js", ""),
+            ("node_modules/exp/private.js", ""),
+        ],
+    );
+    assert_resolve(
+        &root,
+        |_| NodeResolver::new(Default::default()),
+        &[
+            (
+                "plain",
+                DependencyKind::Import,
+                Some("node_modules/plain/lib/main.js"),
+            ),
+            (
+                "plain/sub",
+                DependencyKind::Import,
+                Some("node_modules/plain/sub.js"),
+            ),
+            (
+                "@scope/pkg",
+                DependencyKind::Import,
+                Some("node_modules/@scope/pkg/index.js"),
+            ),
+            (
+                "exp",
+                DependencyKind::Import,
+                Some("node_modules/exp/src/module.js"),
+            ),
+            (
+                "exp",
+                DependencyKind::Require,
+                Some("node_modules/exp/src/common.js"),
+            ),
+            (
+                "exp/features/a",
+                DependencyKind::Import,
+                Some("node_modules/exp/src/features/a.js"),
+            ),
+            // Only the exported paths of a package can be imported.
+            ("exp/private", DependencyKind::Import, None),
+            ("missing", DependencyKind::Import, None),
+        ],
+    );
+    assert_resolve(
+        &root,
+        |_| {
+            NodeResolver::new(NodeResolverOptions {
+                conditions: vec!["react-native".to_string()],
+                ..Default::default()
+            })
+        },
+        &[(
+            "exp",
+            DependencyKind::Require,
+            Some("node_modules/exp/src/native.js"),
+        )],
+    );
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_browser() {
+    let root = create_files(
+        "browser",
+        &[
+            (
+                "package.json",
+                r#"{"browser": {"fs": false, "shimmed": "./src/shim.js"}}"#,
+            ),
+            ("src/main.js", ""),
+            ("src/shim.js", ""),
+            (
+                "node_modules/pkg/package.json",
+                r#"{"main": "server.js", "browser": "client.js"}"#,
+            ),
+            ("node_modules/pkg/server.js", ""),
+            ("node_modules/pkg/client.js", ""),
+            (
+                "node_modules/files/package.json",
+                r#"{"browser": {"./lib/node.js": "./lib/web.js"}}"#,
+            ),
+            ("node_modules/files/lib/node.js", ""),
+            ("node_modules/files/lib/web.js", ""),
+        ],
+    );
+    assert_resolve(
+        &root,
+        |_| {
+            NodeResolver::new(NodeResolverOptions {
+                browser: true,
+                ..Default::default()
+            })
+        },
+        &[
+            (
+                "pkg",
+                DependencyKind::Import,
+                Some("node_modules/pkg/client.js"),
+            ),
+            (
+                "files/lib/node",
+                DependencyKind::Import,
+                Some("node_modules/files/lib/web.js"),
+            ),
+            ("shimmed", DependencyKind::Require, Some("src/shim.js")),
+            ("fs", DependencyKind::Require, None),
+        ],
+    );
+    assert_resolve(
+        &root,
+        |_| NodeResolver::new(Default::default()),
+        &[(
+            "pkg",
+            DependencyKind::Import,
+            Some("node_modules/pkg/server.js"),
+        )],
+    );
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_haste() {
+    let root = create_files(
+        "haste",
+        &[
+            ("src/main.js", ""),
+            ("lib/Bar.js", "/**\n * @providesModule Foo\n */\nfoo();\n"),
+            (
+                "lib/Script.js",
+                "#!/usr/bin/env node\n'use strict';\n/* @providesModule Tool */\n",
+            ),
+            ("lib/Late.js", "late();\n/** @providesModule Other */\n"),
+            ("lib/Baz.js", ""),
+            ("lib/Baz.ios.js", ""),
+            ("lib/Baz.android.js", ""),
+            ("lib/View.js", ""),
+            ("lib/View.native.js", ""),
+            ("node_modules/pkg/Qux.js", ""),
+        ],
+    );
+    let options = HasteResolverOptions {
+        roots: vec![root.clone()],
+        platform: Some("ios".to_string()),
+        ..Default::default()
+    };
+    assert_resolve(
+        &root,
+        |sm| HasteResolver::new(sm, options.clone()),
+        &[
+            ("Foo", DependencyKind::Require, Some("lib/Bar.js")),
+            ("Bar", DependencyKind::Require, None),
+            ("Tool", DependencyKind::Require, Some("lib/Script.js")),
+            ("Other", DependencyKind::Require, None),
+            ("Late", DependencyKind::Require, Some("lib/Late.js")),
+            ("Baz", DependencyKind::Import, Some("lib/Baz.ios.js")),
+            ("View", DependencyKind::Import, Some("lib/View.native.js")),
+            ("../lib/Baz", DependencyKind::Import, Some("lib/Baz.js")),
+            ("Qux", DependencyKind::Import, None),
+        ],
+    );
+    assert_resolve(
+        &root,
+        |sm| {
+            HasteResolver::new(
+                sm,
+                HasteResolverOptions {
+                    platform: Some("web".to_string()),
+                    ..options.clone()
+                },
+            )
+        },
+        &[
+            ("Baz", DependencyKind::Import, Some("lib/Baz.js")),
+            ("View", DependencyKind::Import, Some("lib/View.js")),
+        ],
+    );
+    assert_resolve(
+        &root,
+        |sm| {
+            HasteResolver::new(
+                sm,
+                HasteResolverOptions {
+                    platform: None,
+                    ..options
+                },
+            )
+        },
+        &[
+            ("Baz", DependencyKind::Import, Some("lib/Baz.js")),
+            ("View", DependencyKind::Import, Some("lib/View.js")),
+        ],
+    );
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_haste_duplicates() {
+    let root = create_files(
+        "haste_duplicates",
+        &[("a/Foo.js", ""), ("b/Foo.js", ""), ("b/Foo.ios.js", "")],
+    );
+    let ctx = Context::new();
+    HasteResolver::new(
+        ctx.sm(),
+        HasteResolverOptions {
+            roots: vec![root.clone()],
+            ..Default::default()
+        },
+    );
+    assert_eq!(ctx.sm().num_errors(), 1);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_haste_cache() {
+    let root = create_files(
+        "haste_cache",
+        &[
+            ("src/main.js", ""),
+            ("lib/Bar.js", "/** @providesModule Foo */\nfoo();\n"),
+        ],
+    );
+    let options = HasteResolverOptions {
+        roots: vec![root.clone()],
+        cache_path: Some(root.join("cache.json")),
+        ..Default::default()
+    };
+    assert_resolve(
+        &root,
+        |sm| HasteResolver::new(sm, options.clone()),
+        &[("Foo", DependencyKind::Require, Some("lib/Bar.js"))],
+    );
+
+    // Files which haven't been modified since they were cached aren't scanned again.
+    let cache = fs::read_to_string(root.join("cache.json")).unwrap();
+    assert!(cache.contains(r#""providesModule":"Foo""#), "{}", cache);
+    let cache = cache.replace(r#""providesModule":"Foo""#, r#""providesModule":"Cached""#);
+    fs::write(root.join("cache.json"), cache).unwrap();
+    assert_resolve(
+        &root,
+        |sm| HasteResolver::new(sm, options),
+        &[
+            ("Cached", DependencyKind::Require, Some("lib/Bar.js")),
+            ("Foo", DependencyKind::Require, None),
+        ],
+    );
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_metro() {
+    let root = create_files(
+        "metro",
+        &[
+            ("src/main.js", ""),
+            ("src/Button.js", ""),
+            ("src/Button.ios.js", ""),
+            ("src/Button.native.js", ""),
+            ("src/List.jsx", ""),
+            ("src/List.android.js", ""),
+            ("src/components/index.ios.js", ""),
+            ("src/app/screens/Home.js", ""),
+            (
+                "node_modules/pkg/package.json",
+                r#"{"main": "main.js", "react-native": "native.js"}"#,
+            ),
+            ("node_modules/pkg/main.js", ""),
+            ("node_modules/pkg/native.js", ""),
+        ],
+    );
+    let options = |platform: Option<&str>| MetroResolverOptions {
+        platform: platform.map(str::to_string),
+        aliases: vec![
+            ("@app/*".to_string(), "./src/app/*".to_string()),
+            ("package".to_string(), "pkg".to_string()),
+        ],
+        root: root.clone(),
+        ..Default::default()
+    };
+    assert_resolve(
+        &root,
+        |_| MetroResolver::new(options(Some("ios"))),
+        &[
+            (
+                "./Button",
+                DependencyKind::Import,
+                Some("src/Button.ios.js"),
+            ),
+            ("./Button.js", DependencyKind::Import, Some("src/Button.js")),
+            ("./List", DependencyKind::Import, Some("src/List.jsx")),
+            (
+                "./components",
+                DependencyKind::Import,
+                Some("src/components/index.ios.js"),
+            ),
+            (
+                "@app/screens/Home",
+                DependencyKind::Import,
+                Some("src/app/screens/Home.js"),
+            ),
+            ("@app/missing", DependencyKind::Import, None),
+            (
+                "pkg",
+                DependencyKind::Require,
+                Some("node_modules/pkg/native.js"),
+            ),
+            (
+                "package",
+                DependencyKind::Require,
+                Some("node_modules/pkg/native.js"),
+            ),
+        ],
+    );
+    assert_resolve(
+        &root,
+        |_| MetroResolver::new(options(Some("android"))),
+        &[
+            (
+                "./Button",
+                DependencyKind::Import,
+                Some("src/Button.native.js"),
+            ),
+            (
+                "./List",
+                DependencyKind::Import,
+                Some("src/List.android.js"),
+            ),
+            ("./components", DependencyKind::Import, None),
+        ],
+    );
+    assert_resolve(
+        &root,
+        |_| MetroResolver::new(options(Some("web"))),
+        &[("./Button", DependencyKind::Import, Some("src/Button.js"))],
+    );
+}
+
+#[test]
+fn test_metro_config() {
+    let root = create_files(
+        "metro_config",
+        &[
+            ("src/main.js", ""),
+            ("src/lib/util.ts", ""),
+            ("src/lib/util.js", ""),
+            (
+                "config.json",
+                r#"{"platform": "ios", "sourceExts": ["ts", "js"], "alias": {"~/*": "./src/*"}}"#,
+            ),
+            ("bad.json", r#"{"sourceExts": "js"}"#),
+        ],
+    );
+    let options = MetroResolverOptions::from_config(&root.join("config.json")).unwrap();
+    assert_eq!(options.platform.as_deref(), Some("ios"));
+    assert_eq!(options.source_exts, vec!["ts", "js"]);
+    assert_eq!(options.root, root);
+    assert_resolve(
+        &root,
+        |_| MetroResolver::new(options),
+        &[(
+            "~/lib/util",
+            DependencyKind::Import,
+            Some("src/lib/util.ts"),
+        )],
+    );
+
+    assert!(MetroResolverOptions::from_config(&root.join("bad.json")).is_err());
+    assert!(MetroResolverOptions::from_config(&root.join("missing.json")).is_err());
+}
+
+#[test]
+fn test_metro_candidates() {
+    let root = create_files("metro_candidates", &[("src/main.js", "")]);
+    let mut ctx = Context::new();
+    let main = ctx.sm_mut().add_source(
+        root.join("src/main.js").display().to_string(),
+        NullTerminatedBuf::from_str_copy(""),
+    );
+    let resolver = MetroResolver::new(MetroResolverOptions {
+        platform: Some("ios".to_string()),
+        source_exts: vec!["js".to_string()],
+        ..Default::default()
+    });
+    let lock = GCLock::new(&mut ctx);
+    let tried = resolver.tried_candidates(&lock, main, "./missing", DependencyKind::Import);
+    let expected: Vec<String> = [
+        "missing",
+        "missing.ios.js",
+        "missing.native.js",
+        "missing.js",
+    ]
+    .iter()
+    .map(|path| root.join("src").join(path).display().to_string())
+    .collect();
+    assert_eq!(tried, expected);
+}
+
+#[test]
+fn test_dependencies() {
+    let mut ctx = Context::new();
+    let lib = ctx
+        .sm_mut()
+        .add_source("./lib.js", NullTerminatedBuf::from_str_copy(""));
+    let main = ctx.sm_mut().add_source(
+        "./main.js",
+        NullTerminatedBuf::from_str_copy(
+            r#"
+                import './lib';
+                export * from './lib';
+                import('./lib');
+                require('./lib');
+                require.resolve('./lib');
+                require.async('./missing');
+                require.other('./lib');
+                require(name);
+            "#,
+        ),
+    );
+    let resolver = DefaultResolver::new(ctx.sm());
+    let buf = ctx.sm().source_buffer_rc(main);
+    let parsed = ParsedJS::parse(Default::default(), &buf);
+    let lock = GCLock::new(&mut ctx);
+    let program = node_cast!(Node::Program, parsed.to_ast(&lock, main).unwrap());
+    let module = builder::Module::build_template(
+        &lock,
+        template::Module {
+            metadata: Default::default(),
+            body: program.body,
+        },
+    );
+    let sem = sema::resolve_module(&lock, module, main, &resolver);
+
+    let dependencies: Vec<_> = sem
+        .dependencies()
+        .iter()
+        .map(|dep| (dep.kind, dep.specifier.as_str(), dep.target))
+        .collect();
+    assert_eq!(
+        dependencies,
+        vec![
+            (DependencyKind::Import, "./lib", Some(lib)),
+            (DependencyKind::Import, "./lib", Some(lib)),
+            (DependencyKind::DynamicImport, "./lib", Some(lib)),
+            (DependencyKind::Require, "./lib", Some(lib)),
+            (DependencyKind::RequireResolve, "./lib", Some(lib)),
+            (DependencyKind::AsyncRequire, "./missing", None),
+        ]
+    );
+    let lazy: Vec<_> = sem
+        .lazy_dependencies()
+        .map(|dep| dep.specifier.as_str())
+        .collect();
+    assert_eq!(lazy, vec!["./lib", "./missing"]);
+    let range = sem.dependencies()[2].range;
+    assert_eq!((range.start.line, range.start.col), (4, 24));
+    assert_eq!(lock.sm().num_errors(), 0);
+}
@@ -0,0 +1,59 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Comments attached to AST nodes.
+
+use juno_support::source_manager::SourceRange;
+
+/// The kind of a comment, which determines its delimiters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    /// Comment that begins with `//`.
+    Line,
+    /// Comment that is delimited by `/*` and `*/`.
+    Block,
+    /// Comment that begins with `#!` and starts at the first byte of the file.
+    Hashbang,
+}
+
+/// A single comment from the source.
+#[derive(Debug, Clone)]
+pub struct Comment {
+    pub kind: CommentKind,
+    /// Text of the comment, without the delimiters.
+    pub value: String,
+    /// Location of the comment, including the delimiters.
+    pub range: SourceRange,
+}
+
+impl Comment {
+    /// Whether the comment must be followed by a line break.
+    pub fn ends_line(&self) -> bool {
+        self.kind != CommentKind::Block
+    }
+}
+
+/// All comments attached to a single node, in source order.
+///
+/// Allocated in the [`Context`](crate::Context) and referenced by the `NodeMetadata`,
+/// so they are kept by nodes which are rebuilt from the original node.
+#[derive(Debug, Clone, Default)]
+pub struct NodeComments {
+    /// Comments preceding the node.
+    pub leading: Vec<Comment>,
+    /// Comments inside the node which don't precede any of its children,
+    /// such as the comments in an empty block.
+    pub inner: Vec<Comment>,
+    /// Comments following the node on the same line.
+    pub trailing: Vec<Comment>,
+}
+
+impl NodeComments {
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.inner.is_empty() && self.trailing.is_empty()
+    }
+}
@@ -7,7 +7,7 @@
 
 //! Garbage-collected Storage structures for AST nodes.
 
-use crate::{Node, Path, SourceManager, Visitor};
+use crate::{Node, NodeComments, Path, SourceManager, Visitor};
 use juno_support::atom_table::{Atom, AtomTable, AtomU16};
 use juno_support::{Deque, HeapSize};
 use libc::c_void;
@@ -144,6 +144,10 @@ pub struct Context<'ast> {
     /// Free list for `NodeListElement`s.
     free_list_elements: UnsafeCell<Vec<NonNull<NodeListElement<'ast>>>>,
 
+    /// Comments attached to nodes in this context.
+    /// They are never freed, because they may be shared by any number of nodes.
+    comments: UnsafeCell<Deque<NodeComments>>,
+
     /// `NodeRc` count stored in a `Box` to ensure that `NodeRc`s can also point to it
     /// and decrement the count on drop.
     /// Placed separately to guard against `Context` moving, though relying on that behavior is
@@ -184,6 +188,7 @@ impl<'ast> Context<'ast> {
             free_nodes: Default::default(),
             list_elements: Default::default(),
             free_list_elements: Default::default(),
+            comments: Default::default(),
             noderc_count: Pin::new(Box::new(NodeRcCounter {
                 ctx_id: id,
                 count: Cell::new(0),
@@ -248,8 +253,9 @@ impl<'ast> Context<'ast> {
             entry.ctx_id_markbit.set(self.id);
             entry.set_markbit(!self.markbit_marked);
             entry.inner = node;
+            // The freed element may still point to its old successor.
+            entry.next.set(std::ptr::null());
             if let Some(prev) = prev {
-                entry.next.set(std::ptr::null());
                 prev.next.set(entry as *const _);
             }
             entry
@@ -271,6 +277,12 @@ impl<'ast> Context<'ast> {
         unsafe { std::mem::transmute(entry) }
     }
 
+    /// Allocate comments which can be attached to any number of nodes in this `Context`.
+    pub(crate) fn alloc_comments(&self, comments: NodeComments) -> &NodeComments {
+        let storage: &mut Deque<NodeComments> = unsafe { &mut *self.comments.get() };
+        storage.push(comments)
+    }
+
     /// Return the atom table.
     pub fn atom_table(&self) -> &AtomTable {
         &self.atom_table
@@ -452,11 +464,13 @@ impl HeapSize for Context<'_> {
         let free_nodes = unsafe { &*self.free_nodes.get() };
         let list_elements = unsafe { &*self.list_elements.get() };
         let free_list_elements = unsafe { &*self.free_list_elements.get() };
+        let comments = unsafe { &*self.comments.get() };
         let mut result = 0;
         result += nodes.heap_size();
         result += free_nodes.heap_size();
         result += list_elements.heap_size();
         result += free_list_elements.heap_size();
+        result += comments.heap_size();
         result += std::mem::size_of::<NodeRcCounter>();
         result += self.atom_table.heap_size();
         result += self.source_mgr.heap_size();
@@ -545,6 +559,12 @@ impl<'ast, 'ctx> GCLock<'ast, 'ctx> {
         self.ctx.append_list_element(prev, n)
     }
 
+    /// Allocate comments in the `ctx`, to be attached to nodes via their metadata.
+    #[inline]
+    pub fn alloc_comments(&self, comments: NodeComments) -> &NodeComments {
+        self.ctx.alloc_comments(comments)
+    }
+
     /// Return a reference to the owning Context.
     pub fn ctx(&self) -> &Context<'ast> {
         self.ctx
@@ -248,6 +248,16 @@ macro_rules! gen_nodekind_enum {
                 }
             }
 
+            /// Return the comments attached to this node, if any.
+            #[inline]
+            pub fn comments(&self) -> Option<&'gc NodeComments> {
+                match self {
+                    $(
+                        Self::$kind($kind { metadata, .. }) => metadata.comments
+                    ),*
+                }
+            }
+
             #[inline]
             pub fn range_mut(&mut self) -> &mut SourceRange {
                 match self {
@@ -402,6 +412,7 @@ macro_rules! gen_nodekind_enum {
                             metadata: NodeMetadata {
                                 phantom: node.metadata.phantom,
                                 range: node.metadata.range,
+                                comments: node.metadata.comments,
                             },
                             $($(
                                 $field: (&node.$field).duplicate(),
@@ -435,6 +446,7 @@ macro_rules! gen_nodekind_enum {
                             metadata: NodeMetadata {
                                 phantom: node.metadata.phantom,
                                 range: node.metadata.range,
+                                comments: node.metadata.comments,
                             },
                             $($(
                                 $field: (&node.$field).duplicate(),
@@ -35,6 +35,7 @@ use thiserror::Error;
 #[macro_use]
 mod def;
 
+mod comments;
 mod context;
 mod dump;
 mod field;
@@ -46,6 +47,7 @@ pub use juno_support::source_manager::{SourceId, SourceLoc, SourceManager, Sourc
 pub use field::NodeField;
 pub use kind::NodeVariant;
 
+pub use comments::{Comment, CommentKind, NodeComments};
 pub use context::{Context, GCLock, NodePtr, NodeRc};
 pub use dump::{dump_json, Pretty};
 pub use kind::*;
@@ -113,6 +115,8 @@ pub trait VisitorMut<'gc> {
 pub struct NodeMetadata<'a> {
     phantom: PhantomData<&'a Node<'a>>,
     pub range: SourceRange,
+    /// Comments attached to the node, if any.
+    pub comments: Option<&'a NodeComments>,
 }
 
 impl<'a> NodeMetadata<'a> {
@@ -120,6 +124,7 @@ impl<'a> NodeMetadata<'a> {
         NodeMetadata {
             phantom: template.phantom,
             range: template.range,
+            comments: template.comments,
         }
     }
 }
@@ -130,6 +135,7 @@ impl<'a> NodeMetadata<'a> {
 pub struct TemplateMetadata<'a> {
     pub phantom: PhantomData<&'a Node<'a>>,
     pub range: SourceRange,
+    pub comments: Option<&'a NodeComments>,
 }
 
 impl Default for TemplateMetadata<'_> {
@@ -141,6 +147,7 @@ impl Default for TemplateMetadata<'_> {
                 start: SourceLoc::invalid(),
                 end: SourceLoc::invalid(),
             },
+            comments: None,
         }
     }
 }
@@ -150,6 +157,7 @@ impl<'a> From<&NodeMetadata<'a>> for TemplateMetadata<'a> {
         Self {
             phantom: Default::default(),
             range: metadata.range,
+            comments: metadata.comments,
         }
     }
 }
@@ -159,6 +167,7 @@ impl<'a> From<SourceRange> for TemplateMetadata<'a> {
         Self {
             phantom: Default::default(),
             range,
+            comments: None,
         }
     }
 }
@@ -661,6 +670,7 @@ impl<'gc> NodeChild<'gc> for &'gc Node<'gc> {
                                 start: self.range().start,
                                 end: self.range().start,
                             },
+                            comments: None,
                         },
                     },
                 ))
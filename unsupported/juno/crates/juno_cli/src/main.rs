@@ -8,16 +8,24 @@
 use anyhow::{self, ensure, Context, Error};
 use command_line::{CommandLine, Hidden, Opt, OptDesc};
 use juno::ast::{self, node_cast, validate_tree, NodeRc, SourceRange};
+use juno::dependency_graph::DependencyGraph;
 use juno::hparser::{self, MagicCommentKind, ParsedJS, ParserDialect};
-use juno::sema::SemContext;
-use juno::sourcemap::merge_sourcemaps;
-use juno::{gen_js, resolve_dependency, sema};
-use juno_pass::PassManager;
+use juno::resolve_dependency::{
+    DefaultResolver, DependencyResolver, HasteResolver, HasteResolverOptions, MetroResolver,
+    MetroResolverOptions, NodeResolver, NodeResolverOptions,
+};
+use juno::sema::{LinkModule, SemContext};
+use juno::sourcemap::{merge_sourcemaps, merge_sourcemaps_by_source};
+use juno::{gen_js, sema};
+use juno_pass::{
+    BundleFormat, Bundler, CommonJsOptions, Define, HoistModule, JsxOptions, JsxRuntime,
+    PassManager, ScopeHoisting, ShakeModule, TreeShakeOptions, TreeShaking,
+};
 use juno_support::source_manager::SourceId;
 use juno_support::{fetchurl, Timer};
 use juno_support::{HeapSize, NullTerminatedBuf};
 use sourcemap::SourceMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Write;
 use std::ops::DerefMut;
@@ -37,6 +45,14 @@ enum Gen {
     Js,
     /// Generate JavaScript source with annotations about variable resolution.
     ResolvedJs,
+    /// Dump the graph of the dependencies between modules.
+    Deps,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum DepsFormat {
+    Json,
+    Dot,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -45,13 +61,40 @@ enum InputSourceMap {
     Auto,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Resolver {
+    /// Resolve relative paths and file names among the inputs.
+    Default,
+    /// Resolve like Node.js, loading files from `node_modules` as needed.
+    Node,
+    /// Resolve globally unique module names, like Haste.
+    Haste,
+    /// Resolve platform-specific files and aliases, like Metro.
+    Metro,
+}
+
 struct Options {
     /// Enable pretty printing.
     pretty: Opt<bool>,
 
+    /// Minify the generated JS.
+    minify: Opt<bool>,
+
+    /// Maximum line width when pretty printing.
+    print_width: Opt<Option<usize>>,
+
+    /// Number of columns in each level of indentation.
+    indent_width: Opt<usize>,
+
+    /// Indent with tabs instead of spaces.
+    use_tabs: Opt<bool>,
+
     /// Select what to emit.
     gen: Opt<Gen>,
 
+    /// Format of the dependency graph.
+    deps_format: Opt<DepsFormat>,
+
     /// Perform AST validation.
     validate_ast: Opt<bool>,
 
@@ -80,9 +123,75 @@ struct Options {
     /// Whether to run optimization passes.
     optimize: Opt<bool>,
 
+    /// Whether to remove unreachable code and unused declarations.
+    dce: Opt<bool>,
+
     /// Whether to run strip flow types.
     strip_flow: Opt<bool>,
 
+    /// Whether to strip TypeScript types.
+    strip_ts: Opt<bool>,
+
+    /// Whether to lower JSX to function calls.
+    transform_jsx: Opt<bool>,
+
+    /// Which functions lowered JSX calls.
+    jsx_runtime: Opt<JsxRuntime>,
+
+    /// Globals to replace with literals.
+    define: Opt<Define>,
+
+    /// Whether to convert ES modules to CommonJS.
+    commonjs: Opt<bool>,
+
+    /// Whether converted modules call `require` where the imported bindings are used.
+    inline_requires: Opt<bool>,
+
+    /// Whether to remove the exports which aren't imported by any module.
+    tree_shake: Opt<bool>,
+
+    /// Modules all of whose exports are used when tree shaking, and which the bundle runs.
+    entry: Opt<PathBuf>,
+
+    /// Glob patterns for the modules which may have side effects when tree shaking.
+    side_effects: Opt<String>,
+
+    /// Whether to load the dependencies of the inputs, and theirs, found by the resolver.
+    follow_deps: Opt<bool>,
+
+    /// How the dependencies of modules are resolved.
+    resolver: Opt<Resolver>,
+
+    /// Conditions of the `exports` field of `package.json` which apply.
+    conditions: Opt<String>,
+
+    /// Whether to use the `browser` field of `package.json`.
+    browser: Opt<bool>,
+
+    /// Directories whose JS files are modules with Haste resolution.
+    haste_roots: Opt<PathBuf>,
+
+    /// Platform whose variants of modules are preferred.
+    platform: Opt<Option<String>>,
+
+    /// File caching the module names of Haste resolution.
+    haste_cache: Opt<Option<PathBuf>>,
+
+    /// JSON config of Metro resolution.
+    resolver_config: Opt<Option<PathBuf>>,
+
+    /// Whether to bundle the inputs into a single script.
+    bundle: Opt<bool>,
+
+    /// How the modules of the bundle are defined.
+    bundle_format: Opt<BundleFormat>,
+
+    /// Whether to concatenate ES modules into the scope of the modules importing them.
+    scope_hoist: Opt<bool>,
+
+    /// Whether to rename local variables to short names.
+    mangle: Opt<bool>,
+
     /// Whether to force a space after the `async` keyword in arrow functions.
     force_async_arrow_space: Opt<bool>,
 
@@ -90,6 +199,9 @@ struct Options {
     /// The doc block contains every comment prior to the first non-directive token in the file.
     emit_doc_block: Opt<bool>,
 
+    /// Whether to preserve all comments when generating JS.
+    preserve_comments: Opt<bool>,
+
     /// Whether to use double quotes on string literals.
     double_quote_strings: Opt<bool>,
 
@@ -131,6 +243,45 @@ impl Options {
                     ..Default::default()
                 },
             ),
+            minify: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("minify"),
+                    desc: Some("Generate minified JS, overriding pretty printing."),
+                    category: output_cat,
+                    ..Default::default()
+                },
+            ),
+            print_width: Opt::<Option<usize>>::new_optional(
+                cl,
+                OptDesc {
+                    long: Some("print-width"),
+                    desc: Some("Break lines longer than this width when pretty printing."),
+                    value_desc: Some("N"),
+                    category: output_cat,
+                    ..Default::default()
+                },
+            ),
+            indent_width: Opt::<usize>::new(
+                cl,
+                OptDesc {
+                    long: Some("indent-width"),
+                    desc: Some("Number of spaces per indentation level (default: 2)."),
+                    value_desc: Some("N"),
+                    init: Some(2),
+                    category: output_cat,
+                    ..Default::default()
+                },
+            ),
+            use_tabs: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("use-tabs"),
+                    desc: Some("Indent with tabs instead of spaces."),
+                    category: output_cat,
+                    ..Default::default()
+                },
+            ),
             gen: Opt::new_enum(
                 cl,
                 OptDesc {
@@ -144,6 +295,24 @@ impl Options {
                             Gen::ResolvedJs,
                             "Generate resolution information.",
                         ),
+                        ("gen-deps", Gen::Deps, "Dump the module dependency graph."),
+                    ]),
+                    category: output_cat,
+                    ..Default::default()
+                },
+            ),
+            deps_format: Opt::new_enum(
+                cl,
+                OptDesc {
+                    long: Some("deps-format"),
+                    desc: Some("Format of the dependency graph (default: json)."),
+                    values: Some(&[
+                        (
+                            "json",
+                            DepsFormat::Json,
+                            "Modules, dependencies and cycles as JSON",
+                        ),
+                        ("dot", DepsFormat::Dot, "Graphviz DOT"),
                     ]),
                     category: output_cat,
                     ..Default::default()
@@ -236,6 +405,14 @@ impl Options {
                     ..Default::default()
                 },
             ),
+            dce: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("dce"),
+                    desc: Some("Remove unreachable code and unused declarations"),
+                    ..Default::default()
+                },
+            ),
             strip_flow: Opt::new_bool(
                 cl,
                 OptDesc {
@@ -244,6 +421,245 @@ impl Options {
                     ..Default::default()
                 },
             ),
+            strip_ts: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("strip-ts"),
+                    desc: Some("Strip TypeScript types"),
+                    ..Default::default()
+                },
+            ),
+            transform_jsx: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("transform-jsx"),
+                    desc: Some("Lower JSX to function calls"),
+                    ..Default::default()
+                },
+            ),
+            jsx_runtime: Opt::new_enum(
+                cl,
+                OptDesc {
+                    long: Some("jsx-runtime"),
+                    desc: Some("Runtime called by lowered JSX (default: classic)."),
+                    values: Some(&[
+                        (
+                            "classic",
+                            JsxRuntime::Classic,
+                            "React.createElement or the @jsx pragma",
+                        ),
+                        (
+                            "automatic",
+                            JsxRuntime::Automatic,
+                            "jsx and jsxs imported from react/jsx-runtime",
+                        ),
+                    ]),
+                    ..Default::default()
+                },
+            ),
+            define: Opt::new_list(
+                cl,
+                OptDesc {
+                    long: Some("define"),
+                    desc: Some(
+                        "Replace a global like __DEV__ or process.env.NODE_ENV with a literal",
+                    ),
+                    value_desc: Some("NAME=VALUE"),
+                    ..Default::default()
+                },
+            ),
+            commonjs: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("commonjs"),
+                    desc: Some("Convert ES modules to CommonJS"),
+                    ..Default::default()
+                },
+            ),
+            inline_requires: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("inline-requires"),
+                    desc: Some("Require modules where the imported bindings are used"),
+                    ..Default::default()
+                },
+            ),
+            tree_shake: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("tree-shake"),
+                    desc: Some("Remove the exports which aren't imported by any module"),
+                    ..Default::default()
+                },
+            ),
+            entry: Opt::new_list(
+                cl,
+                OptDesc {
+                    long: Some("entry"),
+                    desc: Some(
+                        "Input whose exports are all used when tree shaking and which is run by \
+                        the bundle (default: the first)",
+                    ),
+                    value_desc: Some("path"),
+                    ..Default::default()
+                },
+            ),
+            side_effects: Opt::new_list(
+                cl,
+                OptDesc {
+                    long: Some("side-effects"),
+                    desc: Some(
+                        "Glob pattern for the modules which may have side effects when tree \
+                        shaking (default: all modules)",
+                    ),
+                    value_desc: Some("pattern"),
+                    ..Default::default()
+                },
+            ),
+            follow_deps: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("follow-deps"),
+                    desc: Some(
+                        "Load the modules which the inputs depend on, and their dependencies, \
+                        as found by the resolver",
+                    ),
+                    ..Default::default()
+                },
+            ),
+            resolver: Opt::new_enum(
+                cl,
+                OptDesc {
+                    long: Some("resolver"),
+                    desc: Some("How the dependencies of modules are resolved (default: default)."),
+                    values: Some(&[
+                        (
+                            "default",
+                            Resolver::Default,
+                            "Relative paths and file names of the inputs",
+                        ),
+                        (
+                            "node",
+                            Resolver::Node,
+                            "Node.js resolution, loading files from node_modules",
+                        ),
+                        (
+                            "haste",
+                            Resolver::Haste,
+                            "Names from @providesModule or file names, like Haste",
+                        ),
+                        (
+                            "metro",
+                            Resolver::Metro,
+                            "Platform-specific files and aliases, like Metro",
+                        ),
+                    ]),
+                    ..Default::default()
+                },
+            ),
+            conditions: Opt::new_list(
+                cl,
+                OptDesc {
+                    long: Some("condition"),
+                    desc: Some(
+                        "Condition of package.json exports which applies with --resolver=node, \
+                        such as react-native",
+                    ),
+                    value_desc: Some("name"),
+                    ..Default::default()
+                },
+            ),
+            browser: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("browser"),
+                    desc: Some("Use the browser field of package.json with --resolver=node"),
+                    ..Default::default()
+                },
+            ),
+            haste_roots: Opt::new_list(
+                cl,
+                OptDesc {
+                    long: Some("haste-root"),
+                    desc: Some("Directory whose JS files are modules with --resolver=haste"),
+                    value_desc: Some("path"),
+                    ..Default::default()
+                },
+            ),
+            platform: Opt::<Option<String>>::new_optional(
+                cl,
+                OptDesc {
+                    long: Some("platform"),
+                    desc: Some("Prefer variants of modules like Foo.ios.js for the platform"),
+                    value_desc: Some("name"),
+                    ..Default::default()
+                },
+            ),
+            haste_cache: Opt::<Option<PathBuf>>::new_optional(
+                cl,
+                OptDesc {
+                    long: Some("haste-cache"),
+                    desc: Some("File caching the module names of --resolver=haste between runs"),
+                    value_desc: Some("path"),
+                    ..Default::default()
+                },
+            ),
+            resolver_config: Opt::<Option<PathBuf>>::new_optional(
+                cl,
+                OptDesc {
+                    long: Some("resolver-config"),
+                    desc: Some(
+                        "JSON config of the platform, source extensions and aliases with \
+                         --resolver=metro",
+                    ),
+                    value_desc: Some("path"),
+                    ..Default::default()
+                },
+            ),
+            bundle: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("bundle"),
+                    desc: Some("Bundle the inputs into a single script"),
+                    ..Default::default()
+                },
+            ),
+            bundle_format: Opt::new_enum(
+                cl,
+                OptDesc {
+                    long: Some("bundle-format"),
+                    desc: Some("How the modules of the bundle are defined (default: metro)."),
+                    values: Some(&[
+                        (
+                            "metro",
+                            BundleFormat::Metro,
+                            "__d(factory, moduleId, dependencyMap) like Metro",
+                        ),
+                        (
+                            "closure",
+                            BundleFormat::Closure,
+                            "Factories passed to a function which runs them",
+                        ),
+                    ]),
+                    ..Default::default()
+                },
+            ),
+            scope_hoist: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("scope-hoist"),
+                    desc: Some("Concatenate bundled ES modules into the modules importing them"),
+                    ..Default::default()
+                },
+            ),
+            mangle: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("mangle"),
+                    desc: Some("Rename local variables to short names"),
+                    ..Default::default()
+                },
+            ),
             force_async_arrow_space: Opt::new_bool(
                 cl,
                 OptDesc {
@@ -262,6 +678,14 @@ impl Options {
                     ..Default::default()
                 },
             ),
+            preserve_comments: Opt::new_bool(
+                cl,
+                OptDesc {
+                    long: Some("preserve-comments"),
+                    desc: Some("Preserve the comments from the original files when generating JS"),
+                    ..Default::default()
+                },
+            ),
             double_quote_strings: Opt::new_bool(
                 cl,
                 OptDesc {
@@ -362,6 +786,64 @@ impl Options {
             );
             ensure!(*self.gen == Gen::Js, "Source map requires JS output");
         }
+        if *self.inline_requires {
+            ensure!(
+                *self.commonjs || *self.bundle,
+                "Inline requires need --commonjs or --bundle"
+            );
+        }
+        if *self.tree_shake {
+            ensure!(*self.sema, "Tree shaking needs --sema");
+        } else {
+            ensure!(
+                self.side_effects.num_values() == 0,
+                "Side effects need --tree-shake"
+            );
+        }
+        if *self.follow_deps {
+            ensure!(*self.sema, "Following dependencies needs --sema");
+        }
+        if *self.resolver != Resolver::Node {
+            ensure!(
+                self.conditions.num_values() == 0 && !*self.browser,
+                "Conditions and the browser field need --resolver=node"
+            );
+        }
+        if *self.resolver != Resolver::Haste {
+            ensure!(
+                self.haste_roots.num_values() == 0 && self.haste_cache.is_none(),
+                "Haste roots and caches need --resolver=haste"
+            );
+        }
+        if *self.resolver != Resolver::Metro {
+            ensure!(
+                self.resolver_config.is_none(),
+                "Resolver configs need --resolver=metro"
+            );
+        }
+        if *self.resolver != Resolver::Haste && *self.resolver != Resolver::Metro {
+            ensure!(
+                self.platform.is_none(),
+                "Platforms need --resolver=haste or --resolver=metro"
+            );
+        }
+        if *self.gen == Gen::Deps {
+            ensure!(*self.sema, "Dependency graphs need --sema");
+            ensure!(
+                !*self.bundle && !*self.tree_shake,
+                "Dependency graphs can't be generated with --bundle or --tree-shake"
+            );
+        }
+        if *self.bundle {
+            ensure!(*self.sema, "Bundling needs --sema");
+            ensure!(*self.gen == Gen::Js, "Bundling requires JS output");
+        } else {
+            ensure!(!*self.scope_hoist, "Scope hoisting needs --bundle");
+            ensure!(
+                *self.tree_shake || self.entry.num_values() == 0,
+                "Entry points need --tree-shake or --bundle"
+            );
+        }
         Ok(())
     }
 }
@@ -414,39 +896,158 @@ fn script_to_module<'gc>(
             metadata: ast::TemplateMetadata {
                 phantom: Default::default(),
                 range: program.metadata.range,
+                comments: program.metadata.comments,
             },
             body: program.body,
         },
     )
 }
 
-/// Generate the specified output, if any.
-/// Returns whether any output was generated.
-fn gen_output(
-    opt: &Options,
-    ctx: &mut ast::Context,
-    sem: Option<&SemContext>,
-    js_module: &ParsedJSModule,
-) -> anyhow::Result<bool> {
+/// Open the output path, or `stdout` if it's `-`.
+fn create_output(opt: &Options) -> anyhow::Result<Box<dyn Write>> {
     let output_path = &*opt.output_path;
-    let mut out: Box<dyn Write> = if output_path == Path::new("-") {
+    Ok(if output_path == Path::new("-") {
         Box::new(std::io::stdout())
     } else {
         Box::new(File::create(output_path).with_context(|| output_path.display().to_string())?)
-    };
+    })
+}
 
+/// Run the transformations requested by `opt` on `js_module`, except mangling.
+fn transform(opt: &Options, ctx: &mut ast::Context, js_module: &ParsedJSModule) -> NodeRc {
     let final_ast = if *opt.strip_flow {
         PassManager::strip_flow().run(ctx, js_module.ast.clone())
     } else {
         js_module.ast.clone()
     };
 
+    let final_ast = if *opt.strip_ts {
+        PassManager::strip_typescript().run(ctx, final_ast)
+    } else {
+        final_ast
+    };
+
+    let final_ast = if *opt.transform_jsx {
+        let mut options = JsxOptions {
+            runtime: *opt.jsx_runtime,
+            ..Default::default()
+        };
+        if let Some(doc_block) = &js_module.doc_block {
+            options.read_pragmas(doc_block);
+        }
+        PassManager::transform_jsx(options).run(ctx, final_ast)
+    } else {
+        final_ast
+    };
+
+    let final_ast = if opt.define.num_values() > 0 {
+        PassManager::define(opt.define.values().clone()).run(ctx, final_ast)
+    } else {
+        final_ast
+    };
+
+    // Bundled modules are loaded with `require`.
+    let final_ast = if *opt.commonjs || *opt.bundle {
+        PassManager::to_commonjs(CommonJsOptions {
+            inline_requires: *opt.inline_requires,
+        })
+        .run(ctx, final_ast)
+    } else {
+        final_ast
+    };
+
     let final_ast = if *opt.optimize {
         PassManager::standard().run(ctx, final_ast)
     } else {
         final_ast
     };
 
+    if *opt.dce {
+        PassManager::dead_code_elimination().run(ctx, final_ast)
+    } else {
+        final_ast
+    }
+}
+
+/// Generate JS for `ast` to `out` and return its source map.
+fn generate_js(
+    opt: &Options,
+    ctx: &mut ast::Context,
+    out: &mut dyn Write,
+    ast: &NodeRc,
+    sem: Option<&SemContext>,
+    doc_block: Option<Rc<String>>,
+) -> anyhow::Result<SourceMap> {
+    Ok(gen_js::generate(
+        out,
+        ctx,
+        ast,
+        gen_js::Opt {
+            pretty: if *opt.minify {
+                gen_js::Pretty::Minify
+            } else if *opt.pretty {
+                gen_js::Pretty::Yes
+            } else {
+                gen_js::Pretty::No
+            },
+            annotation: match sem {
+                Some(sem) if *opt.gen == Gen::ResolvedJs => gen_js::Annotation::Sem(sem),
+                _ => gen_js::Annotation::No,
+            },
+            force_async_arrow_space: *opt.force_async_arrow_space,
+            doc_block: if *opt.emit_doc_block { doc_block } else { None },
+            quote: if *opt.double_quote_strings {
+                gen_js::QuoteChar::Double
+            } else {
+                gen_js::QuoteChar::Single
+            },
+            print_width: *opt.print_width,
+            indent_width: *opt.indent_width,
+            use_tabs: *opt.use_tabs,
+        },
+    )?)
+}
+
+/// Write `source_map` next to the output path and link to it from `out`.
+fn write_source_map(
+    opt: &Options,
+    out: &mut dyn Write,
+    source_map: SourceMap,
+) -> anyhow::Result<()> {
+    // Workaround because `PathBuf` doesn't have a way to append an extension,
+    // only to replace the existing one.
+    let mut path = opt.output_path.as_os_str().to_owned();
+    path.push(".map");
+    let sourcemap_file = File::create(PathBuf::from(&path))?;
+    source_map.to_writer(sourcemap_file)?;
+    write!(out, "\n//# sourceMappingURL={}", path.to_str().unwrap())?;
+    Ok(())
+}
+
+/// Generate the specified output, if any.
+/// Returns whether any output was generated.
+fn gen_output(
+    opt: &Options,
+    ctx: &mut ast::Context,
+    sem: Option<&SemContext>,
+    js_module: &ParsedJSModule,
+) -> anyhow::Result<bool> {
+    let mut out = create_output(opt)?;
+
+    let final_ast = transform(opt, ctx, js_module);
+    if ctx.sm().num_errors() != 0 {
+        anyhow::bail!(
+            "{} error(s) in the transformed input",
+            ctx.sm().num_errors()
+        );
+    }
+
+    let final_ast = if *opt.mangle {
+        PassManager::mangle().run(ctx, final_ast)
+    } else {
+        final_ast
+    };
+
     if *opt.run {
         juno_eval::run(&final_ast);
         return Ok(true);
@@ -454,54 +1055,24 @@ fn gen_output(
 
     match *opt.gen {
         Gen::Ast => {
-            ast::dump_json(
-                out,
-                ctx,
-                &final_ast,
-                if !*opt.pretty {
-                    ast::Pretty::No
-                } else {
-                    ast::Pretty::Yes
-                },
-            )?;
+            ast::dump_json(out, ctx, &final_ast, json_pretty(opt))?;
             Ok(true)
         }
         Gen::Js | Gen::ResolvedJs => {
-            let generated_map = gen_js::generate(
-                out.deref_mut(),
+            let generated_map = generate_js(
+                opt,
                 ctx,
+                out.deref_mut(),
                 &final_ast,
-                gen_js::Opt {
-                    pretty: if *opt.pretty {
-                        gen_js::Pretty::Yes
-                    } else {
-                        gen_js::Pretty::No
-                    },
-                    annotation: match sem {
-                        Some(sem) if *opt.gen == Gen::ResolvedJs => gen_js::Annotation::Sem(sem),
-                        _ => gen_js::Annotation::No,
-                    },
-                    force_async_arrow_space: *opt.force_async_arrow_space,
-                    doc_block: js_module.doc_block.clone(),
-                    quote: if *opt.double_quote_strings {
-                        gen_js::QuoteChar::Double
-                    } else {
-                        gen_js::QuoteChar::Single
-                    },
-                },
+                sem,
+                js_module.doc_block.clone(),
             )?;
             if *opt.sourcemap {
-                // Workaround because `PathBuf` doesn't have a way to append an extension,
-                // only to replace the existing one.
-                let mut path = output_path.clone().into_os_string();
-                path.push(".map");
-                let sourcemap_file = File::create(PathBuf::from(&path))?;
                 let merged_map = match &js_module.source_map {
                     None => generated_map,
                     Some(input_map) => merge_sourcemaps(input_map, &generated_map),
                 };
-                merged_map.to_writer(sourcemap_file)?;
-                write!(out, "\n//# sourceMappingURL={}", path.to_str().unwrap())?;
+                write_source_map(opt, out.deref_mut(), merged_map)?;
             }
             Ok(true)
         }
@@ -511,7 +1082,139 @@ fn gen_output(
             }
             Ok(true)
         }
+        Gen::Deps => unreachable!("the dependency graph is generated for all modules"),
+    }
+}
+
+/// Whether to pretty print JSON output.
+fn json_pretty(opt: &Options) -> ast::Pretty {
+    if !*opt.pretty {
+        ast::Pretty::No
+    } else {
+        ast::Pretty::Yes
+    }
+}
+
+/// Find the IDs of the entry points, which default to the first input.
+fn entry_points(opt: &Options, ctx: &ast::Context) -> anyhow::Result<Vec<SourceId>> {
+    let entry_paths = if opt.entry.num_values() == 0 && *opt.follow_deps {
+        &opt.input_path.values()[..]
+    } else if opt.entry.num_values() == 0 {
+        &opt.input_path.values()[..1]
+    } else {
+        &opt.entry.values()[..]
+    };
+    let mut entry_points = vec![];
+    for path in entry_paths {
+        let name = path.display().to_string();
+        match ctx.sm().lookup_name(&name) {
+            Some(id) => entry_points.push(id),
+            None => anyhow::bail!("Entry point {} is not an input", name),
+        }
     }
+    Ok(entry_points)
+}
+
+/// Bundle `modules` into a single script which runs the entry points, and write it to the
+/// output along with its source map.
+fn gen_bundle(
+    opt: &Options,
+    ctx: &mut ast::Context,
+    modules: &mut [ParsedJSModule],
+    sems: &[SemContext],
+) -> anyhow::Result<()> {
+    let entry_points = entry_points(opt, ctx)?;
+    let mut hoisting = if *opt.scope_hoist {
+        let lock = ast::GCLock::new(ctx);
+        let hoist_modules: Vec<HoistModule> = modules
+            .iter()
+            .zip(sems)
+            .map(|(module, sem)| HoistModule {
+                id: module.id,
+                ast: module.ast.node(&lock),
+                sem,
+            })
+            .collect();
+        Some(ScopeHoisting::analyze(&lock, &hoist_modules, &entry_points))
+    } else {
+        None
+    };
+
+    let mut bundler = Bundler::new(*opt.bundle_format);
+    let mut input_maps = HashMap::new();
+    for (module, sem) in modules.iter_mut().zip(sems) {
+        if let Some(input_map) = module.source_map.take() {
+            input_maps.insert(module.id.0, input_map);
+        }
+        let concatenated = match &mut hoisting {
+            // The module is part of the module it was concatenated into.
+            Some(hoisting) if hoisting.is_concatenated(module.id) => continue,
+            Some(hoisting) => hoisting.take_module(module.id),
+            None => None,
+        };
+        match concatenated {
+            Some(concatenated) => {
+                module.ast = concatenated.ast;
+                let ast = transform(opt, ctx, module);
+                bundler.add_resolved_module(ctx, module.id, &ast, &concatenated.dependencies);
+            }
+            None => {
+                let ast = transform(opt, ctx, module);
+                bundler.add_module(ctx, module.id, &ast, sem);
+            }
+        }
+    }
+    if ctx.sm().num_errors() != 0 {
+        anyhow::bail!(
+            "{} error(s) in the transformed modules",
+            ctx.sm().num_errors()
+        );
+    }
+    let bundle = bundler.finish(ctx, &entry_points);
+
+    // Module scopes are functions in the bundle, so their variables are mangled as well.
+    let bundle = if *opt.mangle {
+        PassManager::mangle().run(ctx, bundle)
+    } else {
+        bundle
+    };
+
+    let mut out = create_output(opt)?;
+    let generated_map = generate_js(opt, ctx, out.deref_mut(), &bundle, None, None)?;
+    if *opt.sourcemap {
+        let merged_map = merge_sourcemaps_by_source(&input_maps, &generated_map);
+        write_source_map(opt, out.deref_mut(), merged_map)?;
+    }
+    Ok(())
+}
+
+/// Find which parts of `modules` are used from the entry points.
+fn tree_shake(
+    opt: &Options,
+    ctx: &mut ast::Context,
+    modules: &[ParsedJSModule],
+    sems: &[SemContext],
+) -> anyhow::Result<TreeShaking> {
+    let options = TreeShakeOptions {
+        entry_points: entry_points(opt, ctx)?,
+        side_effects: if opt.side_effects.num_values() == 0 {
+            None
+        } else {
+            Some(opt.side_effects.values().clone())
+        },
+    };
+
+    let lock = ast::GCLock::new(ctx);
+    let shake_modules: Vec<ShakeModule> = modules
+        .iter()
+        .zip(sems)
+        .map(|(module, sem)| ShakeModule {
+            id: module.id,
+            ast: module.ast.node(&lock),
+            sem,
+        })
+        .collect();
+    Ok(TreeShaking::analyze(&lock, &shake_modules, &options))
 }
 
 /// TransformStatus indicates whether there were parse or validation errors
@@ -530,6 +1233,81 @@ enum TransformStatus {
     Error,
 }
 
+/// Parse the source `file_id`, as an ES module if `as_module` is set and otherwise as a
+/// script. Return `None` if there were parse errors.
+fn parse_module(
+    opt: &Options,
+    ctx: &mut ast::Context,
+    timer: &mut Timer,
+    file_id: SourceId,
+    as_module: bool,
+) -> anyhow::Result<Option<ParsedJSModule>> {
+    let buf = ctx.sm().source_buffer_rc(file_id);
+
+    // Parse.
+    let parsed = hparser::ParsedJS::parse(
+        hparser::ParserFlags {
+            strict_mode: ctx.strict_mode(),
+            enable_jsx: *opt.jsx,
+            dialect: *opt.dialect,
+            // The doc block is printed along with the rest of the comments,
+            // which are also searched for JSX pragmas.
+            store_doc_block: (*opt.emit_doc_block || *opt.transform_jsx) && !*opt.preserve_comments,
+            store_comments: *opt.preserve_comments,
+        },
+        &buf,
+    );
+    timer.mark("Parse");
+    if let Some(e) = parsed.first_error() {
+        ctx.sm().error(SourceRange::from_loc(file_id, e.0), e.1);
+        return Ok(None);
+    }
+
+    // Extract the optional source mapping URL.
+    let sm_url = if *opt.input_source_map != InputSourceMap::Ignore {
+        parse_magic_url(&parsed, MagicCommentKind::SourceMappingUrl, opt)?
+    } else {
+        None
+    };
+
+    let ast = {
+        // Convert to Juno AST.
+        let lock = ast::GCLock::new(ctx);
+        match parsed.to_ast(&lock, file_id) {
+            None => return Ok(None),
+            Some(program) => {
+                if as_module {
+                    NodeRc::from_node(
+                        &lock,
+                        script_to_module(&lock, node_cast!(ast::Node::Program, program)),
+                    )
+                } else {
+                    NodeRc::from_node(&lock, program)
+                }
+            }
+        }
+    };
+    let doc_block = parsed.get_doc_block().map(|s| Rc::new(s.to_string()));
+    // We don't need the original parser anymore.
+    drop(parsed);
+    timer.mark("Cvt");
+
+    if *opt.validate_ast {
+        validate_tree(ctx, &ast).with_context(|| ctx.sm().source_name(file_id).to_string())?;
+        timer.mark("Validate AST");
+    }
+
+    // Fetch and parse the source map before we generate the output.
+    let source_map = sm_url.map(load_source_map).transpose()?;
+
+    Ok(Some(ParsedJSModule {
+        id: file_id,
+        ast,
+        source_map,
+        doc_block,
+    }))
+}
+
 /// Parsed JS file with its associated sourcemap.
 struct ParsedJSModule {
     id: SourceId,
@@ -564,73 +1342,14 @@ fn run(opt: &Options) -> anyhow::Result<TransformStatus> {
         let file_id = ctx
             .sm_mut()
             .add_source(input.display().to_string(), read_file_or_stdin(input)?);
-        let buf = ctx.sm().source_buffer_rc(file_id);
-
-        // Parse.
-        let parsed = hparser::ParsedJS::parse(
-            hparser::ParserFlags {
-                strict_mode: ctx.strict_mode(),
-                enable_jsx: *opt.jsx,
-                dialect: *opt.dialect,
-                store_doc_block: *opt.emit_doc_block,
-            },
-            &buf,
-        );
-        timer.mark("Parse");
-        if let Some(e) = parsed.first_error() {
-            ctx.sm().error(SourceRange::from_loc(file_id, e.0), e.1);
-            return Ok(TransformStatus::Error);
-        }
-
-        // Extract the optional source mapping URL.
-        let sm_url = if *opt.input_source_map != InputSourceMap::Ignore {
-            parse_magic_url(&parsed, MagicCommentKind::SourceMappingUrl, opt)?
-        } else {
-            None
+        let as_module = input_paths.len() > 1 || *opt.bundle || *opt.follow_deps;
+        match parse_module(opt, &mut ctx, &mut timer, file_id, as_module)? {
+            Some(js_module) => js_modules.insert(file_id, js_module),
+            None => return Ok(TransformStatus::Error),
         };
-
-        let ast = {
-            // Convert to Juno AST.
-            let lock = ast::GCLock::new(&mut ctx);
-            match parsed.to_ast(&lock, file_id) {
-                None => return Ok(TransformStatus::Error),
-                Some(program) => {
-                    if input_paths.len() > 1 {
-                        NodeRc::from_node(
-                            &lock,
-                            script_to_module(&lock, node_cast!(ast::Node::Program, program)),
-                        )
-                    } else {
-                        NodeRc::from_node(&lock, program)
-                    }
-                }
-            }
-        };
-        let doc_block = parsed.get_doc_block().map(|s| Rc::new(s.to_string()));
-        // We don't need the original parser anymore.
-        drop(parsed);
-        timer.mark("Cvt");
-
-        if *opt.validate_ast {
-            validate_tree(&mut ctx, &ast).with_context(|| input.display().to_string())?;
-            timer.mark("Validate AST");
-        }
-
-        // Fetch and parse the source map before we generate the output.
-        let source_map = sm_url.map(load_source_map).transpose()?;
-
-        js_modules.insert(
-            file_id,
-            ParsedJSModule {
-                id: file_id,
-                ast,
-                source_map,
-                doc_block,
-            },
-        );
     }
 
-    if js_modules.len() == 1 {
+    if js_modules.len() == 1 && !*opt.bundle && !*opt.follow_deps && *opt.gen != Gen::Deps {
         let js_module = js_modules.into_values().next().unwrap();
         let sem = if *opt.sema {
             let lock = ast::GCLock::new(&mut ctx);
@@ -659,34 +1378,148 @@ fn run(opt: &Options) -> anyhow::Result<TransformStatus> {
     } else {
         // Show information about semantic resolution for all modules if requested.
         if *opt.sema {
-            println!("{} modules", js_modules.len());
+            // The bundle and the dependency graph are written to the output instead.
+            let dump_modules = !*opt.bundle && *opt.gen != Gen::Deps;
+            if dump_modules {
+                println!("{} modules", js_modules.len());
+            }
             let mut sems = Vec::new();
-            let resolver = resolve_dependency::DefaultResolver::new(ctx.sm());
-            for module in js_modules.into_values() {
+            let resolver: Box<dyn DependencyResolver> = match *opt.resolver {
+                Resolver::Default => Box::new(DefaultResolver::new(ctx.sm())),
+                Resolver::Node => Box::new(NodeResolver::new(NodeResolverOptions {
+                    conditions: opt.conditions.values().clone(),
+                    browser: *opt.browser,
+                    ..Default::default()
+                })),
+                Resolver::Haste => Box::new(HasteResolver::new(
+                    ctx.sm(),
+                    HasteResolverOptions {
+                        roots: opt.haste_roots.values().clone(),
+                        platform: (*opt.platform).clone(),
+                        cache_path: (*opt.haste_cache).clone(),
+                    },
+                )),
+                Resolver::Metro => {
+                    let mut options = match &*opt.resolver_config {
+                        Some(path) => MetroResolverOptions::from_config(path)
+                            .with_context(|| format!("Resolver config {}", path.display()))?,
+                        None => Default::default(),
+                    };
+                    if opt.platform.is_some() {
+                        options.platform = (*opt.platform).clone();
+                    }
+                    Box::new(MetroResolver::new(options))
+                }
+            };
+            if ctx.sm().num_errors() != 0 {
+                return Ok(TransformStatus::Error);
+            }
+            let mut modules: Vec<ParsedJSModule> = js_modules.into_values().collect();
+            modules.sort_by_key(|module| module.id.0);
+            // Modules found while resolving are appended, and resolved in turn.
+            let mut loaded: HashSet<SourceId> = modules.iter().map(|module| module.id).collect();
+            let mut i = 0;
+            while i < modules.len() {
+                let module = &modules[i];
+                i += 1;
                 let sem;
                 {
                     let lock = ast::GCLock::new(&mut ctx);
-                    sem = sema::resolve_module(&lock, module.ast.node(&lock), module.id, &resolver);
-
-                    let source_name = lock.sm().source_name(module.id);
-                    println!("Module: {}", source_name);
-                    println!(
-                        "{} error(s), {} warning(s)",
-                        lock.sm().num_errors(),
-                        lock.sm().num_warnings()
-                    );
+                    sem =
+                        sema::resolve_module(&lock, module.ast.node(&lock), module.id, &*resolver);
+
+                    if dump_modules {
+                        let source_name = lock.sm().source_name(module.id);
+                        println!("Module: {}", source_name);
+                        println!(
+                            "{} error(s), {} warning(s)",
+                            lock.sm().num_errors(),
+                            lock.sm().num_warnings()
+                        );
+                    }
                     if lock.sm().num_errors() != 0 {
                         return Ok(TransformStatus::Error);
                     }
                 }
-                // Generate output.
-                if gen_output(opt, &mut ctx, Some(&sem), &module)? {
+                // Generate output, unless it depends on the other modules.
+                if !*opt.tree_shake
+                    && dump_modules
+                    && gen_output(opt, &mut ctx, Some(&sem), module)?
+                {
                     timer.mark("Gen");
                 }
+                if *opt.follow_deps {
+                    let mut dependencies: Vec<SourceId> =
+                        sem.all_requires().values().copied().collect();
+                    dependencies.sort_by_key(|id| id.0);
+                    dependencies.dedup();
+                    for id in dependencies {
+                        if loaded.insert(id) {
+                            match parse_module(opt, &mut ctx, &mut timer, id, true)? {
+                                Some(js_module) => modules.push(js_module),
+                                None => return Ok(TransformStatus::Error),
+                            }
+                        }
+                    }
+                }
                 sems.push(sem);
             }
             timer.mark("Sema");
 
+            {
+                let lock = ast::GCLock::new(&mut ctx);
+                let link_modules: Vec<LinkModule> = modules
+                    .iter()
+                    .zip(&sems)
+                    .map(|(module, sem)| LinkModule {
+                        id: module.id,
+                        ast: module.ast.node(&lock),
+                        sem,
+                    })
+                    .collect();
+                sema::link_modules(&lock, &link_modules);
+                if lock.sm().num_errors() != 0 {
+                    return Ok(TransformStatus::Error);
+                }
+            }
+            timer.mark("Link");
+
+            if *opt.tree_shake {
+                let mut shaking = tree_shake(opt, &mut ctx, &modules, &sems)?;
+                timer.mark("Tree Shake");
+                let mut included = vec![];
+                for (mut module, sem) in modules.into_iter().zip(sems) {
+                    if shaking.is_included(module.id) {
+                        module.ast = PassManager::tree_shake(shaking.take_pass(module.id))
+                            .run(&mut ctx, module.ast.clone());
+                        included.push((module, sem));
+                    }
+                }
+                (modules, sems) = included.into_iter().unzip();
+            }
+
+            if *opt.gen == Gen::Deps {
+                let graph = DependencyGraph::new(modules.iter().map(|module| module.id).zip(&sems));
+                let out = create_output(opt)?;
+                match *opt.deps_format {
+                    DepsFormat::Json => graph.dump_json(out, ctx.sm(), json_pretty(opt))?,
+                    DepsFormat::Dot => graph.dump_dot(out, ctx.sm())?,
+                }
+                timer.mark("Gen");
+            } else if *opt.bundle {
+                if ctx.sm().num_warnings() != 0 {
+                    eprintln!("{} warning(s)", ctx.sm().num_warnings());
+                }
+                gen_bundle(opt, &mut ctx, &mut modules, &sems)?;
+                timer.mark("Bundle");
+            } else if *opt.tree_shake {
+                for (module, sem) in modules.iter().zip(&sems) {
+                    if gen_output(opt, &mut ctx, Some(sem), module)? {
+                        timer.mark("Gen");
+                    }
+                }
+            }
+
             drop(sems);
             timer.mark("Drop Sema");
         }
@@ -0,0 +1,52 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+// Module registry for bundles in the closure format.
+//
+// The bundle calls this function with an object which maps module IDs to
+// their factories and with the IDs of the entry points, which are required in
+// order. Factories are called as
+//   factory(global, require, module, exports)
+// the first time their module is required.
+
+(function (modules, entryPoints) {
+  'use strict';
+
+  var globalObject =
+    typeof globalThis !== 'undefined'
+      ? globalThis
+      : typeof window !== 'undefined'
+      ? window
+      : typeof self !== 'undefined'
+      ? self
+      : {};
+  var cache = Object.create(null);
+
+  function require(moduleId) {
+    // Modules in a cycle see the exports of the module which is still
+    // initializing as they are so far.
+    var module = cache[moduleId];
+    if (module != null) {
+      return module.exports;
+    }
+    if (!Object.prototype.hasOwnProperty.call(modules, moduleId)) {
+      throw new Error('Requiring unknown module "' + moduleId + '".');
+    }
+    module = cache[moduleId] = {id: moduleId, exports: {}};
+    try {
+      modules[moduleId](globalObject, require, module, module.exports);
+    } catch (e) {
+      delete cache[moduleId];
+      throw e;
+    }
+    return module.exports;
+  }
+
+  for (var i = 0; i < entryPoints.length; i++) {
+    require(entryPoints[i]);
+  }
+});
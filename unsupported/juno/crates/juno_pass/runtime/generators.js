@@ -0,0 +1,455 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+// Runtime for the state machines produced by the Lower Generators pass.
+//
+// Each lowered function passes a function which runs its state machine to
+// `generator`, `async` or `asyncGenerator`, together with the locations of its
+// try statements as [tryLoc, catchLoc, finallyLoc, afterLoc].
+// The state machine is a `switch` over locations, which are numbered in the
+// order they appear in the source, so a location is inside a try statement
+// exactly when it is between the locations of the try statement.
+//
+// Async functions and async generators need `Promise`.
+
+var junoGeneratorRuntime = (function () {
+  'use strict';
+
+  // Returned by the state machine when it must be resumed without suspending.
+  var ContinueSentinel = {};
+
+  var iteratorSymbol =
+    (typeof Symbol === 'function' && Symbol.iterator) || '@@iterator';
+  var asyncIteratorSymbol =
+    (typeof Symbol === 'function' && Symbol.asyncIterator) ||
+    '@@asyncIterator';
+
+  // The operand of an `await`, which is suspended on like a `yield`.
+  function Await(value) {
+    this.value = value;
+  }
+
+  function Context(tryLocsList) {
+    this.tryEntries = [{tryLoc: 'root', completion: {type: 'normal'}}];
+    for (var i = 0; tryLocsList && i < tryLocsList.length; i++) {
+      var locs = tryLocsList[i];
+      this.tryEntries.push({
+        tryLoc: locs[0],
+        catchLoc: locs[1],
+        finallyLoc: locs[2],
+        afterLoc: locs[3],
+        completion: {type: 'normal'},
+      });
+    }
+    this.prev = 0;
+    this.next = 0;
+    this.sent = undefined;
+    this.done = false;
+    this.delegate = null;
+    this.method = 'next';
+    this.arg = undefined;
+    this.rval = undefined;
+  }
+
+  Context.prototype = {
+    constructor: Context,
+
+    // Finish the state machine, rethrowing an uncaught exception.
+    stop: function () {
+      this.done = true;
+      var rootRecord = this.tryEntries[0].completion;
+      if (rootRecord.type === 'throw') {
+        throw rootRecord.arg;
+      }
+      return this.rval;
+    },
+
+    // Continue at the innermost handler of the current location.
+    dispatchException: function (exception) {
+      for (var i = this.tryEntries.length - 1; i >= 0; --i) {
+        var entry = this.tryEntries[i];
+        var loc = null;
+        if (entry.tryLoc === 'root') {
+          loc = 'end';
+        } else if (entry.tryLoc <= this.prev) {
+          if (entry.catchLoc != null && this.prev < entry.catchLoc) {
+            loc = entry.catchLoc;
+          } else if (entry.finallyLoc != null && this.prev < entry.finallyLoc) {
+            loc = entry.finallyLoc;
+          }
+        }
+        if (loc !== null) {
+          entry.completion = {type: 'throw', arg: exception};
+          this.next = loc;
+          this.method = 'next';
+          this.arg = undefined;
+          return;
+        }
+      }
+    },
+
+    // Leave the current location by `break`, `continue` or `return`,
+    // running the enclosing finally blocks first.
+    abrupt: function (type, arg) {
+      var finallyEntry = null;
+      for (var i = this.tryEntries.length - 1; i >= 0; --i) {
+        var entry = this.tryEntries[i];
+        if (
+          entry.tryLoc <= this.prev &&
+          entry.finallyLoc != null &&
+          this.prev < entry.finallyLoc
+        ) {
+          finallyEntry = entry;
+          break;
+        }
+      }
+      // Jumps which stay inside the try statement don't run the finally block.
+      if (
+        finallyEntry &&
+        (type === 'break' || type === 'continue') &&
+        finallyEntry.tryLoc <= arg &&
+        arg <= finallyEntry.finallyLoc
+      ) {
+        finallyEntry = null;
+      }
+      var record = {type: type, arg: arg};
+      if (finallyEntry) {
+        finallyEntry.completion = record;
+        this.method = 'next';
+        this.next = finallyEntry.finallyLoc;
+        return ContinueSentinel;
+      }
+      return this.complete(record);
+    },
+
+    complete: function (record, afterLoc) {
+      if (record.type === 'throw') {
+        throw record.arg;
+      }
+      if (record.type === 'break' || record.type === 'continue') {
+        this.next = record.arg;
+      } else if (record.type === 'return') {
+        // The return is dispatched again in case there are outer finally blocks.
+        this.rval = this.arg = record.arg;
+        this.method = 'return';
+        this.next = 'end';
+      } else if (afterLoc !== undefined) {
+        this.next = afterLoc;
+      }
+      return ContinueSentinel;
+    },
+
+    // Resume what was interrupted by the finally block at `finallyLoc`.
+    finish: function (finallyLoc) {
+      for (var i = this.tryEntries.length - 1; i >= 0; --i) {
+        var entry = this.tryEntries[i];
+        if (entry.finallyLoc === finallyLoc) {
+          var record = entry.completion;
+          entry.completion = {type: 'normal'};
+          return this.complete(record, entry.afterLoc);
+        }
+      }
+    },
+
+    // Return the exception caught by the try statement at `tryLoc`.
+    catch: function (tryLoc) {
+      for (var i = this.tryEntries.length - 1; i >= 0; --i) {
+        var entry = this.tryEntries[i];
+        if (entry.tryLoc === tryLoc) {
+          var record = entry.completion;
+          entry.completion = {type: 'normal'};
+          return record.arg;
+        }
+      }
+      throw new Error('Illegal catch attempt');
+    },
+
+    // Yield every value of `iterable`, then store its result in `resultName`
+    // and continue at `nextLoc`.
+    delegateYield: function (iterable, resultName, nextLoc) {
+      this.delegate = {
+        iterator: values(iterable),
+        resultName: resultName,
+        nextLoc: nextLoc,
+      };
+      if (this.method === 'next') {
+        this.arg = undefined;
+      }
+      return ContinueSentinel;
+    },
+
+    await: function (value) {
+      return new Await(value);
+    },
+  };
+
+  // Forward the current method to the iterator of `yield*`.
+  // Returns the result to yield, or ContinueSentinel to resume the state machine.
+  function invokeDelegate(delegate, context) {
+    var iterator = delegate.iterator;
+    var method = iterator[context.method];
+    if (method === undefined) {
+      context.delegate = null;
+      if (context.method === 'throw') {
+        if (iterator['return']) {
+          iterator['return']();
+        }
+        context.arg = new TypeError(
+          "The iterator does not provide a 'throw' method"
+        );
+      }
+      return ContinueSentinel;
+    }
+    var info;
+    try {
+      info = method.call(iterator, context.arg);
+    } catch (e) {
+      context.delegate = null;
+      context.method = 'throw';
+      context.arg = e;
+      return ContinueSentinel;
+    }
+    if (!info.done) {
+      return info;
+    }
+    context.delegate = null;
+    context[delegate.resultName] = info.value;
+    context.next = delegate.nextLoc;
+    if (context.method !== 'return') {
+      context.method = 'next';
+      context.arg = undefined;
+    }
+    return ContinueSentinel;
+  }
+
+  function makeInvoke(innerFn, self, context) {
+    var state = 'suspendedStart';
+    return function invoke(method, arg) {
+      if (state === 'executing') {
+        throw new Error('Generator is already running');
+      }
+      if (state === 'suspendedStart' && method !== 'next') {
+        state = 'completed';
+      }
+      if (state === 'completed') {
+        if (method === 'throw') {
+          throw arg;
+        }
+        return {value: method === 'return' ? arg : undefined, done: true};
+      }
+
+      context.method = method;
+      context.arg = arg;
+      state = 'executing';
+      for (;;) {
+        if (context.delegate) {
+          var delegated = invokeDelegate(context.delegate, context);
+          if (delegated !== ContinueSentinel) {
+            state = 'suspendedYield';
+            return delegated;
+          }
+        }
+        if (context.method === 'next') {
+          context.sent = context.arg;
+        } else if (context.method === 'throw') {
+          if (context.done) {
+            state = 'completed';
+            throw context.arg;
+          }
+          context.dispatchException(context.arg);
+        } else {
+          context.abrupt('return', context.arg);
+        }
+
+        var value;
+        try {
+          value = innerFn.call(self, context);
+        } catch (e) {
+          context.method = 'throw';
+          context.arg = e;
+          continue;
+        }
+        if (value === ContinueSentinel) {
+          continue;
+        }
+        state = context.done ? 'completed' : 'suspendedYield';
+        return {value: value, done: context.done};
+      }
+    };
+  }
+
+  function Generator(innerFn, self, tryLocsList) {
+    this._invoke = makeInvoke(innerFn, self, new Context(tryLocsList));
+  }
+  Generator.prototype.next = function (arg) {
+    return this._invoke('next', arg);
+  };
+  Generator.prototype['throw'] = function (arg) {
+    return this._invoke('throw', arg);
+  };
+  Generator.prototype['return'] = function (arg) {
+    return this._invoke('return', arg);
+  };
+  Generator.prototype[iteratorSymbol] = function () {
+    return this;
+  };
+
+  function generator(innerFn, self, tryLocsList) {
+    return new Generator(innerFn, self, tryLocsList);
+  }
+
+  function async(innerFn, self, tryLocsList) {
+    var gen = new Generator(innerFn, self, tryLocsList);
+    return new Promise(function (resolve, reject) {
+      function step(method, arg) {
+        var result;
+        try {
+          result = gen._invoke(method, arg);
+        } catch (e) {
+          reject(e);
+          return;
+        }
+        if (result.done) {
+          resolve(result.value);
+          return;
+        }
+        Promise.resolve(result.value.value).then(
+          function (value) {
+            step('next', value);
+          },
+          function (error) {
+            step('throw', error);
+          }
+        );
+      }
+      step('next');
+    });
+  }
+
+  // Requests to an async generator are queued until the previous ones settle.
+  function AsyncGenerator(innerFn, self, tryLocsList) {
+    this._gen = new Generator(innerFn, self, tryLocsList);
+    this._queue = [];
+  }
+  AsyncGenerator.prototype._enqueue = function (method, arg) {
+    var self = this;
+    return new Promise(function (resolve, reject) {
+      self._queue.push({method: method, resolve: resolve, reject: reject});
+      if (self._queue.length === 1) {
+        self._resume(method, arg);
+      }
+    });
+  };
+  AsyncGenerator.prototype._resume = function (method, arg) {
+    var self = this;
+    var result;
+    try {
+      result = self._gen._invoke(method, arg);
+    } catch (e) {
+      self._settle('reject', e);
+      return;
+    }
+    var resumeWith = function (resumeMethod) {
+      return function (value) {
+        self._resume(resumeMethod, value);
+      };
+    };
+    if (result.value instanceof Await) {
+      Promise.resolve(result.value.value).then(
+        resumeWith('next'),
+        resumeWith('throw')
+      );
+      return;
+    }
+    Promise.resolve(result.value).then(function (value) {
+      self._settle('resolve', {value: value, done: result.done});
+    }, resumeWith('throw'));
+  };
+  AsyncGenerator.prototype._settle = function (type, value) {
+    this._queue.shift()[type](value);
+    if (this._queue.length) {
+      var request = this._queue[0];
+      this._resume(request.method, request.arg);
+    }
+  };
+  AsyncGenerator.prototype.next = function (arg) {
+    return this._enqueue('next', arg);
+  };
+  AsyncGenerator.prototype['throw'] = function (arg) {
+    return this._enqueue('throw', arg);
+  };
+  AsyncGenerator.prototype['return'] = function (arg) {
+    return this._enqueue('return', arg);
+  };
+  AsyncGenerator.prototype[asyncIteratorSymbol] = function () {
+    return this;
+  };
+
+  function asyncGenerator(innerFn, self, tryLocsList) {
+    return new AsyncGenerator(innerFn, self, tryLocsList);
+  }
+
+  // Return an iterator for `iterable`, falling back to array-likes.
+  function values(iterable) {
+    if (iterable != null) {
+      var method = iterable[iteratorSymbol];
+      if (method) {
+        return method.call(iterable);
+      }
+      if (typeof iterable.length === 'number') {
+        var i = 0;
+        return {
+          next: function () {
+            return i < iterable.length
+              ? {value: iterable[i++], done: false}
+              : {value: undefined, done: true};
+          },
+        };
+      }
+    }
+    throw new TypeError(iterable + ' is not iterable');
+  }
+
+  // Return an iterator over the keys visited by `for-in` on `object`.
+  function keys(object) {
+    var result = [];
+    for (var key in object) {
+      result.push(key);
+    }
+    return values(result);
+  }
+
+  // Return an async iterator for `iterable`, falling back to sync iterators.
+  function asyncIterator(iterable) {
+    var method = iterable != null ? iterable[asyncIteratorSymbol] : undefined;
+    if (method) {
+      return method.call(iterable);
+    }
+    var iterator = values(iterable);
+    return {
+      next: function (arg) {
+        return new Promise(function (resolve) {
+          var result = iterator.next(arg);
+          resolve(
+            Promise.resolve(result.value).then(function (value) {
+              return {value: value, done: result.done};
+            })
+          );
+        });
+      },
+    };
+  }
+
+  return {
+    generator: generator,
+    async: async,
+    asyncGenerator: asyncGenerator,
+    values: values,
+    keys: keys,
+    asyncIterator: asyncIterator,
+  };
+})();
@@ -0,0 +1,107 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+// Module registry for bundles in the Metro format.
+//
+// Each module is defined by `__d(factory, moduleId, dependencyMap)` and runs
+// the first time it's required by `__r(moduleId)`. Factories are called like
+// in Metro, as
+//   factory(global, require, importDefault, importAll, module, exports,
+//           dependencyMap)
+// so the modules of a bundle can also be loaded by Metro's require polyfill.
+
+(function (global) {
+  'use strict';
+
+  var modules = Object.create(null);
+
+  function define(factory, moduleId, dependencyMap) {
+    // Like in Metro, the first definition of a module wins.
+    if (modules[moduleId] != null) {
+      return;
+    }
+    modules[moduleId] = {
+      factory: factory,
+      dependencyMap: dependencyMap,
+      isInitialized: false,
+      hasError: false,
+      error: undefined,
+      publicModule: {exports: {}},
+    };
+  }
+
+  function metroRequire(moduleId) {
+    var module = modules[moduleId];
+    if (module == null) {
+      throw new Error('Requiring unknown module "' + moduleId + '".');
+    }
+    if (module.hasError) {
+      throw module.error;
+    }
+    // Modules in a cycle see the exports of the module which is still
+    // initializing as they are so far.
+    if (module.isInitialized) {
+      return module.publicModule.exports;
+    }
+    module.isInitialized = true;
+    var moduleObject = module.publicModule;
+    moduleObject.id = moduleId;
+    try {
+      module.factory(
+        global,
+        metroRequire,
+        metroImportDefault,
+        metroImportAll,
+        moduleObject,
+        moduleObject.exports,
+        module.dependencyMap
+      );
+    } catch (e) {
+      module.hasError = true;
+      module.error = e;
+      module.isInitialized = false;
+      moduleObject.exports = undefined;
+      throw e;
+    }
+    module.factory = undefined;
+    module.dependencyMap = undefined;
+    return moduleObject.exports;
+  }
+
+  function metroImportDefault(moduleId) {
+    var exports = metroRequire(moduleId);
+    return exports && exports.__esModule ? exports.default : exports;
+  }
+
+  function metroImportAll(moduleId) {
+    var exports = metroRequire(moduleId);
+    if (exports && exports.__esModule) {
+      return exports;
+    }
+    var importedAll = {};
+    if (exports != null) {
+      for (var key in exports) {
+        if (Object.prototype.hasOwnProperty.call(exports, key)) {
+          importedAll[key] = exports[key];
+        }
+      }
+    }
+    importedAll.default = exports;
+    return importedAll;
+  }
+
+  global.__d = define;
+  global.__r = metroRequire;
+})(
+  typeof globalThis !== 'undefined'
+    ? globalThis
+    : typeof global !== 'undefined'
+    ? global
+    : typeof window !== 'undefined'
+    ? window
+    : this
+);
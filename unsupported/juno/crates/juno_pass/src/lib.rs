@@ -13,3 +13,12 @@ mod manager;
 pub use manager::{Pass, PassManager};
 
 mod passes;
+pub use passes::bundle::{BundleFormat, Bundler, CLOSURE_RUNTIME, METRO_RUNTIME};
+pub use passes::commonjs::CommonJsOptions;
+pub use passes::define::{Define, DefineValue};
+pub use passes::jsx::{JsxOptions, JsxRuntime};
+pub use passes::lower_classes::ClassMode;
+pub use passes::lower_generators::GENERATOR_RUNTIME;
+pub use passes::scope_hoist::{ConcatenatedModule, HoistModule, ScopeHoisting};
+pub use passes::tree_shake::{ShakeModule, TreeShake, TreeShakeOptions, TreeShaking};
+mod utils;
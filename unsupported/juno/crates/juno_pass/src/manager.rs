@@ -30,11 +30,19 @@ impl PassManager {
         Self {
             passes: vec![
                 Box::new(add_negative::AddNegative::new()),
+                Box::new(constant_fold::ConstantFold::new()),
                 Box::new(reduce_conditional::ReduceConditional::new()),
             ],
         }
     }
 
+    /// Pipeline which removes unreachable code and unused declarations.
+    pub fn dead_code_elimination() -> Self {
+        Self {
+            passes: vec![Box::new(dead_code_elimination::DeadCodeElimination::new())],
+        }
+    }
+
     /// Pipeline containing a list of standard passes.
     pub fn strip_flow() -> Self {
         Self {
@@ -42,6 +50,158 @@ impl PassManager {
         }
     }
 
+    /// Pipeline which strips TypeScript types.
+    pub fn strip_typescript() -> Self {
+        Self {
+            passes: vec![Box::new(strip_typescript::StripTypeScript::new())],
+        }
+    }
+
+    /// Pipeline which lowers JSX to function calls.
+    pub fn transform_jsx(options: jsx::JsxOptions) -> Self {
+        Self {
+            passes: vec![Box::new(jsx::TransformJsx::new(options))],
+        }
+    }
+
+    /// Pipeline which converts ES modules to CommonJS.
+    pub fn to_commonjs(options: commonjs::CommonJsOptions) -> Self {
+        Self {
+            passes: vec![Box::new(commonjs::TransformCommonJs::new(options))],
+        }
+    }
+
+    /// Pipeline which replaces the globals in `defines` with literals and removes the branches
+    /// which become dead as a result.
+    pub fn define(defines: Vec<define::Define>) -> Self {
+        Self {
+            passes: vec![
+                Box::new(define::ReplaceDefines::new(defines)),
+                Box::new(constant_fold::ConstantFold::new()),
+                Box::new(reduce_conditional::ReduceConditional::new()),
+            ],
+        }
+    }
+
+    /// Pipeline which lowers ES2015+ syntax to ES5.
+    /// The passes run in an order where the output of each pass is handled by the later ones.
+    pub fn es5() -> Self {
+        Self {
+            passes: vec![
+                Box::new(lower_optional_chaining::LowerOptionalChaining::new()),
+                Box::new(lower_classes::LowerClasses::new(
+                    lower_classes::ClassMode::Spec,
+                )),
+                Box::new(lower_generators::LowerGenerators::new()),
+                Box::new(lower_block_scoping::LowerBlockScoping::new()),
+                Box::new(lower_arrow_functions::LowerArrowFunctions::new()),
+                Box::new(lower_template_literals::LowerTemplateLiterals::new()),
+                Box::new(lower_object_literals::LowerObjectLiterals::new()),
+                Box::new(lower_parameters::LowerParameters::new()),
+                Box::new(lower_destructuring::LowerDestructuring::new()),
+                Box::new(lower_for_of::LowerForOf::new()),
+                Box::new(lower_exponent::LowerExponent::new()),
+            ],
+        }
+    }
+
+    /// Pipeline which lowers optional chaining and nullish coalescing to conditionals.
+    pub fn lower_optional_chaining() -> Self {
+        Self {
+            passes: vec![Box::new(
+                lower_optional_chaining::LowerOptionalChaining::new(),
+            )],
+        }
+    }
+
+    /// Pipeline which lowers classes to constructor functions in `mode`.
+    pub fn lower_classes(mode: lower_classes::ClassMode) -> Self {
+        Self {
+            passes: vec![Box::new(lower_classes::LowerClasses::new(mode))],
+        }
+    }
+
+    /// Pipeline which lowers generators and async functions to state machines run by
+    /// [`crate::GENERATOR_RUNTIME`].
+    pub fn lower_generators() -> Self {
+        Self {
+            passes: vec![Box::new(lower_generators::LowerGenerators::new())],
+        }
+    }
+
+    /// Pipeline which lowers `let` and `const` to `var`.
+    pub fn lower_block_scoping() -> Self {
+        Self {
+            passes: vec![Box::new(lower_block_scoping::LowerBlockScoping::new())],
+        }
+    }
+
+    /// Pipeline which lowers arrow functions to function expressions.
+    pub fn lower_arrow_functions() -> Self {
+        Self {
+            passes: vec![Box::new(lower_arrow_functions::LowerArrowFunctions::new())],
+        }
+    }
+
+    /// Pipeline which lowers template literals to string concatenation.
+    pub fn lower_template_literals() -> Self {
+        Self {
+            passes: vec![Box::new(
+                lower_template_literals::LowerTemplateLiterals::new(),
+            )],
+        }
+    }
+
+    /// Pipeline which lowers shorthand properties, methods and computed keys.
+    pub fn lower_object_literals() -> Self {
+        Self {
+            passes: vec![Box::new(lower_object_literals::LowerObjectLiterals::new())],
+        }
+    }
+
+    /// Pipeline which lowers default, rest and pattern parameters and spread.
+    pub fn lower_parameters() -> Self {
+        Self {
+            passes: vec![Box::new(lower_parameters::LowerParameters::new())],
+        }
+    }
+
+    /// Pipeline which lowers destructuring patterns.
+    pub fn lower_destructuring() -> Self {
+        Self {
+            passes: vec![Box::new(lower_destructuring::LowerDestructuring::new())],
+        }
+    }
+
+    /// Pipeline which lowers `for-of` loops to the iterator protocol.
+    pub fn lower_for_of() -> Self {
+        Self {
+            passes: vec![Box::new(lower_for_of::LowerForOf::new())],
+        }
+    }
+
+    /// Pipeline which lowers the `**` operator to `Math.pow`.
+    pub fn lower_exponent() -> Self {
+        Self {
+            passes: vec![Box::new(lower_exponent::LowerExponent::new())],
+        }
+    }
+
+    /// Pipeline which removes the unused parts of a module, as found by
+    /// [`crate::TreeShaking::analyze`].
+    pub fn tree_shake(pass: tree_shake::TreeShake) -> Self {
+        Self {
+            passes: vec![Box::new(pass)],
+        }
+    }
+
+    /// Pipeline which renames local variables to short names.
+    pub fn mangle() -> Self {
+        Self {
+            passes: vec![Box::new(mangle::Mangle::new())],
+        }
+    }
+
     /// Run the pipeline on `node`, consuming it in the process.
     pub fn run(mut self, ctx: &mut Context, node: NodeRc) -> NodeRc {
         let mut result = node;
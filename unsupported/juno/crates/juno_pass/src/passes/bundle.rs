@@ -0,0 +1,388 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Bundle CommonJS modules into a single script.
+//!
+//! Each module is wrapped in a factory function, and its calls to `require` with a specifier
+//! which was resolved to a bundled module are changed to pass the numeric ID of that module,
+//! which is its [`SourceId`]. In the [`BundleFormat::Metro`] format,
+//! ```js
+//! var lib = require('./lib');
+//! ```
+//! becomes
+//! ```js
+//! __d(function (global, require, _$$_IMPORT_DEFAULT, _$$_IMPORT_ALL, module, exports,
+//!               _dependencyMap) {
+//!   var lib = require(1);
+//! }, 0, [1]);
+//! __r(0);
+//! ```
+//! after [`METRO_RUNTIME`], which defines `__d` and `__r`.
+//! `require.resolve('./lib')` becomes the ID `1`, and the lazy dependencies `import('./lib')`
+//! and `require.async('./lib')` become `Promise.resolve().then(function () { return
+//! require(1); })`, so the module runs after the one which loads it.
+//! In the [`BundleFormat::Closure`] format, the factories are passed to [`CLOSURE_RUNTIME`]:
+//! ```js
+//! (function (modules, entryPoints) { ... })({
+//!   0: function (global, require, module, exports) {
+//!     var lib = require(1);
+//!   },
+//!   1: ...
+//! }, [0]);
+//! ```
+
+use crate::utils;
+use juno::ast::*;
+use juno::hparser;
+use juno::resolve_dependency::DependencyKind;
+use juno::sema::{DeclKind, Resolution, SemContext};
+use juno_support::source_manager::SourceId;
+use juno_support::NullTerminatedBuf;
+use std::collections::HashMap;
+
+/// Source of the module registry which defines `__d` and `__r` for [`BundleFormat::Metro`].
+pub const METRO_RUNTIME: &str = include_str!("../../runtime/metro_require.js");
+
+/// Source of the function which runs the modules of a [`BundleFormat::Closure`] bundle.
+pub const CLOSURE_RUNTIME: &str = include_str!("../../runtime/closure_require.js");
+
+/// How the modules of a bundle are defined.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BundleFormat {
+    /// Define each module with `__d(factory, moduleId, dependencyMap)`, like Metro.
+    Metro,
+    /// Pass the factories of all modules to a function which runs them.
+    Closure,
+}
+
+impl BundleFormat {
+    /// Parameters of the factory functions.
+    fn params(self) -> &'static [&'static str] {
+        match self {
+            BundleFormat::Metro => &[
+                "global",
+                "require",
+                "_$$_IMPORT_DEFAULT",
+                "_$$_IMPORT_ALL",
+                "module",
+                "exports",
+                "_dependencyMap",
+            ],
+            BundleFormat::Closure => &["global", "require", "module", "exports"],
+        }
+    }
+
+    fn runtime(self) -> (&'static str, &'static str) {
+        match self {
+            BundleFormat::Metro => ("<juno metro runtime>", METRO_RUNTIME),
+            BundleFormat::Closure => ("<juno closure runtime>", CLOSURE_RUNTIME),
+        }
+    }
+}
+
+/// A module which was added to a [`Bundler`].
+struct BundledModule {
+    id: SourceId,
+    /// Function which runs the module.
+    factory: NodeRc,
+    /// The bundled modules it requires, in the order they're first required.
+    dependencies: Vec<SourceId>,
+}
+
+/// Builder of a script which defines CommonJS modules and runs the entry points.
+pub struct Bundler {
+    format: BundleFormat,
+    modules: Vec<BundledModule>,
+}
+
+impl Bundler {
+    pub fn new(format: BundleFormat) -> Self {
+        Self {
+            format,
+            modules: vec![],
+        }
+    }
+
+    /// Add the module `id`, whose `ast` is a CommonJS script.
+    /// `sem` is the result of resolving the module before it was converted to CommonJS, and its
+    /// resolved dependencies are loaded from the bundle.
+    pub fn add_module(&mut self, ctx: &mut Context, id: SourceId, ast: &NodeRc, sem: &SemContext) {
+        let specifiers = resolved_specifiers(&GCLock::new(ctx), sem);
+        self.add_resolved_module(ctx, id, ast, &specifiers);
+    }
+
+    /// Add the module `id`, whose `ast` is a CommonJS script, in which `require` of each of
+    /// the `specifiers` loads the module it maps to from the bundle.
+    pub fn add_resolved_module(
+        &mut self,
+        ctx: &mut Context,
+        id: SourceId,
+        ast: &NodeRc,
+        specifiers: &HashMap<String, SourceId>,
+    ) {
+        let gc = GCLock::new(ctx);
+        let node = ast.node(&gc);
+        let resolution = utils::resolve(&gc, node);
+        let mut rewriter = RewriteRequires {
+            sem: &resolution,
+            specifiers,
+            dependencies: vec![],
+        };
+        let node = utils::changed_or(VisitorMut::call(&mut rewriter, &gc, node, None), node);
+        let body = match node {
+            Node::Program(Program { body, .. }) | Node::Module(Module { body, .. }) => body,
+            _ => panic!("bundled modules must be a Program or a Module"),
+        };
+        let params = self
+            .format
+            .params()
+            .iter()
+            .map(|&param| utils::make_identifier(&gc, gc.atom(param)))
+            .collect();
+        let factory = utils::make_function(&gc, None, params, body.iter().collect());
+        self.modules.push(BundledModule {
+            id,
+            factory: NodeRc::from_node(&gc, factory),
+            dependencies: rewriter.dependencies,
+        });
+    }
+
+    /// Build the script which defines the modules in the order they were added and then
+    /// requires each of the `entry_points`.
+    pub fn finish(self, ctx: &mut Context, entry_points: &[SourceId]) -> NodeRc {
+        let runtime = parse_runtime(ctx, self.format);
+        let gc = GCLock::new(ctx);
+        let runtime_body = &node_cast!(Node::Program, runtime.node(&gc)).body;
+        let module_id = |id: SourceId| utils::make_number(&gc, id.0 as f64);
+
+        let body: Vec<&Node> = match self.format {
+            BundleFormat::Metro => {
+                let mut body: Vec<&Node> = runtime_body.iter().collect();
+                for module in &self.modules {
+                    let dependencies = module.dependencies.iter().map(|&id| module_id(id));
+                    let define = utils::make_call(
+                        &gc,
+                        utils::make_identifier(&gc, gc.atom("__d")),
+                        vec![
+                            module.factory.node(&gc),
+                            module_id(module.id),
+                            utils::make_array(&gc, dependencies.collect()),
+                        ],
+                    );
+                    body.push(utils::make_expression_statement(&gc, define));
+                }
+                for &entry in entry_points {
+                    let run = utils::make_call(
+                        &gc,
+                        utils::make_identifier(&gc, gc.atom("__r")),
+                        vec![module_id(entry)],
+                    );
+                    body.push(utils::make_expression_statement(&gc, run));
+                }
+                body
+            }
+            BundleFormat::Closure => {
+                let run_modules = match runtime_body.iter().last() {
+                    Some(Node::ExpressionStatement(ExpressionStatement { expression, .. })) => {
+                        expression
+                    }
+                    _ => panic!("the closure runtime must be a function expression"),
+                };
+                let factories = builder::ObjectExpression::build_template(
+                    &gc,
+                    template::ObjectExpression {
+                        metadata: Default::default(),
+                        properties: NodeList::from_iter(
+                            &gc,
+                            self.modules.iter().map(|module| {
+                                builder::Property::build_template(
+                                    &gc,
+                                    template::Property {
+                                        metadata: Default::default(),
+                                        key: module_id(module.id),
+                                        value: module.factory.node(&gc),
+                                        kind: PropertyKind::Init,
+                                        computed: false,
+                                        method: false,
+                                        shorthand: false,
+                                    },
+                                )
+                            }),
+                        ),
+                    },
+                );
+                let entry_points = entry_points.iter().map(|&id| module_id(id)).collect();
+                let run = utils::make_call(
+                    &gc,
+                    run_modules,
+                    vec![factories, utils::make_array(&gc, entry_points)],
+                );
+                vec![utils::make_expression_statement(&gc, run)]
+            }
+        };
+
+        let program = builder::Program::build_template(
+            &gc,
+            template::Program {
+                metadata: Default::default(),
+                body: NodeList::from_iter(&gc, body),
+            },
+        );
+        NodeRc::from_node(&gc, program)
+    }
+}
+
+/// Parse the runtime of `format`, adding it to the sources of `ctx` the first time.
+fn parse_runtime(ctx: &mut Context, format: BundleFormat) -> NodeRc {
+    let (name, source) = format.runtime();
+    let file_id = match ctx.sm().lookup_name(name) {
+        Some(file_id) => file_id,
+        None => ctx
+            .sm_mut()
+            .add_source(name, NullTerminatedBuf::from_str_copy(source)),
+    };
+    let buf = ctx.sm().source_buffer_rc(file_id);
+    let parsed = hparser::ParsedJS::parse(Default::default(), &buf);
+    assert!(
+        !parsed.has_errors(),
+        "invalid bundle runtime: {:?}",
+        parsed.first_error()
+    );
+    let gc = GCLock::new(ctx);
+    NodeRc::from_node(&gc, parsed.to_ast(&gc, file_id).unwrap())
+}
+
+/// Map the specifiers of the imports, re-exports, `require` calls and `import()` expressions
+/// which `sem` resolved to the modules they were resolved to.
+pub(crate) fn resolved_specifiers(gc: &GCLock, sem: &SemContext) -> HashMap<String, SourceId> {
+    let mut result = HashMap::new();
+    for (node, &target) in sem.all_requires() {
+        let source = match node.node(gc) {
+            Node::CallExpression(CallExpression { arguments, .. }) => arguments.head(),
+            Node::ImportDeclaration(ImportDeclaration { source, .. })
+            | Node::ImportExpression(ImportExpression { source, .. })
+            | Node::ExportAllDeclaration(ExportAllDeclaration { source, .. }) => Some(*source),
+            Node::ExportNamedDeclaration(ExportNamedDeclaration { source, .. }) => *source,
+            _ => None,
+        };
+        if let Some(Node::StringLiteral(StringLiteral { value, .. })) = source {
+            result.insert(String::from_utf16_lossy(gc.str_u16(*value)), target);
+        }
+    }
+    result
+}
+
+/// Replace the specifiers passed to the global `require`, `require.resolve`, `require.async`
+/// and `import()` with the IDs of the modules they were resolved to.
+struct RewriteRequires<'a> {
+    sem: &'a SemContext,
+    specifiers: &'a HashMap<String, SourceId>,
+    dependencies: Vec<SourceId>,
+}
+
+impl<'gc> VisitorMut<'gc> for RewriteRequires<'_> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let (id, kind) = match node {
+            Node::CallExpression(call) => match self.required_module(gc, call) {
+                Some(required) => required,
+                None => return node.visit_children_mut(gc, self),
+            },
+            Node::ImportExpression(ImportExpression { source, .. }) => {
+                match self.resolved(gc, source) {
+                    Some(id) => (id, DependencyKind::DynamicImport),
+                    None => return node.visit_children_mut(gc, self),
+                }
+            }
+            _ => return node.visit_children_mut(gc, self),
+        };
+        if !self.dependencies.contains(&id) {
+            self.dependencies.push(id);
+        }
+        let module_id = utils::make_number(gc, id.0 as f64);
+        let require = || {
+            utils::make_call(
+                gc,
+                utils::make_identifier(gc, gc.atom("require")),
+                vec![module_id],
+            )
+        };
+        TransformResult::Changed(match kind {
+            DependencyKind::RequireResolve => module_id,
+            kind if kind.is_lazy() => {
+                // Load the module once the promise is settled, after the current one runs.
+                let promise = utils::make_call(
+                    gc,
+                    utils::make_member(gc, utils::make_global(gc, "Promise"), "resolve"),
+                    vec![],
+                );
+                let load = utils::make_function(
+                    gc,
+                    None,
+                    vec![],
+                    vec![utils::make_return(gc, Some(require()))],
+                );
+                utils::make_call(gc, utils::make_member(gc, promise, "then"), vec![load])
+            }
+            _ => require(),
+        })
+    }
+}
+
+impl RewriteRequires<'_> {
+    /// The module loaded by `call` and the kind of dependency on it, if it's `require`,
+    /// `require.resolve` or `require.async` of a resolved specifier.
+    fn required_module<'gc>(
+        &self,
+        gc: &'gc GCLock,
+        call: &'gc CallExpression<'gc>,
+    ) -> Option<(SourceId, DependencyKind)> {
+        let (require, kind) = match call.callee {
+            require @ Node::Identifier(_) => (require, DependencyKind::Require),
+            Node::MemberExpression(MemberExpression {
+                object,
+                property: Node::Identifier(Identifier { name, .. }),
+                computed: false,
+                ..
+            }) => match gc.str(*name) {
+                "resolve" => (*object, DependencyKind::RequireResolve),
+                "async" => (*object, DependencyKind::AsyncRequire),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        match require {
+            Node::Identifier(Identifier { name, .. }) if gc.str(*name) == "require" => {}
+            _ => return None,
+        }
+        if call.arguments.len() != 1 {
+            return None;
+        }
+        match self.sem.ident_decl(&NodeRc::from_node(gc, require)) {
+            Some(Resolution::Decl(decl))
+                if self.sem.decl(decl).kind == DeclKind::UndeclaredGlobalProperty => {}
+            Some(Resolution::Unresolvable) => {}
+            _ => return None,
+        }
+        Some((self.resolved(gc, call.arguments.head()?)?, kind))
+    }
+
+    /// The module which the specifier `source` was resolved to, if it's a string literal.
+    fn resolved<'gc>(&self, gc: &'gc GCLock, source: &'gc Node<'gc>) -> Option<SourceId> {
+        match source {
+            Node::StringLiteral(StringLiteral { value, .. }) => self
+                .specifiers
+                .get(&String::from_utf16_lossy(gc.str_u16(*value)))
+                .copied(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,928 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Convert an ES module to a CommonJS script.
+//!
+//! ```js
+//! import React, {useState} from 'react';
+//! export function f() { return useState(React); }
+//! ```
+//! becomes
+//! ```js
+//! 'use strict';
+//! Object.defineProperty(exports, '__esModule', {value: true});
+//! Object.defineProperty(exports, 'f', {enumerable: true, get: function () { return f; }});
+//! var _react = _interopRequireWildcard(require('react'));
+//! function f() { return (0, _react.useState)(_react.default); }
+//! ```
+//! Exports are defined as getters, so they see later assignments to the exported bindings,
+//! and imported bindings are read from the required module whenever they're used.
+//! Modules which weren't compiled from ES modules are wrapped by an inline interop helper,
+//! so their `module.exports` is the default export.
+//!
+//! Scripts and modules without imports or exports are CommonJS modules already, and are left
+//! unchanged so that they don't become strict.
+//!
+//! With `inline_requires`, the `require` calls are moved to where the imported bindings are
+//! used, so each module is only loaded when it's first needed.
+
+use crate::utils::{self, NameGenerator};
+use crate::Pass;
+use juno::ast::*;
+use juno::sema::{DeclId, Resolution, SemContext};
+use juno_support::atom_table::Atom;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct CommonJsOptions {
+    /// Call `require` wherever an imported binding is used, instead of once at the top.
+    pub inline_requires: bool,
+}
+
+pub struct TransformCommonJs {
+    options: CommonJsOptions,
+}
+
+impl TransformCommonJs {
+    pub fn new(options: CommonJsOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Pass for TransformCommonJs {
+    fn name(&self) -> &'static str {
+        "Transform CommonJS"
+    }
+    fn description(&self) -> &'static str {
+        "Converts imports and exports to require calls and exports properties"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let body = match node {
+            Node::Module(Module { body, .. }) | Node::Program(Program { body, .. })
+                if body.iter().any(utils::is_module_declaration) =>
+            {
+                body
+            }
+            _ => return TransformResult::Unchanged,
+        };
+        let sem = utils::resolve(gc, node);
+        let mut transform = Transform {
+            sem: &sem,
+            options: &self.options,
+            names: NameGenerator::new(gc, node),
+            sources: vec![],
+            imports: HashMap::new(),
+            helpers: Default::default(),
+        };
+        for stmt in body.iter() {
+            transform.collect(gc, stmt);
+        }
+        TransformResult::Changed(transform.transform(gc, node, body))
+    }
+}
+
+/// Which binding of a module an import refers to.
+#[derive(Debug, Copy, Clone)]
+enum Imported {
+    Named(Atom),
+    Default,
+    Namespace,
+}
+
+/// A module which is required.
+struct Source {
+    specifier: String,
+    /// Variable holding the module, unless the requires are inlined.
+    name: Option<Atom>,
+    uses_named: bool,
+    uses_default: bool,
+    uses_namespace: bool,
+    /// Whether all the exports of the module are exported.
+    export_all: bool,
+}
+
+impl Source {
+    /// Which helper the module must be wrapped with.
+    fn interop(&self) -> Option<Interop> {
+        if self.uses_namespace || (self.uses_default && self.uses_named) {
+            Some(Interop::Wildcard)
+        } else if self.uses_default {
+            Some(Interop::Default)
+        } else {
+            None
+        }
+    }
+
+    fn uses_bindings(&self) -> bool {
+        self.uses_named || self.uses_default || self.uses_namespace
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Interop {
+    /// Wrap modules which aren't ES modules as the default export.
+    Default,
+    /// Wrap modules which aren't ES modules as a namespace whose properties are copied from
+    /// the module, with the module as the default export.
+    Wildcard,
+}
+
+/// Names of the helper functions which are used.
+#[derive(Default)]
+struct Helpers {
+    interop_default: Option<Atom>,
+    interop_wildcard: Option<Atom>,
+    export_star: Option<Atom>,
+}
+
+struct Transform<'s> {
+    sem: &'s SemContext,
+    options: &'s CommonJsOptions,
+    names: NameGenerator,
+    /// Required modules, in the order they're first imported.
+    sources: Vec<Source>,
+    /// The module and binding each imported declaration refers to.
+    imports: HashMap<DeclId, (usize, Imported)>,
+    helpers: Helpers,
+}
+
+impl Transform<'_> {
+    fn source_index(&mut self, gc: &GCLock, source: &Node) -> usize {
+        let specifier =
+            String::from_utf16_lossy(gc.str_u16(node_cast!(Node::StringLiteral, source).value));
+        match self.sources.iter().position(|s| s.specifier == specifier) {
+            Some(index) => index,
+            None => {
+                self.sources.push(Source {
+                    specifier,
+                    name: None,
+                    uses_named: false,
+                    uses_default: false,
+                    uses_namespace: false,
+                    export_all: false,
+                });
+                self.sources.len() - 1
+            }
+        }
+    }
+
+    fn use_binding(&mut self, index: usize, imported: Imported) {
+        let source = &mut self.sources[index];
+        match imported {
+            Imported::Named(_) => source.uses_named = true,
+            Imported::Default => source.uses_default = true,
+            Imported::Namespace => source.uses_namespace = true,
+        }
+    }
+
+    /// Record the modules required by `stmt` and the bindings imported from them.
+    fn collect<'gc>(&mut self, gc: &'gc GCLock, stmt: &'gc Node<'gc>) {
+        match stmt {
+            Node::ImportDeclaration(ImportDeclaration {
+                specifiers,
+                source,
+                import_kind: ImportKind::Value,
+                ..
+            }) => {
+                let index = self.source_index(gc, source);
+                for specifier in specifiers.iter() {
+                    let (local, imported) = match specifier {
+                        Node::ImportSpecifier(ImportSpecifier {
+                            import_kind: ImportKind::Value,
+                            imported,
+                            local,
+                            ..
+                        }) => (*local, imported_name(gc, imported)),
+                        Node::ImportDefaultSpecifier(ImportDefaultSpecifier { local, .. }) => {
+                            (*local, Imported::Default)
+                        }
+                        Node::ImportNamespaceSpecifier(ImportNamespaceSpecifier {
+                            local, ..
+                        }) => (*local, Imported::Namespace),
+                        _ => continue,
+                    };
+                    self.use_binding(index, imported);
+                    if let Some(Resolution::Decl(decl)) =
+                        self.sem.ident_decl(&NodeRc::from_node(gc, local))
+                    {
+                        self.imports.insert(decl, (index, imported));
+                    }
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                specifiers,
+                source: Some(source),
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                let index = self.source_index(gc, source);
+                for specifier in specifiers.iter() {
+                    let imported = match specifier {
+                        Node::ExportSpecifier(ExportSpecifier { local, .. }) => {
+                            imported_name(gc, local)
+                        }
+                        _ => Imported::Namespace,
+                    };
+                    self.use_binding(index, imported);
+                }
+            }
+            Node::ExportAllDeclaration(ExportAllDeclaration {
+                source,
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                let index = self.source_index(gc, source);
+                self.sources[index].export_all = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Build the script for the module `node` with `body`.
+    fn transform<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        body: &NodeList<'gc>,
+    ) -> &'gc Node<'gc> {
+        if !self.options.inline_requires {
+            for source in &mut self.sources {
+                if source.uses_bindings() {
+                    source.name = Some(self.names.fresh(gc, &module_name_base(&source.specifier)));
+                }
+            }
+        }
+
+        let mut directives = vec![];
+        let mut stmts = vec![];
+        // The exported names and the values of their getters.
+        let mut exports: Vec<(Atom, &Node)> = vec![];
+        let mut stmt_iter = body.iter().peekable();
+        while let Some(stmt) = stmt_iter.next_if(|stmt| is_directive(stmt)) {
+            directives.push(stmt);
+        }
+        if !directives.iter().any(|stmt| is_use_strict(gc, stmt)) {
+            directives.insert(0, make_use_strict(gc));
+        }
+
+        for stmt in stmt_iter {
+            match stmt {
+                Node::ImportDeclaration(_) => {}
+                Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                    export_kind: ExportKind::Type,
+                    ..
+                })
+                | Node::ExportAllDeclaration(_) => {}
+                Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                    declaration: Some(declaration),
+                    ..
+                }) => {
+                    for id in declared_identifiers(declaration) {
+                        let name = node_cast!(Node::Identifier, id).name;
+                        exports.push((name, utils::make_identifier(gc, name)));
+                    }
+                    stmts.push(self.rewrite(gc, declaration));
+                }
+                Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                    specifiers, source, ..
+                }) => {
+                    let index = source.map(|source| self.source_index(gc, source));
+                    for specifier in specifiers.iter() {
+                        let (exported, value) = match (specifier, index) {
+                            (
+                                Node::ExportSpecifier(ExportSpecifier {
+                                    exported, local, ..
+                                }),
+                                Some(index),
+                            ) => (
+                                *exported,
+                                self.import_value(gc, index, imported_name(gc, local)),
+                            ),
+                            (
+                                Node::ExportSpecifier(ExportSpecifier {
+                                    exported, local, ..
+                                }),
+                                None,
+                            ) => (*exported, self.local_value(gc, local)),
+                            (
+                                Node::ExportNamespaceSpecifier(ExportNamespaceSpecifier {
+                                    exported,
+                                    ..
+                                }),
+                                Some(index),
+                            ) => (*exported, self.import_value(gc, index, Imported::Namespace)),
+                            _ => continue,
+                        };
+                        exports.push((node_cast!(Node::Identifier, exported).name, value));
+                    }
+                }
+                Node::ExportDefaultDeclaration(ExportDefaultDeclaration {
+                    declaration, ..
+                }) => {
+                    let default = gc.atom("default");
+                    match declaration {
+                        Node::FunctionDeclaration(_) | Node::ClassDeclaration(_) => {
+                            let declaration = self.name_default(gc, declaration);
+                            let id = declared_identifiers(declaration)[0];
+                            let name = node_cast!(Node::Identifier, id).name;
+                            exports.push((default, utils::make_identifier(gc, name)));
+                            stmts.push(self.rewrite(gc, declaration));
+                        }
+                        _ => {
+                            // exports.default = value;
+                            let value = self.rewrite(gc, declaration);
+                            let target = utils::make_member(
+                                gc,
+                                utils::make_global(gc, "exports"),
+                                "default",
+                            );
+                            stmts.push(utils::make_expression_statement(
+                                gc,
+                                utils::make_assignment(gc, target, value),
+                            ));
+                        }
+                    }
+                }
+                _ => stmts.push(self.rewrite(gc, stmt)),
+            }
+        }
+
+        let mut prologue = vec![];
+        if body.iter().any(is_export) {
+            prologue.push(make_define_property(
+                gc,
+                "__esModule",
+                utils::make_object(gc, vec![("value", utils::make_boolean(gc, true))]),
+            ));
+        }
+        for (name, value) in exports {
+            let getter =
+                utils::make_function(gc, None, vec![], vec![utils::make_return(gc, Some(value))]);
+            prologue.push(make_define_property(
+                gc,
+                gc.str(name),
+                utils::make_object(
+                    gc,
+                    vec![
+                        ("enumerable", utils::make_boolean(gc, true)),
+                        ("get", getter),
+                    ],
+                ),
+            ));
+        }
+        for index in 0..self.sources.len() {
+            let source = &self.sources[index];
+            let (name, export_all, uses_bindings) =
+                (source.name, source.export_all, source.uses_bindings());
+            if let Some(name) = name {
+                // var _module = interop(require('module'));
+                let value = self.require(gc, index);
+                prologue.push(utils::make_var(
+                    gc,
+                    utils::make_identifier(gc, name),
+                    Some(value),
+                ));
+            } else if !export_all && !uses_bindings {
+                // import 'module';
+                let value = make_require(gc, &self.sources[index].specifier);
+                prologue.push(utils::make_expression_statement(gc, value));
+            }
+            if export_all {
+                // _exportStar(require('module'));
+                let module = match name {
+                    Some(name) => utils::make_identifier(gc, name),
+                    None => make_require(gc, &self.sources[index].specifier),
+                };
+                let helper = self.helper(gc, |h| &mut h.export_star, "exportStar");
+                prologue.push(utils::make_expression_statement(
+                    gc,
+                    utils::make_call(gc, utils::make_identifier(gc, helper), vec![module]),
+                ));
+            }
+        }
+
+        let mut result = directives;
+        result.extend(prologue);
+        result.extend(stmts);
+        result.extend(self.make_helpers(gc));
+        builder::Program::build_template(
+            gc,
+            template::Program {
+                metadata: TemplateMetadata {
+                    range: *node.range(),
+                    ..Default::default()
+                },
+                body: NodeList::from_iter(gc, result),
+            },
+        )
+    }
+
+    /// Replace the references to imported bindings in `node`.
+    fn rewrite<'gc>(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>) -> &'gc Node<'gc> {
+        if self.imports.is_empty() {
+            return node;
+        }
+        let mut rewriter = Rewriter { transform: self };
+        utils::changed_or(VisitorMut::call(&mut rewriter, gc, node, None), node)
+    }
+
+    /// The value of the local binding `id`, which is exported.
+    fn local_value<'gc>(&mut self, gc: &'gc GCLock, id: &'gc Node<'gc>) -> &'gc Node<'gc> {
+        match self.sem.ident_decl(&NodeRc::from_node(gc, id)) {
+            Some(Resolution::Decl(decl)) if self.imports.contains_key(&decl) => {
+                let (index, imported) = self.imports[&decl];
+                self.import_value(gc, index, imported)
+            }
+            _ => utils::make_identifier(gc, node_cast!(Node::Identifier, id).name),
+        }
+    }
+
+    /// Build the expression which reads `imported` from the module at `index`.
+    fn import_value<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        index: usize,
+        imported: Imported,
+    ) -> &'gc Node<'gc> {
+        let module = match self.sources[index].name {
+            Some(name) => utils::make_identifier(gc, name),
+            None => self.require(gc, index),
+        };
+        match imported {
+            Imported::Named(name) => utils::make_member(gc, module, gc.str(name)),
+            Imported::Default => utils::make_member(gc, module, "default"),
+            Imported::Namespace => module,
+        }
+    }
+
+    /// Build the call which requires the module at `index`, wrapped by its interop helper.
+    fn require<'gc>(&mut self, gc: &'gc GCLock, index: usize) -> &'gc Node<'gc> {
+        let call = make_require(gc, &self.sources[index].specifier);
+        let helper = match self.sources[index].interop() {
+            Some(Interop::Default) => {
+                self.helper(gc, |h| &mut h.interop_default, "interopRequireDefault")
+            }
+            Some(Interop::Wildcard) => {
+                self.helper(gc, |h| &mut h.interop_wildcard, "interopRequireWildcard")
+            }
+            None => return call,
+        };
+        utils::make_call(gc, utils::make_identifier(gc, helper), vec![call])
+    }
+
+    /// Name of the helper function stored in `field`, which is declared once it's used.
+    fn helper(
+        &mut self,
+        gc: &GCLock,
+        field: impl Fn(&mut Helpers) -> &mut Option<Atom>,
+        base: &str,
+    ) -> Atom {
+        if let Some(name) = *field(&mut self.helpers) {
+            return name;
+        }
+        let name = self.names.fresh(gc, base);
+        *field(&mut self.helpers) = Some(name);
+        name
+    }
+
+    /// Give the anonymous default exported function or class `declaration` a name.
+    fn name_default<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        declaration: &'gc Node<'gc>,
+    ) -> &'gc Node<'gc> {
+        match declaration {
+            Node::FunctionDeclaration(decl) if decl.id.is_none() => {
+                let mut builder = builder::FunctionDeclaration::from_node(decl);
+                builder.id(Some(utils::make_identifier(
+                    gc,
+                    self.names.fresh(gc, "default"),
+                )));
+                builder.build_forced(gc)
+            }
+            Node::ClassDeclaration(decl) if decl.id.is_none() => {
+                let mut builder = builder::ClassDeclaration::from_node(decl);
+                builder.id(Some(utils::make_identifier(
+                    gc,
+                    self.names.fresh(gc, "default"),
+                )));
+                builder.build_forced(gc)
+            }
+            _ => declaration,
+        }
+    }
+
+    /// Declare the helper functions which were used.
+    fn make_helpers<'gc>(&self, gc: &'gc GCLock) -> Vec<&'gc Node<'gc>> {
+        let id = |name: &str| utils::make_identifier(gc, gc.atom(name));
+        // obj && obj.__esModule
+        let is_es_module = || {
+            utils::make_logical(
+                gc,
+                LogicalExpressionOperator::And,
+                id("obj"),
+                utils::make_member(gc, id("obj"), "__esModule"),
+            )
+        };
+        let mut result = vec![];
+        if let Some(name) = self.helpers.interop_default {
+            // return obj && obj.__esModule ? obj : {default: obj};
+            let value = utils::make_conditional(
+                gc,
+                is_es_module(),
+                id("obj"),
+                utils::make_object(gc, vec![("default", id("obj"))]),
+            );
+            result.push(make_function_declaration(
+                gc,
+                name,
+                vec![id("obj")],
+                vec![utils::make_return(gc, Some(value))],
+            ));
+        }
+        if let Some(name) = self.helpers.interop_wildcard {
+            // if (obj && obj.__esModule) return obj;
+            // var ns = {default: obj};
+            // if (obj != null)
+            //   for (var k in obj)
+            //     if (k !== 'default' && Object.prototype.hasOwnProperty.call(obj, k))
+            //       ns[k] = obj[k];
+            // return ns;
+            let copy = make_if(
+                gc,
+                utils::make_logical(
+                    gc,
+                    LogicalExpressionOperator::And,
+                    utils::make_binary(
+                        gc,
+                        BinaryExpressionOperator::StrictNotEquals,
+                        id("k"),
+                        utils::make_string(gc, "default"),
+                    ),
+                    utils::make_call(
+                        gc,
+                        utils::make_member(
+                            gc,
+                            utils::make_member(
+                                gc,
+                                utils::make_member(
+                                    gc,
+                                    utils::make_global(gc, "Object"),
+                                    "prototype",
+                                ),
+                                "hasOwnProperty",
+                            ),
+                            "call",
+                        ),
+                        vec![id("obj"), id("k")],
+                    ),
+                ),
+                utils::make_expression_statement(
+                    gc,
+                    utils::make_assignment(
+                        gc,
+                        utils::make_computed_member(gc, id("ns"), id("k")),
+                        utils::make_computed_member(gc, id("obj"), id("k")),
+                    ),
+                ),
+            );
+            let loop_ = builder::ForInStatement::build_template(
+                gc,
+                template::ForInStatement {
+                    metadata: Default::default(),
+                    left: utils::make_var(gc, id("k"), None),
+                    right: id("obj"),
+                    body: copy,
+                },
+            );
+            let not_null = utils::make_binary(
+                gc,
+                BinaryExpressionOperator::LooseNotEquals,
+                id("obj"),
+                make_null(gc),
+            );
+            result.push(make_function_declaration(
+                gc,
+                name,
+                vec![id("obj")],
+                vec![
+                    make_if(gc, is_es_module(), utils::make_return(gc, Some(id("obj")))),
+                    utils::make_var(
+                        gc,
+                        id("ns"),
+                        Some(utils::make_object(gc, vec![("default", id("obj"))])),
+                    ),
+                    make_if(gc, not_null, loop_),
+                    utils::make_return(gc, Some(id("ns"))),
+                ],
+            ));
+        }
+        if let Some(name) = self.helpers.export_star {
+            // Object.keys(obj).forEach(function (k) {
+            //   if (k !== 'default' && !(k in exports))
+            //     Object.defineProperty(exports, k, {
+            //       enumerable: true,
+            //       get: function () { return obj[k]; }
+            //     });
+            // });
+            let getter = utils::make_function(
+                gc,
+                None,
+                vec![],
+                vec![utils::make_return(
+                    gc,
+                    Some(utils::make_computed_member(gc, id("obj"), id("k"))),
+                )],
+            );
+            let define = utils::make_call(
+                gc,
+                utils::make_member(gc, utils::make_global(gc, "Object"), "defineProperty"),
+                vec![
+                    utils::make_global(gc, "exports"),
+                    id("k"),
+                    utils::make_object(
+                        gc,
+                        vec![
+                            ("enumerable", utils::make_boolean(gc, true)),
+                            ("get", getter),
+                        ],
+                    ),
+                ],
+            );
+            let not_exported = builder::UnaryExpression::build_template(
+                gc,
+                template::UnaryExpression {
+                    metadata: Default::default(),
+                    operator: UnaryExpressionOperator::Not,
+                    argument: utils::make_binary(
+                        gc,
+                        BinaryExpressionOperator::In,
+                        id("k"),
+                        utils::make_global(gc, "exports"),
+                    ),
+                    prefix: true,
+                },
+            );
+            let test = utils::make_logical(
+                gc,
+                LogicalExpressionOperator::And,
+                utils::make_binary(
+                    gc,
+                    BinaryExpressionOperator::StrictNotEquals,
+                    id("k"),
+                    utils::make_string(gc, "default"),
+                ),
+                not_exported,
+            );
+            let callback = utils::make_function(
+                gc,
+                None,
+                vec![id("k")],
+                vec![make_if(
+                    gc,
+                    test,
+                    utils::make_expression_statement(gc, define),
+                )],
+            );
+            let keys = utils::make_call(
+                gc,
+                utils::make_member(gc, utils::make_global(gc, "Object"), "keys"),
+                vec![id("obj")],
+            );
+            let for_each =
+                utils::make_call(gc, utils::make_member(gc, keys, "forEach"), vec![callback]);
+            result.push(make_function_declaration(
+                gc,
+                name,
+                vec![id("obj")],
+                vec![utils::make_expression_statement(gc, for_each)],
+            ));
+        }
+        result
+    }
+}
+
+/// Replaces references to imported bindings with reads from the required modules.
+struct Rewriter<'t, 's> {
+    transform: &'t mut Transform<'s>,
+}
+
+impl<'gc> VisitorMut<'gc> for Rewriter<'_, '_> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            Node::Identifier(_) if utils::is_reference(path) => {
+                let (index, imported) =
+                    match self.transform.sem.ident_decl(&NodeRc::from_node(gc, node)) {
+                        Some(Resolution::Decl(decl)) => match self.transform.imports.get(&decl) {
+                            Some(&import) => import,
+                            None => return TransformResult::Unchanged,
+                        },
+                        _ => return TransformResult::Unchanged,
+                    };
+                let value = self.transform.import_value(gc, index, imported);
+                let is_callee = matches!(
+                    path,
+                    Some(Path {
+                        parent: Node::CallExpression(_),
+                        field: NodeField::callee,
+                    }) | Some(Path {
+                        parent: Node::TaggedTemplateExpression(_),
+                        field: NodeField::tag,
+                    })
+                );
+                if is_callee && !matches!(imported, Imported::Namespace) {
+                    // Call the imported function without the module as `this`: (0, _m.f)()
+                    return TransformResult::Changed(utils::make_sequence(
+                        gc,
+                        vec![utils::make_number(gc, 0.0), value],
+                    ));
+                }
+                TransformResult::Changed(value)
+            }
+            Node::Property(Property {
+                shorthand: true, ..
+            }) => match node.visit_children_mut(gc, self) {
+                // The value isn't an identifier anymore.
+                TransformResult::Changed(Node::Property(prop)) => {
+                    let mut builder = builder::Property::from_node(prop);
+                    builder.shorthand(false);
+                    TransformResult::Changed(builder.build_forced(gc))
+                }
+                result => result,
+            },
+            _ => node.visit_children_mut(gc, self),
+        }
+    }
+}
+
+fn imported_name(gc: &GCLock, id: &Node) -> Imported {
+    let name = node_cast!(Node::Identifier, id).name;
+    if gc.str(name) == "default" {
+        Imported::Default
+    } else {
+        Imported::Named(name)
+    }
+}
+
+/// Base for the name of the variable holding the module `specifier`,
+/// such as `react_dom` for `react-dom` or `utils` for `./lib/utils.js`.
+pub(crate) fn module_name_base(specifier: &str) -> String {
+    let file = specifier.rsplit('/').next().unwrap_or(specifier);
+    let stem = file.split('.').next().unwrap_or(file);
+    let name: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() {
+        "module".to_string()
+    } else {
+        name
+    }
+}
+
+/// The identifiers declared by the exported `declaration`.
+fn declared_identifiers<'gc>(declaration: &'gc Node<'gc>) -> Vec<&'gc Node<'gc>> {
+    let mut result = vec![];
+    match declaration {
+        Node::FunctionDeclaration(FunctionDeclaration { id: Some(id), .. })
+        | Node::ClassDeclaration(ClassDeclaration { id: Some(id), .. }) => result.push(*id),
+        Node::VariableDeclaration(VariableDeclaration { declarations, .. }) => {
+            for declarator in declarations.iter() {
+                let id = node_cast!(Node::VariableDeclarator, declarator).id;
+                utils::collect_binding_identifiers(id, &mut result);
+            }
+        }
+        _ => {}
+    }
+    result
+}
+
+fn is_export(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::ExportNamedDeclaration(_)
+            | Node::ExportDefaultDeclaration(_)
+            | Node::ExportAllDeclaration(_)
+    )
+}
+
+fn is_directive(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::ExpressionStatement(ExpressionStatement {
+            directive: Some(_),
+            ..
+        })
+    )
+}
+
+fn is_use_strict(gc: &GCLock, node: &Node) -> bool {
+    match node {
+        Node::ExpressionStatement(ExpressionStatement {
+            directive: Some(directive),
+            ..
+        }) => String::from_utf16_lossy(gc.str_u16(*directive)) == "use strict",
+        _ => false,
+    }
+}
+
+fn make_use_strict<'gc>(gc: &'gc GCLock) -> &'gc Node<'gc> {
+    builder::ExpressionStatement::build_template(
+        gc,
+        template::ExpressionStatement {
+            metadata: Default::default(),
+            expression: utils::make_string(gc, "use strict"),
+            directive: Some(gc.atom_u16("use strict".encode_utf16().collect::<Vec<u16>>())),
+        },
+    )
+}
+
+/// Build `require('specifier')`.
+fn make_require<'gc>(gc: &'gc GCLock, specifier: &str) -> &'gc Node<'gc> {
+    utils::make_call(
+        gc,
+        utils::make_global(gc, "require"),
+        vec![utils::make_string(gc, specifier)],
+    )
+}
+
+/// Build `Object.defineProperty(exports, 'name', descriptor);`.
+fn make_define_property<'gc>(
+    gc: &'gc GCLock,
+    name: &str,
+    descriptor: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    utils::make_expression_statement(
+        gc,
+        utils::make_call(
+            gc,
+            utils::make_member(gc, utils::make_global(gc, "Object"), "defineProperty"),
+            vec![
+                utils::make_global(gc, "exports"),
+                utils::make_string(gc, name),
+                descriptor,
+            ],
+        ),
+    )
+}
+
+fn make_function_declaration<'gc>(
+    gc: &'gc GCLock,
+    name: Atom,
+    params: Vec<&'gc Node<'gc>>,
+    body: Vec<&'gc Node<'gc>>,
+) -> &'gc Node<'gc> {
+    builder::FunctionDeclaration::build_template(
+        gc,
+        template::FunctionDeclaration {
+            metadata: Default::default(),
+            id: Some(utils::make_identifier(gc, name)),
+            params: NodeList::from_iter(gc, params),
+            body: utils::make_block(gc, body),
+            type_parameters: None,
+            return_type: None,
+            predicate: None,
+            generator: false,
+            is_async: false,
+        },
+    )
+}
+
+fn make_if<'gc>(
+    gc: &'gc GCLock,
+    test: &'gc Node<'gc>,
+    consequent: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    builder::IfStatement::build_template(
+        gc,
+        template::IfStatement {
+            metadata: Default::default(),
+            test,
+            consequent,
+            alternate: None,
+        },
+    )
+}
+
+fn make_null<'gc>(gc: &'gc GCLock) -> &'gc Node<'gc> {
+    builder::NullLiteral::build_template(
+        gc,
+        template::NullLiteral {
+            metadata: Default::default(),
+        },
+    )
+}
@@ -0,0 +1,508 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Pass for evaluating operators whose operands are all literals.
+//!
+//! Handles unary, binary and logical operators, as well as untagged template literals,
+//! following the ECMAScript semantics for primitive values.
+//! For example, transforms
+//! ```js
+//! if (1 + 1 === 2) foo(`a${1}b`);
+//! ```
+//! into
+//! ```js
+//! if (true) foo("a1b");
+//! ```
+//!
+//! Results which can't be represented by a literal (`NaN` and `Infinity`) are not folded,
+//! because the identifiers used to spell them may be shadowed.
+
+use crate::Pass;
+use juno::ast::*;
+use juno_support::convert::number_to_string;
+use std::cmp::Ordering;
+
+#[derive(Default)]
+pub struct ConstantFold {}
+
+impl ConstantFold {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for ConstantFold {
+    fn name(&self) -> &'static str {
+        "Constant fold"
+    }
+    fn description(&self) -> &'static str {
+        "Evaluates operators on literal operands at compile time"
+    }
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        VisitorMut::call(self, gc, node, None)
+    }
+}
+
+impl<'gc> VisitorMut<'gc> for ConstantFold {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        // Fold the children first, so that nested expressions are reduced bottom-up.
+        let result = node.visit_children_mut(gc, self);
+        let current = match result {
+            TransformResult::Unchanged => node,
+            TransformResult::Changed(new_node) => new_node,
+            _ => return result,
+        };
+        match fold(gc, current, parent) {
+            // A string literal in statement position could turn into a directive.
+            Some(Node::StringLiteral(_))
+                if matches!(
+                    parent,
+                    Some(Path {
+                        parent: Node::ExpressionStatement(_),
+                        ..
+                    })
+                ) =>
+            {
+                result
+            }
+            Some(folded) => TransformResult::Changed(folded),
+            None => result,
+        }
+    }
+}
+
+/// Whether the expression at `path` behaves differently when it is a bare reference
+/// instead of a value: the callee of a call, the tag of a template, or the operand of
+/// `delete` and `typeof`.
+fn is_reference_sensitive(path: Option<Path>) -> bool {
+    match path {
+        Some(Path { parent, field }) => matches!(
+            (parent, field),
+            (Node::CallExpression(_), NodeField::callee)
+                | (Node::OptionalCallExpression(_), NodeField::callee)
+                | (Node::TaggedTemplateExpression(_), NodeField::tag)
+                | (
+                    Node::UnaryExpression(UnaryExpression {
+                        operator: UnaryExpressionOperator::Delete | UnaryExpressionOperator::Typeof,
+                        ..
+                    }),
+                    NodeField::argument
+                )
+        ),
+        None => false,
+    }
+}
+
+/// A primitive value known at compile time.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Undefined,
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(Vec<u16>),
+}
+
+/// Attempt to evaluate `node`, whose children have already been folded.
+/// Return the literal replacing it, or `None` if it must be left alone.
+fn fold<'gc>(
+    gc: &'gc GCLock,
+    node: &'gc Node<'gc>,
+    parent: Option<Path<'gc>>,
+) -> Option<&'gc Node<'gc>> {
+    match node {
+        Node::UnaryExpression(UnaryExpression {
+            operator, argument, ..
+        }) => {
+            // These are already in canonical form, folding them would build the same node.
+            match (operator, argument) {
+                (UnaryExpressionOperator::Minus, Node::NumericLiteral(_)) => return None,
+                (
+                    UnaryExpressionOperator::Void,
+                    Node::NumericLiteral(NumericLiteral { value, .. }),
+                ) if *value == 0.0 => return None,
+                _ => {}
+            }
+            let value = value_of(gc, argument)?;
+            let result = match operator {
+                UnaryExpressionOperator::Void => Value::Undefined,
+                UnaryExpressionOperator::Typeof => Value::String(str_to_u16(value.type_of())),
+                UnaryExpressionOperator::Not => Value::Bool(!value.to_boolean()),
+                UnaryExpressionOperator::Plus => Value::Number(value.to_number()),
+                UnaryExpressionOperator::Minus => Value::Number(-value.to_number()),
+                UnaryExpressionOperator::BitNot => Value::Number(!value.to_int32() as f64),
+                UnaryExpressionOperator::Delete => return None,
+            };
+            build_value(gc, node, result)
+        }
+        Node::BinaryExpression(BinaryExpression {
+            left,
+            right,
+            operator,
+            ..
+        }) => {
+            let result = fold_binary(*operator, value_of(gc, left)?, value_of(gc, right)?)?;
+            build_value(gc, node, result)
+        }
+        Node::LogicalExpression(LogicalExpression {
+            left,
+            right,
+            operator,
+            ..
+        }) => {
+            let value = value_of(gc, left)?;
+            let take_left = match operator {
+                LogicalExpressionOperator::And => !value.to_boolean(),
+                LogicalExpressionOperator::Or => value.to_boolean(),
+                LogicalExpressionOperator::NullishCoalesce => {
+                    !matches!(value, Value::Undefined | Value::Null)
+                }
+            };
+            let result = if take_left { *left } else { *right };
+            // Unwrapping a reference would rebind `this` in a call, or change what
+            // `delete` and `typeof` operate on: `(0, o.f)()` is not `o.f()`.
+            if value_of(gc, result).is_none() && is_reference_sensitive(parent) {
+                return None;
+            }
+            Some(result)
+        }
+        Node::TemplateLiteral(TemplateLiteral {
+            quasis,
+            expressions,
+            ..
+        }) => {
+            // The strings of a tagged template are observable by the tag function.
+            if let Some(Path {
+                parent: Node::TaggedTemplateExpression(_),
+                ..
+            }) = parent
+            {
+                return None;
+            }
+            let mut result: Vec<u16> = vec![];
+            let mut expressions = expressions.iter();
+            for quasi in quasis.iter() {
+                let element = node_cast!(Node::TemplateElement, quasi);
+                result.extend_from_slice(gc.str_u16(element.cooked?));
+                if let Some(expr) = expressions.next() {
+                    result.extend(value_of(gc, expr)?.to_string());
+                }
+            }
+            build_value(gc, node, Value::String(result))
+        }
+        _ => None,
+    }
+}
+
+/// Return the value of `node` if it is a literal.
+/// Negative numbers and `undefined` are recognized in the form produced by `build_value`.
+fn value_of<'gc>(gc: &'gc GCLock, node: &'gc Node<'gc>) -> Option<Value> {
+    match node {
+        Node::NullLiteral(_) => Some(Value::Null),
+        Node::BooleanLiteral(BooleanLiteral { value, .. }) => Some(Value::Bool(*value)),
+        Node::NumericLiteral(NumericLiteral { value, .. }) => Some(Value::Number(*value)),
+        Node::StringLiteral(StringLiteral { value, .. }) => {
+            Some(Value::String(gc.str_u16(*value).to_vec()))
+        }
+        Node::UnaryExpression(UnaryExpression {
+            operator: UnaryExpressionOperator::Minus,
+            argument: Node::NumericLiteral(NumericLiteral { value, .. }),
+            ..
+        }) => Some(Value::Number(-*value)),
+        Node::UnaryExpression(UnaryExpression {
+            operator: UnaryExpressionOperator::Void,
+            argument,
+            ..
+        }) => value_of(gc, argument).map(|_| Value::Undefined),
+        _ => None,
+    }
+}
+
+/// Create the literal representing `value`, using the range of `node`.
+/// Return `None` if there is no literal for `value`.
+fn build_value<'gc>(gc: &'gc GCLock, node: &'gc Node<'gc>, value: Value) -> Option<&'gc Node<'gc>> {
+    let range = *node.range();
+    let metadata = || TemplateMetadata {
+        range,
+        ..Default::default()
+    };
+    Some(match value {
+        Value::Undefined => builder::UnaryExpression::build_template(
+            gc,
+            template::UnaryExpression {
+                metadata: metadata(),
+                operator: UnaryExpressionOperator::Void,
+                argument: builder::NumericLiteral::build_template(
+                    gc,
+                    template::NumericLiteral {
+                        metadata: metadata(),
+                        value: 0.0,
+                    },
+                ),
+                prefix: true,
+            },
+        ),
+        Value::Null => builder::NullLiteral::build_template(
+            gc,
+            template::NullLiteral {
+                metadata: metadata(),
+            },
+        ),
+        Value::Bool(value) => builder::BooleanLiteral::build_template(
+            gc,
+            template::BooleanLiteral {
+                metadata: metadata(),
+                value,
+            },
+        ),
+        Value::String(value) => builder::StringLiteral::build_template(
+            gc,
+            template::StringLiteral {
+                metadata: metadata(),
+                value: gc.atom_u16(value),
+            },
+        ),
+        Value::Number(value) => {
+            if !value.is_finite() {
+                return None;
+            }
+            let literal = builder::NumericLiteral::build_template(
+                gc,
+                template::NumericLiteral {
+                    metadata: metadata(),
+                    value: value.abs(),
+                },
+            );
+            // Also handles -0.
+            if value.is_sign_negative() {
+                builder::UnaryExpression::build_template(
+                    gc,
+                    template::UnaryExpression {
+                        metadata: metadata(),
+                        operator: UnaryExpressionOperator::Minus,
+                        argument: literal,
+                        prefix: true,
+                    },
+                )
+            } else {
+                literal
+            }
+        }
+    })
+}
+
+/// Evaluate a binary operator on two primitive values.
+/// Return `None` for operators which can't be applied to primitives without throwing.
+fn fold_binary(op: BinaryExpressionOperator, left: Value, right: Value) -> Option<Value> {
+    use BinaryExpressionOperator::*;
+    Some(match op {
+        Plus => match (&left, &right) {
+            (Value::String(_), _) | (_, Value::String(_)) => {
+                let mut result = left.to_string();
+                result.extend(right.to_string());
+                Value::String(result)
+            }
+            _ => Value::Number(left.to_number() + right.to_number()),
+        },
+        Minus => Value::Number(left.to_number() - right.to_number()),
+        Mult => Value::Number(left.to_number() * right.to_number()),
+        Div => Value::Number(left.to_number() / right.to_number()),
+        // Rust's `%` on floats has the same semantics as fmod(), like JS.
+        Mod => Value::Number(left.to_number() % right.to_number()),
+        Exp => Value::Number(exponentiate(left.to_number(), right.to_number())),
+        BitAnd => Value::Number((left.to_int32() & right.to_int32()) as f64),
+        BitOr => Value::Number((left.to_int32() | right.to_int32()) as f64),
+        BitXor => Value::Number((left.to_int32() ^ right.to_int32()) as f64),
+        LShift => Value::Number(left.to_int32().wrapping_shl(right.to_uint32()) as f64),
+        RShift => Value::Number(left.to_int32().wrapping_shr(right.to_uint32()) as f64),
+        RShift3 => Value::Number(left.to_uint32().wrapping_shr(right.to_uint32()) as f64),
+        StrictEquals => Value::Bool(strict_equals(&left, &right)),
+        StrictNotEquals => Value::Bool(!strict_equals(&left, &right)),
+        LooseEquals => Value::Bool(loose_equals(&left, &right)),
+        LooseNotEquals => Value::Bool(!loose_equals(&left, &right)),
+        Less => Value::Bool(compare(&left, &right) == Some(Ordering::Less)),
+        Greater => Value::Bool(compare(&left, &right) == Some(Ordering::Greater)),
+        LessEquals => Value::Bool(matches!(
+            compare(&left, &right),
+            Some(Ordering::Less | Ordering::Equal)
+        )),
+        GreaterEquals => Value::Bool(matches!(
+            compare(&left, &right),
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        // Both of these throw when the right operand isn't an object.
+        In | Instanceof => return None,
+    })
+}
+
+/// The `**` operator, ES2022 6.1.6.1.3 Number::exponentiate.
+fn exponentiate(base: f64, exponent: f64) -> f64 {
+    // Unlike C's pow(), the result is NaN when the base is +-1 and the exponent is infinite.
+    if exponent.is_nan() || (base.abs() == 1.0 && exponent.is_infinite()) {
+        f64::NAN
+    } else {
+        base.powf(exponent)
+    }
+}
+
+/// IsStrictlyEqual for primitive values.
+fn strict_equals(left: &Value, right: &Value) -> bool {
+    // Comparing the f64 values directly handles NaN and -0.
+    left == right
+}
+
+/// IsLooselyEqual for primitive values.
+fn loose_equals(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Undefined | Value::Null, Value::Undefined | Value::Null) => true,
+        (Value::Undefined | Value::Null, _) | (_, Value::Undefined | Value::Null) => false,
+        (Value::Number(_), Value::String(_))
+        | (Value::String(_), Value::Number(_))
+        | (Value::Bool(_), _)
+        | (_, Value::Bool(_)) => left.to_number() == right.to_number(),
+        _ => strict_equals(left, right),
+    }
+}
+
+/// IsLessThan for primitive values. Returns `None` when the result is undefined (NaN operands).
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        // Strings are compared by their UTF-16 code units.
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        _ => left.to_number().partial_cmp(&right.to_number()),
+    }
+}
+
+impl Value {
+    fn type_of(&self) -> &'static str {
+        match self {
+            Value::Undefined => "undefined",
+            Value::Null => "object",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+        }
+    }
+
+    fn to_boolean(&self) -> bool {
+        match self {
+            Value::Undefined | Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Number(n) => !(*n == 0.0 || n.is_nan()),
+            Value::String(s) => !s.is_empty(),
+        }
+    }
+
+    fn to_number(&self) -> f64 {
+        match self {
+            Value::Undefined => f64::NAN,
+            Value::Null => 0.0,
+            Value::Bool(b) => *b as u8 as f64,
+            Value::Number(n) => *n,
+            Value::String(s) => string_to_number(s),
+        }
+    }
+
+    /// ES2022 7.1.6 ToInt32.
+    fn to_int32(&self) -> i32 {
+        self.to_uint32() as i32
+    }
+
+    /// ES2022 7.1.7 ToUint32.
+    fn to_uint32(&self) -> u32 {
+        let n = self.to_number();
+        if !n.is_finite() {
+            return 0;
+        }
+        n.trunc().rem_euclid(4294967296.0) as u32
+    }
+
+    fn to_string(&self) -> Vec<u16> {
+        match self {
+            Value::Undefined => str_to_u16("undefined"),
+            Value::Null => str_to_u16("null"),
+            Value::Bool(b) => str_to_u16(if *b { "true" } else { "false" }),
+            Value::Number(n) => str_to_u16(&number_to_string(*n)),
+            Value::String(s) => s.clone(),
+        }
+    }
+}
+
+fn str_to_u16(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+/// Whether `c` is a WhiteSpace or LineTerminator code unit.
+fn is_js_whitespace(c: u16) -> bool {
+    (0x09..=0x0D).contains(&c)
+        || (0x2000..=0x200A).contains(&c)
+        || matches!(
+            c,
+            0x20 | 0xA0 | 0x1680 | 0x2028 | 0x2029 | 0x202F | 0x205F | 0x3000 | 0xFEFF
+        )
+}
+
+/// ES2022 7.1.4.1.1 StringToNumber.
+fn string_to_number(s: &[u16]) -> f64 {
+    let start = s.iter().position(|c| !is_js_whitespace(*c));
+    let end = s.iter().rposition(|c| !is_js_whitespace(*c));
+    let trimmed = match (start, end) {
+        (Some(start), Some(end)) => &s[start..=end],
+        _ => return 0.0,
+    };
+    // Everything valid is ASCII.
+    let str = match String::from_utf16(trimmed) {
+        Ok(str) if str.is_ascii() => str,
+        _ => return f64::NAN,
+    };
+
+    let radix = match str.get(..2) {
+        Some("0x" | "0X") => 16,
+        Some("0o" | "0O") => 8,
+        Some("0b" | "0B") => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        let digits = &str[2..];
+        if digits.is_empty() {
+            return f64::NAN;
+        }
+        return digits
+            .chars()
+            .try_fold(0.0, |acc, c| {
+                c.to_digit(radix).map(|d| acc * radix as f64 + d as f64)
+            })
+            .unwrap_or(f64::NAN);
+    }
+
+    let unsigned = str.strip_prefix(['+', '-']).unwrap_or(&str);
+    if unsigned == "Infinity" {
+        return if str.starts_with('-') {
+            f64::NEG_INFINITY
+        } else {
+            f64::INFINITY
+        };
+    }
+    // Rust accepts spellings of infinity and NaN which JS doesn't, so restrict the characters.
+    // The remaining syntax of StrDecimalLiteral matches Rust's float syntax.
+    if !unsigned
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return f64::NAN;
+    }
+    str.parse().unwrap_or(f64::NAN)
+}
@@ -0,0 +1,488 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Pass for removing code which can never execute or whose result is never used.
+//!
+//! Removes statements which follow a `return`, `throw`, `break` or `continue`,
+//! empty blocks and empty `if` branches, as well as unused function declarations and
+//! `var` declarations with side-effect-free initializers.
+//! For example, transforms
+//! ```js
+//! function foo(x) {
+//!   var unused = 10;
+//!   function bar() {}
+//!   if (x) {} else { return 1; }
+//!   return 2;
+//!   x++;
+//! }
+//! ```
+//! into
+//! ```js
+//! function foo(x) {
+//!   if (!x) { return 1; }
+//!   return 2;
+//! }
+//! ```
+//!
+//! Declarations in unreachable code are hoisted, so unreachable `var` declarations
+//! are kept without their initializers and unreachable function declarations are kept.
+//! Unreachable `let`, `const` and `class` declarations are kept when their bindings are
+//! referenced, because closures in reachable code may refer to them.
+//! Global declarations are never removed, because they are observable as properties of
+//! the global object.
+
+use crate::utils;
+use crate::Pass;
+use juno::ast::*;
+use juno::sema::{DeclId, DeclKind, Resolution, SemContext, Special};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Default)]
+pub struct DeadCodeElimination {}
+
+impl DeadCodeElimination {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for DeadCodeElimination {
+    fn name(&self) -> &'static str {
+        "Dead code elimination"
+    }
+    fn description(&self) -> &'static str {
+        "Removes unreachable statements, empty blocks and unused declarations"
+    }
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let mut root = node;
+        loop {
+            let sem = utils::resolve(gc, root);
+            let mut eliminator = Eliminator::new(&sem);
+            match VisitorMut::call(&mut eliminator, gc, root, None) {
+                TransformResult::Changed(new_root) => root = new_root,
+                _ => break,
+            }
+            // Removing a declaration may have removed the last references to other ones,
+            // which can only be discovered by resolving the new AST.
+            if !eliminator.removed_decl {
+                break;
+            }
+        }
+        if root.ptr_eq(node) {
+            TransformResult::Unchanged
+        } else {
+            TransformResult::Changed(root)
+        }
+    }
+}
+
+/// Performs a single round of elimination, using the results of semantic resolution.
+struct Eliminator<'s> {
+    sem: &'s SemContext,
+    /// Number of identifiers resolving to each declaration,
+    /// or `None` if some identifiers couldn't be resolved.
+    counts: Option<HashMap<DeclId, usize>>,
+    /// Declarations which can be removed because their only identifier is the declaration.
+    unused: HashSet<DeclId>,
+    /// Whether any declaration was removed.
+    removed_decl: bool,
+}
+
+impl<'s> Eliminator<'s> {
+    fn new(sem: &'s SemContext) -> Self {
+        let counts = count_identifiers(sem);
+        Self {
+            sem,
+            unused: find_unused(sem, counts.as_ref()),
+            counts,
+            removed_decl: false,
+        }
+    }
+
+    /// Return true if `id` is the declaration of an unused variable or function.
+    fn is_unused<'gc>(&self, gc: &'gc GCLock, id: &'gc Node<'gc>) -> bool {
+        matches!(
+            self.sem.ident_decl(&NodeRc::from_node(gc, id)),
+            Some(Resolution::Decl(decl)) if self.unused.contains(&decl)
+        )
+    }
+
+    /// Return true if the variable declared by `id` may be referenced by other identifiers.
+    fn is_referenced<'gc>(&self, gc: &'gc GCLock, id: &'gc Node<'gc>) -> bool {
+        match (
+            &self.counts,
+            self.sem.ident_decl(&NodeRc::from_node(gc, id)),
+        ) {
+            (Some(counts), Some(Resolution::Decl(decl))) => {
+                counts.get(&decl).is_some_and(|&count| count > 1)
+            }
+            _ => true,
+        }
+    }
+
+    /// Remove dead statements from the statement list `body`.
+    /// `function_level` indicates that the list is the body of a function or a program,
+    /// as opposed to a nested block.
+    /// Return the new list, or `None` if nothing was removed.
+    fn prune_statements<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        body: &NodeList<'gc>,
+        function_level: bool,
+    ) -> Option<NodeList<'gc>> {
+        let mut result: Vec<&'gc Node<'gc>> = vec![];
+        let mut changed = false;
+        let mut reachable = true;
+        for stmt in body.iter() {
+            let new_stmt = if reachable {
+                self.prune_statement(gc, stmt, function_level)
+            } else {
+                self.hoisted_declaration(gc, stmt, function_level)
+            };
+            match new_stmt {
+                Some(new_stmt) => {
+                    changed |= !new_stmt.ptr_eq(stmt);
+                    reachable = reachable && !terminates(new_stmt);
+                    result.push(new_stmt);
+                }
+                None => changed = true,
+            }
+        }
+        if changed {
+            Some(NodeList::from_iter(gc, result))
+        } else {
+            None
+        }
+    }
+
+    /// Return `stmt` with unused declarations removed,
+    /// or `None` if the entire statement can be removed.
+    fn prune_statement<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        stmt: &'gc Node<'gc>,
+        function_level: bool,
+    ) -> Option<&'gc Node<'gc>> {
+        match stmt {
+            Node::EmptyStatement(_) => None,
+            Node::BlockStatement(BlockStatement { body, .. }) if body.is_empty() => None,
+            // Only remove functions which are not in a nested block, because sloppy mode
+            // block-level functions may also be visible outside of the block.
+            Node::FunctionDeclaration(FunctionDeclaration { id: Some(id), .. })
+                if function_level && self.is_unused(gc, id) =>
+            {
+                self.removed_decl = true;
+                None
+            }
+            Node::VariableDeclaration(
+                decl @ VariableDeclaration {
+                    kind: VariableDeclarationKind::Var,
+                    declarations,
+                    ..
+                },
+            ) => {
+                let kept: Vec<&'gc Node<'gc>> = declarations
+                    .iter()
+                    .filter(|declarator| {
+                        let VariableDeclarator { id, init, .. } =
+                            node_cast!(Node::VariableDeclarator, declarator);
+                        !(node_isa!(Node::Identifier, id)
+                            && self.is_unused(gc, id)
+                            && init.is_none_or(|init| utils::is_pure(gc, self.sem, init)))
+                    })
+                    .collect();
+                if kept.len() == declarations.len() {
+                    return Some(stmt);
+                }
+                self.removed_decl = true;
+                if kept.is_empty() {
+                    return None;
+                }
+                let mut builder = builder::VariableDeclaration::from_node(decl);
+                builder.declarations(NodeList::from_iter(gc, kept));
+                Some(builder.build_forced(gc))
+            }
+            _ => Some(stmt),
+        }
+    }
+
+    /// `stmt` is unreachable, return the part of it which must be kept because
+    /// declarations are hoisted, or `None` if it can be removed entirely.
+    fn hoisted_declaration<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        stmt: &'gc Node<'gc>,
+        function_level: bool,
+    ) -> Option<&'gc Node<'gc>> {
+        match stmt {
+            Node::FunctionDeclaration(_) => self.prune_statement(gc, stmt, function_level),
+            Node::VariableDeclaration(
+                decl @ VariableDeclaration {
+                    kind: VariableDeclarationKind::Var,
+                    declarations,
+                    ..
+                },
+            ) => {
+                let mut idents = vec![];
+                let mut has_init = false;
+                for declarator in declarations.iter() {
+                    let VariableDeclarator { id, init, .. } =
+                        node_cast!(Node::VariableDeclarator, declarator);
+                    has_init |= init.is_some();
+                    utils::collect_binding_identifiers(id, &mut idents);
+                }
+                if !has_init
+                    && idents.len() == declarations.len()
+                    && !idents.iter().any(|id| self.is_unused(gc, id))
+                {
+                    // Already in the hoisted form.
+                    return Some(stmt);
+                }
+                let declarators: Vec<&'gc Node<'gc>> = idents
+                    .into_iter()
+                    .filter(|id| !self.is_unused(gc, id))
+                    .map(|id| {
+                        builder::VariableDeclarator::build_template(
+                            gc,
+                            template::VariableDeclarator {
+                                metadata: TemplateMetadata {
+                                    range: *id.range(),
+                                    ..Default::default()
+                                },
+                                init: None,
+                                id,
+                            },
+                        )
+                    })
+                    .collect();
+                if declarators.is_empty() {
+                    return None;
+                }
+                let mut builder = builder::VariableDeclaration::from_node(decl);
+                builder.declarations(NodeList::from_iter(gc, declarators));
+                Some(builder.build_forced(gc))
+            }
+            // Lexical declarations aren't hoisted, but closures which refer to them must
+            // keep throwing when called, instead of reading a global with the same name.
+            Node::VariableDeclaration(VariableDeclaration { declarations, .. }) => {
+                let mut idents = vec![];
+                for declarator in declarations.iter() {
+                    let VariableDeclarator { id, .. } =
+                        node_cast!(Node::VariableDeclarator, declarator);
+                    utils::collect_binding_identifiers(id, &mut idents);
+                }
+                if idents.iter().any(|id| self.is_referenced(gc, id)) {
+                    Some(stmt)
+                } else {
+                    None
+                }
+            }
+            Node::ClassDeclaration(ClassDeclaration { id: Some(id), .. })
+                if self.is_referenced(gc, id) =>
+            {
+                Some(stmt)
+            }
+            _ => None,
+        }
+    }
+
+    /// Return `stmt` with empty branches removed, or `None` if the statement can be
+    /// removed entirely.
+    fn simplify_if<'gc>(
+        &self,
+        gc: &'gc GCLock,
+        stmt: &'gc Node<'gc>,
+        if_stmt: &'gc IfStatement<'gc>,
+    ) -> Option<&'gc Node<'gc>> {
+        let IfStatement {
+            test,
+            consequent,
+            alternate,
+            ..
+        } = if_stmt;
+        let empty_alternate = alternate.is_none_or(is_empty);
+        if is_empty(consequent) && empty_alternate {
+            if utils::is_pure(gc, self.sem, test) {
+                return None;
+            }
+            // Keep the side effects of the test.
+            return Some(builder::ExpressionStatement::build_template(
+                gc,
+                template::ExpressionStatement {
+                    metadata: TemplateMetadata {
+                        range: *stmt.range(),
+                        comments: stmt.comments(),
+                        ..Default::default()
+                    },
+                    expression: test,
+                    directive: None,
+                },
+            ));
+        }
+        let mut builder = builder::IfStatement::from_node(if_stmt);
+        if is_empty(consequent) {
+            // `if (x) {} else y;` becomes `if (!x) y;`.
+            builder.test(builder::UnaryExpression::build_template(
+                gc,
+                template::UnaryExpression {
+                    metadata: TemplateMetadata {
+                        range: *test.range(),
+                        ..Default::default()
+                    },
+                    operator: UnaryExpressionOperator::Not,
+                    argument: test,
+                    prefix: true,
+                },
+            ));
+            builder.consequent(alternate.unwrap());
+            builder.alternate(None);
+        } else if alternate.is_some() && empty_alternate {
+            builder.alternate(None);
+        } else {
+            return Some(stmt);
+        }
+        Some(builder.build_forced(gc))
+    }
+}
+
+impl<'gc> VisitorMut<'gc> for Eliminator<'_> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        // Process the children first, so that nested blocks which become empty are removed.
+        let result = node.visit_children_mut(gc, self);
+        let current = match result {
+            TransformResult::Unchanged => node,
+            TransformResult::Changed(new_node) => new_node,
+            _ => return result,
+        };
+        match current {
+            Node::Program(program @ Program { body, .. }) => {
+                if let Some(body) = self.prune_statements(gc, body, true) {
+                    let mut builder = builder::Program::from_node(program);
+                    builder.body(body);
+                    return TransformResult::Changed(builder.build_forced(gc));
+                }
+            }
+            Node::Module(module @ Module { body, .. }) => {
+                if let Some(body) = self.prune_statements(gc, body, true) {
+                    let mut builder = builder::Module::from_node(module);
+                    builder.body(body);
+                    return TransformResult::Changed(builder.build_forced(gc));
+                }
+            }
+            Node::BlockStatement(block @ BlockStatement { body, .. }) => {
+                let function_level =
+                    matches!(path, Some(Path { parent, .. }) if parent.is_function_like());
+                if let Some(body) = self.prune_statements(gc, body, function_level) {
+                    let mut builder = builder::BlockStatement::from_node(block);
+                    builder.body(body);
+                    return TransformResult::Changed(builder.build_forced(gc));
+                }
+            }
+            Node::SwitchCase(case @ SwitchCase { consequent, .. }) => {
+                if let Some(consequent) = self.prune_statements(gc, consequent, false) {
+                    let mut builder = builder::SwitchCase::from_node(case);
+                    builder.consequent(consequent);
+                    return TransformResult::Changed(builder.build_forced(gc));
+                }
+            }
+            Node::IfStatement(if_stmt) => {
+                return match self.simplify_if(gc, current, if_stmt) {
+                    None => TransformResult::Removed,
+                    Some(new_node) if !new_node.ptr_eq(current) => {
+                        TransformResult::Changed(new_node)
+                    }
+                    Some(_) => result,
+                };
+            }
+            _ => {}
+        }
+        result
+    }
+}
+
+/// Count the identifiers resolving to each declaration, including the declarations
+/// themselves. Return `None` if some identifiers couldn't be resolved.
+fn count_identifiers(sem: &SemContext) -> Option<HashMap<DeclId, usize>> {
+    let mut counts: HashMap<DeclId, usize> = HashMap::new();
+    for resolution in sem.all_ident_decls().values() {
+        match resolution {
+            // Variables accessed in a `with` statement aren't resolved,
+            // so any variable may be referenced.
+            Resolution::Unresolvable => return None,
+            Resolution::Decl(decl) => *counts.entry(*decl).or_default() += 1,
+        }
+    }
+    Some(counts)
+}
+
+/// Find the declarations which can be removed if they have no references.
+fn find_unused(sem: &SemContext, counts: Option<&HashMap<DeclId, usize>>) -> HashSet<DeclId> {
+    let counts = match counts {
+        Some(counts) => counts,
+        None => return HashSet::new(),
+    };
+    counts
+        .iter()
+        .filter(|&(&id, &count)| {
+            let decl = sem.decl(id);
+            // The only identifier is the declaration itself.
+            count == 1
+                && decl.special == Special::NotSpecial
+                && matches!(decl.kind, DeclKind::Var | DeclKind::ScopedFunction)
+                && !sem.scope(decl.scope).local_eval
+        })
+        .map(|(&id, _)| id)
+        .collect()
+}
+
+/// Return true if control never flows past the end of `stmt`.
+fn terminates(stmt: &Node) -> bool {
+    match stmt {
+        Node::ReturnStatement(_)
+        | Node::ThrowStatement(_)
+        | Node::BreakStatement(_)
+        | Node::ContinueStatement(_) => true,
+        Node::BlockStatement(BlockStatement { body, .. }) => body.iter().any(terminates),
+        Node::IfStatement(IfStatement {
+            consequent,
+            alternate: Some(alternate),
+            ..
+        }) => terminates(consequent) && terminates(alternate),
+        Node::TryStatement(TryStatement {
+            block,
+            handler,
+            finalizer,
+            ..
+        }) => {
+            finalizer.is_some_and(terminates)
+                || (terminates(block)
+                    && handler.is_none_or(|handler| {
+                        terminates(node_cast!(Node::CatchClause, handler).body)
+                    }))
+        }
+        // Labeled statements may be exited by a `break`.
+        _ => false,
+    }
+}
+
+/// Return true if `stmt` has no effect.
+fn is_empty(stmt: &Node) -> bool {
+    match stmt {
+        Node::EmptyStatement(_) => true,
+        Node::BlockStatement(BlockStatement { body, .. }) => body.is_empty(),
+        _ => false,
+    }
+}
@@ -0,0 +1,282 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Replace references to globals with literals.
+//!
+//! Each [`Define`] maps a global like `__DEV__` or a member chain rooted at a global like
+//! `process.env.NODE_ENV` to a literal. With `__DEV__=false`, transforms
+//! ```js
+//! if (__DEV__) check();
+//! ```
+//! into
+//! ```js
+//! if (false) check();
+//! ```
+//! Only reads of globals which aren't shadowed or declared by the program are replaced.
+
+use crate::utils;
+use crate::Pass;
+use juno::ast::*;
+use juno::sema::{DeclKind, Resolution, SemContext};
+use std::str::FromStr;
+
+/// Literal which replaces a defined expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefineValue {
+    Null,
+    Undefined,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+}
+
+/// Replacement of the global expression `name` with `value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Define {
+    /// The global followed by the names of the properties read from it.
+    name: Vec<String>,
+    value: DefineValue,
+}
+
+impl Define {
+    /// Replace `name`, which is a global identifier optionally followed by `.property`
+    /// accesses, with `value`.
+    /// Return `None` if `name` isn't of that form.
+    pub fn new(name: &str, value: DefineValue) -> Option<Self> {
+        let name: Vec<String> = name.split('.').map(String::from).collect();
+        if name.iter().all(|part| is_identifier_name(part)) {
+            Some(Define { name, value })
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse `NAME=VALUE`, where `VALUE` is `null`, `undefined`, `true`, `false`, a number or a
+/// string in single or double quotes.
+impl FromStr for Define {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected NAME=VALUE, found '{}'", s))?;
+        let value = value.parse()?;
+        Define::new(name, value).ok_or_else(|| format!("invalid global expression '{}'", name))
+    }
+}
+
+impl FromStr for DefineValue {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "null" => return Ok(DefineValue::Null),
+            "undefined" => return Ok(DefineValue::Undefined),
+            "true" => return Ok(DefineValue::Boolean(true)),
+            "false" => return Ok(DefineValue::Boolean(false)),
+            _ => {}
+        }
+        for quote in ['"', '\''] {
+            if let Some(inner) = s
+                .strip_prefix(quote)
+                .and_then(|rest| rest.strip_suffix(quote))
+            {
+                return Ok(DefineValue::String(inner.to_string()));
+            }
+        }
+        match s.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(DefineValue::Number(value)),
+            _ => Err(format!(
+                "'{}' is not null, undefined, a boolean, a number or a quoted string",
+                s
+            )),
+        }
+    }
+}
+
+fn is_identifier_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == '$' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+pub struct ReplaceDefines {
+    defines: Vec<Define>,
+}
+
+impl ReplaceDefines {
+    pub fn new(defines: Vec<Define>) -> Self {
+        Self { defines }
+    }
+}
+
+impl Pass for ReplaceDefines {
+    fn name(&self) -> &'static str {
+        "Replace Defines"
+    }
+    fn description(&self) -> &'static str {
+        "Replaces references to defined globals with literals"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        if self.defines.is_empty() {
+            return TransformResult::Unchanged;
+        }
+        let sem = utils::resolve(gc, node);
+        let mut replacer = Replacer {
+            sem: &sem,
+            defines: &self.defines,
+        };
+        VisitorMut::call(&mut replacer, gc, node, None)
+    }
+}
+
+struct Replacer<'a> {
+    sem: &'a SemContext,
+    defines: &'a [Define],
+}
+
+impl<'gc> VisitorMut<'gc> for Replacer<'_> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            Node::Identifier(_) if !utils::is_reference(parent) => {}
+            Node::Identifier(_) | Node::MemberExpression(_) if !is_assigned(parent) => {
+                if let Some(value) = self.lookup(gc, node) {
+                    return TransformResult::Changed(make_value(gc, value, *node.range()));
+                }
+            }
+            // Patterns are assignment targets. Default values in them are left alone as well.
+            Node::ObjectPattern(_) | Node::ArrayPattern(_) => return TransformResult::Unchanged,
+            Node::Property(Property {
+                shorthand: true, ..
+            }) => {
+                return match node.visit_children_mut(gc, self) {
+                    // The value isn't an identifier anymore.
+                    TransformResult::Changed(Node::Property(prop)) => {
+                        let mut builder = builder::Property::from_node(prop);
+                        builder.shorthand(false);
+                        TransformResult::Changed(builder.build_forced(gc))
+                    }
+                    result => result,
+                };
+            }
+            _ => {}
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+impl Replacer<'_> {
+    /// Find the value of the define matching `node`, if its root is an unshadowed global.
+    fn lookup<'gc>(&self, gc: &'gc GCLock, node: &'gc Node<'gc>) -> Option<&DefineValue> {
+        let mut name = vec![];
+        let mut root = node;
+        let root = loop {
+            match root {
+                Node::MemberExpression(MemberExpression {
+                    object,
+                    property: Node::Identifier(Identifier { name: property, .. }),
+                    computed: false,
+                    ..
+                }) => {
+                    name.push(gc.str(*property).to_string());
+                    root = object;
+                }
+                Node::MemberExpression(MemberExpression {
+                    object,
+                    property: Node::StringLiteral(StringLiteral { value, .. }),
+                    computed: true,
+                    ..
+                }) => {
+                    name.push(String::from_utf16_lossy(gc.str_u16(*value)));
+                    root = object;
+                }
+                Node::Identifier(Identifier { name: global, .. }) => {
+                    name.push(gc.str(*global).to_string());
+                    break root;
+                }
+                _ => return None,
+            }
+        };
+        name.reverse();
+        let define = self.defines.iter().find(|define| define.name == name)?;
+        match self.sem.ident_decl(&NodeRc::from_node(gc, root)) {
+            Some(Resolution::Decl(decl))
+                if self.sem.decl(decl).kind == DeclKind::UndeclaredGlobalProperty => {}
+            Some(Resolution::Unresolvable) => {}
+            _ => return None,
+        }
+        Some(&define.value)
+    }
+}
+
+/// Whether the node at `path` is written to rather than read.
+fn is_assigned(path: Option<Path>) -> bool {
+    matches!(
+        path,
+        Some(Path {
+            parent: Node::AssignmentExpression(_)
+                | Node::ForInStatement(_)
+                | Node::ForOfStatement(_),
+            field: NodeField::left,
+        }) | Some(Path {
+            parent: Node::UpdateExpression(_)
+                | Node::UnaryExpression(UnaryExpression {
+                    operator: UnaryExpressionOperator::Delete,
+                    ..
+                }),
+            field: NodeField::argument,
+        })
+    )
+}
+
+fn make_value<'gc>(gc: &'gc GCLock, value: &DefineValue, range: SourceRange) -> &'gc Node<'gc> {
+    let metadata = TemplateMetadata {
+        range,
+        ..Default::default()
+    };
+    match value {
+        DefineValue::Null => {
+            builder::NullLiteral::build_template(gc, template::NullLiteral { metadata })
+        }
+        DefineValue::Undefined => utils::make_undefined(gc),
+        DefineValue::Boolean(value) => builder::BooleanLiteral::build_template(
+            gc,
+            template::BooleanLiteral {
+                metadata,
+                value: *value,
+            },
+        ),
+        DefineValue::Number(value) => builder::NumericLiteral::build_template(
+            gc,
+            template::NumericLiteral {
+                metadata,
+                value: *value,
+            },
+        ),
+        DefineValue::String(value) => builder::StringLiteral::build_template(
+            gc,
+            template::StringLiteral {
+                metadata,
+                value: gc.atom_u16(value.encode_utf16().collect::<Vec<u16>>()),
+            },
+        ),
+    }
+}
@@ -0,0 +1,608 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower JSX to plain function calls.
+//!
+//! The classic runtime calls `React.createElement(type, props, ...children)`, or the function
+//! named by a `@jsx` pragma comment.
+//! The automatic runtime calls `jsx(type, props, key)` (or `jsxs` when there are multiple
+//! children), importing the functions from `react/jsx-runtime` into modules and requiring them
+//! in scripts, under names which don't clash with the program's.
+//!
+//! Entities in JSX text and attribute strings are decoded by the parser,
+//! so the transform only has to trim the whitespace in `JSXText`.
+
+use crate::utils::{self, NameGenerator};
+use crate::Pass;
+use juno::ast::*;
+use juno_support::atom_table::Atom;
+
+/// Which functions the transformed JSX calls.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JsxRuntime {
+    /// Call the pragma function, `React.createElement` by default.
+    Classic,
+    /// Call `jsx` and `jsxs`, imported from `<import_source>/jsx-runtime`.
+    Automatic,
+}
+
+#[derive(Debug, Clone)]
+pub struct JsxOptions {
+    pub runtime: JsxRuntime,
+    /// Function to create elements with in the classic runtime.
+    pub pragma: String,
+    /// Component to create fragments with in the classic runtime.
+    pub pragma_frag: String,
+    /// Module which provides the automatic runtime.
+    pub import_source: String,
+}
+
+impl Default for JsxOptions {
+    fn default() -> Self {
+        Self {
+            runtime: JsxRuntime::Classic,
+            pragma: "React.createElement".to_string(),
+            pragma_frag: "React.Fragment".to_string(),
+            import_source: "react".to_string(),
+        }
+    }
+}
+
+impl JsxOptions {
+    /// Override the options with the `@jsx`, `@jsxFrag`, `@jsxRuntime` and `@jsxImportSource`
+    /// pragmas found in `comment`.
+    pub fn read_pragmas(&mut self, comment: &str) {
+        let mut words = comment.split_whitespace();
+        while let Some(word) = words.next() {
+            let field = match word {
+                "@jsx" => &mut self.pragma,
+                "@jsxFrag" => &mut self.pragma_frag,
+                "@jsxImportSource" => &mut self.import_source,
+                "@jsxRuntime" => {
+                    match words.next() {
+                        Some("classic") => self.runtime = JsxRuntime::Classic,
+                        Some("automatic") => self.runtime = JsxRuntime::Automatic,
+                        _ => {}
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            if let Some(value) = words.next() {
+                *field = value.to_string();
+            }
+        }
+    }
+}
+
+pub struct TransformJsx {
+    options: JsxOptions,
+
+    /// Generates the local names of the automatic runtime functions in the current program.
+    names: Option<NameGenerator>,
+    /// Local names of the automatic runtime functions which have been used and must be
+    /// imported.
+    jsx: Option<Atom>,
+    jsxs: Option<Atom>,
+    fragment: Option<Atom>,
+}
+
+impl TransformJsx {
+    pub fn new(options: JsxOptions) -> Self {
+        Self {
+            options,
+            names: None,
+            jsx: None,
+            jsxs: None,
+            fragment: None,
+        }
+    }
+}
+
+impl Pass for TransformJsx {
+    fn name(&self) -> &'static str {
+        "Transform JSX"
+    }
+    fn description(&self) -> &'static str {
+        "Lower JSX elements to function calls"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        VisitorMut::call(self, gc, node, None)
+    }
+}
+
+impl<'gc> VisitorMut<'gc> for TransformJsx {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            Node::Program(Program { body, .. }) | Node::Module(Module { body, .. }) => {
+                // Pragmas are only recognized before the first statement.
+                for comments in [node.comments(), body.head().and_then(|n| n.comments())]
+                    .iter()
+                    .flatten()
+                {
+                    for c in comments.leading.iter().chain(&comments.inner) {
+                        self.options.read_pragmas(&c.value);
+                    }
+                }
+                if self.options.runtime == JsxRuntime::Automatic {
+                    self.names = Some(NameGenerator::new(gc, node));
+                }
+                let result = node.visit_children_mut(gc, self);
+                if self.jsx.is_some() || self.jsxs.is_some() || self.fragment.is_some() {
+                    let program = utils::changed_or(result, node);
+                    return TransformResult::Changed(self.add_runtime_import(gc, program));
+                }
+                return result;
+            }
+            Node::JSXElement(JSXElement {
+                metadata,
+                opening_element: Node::JSXOpeningElement(opening),
+                children,
+                ..
+            }) => {
+                let call = self.transform_element(
+                    gc,
+                    metadata,
+                    element_type(gc, opening.name),
+                    opening.attributes,
+                    *children,
+                );
+                return node.replace_with_existing(call, gc, self);
+            }
+            Node::JSXFragment(JSXFragment {
+                metadata, children, ..
+            }) => {
+                let fragment = match self.options.runtime {
+                    JsxRuntime::Classic => member_chain(gc, &self.options.pragma_frag),
+                    JsxRuntime::Automatic => {
+                        let name =
+                            runtime_name(gc, &mut self.names, &mut self.fragment, "Fragment");
+                        utils::make_identifier(gc, name)
+                    }
+                };
+                let call =
+                    self.transform_element(gc, metadata, fragment, NodeList::new(gc), *children);
+                return node.replace_with_existing(call, gc, self);
+            }
+            _ => {}
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+impl TransformJsx {
+    /// Build the call which creates the element.
+    /// The children of the call are still untransformed, and must be visited by the caller.
+    fn transform_element<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        metadata: &NodeMetadata<'gc>,
+        element_type: &'gc Node<'gc>,
+        attributes: NodeList<'gc>,
+        children: NodeList<'gc>,
+    ) -> &'gc Node<'gc> {
+        let children: Vec<&Node> = children
+            .iter()
+            .filter_map(|child| transform_child(gc, child))
+            .collect();
+
+        let mut key = None;
+        let mut props = vec![];
+        for attr in attributes.iter() {
+            match attr {
+                Node::JSXAttribute(JSXAttribute {
+                    name: Node::JSXIdentifier(JSXIdentifier { name, .. }),
+                    value,
+                    ..
+                }) if self.options.runtime == JsxRuntime::Automatic && gc.str(*name) == "key" => {
+                    key = Some(attribute_value(gc, *value));
+                }
+                Node::JSXAttribute(JSXAttribute {
+                    metadata,
+                    name,
+                    value,
+                }) => props.push(utils::make_property(
+                    gc,
+                    TemplateMetadata {
+                        range: metadata.range,
+                        comments: metadata.comments,
+                        ..Default::default()
+                    },
+                    attribute_name(gc, name),
+                    attribute_value(gc, *value),
+                )),
+                Node::JSXSpreadAttribute(JSXSpreadAttribute { metadata, argument }) => {
+                    props.push(builder::SpreadElement::build_template(
+                        gc,
+                        template::SpreadElement {
+                            metadata: TemplateMetadata {
+                                range: metadata.range,
+                                ..Default::default()
+                            },
+                            argument,
+                        },
+                    ))
+                }
+                _ => unreachable!("invalid JSX attribute"),
+            }
+        }
+
+        let (callee, arguments) = match self.options.runtime {
+            JsxRuntime::Classic => {
+                let props = if props.is_empty() {
+                    builder::NullLiteral::build_template(
+                        gc,
+                        template::NullLiteral {
+                            metadata: Default::default(),
+                        },
+                    )
+                } else {
+                    utils::make_object_expression(gc, props)
+                };
+                let mut arguments = vec![element_type, props];
+                arguments.extend(children);
+                (member_chain(gc, &self.options.pragma), arguments)
+            }
+            JsxRuntime::Automatic => {
+                let is_static = children.len() > 1;
+                match children.len() {
+                    0 => {}
+                    1 => props.push(utils::make_property(
+                        gc,
+                        Default::default(),
+                        utils::make_identifier(gc, gc.atom("children")),
+                        children[0],
+                    )),
+                    _ => props.push(utils::make_property(
+                        gc,
+                        Default::default(),
+                        utils::make_identifier(gc, gc.atom("children")),
+                        utils::make_array(gc, children),
+                    )),
+                }
+                let mut arguments = vec![element_type, utils::make_object_expression(gc, props)];
+                arguments.extend(key);
+                let callee = if is_static {
+                    runtime_name(gc, &mut self.names, &mut self.jsxs, "jsxs")
+                } else {
+                    runtime_name(gc, &mut self.names, &mut self.jsx, "jsx")
+                };
+                (utils::make_identifier(gc, callee), arguments)
+            }
+        };
+
+        builder::CallExpression::build_template(
+            gc,
+            template::CallExpression {
+                metadata: TemplateMetadata {
+                    range: metadata.range,
+                    comments: metadata.comments,
+                    ..Default::default()
+                },
+                callee,
+                type_arguments: None,
+                arguments: NodeList::from_iter(gc, arguments),
+            },
+        )
+    }
+
+    /// Import the automatic runtime functions which were used into `program` if it's a module,
+    /// or require them if it's a script, placing the declarations after the directives.
+    fn add_runtime_import<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        program: &'gc Node<'gc>,
+    ) -> &'gc Node<'gc> {
+        let used: Vec<(&str, Atom)> = [
+            ("jsx", self.jsx.take()),
+            ("jsxs", self.jsxs.take()),
+            ("Fragment", self.fragment.take()),
+        ]
+        .iter()
+        .filter_map(|&(imported, local)| Some((imported, local?)))
+        .collect();
+        let source = format!("{}/jsx-runtime", self.options.import_source);
+
+        let (body, is_module) = match program {
+            Node::Program(Program { body, .. }) => {
+                (*body, body.iter().any(utils::is_module_declaration))
+            }
+            Node::Module(Module { body, .. }) => (*body, true),
+            _ => unreachable!("JSX runtime must be imported into a program"),
+        };
+        let declarations: Vec<&Node> = if is_module {
+            let specifiers = used.iter().map(|&(imported, local)| {
+                builder::ImportSpecifier::build_template(
+                    gc,
+                    template::ImportSpecifier {
+                        metadata: Default::default(),
+                        imported: utils::make_global(gc, imported),
+                        local: utils::make_identifier(gc, local),
+                        import_kind: ImportKind::Value,
+                    },
+                )
+            });
+            vec![builder::ImportDeclaration::build_template(
+                gc,
+                template::ImportDeclaration {
+                    metadata: Default::default(),
+                    specifiers: NodeList::from_iter(gc, specifiers),
+                    source: utils::make_string(gc, &source),
+                    assertions: None,
+                    import_kind: ImportKind::Value,
+                },
+            )]
+        } else {
+            used.iter()
+                .map(|&(imported, local)| {
+                    let require = utils::make_call(
+                        gc,
+                        utils::make_global(gc, "require"),
+                        vec![utils::make_string(gc, &source)],
+                    );
+                    utils::make_var(
+                        gc,
+                        utils::make_identifier(gc, local),
+                        Some(utils::make_member(gc, require, imported)),
+                    )
+                })
+                .collect()
+        };
+
+        let mut stmts: Vec<&Node> = body.iter().collect();
+        let directives = stmts
+            .iter()
+            .take_while(|stmt| {
+                matches!(
+                    stmt,
+                    Node::ExpressionStatement(ExpressionStatement {
+                        directive: Some(_),
+                        ..
+                    })
+                )
+            })
+            .count();
+        let rest = stmts.split_off(directives);
+        stmts.extend(declarations);
+        stmts.extend(rest);
+        let body = NodeList::from_iter(gc, stmts);
+        match program {
+            Node::Program(p) => {
+                let mut builder = builder::Program::from_node(p);
+                builder.body(body);
+                builder.build_forced(gc)
+            }
+            Node::Module(m) => {
+                let mut builder = builder::Module::from_node(m);
+                builder.body(body);
+                builder.build_forced(gc)
+            }
+            _ => unreachable!("JSX runtime must be imported into a program"),
+        }
+    }
+}
+
+/// The local name of the automatic runtime function `imported`, which is generated by `names`
+/// the first time it's used and recorded in `local`.
+fn runtime_name(
+    gc: &GCLock,
+    names: &mut Option<NameGenerator>,
+    local: &mut Option<Atom>,
+    imported: &str,
+) -> Atom {
+    *local.get_or_insert_with(|| {
+        names
+            .as_mut()
+            .expect("names are generated for the program")
+            .fresh(gc, imported)
+    })
+}
+
+/// Convert a JSX child to the expression passed to the runtime, or `None` if it produces nothing.
+fn transform_child<'gc>(gc: &'gc GCLock<'_, '_>, child: &'gc Node<'gc>) -> Option<&'gc Node<'gc>> {
+    match child {
+        Node::JSXText(JSXText { value, .. }) => {
+            let text = clean_text(&String::from_utf16_lossy(gc.str_u16(*value)));
+            if text.is_empty() {
+                None
+            } else {
+                Some(utils::make_string(gc, &text))
+            }
+        }
+        Node::JSXExpressionContainer(JSXExpressionContainer {
+            expression: Node::JSXEmptyExpression(_),
+            ..
+        }) => None,
+        Node::JSXExpressionContainer(JSXExpressionContainer { expression, .. }) => Some(expression),
+        Node::JSXSpreadChild(JSXSpreadChild {
+            metadata,
+            expression,
+        }) => Some(builder::SpreadElement::build_template(
+            gc,
+            template::SpreadElement {
+                metadata: TemplateMetadata {
+                    range: metadata.range,
+                    ..Default::default()
+                },
+                argument: expression,
+            },
+        )),
+        _ => Some(child),
+    }
+}
+
+/// Trim the whitespace in JSX text the same way React does:
+/// lines are trimmed, except at the start of the first line and the end of the last line,
+/// and the non-empty lines are joined by a single space.
+fn clean_text(text: &str) -> String {
+    let lines: Vec<&str> = text
+        .split("\r\n")
+        .flat_map(|l| l.split(['\n', '\r']))
+        .collect();
+    let last_non_empty = lines
+        .iter()
+        .rposition(|l| l.contains(|c| c != ' ' && c != '\t'))
+        .unwrap_or(0);
+
+    let mut result = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.replace('\t', " ");
+        let mut trimmed = line.as_str();
+        if i != 0 {
+            trimmed = trimmed.trim_start_matches(' ');
+        }
+        if i != lines.len() - 1 {
+            trimmed = trimmed.trim_end_matches(' ');
+        }
+        if !trimmed.is_empty() {
+            result.push_str(trimmed);
+            if i != last_non_empty {
+                result.push(' ');
+            }
+        }
+    }
+    result
+}
+
+/// The first argument to the runtime: a string for intrinsic elements, otherwise the component.
+fn element_type<'gc>(gc: &'gc GCLock<'_, '_>, name: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    match name {
+        Node::JSXIdentifier(JSXIdentifier { name, .. }) => {
+            let text = gc.str(*name);
+            if text == "this" {
+                utils::make_this(gc)
+            } else if text.starts_with(|c: char| c.is_ascii_lowercase())
+                || !is_valid_identifier(text)
+            {
+                utils::make_string(gc, text)
+            } else {
+                utils::make_identifier(gc, *name)
+            }
+        }
+        Node::JSXMemberExpression(_) => jsx_member_expression(gc, name),
+        Node::JSXNamespacedName(_) => utils::make_string(gc, &namespaced_name(gc, name)),
+        _ => unreachable!("invalid JSX element name"),
+    }
+}
+
+fn jsx_member_expression<'gc>(gc: &'gc GCLock<'_, '_>, node: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    match node {
+        Node::JSXMemberExpression(JSXMemberExpression {
+            metadata,
+            object,
+            property: Node::JSXIdentifier(property),
+        }) => builder::MemberExpression::build_template(
+            gc,
+            template::MemberExpression {
+                metadata: TemplateMetadata {
+                    range: metadata.range,
+                    ..Default::default()
+                },
+                object: jsx_member_expression(gc, object),
+                property: utils::make_identifier(gc, property.name),
+                computed: false,
+            },
+        ),
+        Node::JSXIdentifier(JSXIdentifier { name, .. }) => match gc.str(*name) {
+            "this" => utils::make_this(gc),
+            _ => utils::make_identifier(gc, *name),
+        },
+        _ => unreachable!("invalid JSX member expression"),
+    }
+}
+
+fn namespaced_name(gc: &GCLock, node: &Node) -> String {
+    match node {
+        Node::JSXNamespacedName(JSXNamespacedName {
+            namespace: Node::JSXIdentifier(namespace),
+            name: Node::JSXIdentifier(name),
+            ..
+        }) => format!("{}:{}", gc.str(namespace.name), gc.str(name.name)),
+        _ => unreachable!("invalid JSX namespaced name"),
+    }
+}
+
+/// The key of the prop created for an attribute.
+fn attribute_name<'gc>(gc: &'gc GCLock<'_, '_>, name: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    match name {
+        Node::JSXIdentifier(JSXIdentifier { name, .. }) => {
+            let text = gc.str(*name);
+            if is_valid_identifier(text) {
+                utils::make_identifier(gc, *name)
+            } else {
+                utils::make_string(gc, text)
+            }
+        }
+        Node::JSXNamespacedName(_) => utils::make_string(gc, &namespaced_name(gc, name)),
+        _ => unreachable!("invalid JSX attribute name"),
+    }
+}
+
+/// The value of the prop created for an attribute, which is `true` when the value is omitted.
+fn attribute_value<'gc>(gc: &'gc GCLock<'_, '_>, value: Option<&'gc Node<'gc>>) -> &'gc Node<'gc> {
+    match value {
+        None => utils::make_boolean(gc, true),
+        Some(Node::JSXStringLiteral(JSXStringLiteral {
+            metadata, value, ..
+        })) => {
+            // Line breaks and the indentation following them collapse to a single space.
+            let value = String::from_utf16_lossy(gc.str_u16(*value));
+            let mut result = String::with_capacity(value.len());
+            let mut chars = value.chars().peekable();
+            while let Some(c) = chars.next() {
+                if c == '\n' && chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                        chars.next();
+                    }
+                    result.push(' ');
+                } else {
+                    result.push(c);
+                }
+            }
+            builder::StringLiteral::build_template(
+                gc,
+                template::StringLiteral {
+                    metadata: TemplateMetadata {
+                        range: metadata.range,
+                        ..Default::default()
+                    },
+                    value: gc.atom_u16(result.encode_utf16().collect::<Vec<u16>>()),
+                },
+            )
+        }
+        Some(Node::JSXExpressionContainer(JSXExpressionContainer { expression, .. })) => expression,
+        Some(value) => value,
+    }
+}
+
+fn is_valid_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Build `a.b.c` from the dotted `path`.
+fn member_chain<'gc>(gc: &'gc GCLock<'_, '_>, path: &str) -> &'gc Node<'gc> {
+    let mut parts = path.split('.');
+    let mut result = utils::make_global(gc, parts.next().unwrap_or_default());
+    for part in parts {
+        result = utils::make_member(gc, result, part);
+    }
+    result
+}
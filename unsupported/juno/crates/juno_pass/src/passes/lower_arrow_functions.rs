@@ -0,0 +1,173 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower arrow functions to function expressions.
+//!
+//! Arrow functions don't have their own `this` and `arguments`, so uses of them in arrows are
+//! replaced with variables declared in the enclosing function:
+//! `function f() { return () => this; }` becomes
+//! `function f() { var _this = this; return function() { return _this; }; }`.
+
+use crate::utils::{self, NameGenerator};
+use crate::Pass;
+use juno::ast::*;
+use juno_support::atom_table::Atom;
+
+#[derive(Default)]
+pub struct LowerArrowFunctions {}
+
+impl LowerArrowFunctions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for LowerArrowFunctions {
+    fn name(&self) -> &'static str {
+        "Lower Arrow Functions"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers arrow functions to function expressions"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let mut lowering = Lowering {
+            names: NameGenerator::new(gc, node),
+            frames: vec![],
+        };
+        VisitorMut::call(&mut lowering, gc, node, None)
+    }
+}
+
+/// A function or program which provides `this` and `arguments` to the arrows within it.
+struct Frame {
+    is_function: bool,
+    /// Number of arrow functions being visited in this frame.
+    arrows: usize,
+    this: Option<Atom>,
+    arguments: Option<Atom>,
+}
+
+struct Lowering {
+    names: NameGenerator,
+    frames: Vec<Frame>,
+}
+
+impl<'gc> VisitorMut<'gc> for Lowering {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            Node::Program(_)
+            | Node::Module(_)
+            | Node::FunctionDeclaration(_)
+            | Node::FunctionExpression(_) => {
+                self.frames.push(Frame {
+                    is_function: matches!(
+                        node,
+                        Node::FunctionDeclaration(_) | Node::FunctionExpression(_)
+                    ),
+                    arrows: 0,
+                    this: None,
+                    arguments: None,
+                });
+                let result = node.visit_children_mut(gc, self);
+                let frame = self.frames.pop().unwrap();
+                let captures = vec![
+                    (frame.this, utils::make_this(gc)),
+                    (frame.arguments, utils::make_global(gc, "arguments")),
+                ]
+                .into_iter()
+                .filter_map(|(name, init)| {
+                    name.map(|name| {
+                        utils::make_var(gc, utils::make_identifier(gc, name), Some(init))
+                    })
+                })
+                .collect::<Vec<_>>();
+                if captures.is_empty() {
+                    return result;
+                }
+                TransformResult::Changed(utils::prepend_statements(
+                    gc,
+                    utils::changed_or(result, node),
+                    captures,
+                ))
+            }
+            Node::ArrowFunctionExpression(_) => {
+                self.frame().arrows += 1;
+                let result = node.visit_children_mut(gc, self);
+                self.frame().arrows -= 1;
+                let arrow = utils::changed_or(result, node);
+                let arrow = node_cast!(Node::ArrowFunctionExpression, arrow);
+                let body = if arrow.expression {
+                    utils::make_block(gc, vec![utils::make_return(gc, Some(arrow.body))])
+                } else {
+                    arrow.body
+                };
+                TransformResult::Changed(builder::FunctionExpression::build_template(
+                    gc,
+                    template::FunctionExpression {
+                        metadata: TemplateMetadata {
+                            range: arrow.metadata.range,
+                            comments: arrow.metadata.comments,
+                            ..Default::default()
+                        },
+                        id: None,
+                        params: arrow.params,
+                        body,
+                        type_parameters: arrow.type_parameters,
+                        return_type: arrow.return_type,
+                        predicate: arrow.predicate,
+                        generator: false,
+                        is_async: arrow.is_async,
+                    },
+                ))
+            }
+            Node::ThisExpression(_) if self.frame().arrows > 0 => {
+                let name = self.capture(gc, |f| &mut f.this, "this");
+                TransformResult::Changed(utils::make_identifier(gc, name))
+            }
+            Node::Identifier(Identifier { name, .. })
+                if self.frame().arrows > 0
+                    && self.frame().is_function
+                    && gc.str(*name) == "arguments"
+                    && utils::is_reference(path) =>
+            {
+                let name = self.capture(gc, |f| &mut f.arguments, "arguments");
+                TransformResult::Changed(utils::make_identifier(gc, name))
+            }
+            _ => node.visit_children_mut(gc, self),
+        }
+    }
+}
+
+impl Lowering {
+    fn frame(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("arrow function outside of a program")
+    }
+
+    /// The variable which holds the value captured in `slot` of the current frame.
+    fn capture(
+        &mut self,
+        gc: &GCLock,
+        slot: impl Fn(&mut Frame) -> &mut Option<Atom>,
+        base: &str,
+    ) -> Atom {
+        let names = &mut self.names;
+        let frame = self.frames.last_mut().unwrap();
+        *slot(frame).get_or_insert_with(|| names.fresh(gc, base))
+    }
+}
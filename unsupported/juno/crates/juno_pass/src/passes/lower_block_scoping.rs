@@ -0,0 +1,659 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower `let` and `const` declarations to `var`.
+//!
+//! Block scoped declarations whose names would clash with another declaration or reference in
+//! the enclosing function once hoisted are renamed first.
+//! `let` declarations without an initializer in loops are initialized to `void 0`,
+//! since they must start out undefined in each iteration.
+//!
+//! When a closure captures a binding declared in a loop, each iteration needs its own copy of
+//! the binding, so the loop body is moved into a function which is called on every iteration:
+//! ```js
+//! for (let i = 0; i < 3; i++) fns.push(() => i);
+//! ```
+//! becomes
+//! ```js
+//! for (var i = 0; i < 3; i++) { var _loop = (i) => { fns.push(() => i); }; _loop(i); }
+//! ```
+//! `break`, `continue` and `return` in the body are forwarded through the result of the call.
+//! Loops whose body can't be moved into a function (because it contains `var` declarations,
+//! `yield`, `await`, jumps to outer labels or assignments to the loop bindings) keep their
+//! block scoped declarations.
+
+use crate::utils::{self, NameGenerator};
+use crate::Pass;
+use juno::ast::*;
+use juno::sema::{DeclId, DeclKind, FunctionInfoId, LexicalScopeId, Resolution, SemContext};
+use juno_support::atom_table::Atom;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Default)]
+pub struct LowerBlockScoping {}
+
+impl LowerBlockScoping {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for LowerBlockScoping {
+    fn name(&self) -> &'static str {
+        "Lower Block Scoping"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers let and const declarations to var"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let mut names = NameGenerator::new(gc, node);
+
+        // Rename the declarations which would clash once hoisted to their function.
+        let sem = utils::resolve(gc, node);
+        let mut analysis = Analysis::new(&sem);
+        node.visit(gc, &mut analysis, None);
+        let renames = analysis.renames(gc, &mut names);
+        let mut wrap = analysis.loops_to_wrap();
+        let mut root = node;
+        if !renames.is_empty() {
+            root = utils::changed_or(
+                VisitorMut::call(
+                    &mut utils::Renamer {
+                        sem: &sem,
+                        names: renames,
+                    },
+                    gc,
+                    node,
+                    None,
+                ),
+                node,
+            );
+            // The loops must be found again in the renamed tree.
+            let sem = utils::resolve(gc, root);
+            let mut analysis = Analysis::new(&sem);
+            root.visit(gc, &mut analysis, None);
+            wrap = analysis.loops_to_wrap();
+        }
+
+        let mut lowering = Lowering {
+            names,
+            wrap,
+            loop_depth: vec![],
+            kept_depth: vec![],
+        };
+        let result = VisitorMut::call(&mut lowering, gc, root, None);
+        if std::ptr::eq(root, node) {
+            result
+        } else {
+            TransformResult::Changed(utils::changed_or(result, root))
+        }
+    }
+}
+
+/// Collects the names which can't be used by hoisted declarations and the bindings captured by
+/// closures.
+struct Analysis<'s> {
+    sem: &'s SemContext,
+    /// Lexical scopes being visited.
+    scopes: Vec<LexicalScopeId>,
+    /// Loops being visited and the function containing each of them.
+    loops: Vec<(NodeRc, FunctionInfoId)>,
+    /// The innermost loop in the same function as each scope.
+    scope_loops: HashMap<LexicalScopeId, NodeRc>,
+    /// Names referenced in each function (including nested functions) which resolve outside it.
+    outer_names: HashMap<FunctionInfoId, HashSet<Atom>>,
+    /// Block scoped declarations referenced from another function.
+    captured: HashSet<DeclId>,
+}
+
+impl<'s> Analysis<'s> {
+    fn new(sem: &'s SemContext) -> Self {
+        Self {
+            sem,
+            scopes: sem.global_scope_id().into_iter().collect(),
+            loops: vec![],
+            scope_loops: HashMap::new(),
+            outer_names: HashMap::new(),
+            captured: HashSet::new(),
+        }
+    }
+
+    fn function_of(&self, decl: DeclId) -> FunctionInfoId {
+        self.sem.scope(self.sem.decl(decl).scope).parent_function
+    }
+
+    fn current_function(&self) -> FunctionInfoId {
+        match self.scopes.last() {
+            Some(&scope) => self.sem.scope(scope).parent_function,
+            None => FunctionInfoId::GLOBAL_FUNCTION_ID,
+        }
+    }
+
+    /// Record that `name` is referenced in every function from `func` up to, but not
+    /// including, `until`.
+    fn add_outer_name(&mut self, name: Atom, func: FunctionInfoId, until: Option<FunctionInfoId>) {
+        let mut func = Some(func);
+        while let Some(f) = func {
+            if Some(f) == until {
+                break;
+            }
+            self.outer_names.entry(f).or_default().insert(name);
+            func = self.sem.function(f).parent_function;
+        }
+    }
+
+    /// Choose new names for the `let` and `const` declarations which clash with another name
+    /// in their function.
+    fn renames(&self, gc: &GCLock, names: &mut NameGenerator) -> HashMap<DeclId, Atom> {
+        let mut taken = self.outer_names.clone();
+        let mut block_scoped = vec![];
+        for scope in self.sem.all_scopes() {
+            for &decl_id in &scope.decls {
+                let decl = self.sem.decl(decl_id);
+                if is_block_scoped(decl.kind) {
+                    block_scoped.push(decl_id);
+                } else {
+                    taken
+                        .entry(scope.parent_function)
+                        .or_default()
+                        .insert(decl.name);
+                }
+            }
+        }
+        let mut result = HashMap::new();
+        for decl_id in block_scoped {
+            let decl = self.sem.decl(decl_id);
+            let taken = taken.entry(self.function_of(decl_id)).or_default();
+            if !taken.insert(decl.name) && decl.can_rename {
+                let name = names.fresh(gc, gc.str(decl.name));
+                taken.insert(name);
+                result.insert(decl_id, name);
+            }
+        }
+        result
+    }
+
+    /// The loops which contain a block scoped declaration captured by a closure.
+    fn loops_to_wrap(&self) -> HashSet<NodeRc> {
+        self.captured
+            .iter()
+            .filter_map(|&decl| self.scope_loops.get(&self.sem.decl(decl).scope))
+            .cloned()
+            .collect()
+    }
+}
+
+impl<'gc> Visitor<'gc> for Analysis<'_> {
+    fn call(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>, _path: Option<Path<'gc>>) {
+        if is_loop(node) {
+            self.loops
+                .push((NodeRc::from_node(gc, node), self.current_function()));
+        }
+        let scope = self.sem.node_scope(NodeRc::from_node(gc, node));
+        if let Some(scope) = scope {
+            self.scopes.push(scope);
+            if let Some((ref loop_node, func)) = self.loops.last() {
+                if *func == self.sem.scope(scope).parent_function {
+                    self.scope_loops.insert(scope, loop_node.clone());
+                }
+            }
+        }
+
+        if let Node::Identifier(Identifier { name, .. }) = node {
+            let func = self.current_function();
+            match self.sem.ident_decl(&NodeRc::from_node(gc, node)) {
+                Some(Resolution::Decl(decl_id)) => {
+                    let decl_func = self.function_of(decl_id);
+                    if decl_func != func {
+                        if is_block_scoped(self.sem.decl(decl_id).kind) {
+                            self.captured.insert(decl_id);
+                        }
+                        self.add_outer_name(self.sem.decl(decl_id).name, func, Some(decl_func));
+                    }
+                }
+                Some(Resolution::Unresolvable) => self.add_outer_name(*name, func, None),
+                None => {}
+            }
+        }
+        node.visit_children(gc, self);
+
+        if scope.is_some() {
+            self.scopes.pop();
+        }
+        if is_loop(node) {
+            self.loops.pop();
+        }
+    }
+}
+
+struct Lowering {
+    names: NameGenerator,
+    /// Loops whose body must be moved into a function.
+    wrap: HashSet<NodeRc>,
+    /// Number of loops being visited in each function.
+    loop_depth: Vec<usize>,
+    /// Number of loops being visited in each function which must be wrapped but can't be,
+    /// whose block scoped declarations are kept.
+    kept_depth: Vec<usize>,
+}
+
+impl<'gc> VisitorMut<'gc> for Lowering {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            Node::Program(_)
+            | Node::Module(_)
+            | Node::FunctionDeclaration(_)
+            | Node::FunctionExpression(_)
+            | Node::ArrowFunctionExpression(_) => {
+                self.loop_depth.push(0);
+                self.kept_depth.push(0);
+                let result = node.visit_children_mut(gc, self);
+                self.loop_depth.pop();
+                self.kept_depth.pop();
+                result
+            }
+            _ if is_loop(node) => {
+                let bindings = loop_bindings(node);
+                let must_wrap = self.wrap.contains(&NodeRc::from_node(gc, node));
+                let wrap = must_wrap && can_wrap(gc, node, &bindings);
+                // Lowering the captured bindings to `var` would share them between iterations.
+                let keep = must_wrap && !wrap;
+                *self.loop_depth.last_mut().unwrap() += 1;
+                *self.kept_depth.last_mut().unwrap() += keep as usize;
+                let result = node.visit_children_mut(gc, self);
+                *self.loop_depth.last_mut().unwrap() -= 1;
+                *self.kept_depth.last_mut().unwrap() -= keep as usize;
+                if !wrap {
+                    return result;
+                }
+                TransformResult::Changed(self.wrap_body(
+                    gc,
+                    utils::changed_or(result, node),
+                    &bindings,
+                ))
+            }
+            Node::VariableDeclaration(decl)
+                if decl.kind != VariableDeclarationKind::Var
+                    && !self.kept_depth.last().is_some_and(|&depth| depth > 0) =>
+            {
+                // The binding of a for-in/of loop is always assigned.
+                let in_loop_head = matches!(
+                    path,
+                    Some(Path {
+                        parent: Node::ForInStatement(_)
+                            | Node::ForOfStatement(_)
+                            | Node::ForStatement(_),
+                        ..
+                    })
+                );
+                let in_loop = self.loop_depth.last().is_some_and(|&depth| depth > 0);
+                let mut builder = builder::VariableDeclaration::from_node(decl);
+                builder.kind(VariableDeclarationKind::Var);
+                if decl.kind == VariableDeclarationKind::Let && in_loop && !in_loop_head {
+                    builder.declarations(NodeList::from_iter(
+                        gc,
+                        decl.declarations.iter().map(|declarator| match declarator {
+                            Node::VariableDeclarator(d @ VariableDeclarator { init: None, .. }) => {
+                                let mut builder = builder::VariableDeclarator::from_node(d);
+                                builder.init(Some(utils::make_undefined(gc)));
+                                builder.build_forced(gc)
+                            }
+                            _ => declarator,
+                        }),
+                    ));
+                }
+                node.replace_with_new(builder::Builder::VariableDeclaration(builder), gc, self)
+            }
+            _ => node.visit_children_mut(gc, self),
+        }
+    }
+}
+
+impl Lowering {
+    /// Move the body of `loop_node` into a function which is called on every iteration with
+    /// the loop `bindings`.
+    fn wrap_body<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        loop_node: &'gc Node<'gc>,
+        bindings: &[Atom],
+    ) -> &'gc Node<'gc> {
+        let body = loop_body(loop_node);
+        let mut flow = ControlFlow::default();
+        let body = utils::changed_or(VisitorMut::call(&mut flow, gc, body, None), body);
+        let stmts = match body {
+            Node::BlockStatement(block) => block.body,
+            _ => NodeList::from_iter(gc, [body]),
+        };
+
+        let loop_name = self.names.fresh(gc, "loop");
+        let params =
+            || NodeList::from_iter(gc, bindings.iter().map(|&b| utils::make_identifier(gc, b)));
+        let func = builder::ArrowFunctionExpression::build_template(
+            gc,
+            template::ArrowFunctionExpression {
+                metadata: Default::default(),
+                id: None,
+                params: params(),
+                body: builder::BlockStatement::build_template(
+                    gc,
+                    template::BlockStatement {
+                        metadata: Default::default(),
+                        body: stmts,
+                    },
+                ),
+                type_parameters: None,
+                return_type: None,
+                predicate: None,
+                expression: false,
+                is_async: false,
+            },
+        );
+        let call = builder::CallExpression::build_template(
+            gc,
+            template::CallExpression {
+                metadata: Default::default(),
+                callee: utils::make_identifier(gc, loop_name),
+                type_arguments: None,
+                arguments: params(),
+            },
+        );
+
+        let mut new_body = vec![utils::make_var(
+            gc,
+            utils::make_identifier(gc, loop_name),
+            Some(func),
+        )];
+        if flow.has_break || flow.has_return {
+            // var _ret = _loop(...);
+            // if (_ret === "break") break;
+            // if (typeof _ret === "object") return _ret.v;
+            let ret = self.names.fresh(gc, "ret");
+            new_body.push(utils::make_var(
+                gc,
+                utils::make_identifier(gc, ret),
+                Some(call),
+            ));
+            let make_if = |test, consequent| {
+                builder::IfStatement::build_template(
+                    gc,
+                    template::IfStatement {
+                        metadata: Default::default(),
+                        test,
+                        consequent,
+                        alternate: None,
+                    },
+                )
+            };
+            if flow.has_break {
+                new_body.push(make_if(
+                    utils::make_binary(
+                        gc,
+                        BinaryExpressionOperator::StrictEquals,
+                        utils::make_identifier(gc, ret),
+                        utils::make_string(gc, "break"),
+                    ),
+                    builder::BreakStatement::build_template(
+                        gc,
+                        template::BreakStatement {
+                            metadata: Default::default(),
+                            label: None,
+                        },
+                    ),
+                ));
+            }
+            if flow.has_return {
+                let type_of = builder::UnaryExpression::build_template(
+                    gc,
+                    template::UnaryExpression {
+                        metadata: Default::default(),
+                        operator: UnaryExpressionOperator::Typeof,
+                        argument: utils::make_identifier(gc, ret),
+                        prefix: true,
+                    },
+                );
+                new_body.push(make_if(
+                    utils::make_binary(
+                        gc,
+                        BinaryExpressionOperator::StrictEquals,
+                        type_of,
+                        utils::make_string(gc, "object"),
+                    ),
+                    utils::make_return(
+                        gc,
+                        Some(utils::make_member(gc, utils::make_identifier(gc, ret), "v")),
+                    ),
+                ));
+            }
+        } else {
+            new_body.push(utils::make_expression_statement(gc, call));
+        }
+
+        let new_body = utils::make_block(gc, new_body);
+        match loop_node {
+            Node::ForStatement(n) => {
+                let mut builder = builder::ForStatement::from_node(n);
+                builder.body(new_body);
+                builder.build_forced(gc)
+            }
+            Node::ForInStatement(n) => {
+                let mut builder = builder::ForInStatement::from_node(n);
+                builder.body(new_body);
+                builder.build_forced(gc)
+            }
+            Node::ForOfStatement(n) => {
+                let mut builder = builder::ForOfStatement::from_node(n);
+                builder.body(new_body);
+                builder.build_forced(gc)
+            }
+            Node::WhileStatement(n) => {
+                let mut builder = builder::WhileStatement::from_node(n);
+                builder.body(new_body);
+                builder.build_forced(gc)
+            }
+            Node::DoWhileStatement(n) => {
+                let mut builder = builder::DoWhileStatement::from_node(n);
+                builder.body(new_body);
+                builder.build_forced(gc)
+            }
+            _ => unreachable!("not a loop"),
+        }
+    }
+}
+
+/// Rewrites the jumps out of a loop body which has been moved into a function.
+#[derive(Default)]
+struct ControlFlow {
+    /// Number of nested loops being visited.
+    loops: usize,
+    /// Number of nested switch statements being visited.
+    switches: usize,
+    has_break: bool,
+    has_return: bool,
+}
+
+impl<'gc> VisitorMut<'gc> for ControlFlow {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            Node::FunctionDeclaration(_)
+            | Node::FunctionExpression(_)
+            | Node::ArrowFunctionExpression(_) => TransformResult::Unchanged,
+            _ if is_loop(node) => {
+                self.loops += 1;
+                let result = node.visit_children_mut(gc, self);
+                self.loops -= 1;
+                result
+            }
+            Node::SwitchStatement(_) => {
+                self.switches += 1;
+                let result = node.visit_children_mut(gc, self);
+                self.switches -= 1;
+                result
+            }
+            Node::ContinueStatement(ContinueStatement { label: None, .. }) if self.loops == 0 => {
+                TransformResult::Changed(utils::make_return(gc, None))
+            }
+            Node::BreakStatement(BreakStatement { label: None, .. })
+                if self.loops == 0 && self.switches == 0 =>
+            {
+                self.has_break = true;
+                TransformResult::Changed(utils::make_return(
+                    gc,
+                    Some(utils::make_string(gc, "break")),
+                ))
+            }
+            Node::ReturnStatement(ReturnStatement { argument, .. }) => {
+                // return {v: argument};
+                self.has_return = true;
+                let value = argument.unwrap_or_else(|| utils::make_undefined(gc));
+                TransformResult::Changed(utils::make_return(
+                    gc,
+                    Some(utils::make_object(gc, vec![("v", value)])),
+                ))
+            }
+            _ => node.visit_children_mut(gc, self),
+        }
+    }
+}
+
+/// Checks whether a loop body can be moved into a function.
+struct WrapChecker<'a> {
+    bindings: &'a [Atom],
+    /// Labels declared in the body.
+    labels: Vec<Atom>,
+    ok: bool,
+}
+
+impl<'gc> Visitor<'gc> for WrapChecker<'_> {
+    fn call(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>, _path: Option<Path<'gc>>) {
+        match node {
+            Node::FunctionDeclaration(_)
+            | Node::FunctionExpression(_)
+            | Node::ArrowFunctionExpression(_) => return,
+            Node::VariableDeclaration(VariableDeclaration {
+                kind: VariableDeclarationKind::Var,
+                ..
+            })
+            | Node::YieldExpression(_)
+            | Node::AwaitExpression(_)
+            | Node::ForOfStatement(ForOfStatement { is_await: true, .. }) => {
+                self.ok = false;
+            }
+            Node::BreakStatement(BreakStatement {
+                label: Some(Node::Identifier(label)),
+                ..
+            })
+            | Node::ContinueStatement(ContinueStatement {
+                label: Some(Node::Identifier(label)),
+                ..
+            }) if !self.labels.contains(&label.name) => {
+                self.ok = false;
+            }
+            Node::LabeledStatement(LabeledStatement {
+                label: Node::Identifier(label),
+                ..
+            }) => {
+                self.labels.push(label.name);
+                node.visit_children(gc, self);
+                self.labels.pop();
+                return;
+            }
+            Node::AssignmentExpression(AssignmentExpression { left: target, .. })
+            | Node::UpdateExpression(UpdateExpression {
+                argument: target, ..
+            }) => {
+                let mut targets = vec![];
+                utils::collect_binding_identifiers(target, &mut targets);
+                if targets.iter().any(|t| {
+                    self.bindings
+                        .contains(&node_cast!(Node::Identifier, t).name)
+                }) {
+                    self.ok = false;
+                }
+            }
+            _ => {}
+        }
+        node.visit_children(gc, self);
+    }
+}
+
+fn is_block_scoped(kind: DeclKind) -> bool {
+    matches!(kind, DeclKind::Let | DeclKind::Const)
+}
+
+fn is_loop(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::ForStatement(_)
+            | Node::ForInStatement(_)
+            | Node::ForOfStatement(_)
+            | Node::WhileStatement(_)
+            | Node::DoWhileStatement(_)
+    )
+}
+
+fn loop_body<'gc>(node: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    match node {
+        Node::ForStatement(ForStatement { body, .. })
+        | Node::ForInStatement(ForInStatement { body, .. })
+        | Node::ForOfStatement(ForOfStatement { body, .. })
+        | Node::WhileStatement(WhileStatement { body, .. })
+        | Node::DoWhileStatement(DoWhileStatement { body, .. }) => body,
+        _ => unreachable!("not a loop"),
+    }
+}
+
+/// The names of the block scoped bindings declared in the head of `loop_node`.
+fn loop_bindings(loop_node: &Node) -> Vec<Atom> {
+    let head = match loop_node {
+        Node::ForStatement(ForStatement {
+            init: Some(head), ..
+        })
+        | Node::ForInStatement(ForInStatement { left: head, .. })
+        | Node::ForOfStatement(ForOfStatement { left: head, .. }) => head,
+        _ => return vec![],
+    };
+    let mut ids = vec![];
+    if let Node::VariableDeclaration(decl) = head {
+        if decl.kind != VariableDeclarationKind::Var {
+            for declarator in decl.declarations.iter() {
+                utils::collect_binding_identifiers(
+                    node_cast!(Node::VariableDeclarator, declarator).id,
+                    &mut ids,
+                );
+            }
+        }
+    }
+    ids.iter()
+        .map(|id| node_cast!(Node::Identifier, id).name)
+        .collect()
+}
+
+fn can_wrap<'gc>(gc: &'gc GCLock, loop_node: &'gc Node<'gc>, bindings: &[Atom]) -> bool {
+    let mut checker = WrapChecker {
+        bindings,
+        labels: vec![],
+        ok: true,
+    };
+    loop_body(loop_node).visit(gc, &mut checker, None);
+    checker.ok
+}
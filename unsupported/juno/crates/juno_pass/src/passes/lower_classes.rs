@@ -0,0 +1,1353 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower classes to constructor functions and prototype assignments.
+//!
+//! In loose mode,
+//! ```js
+//! class A extends B {
+//!   x = 1;
+//!   constructor(y) { super(y); }
+//!   m() { return super.m(); }
+//! }
+//! ```
+//! becomes
+//! ```js
+//! let A = (function (_B) {
+//!   function A(y) {
+//!     var _this;
+//!     _this = _B.call(this, y) || this, _this.x = 1, _this;
+//!     return _this;
+//!   }
+//!   A.prototype = Object.create(_B.prototype);
+//!   A.prototype.constructor = A;
+//!   A.__proto__ = _B;
+//!   A.prototype.m = function () { return _B.prototype.m.call(this); };
+//!   return A;
+//! })(B);
+//! ```
+//! Spec mode also checks that the class isn't called as a function and that the superclass is
+//! a constructor or `null`, keeps objects returned by the superclass constructor,
+//! and defines methods and fields with `Object.defineProperty` so that methods aren't enumerable.
+//! Private fields are stored in a `WeakMap` per field in spec mode, and in non-enumerable
+//! properties with unique names in loose mode. Private methods are stored like private fields,
+//! so private accessors aren't supported.
+//!
+//! Superclass constructors are called as functions, so built-in classes such as `Error` can't
+//! be extended.
+
+use crate::utils::{self, NameGenerator, TempVars};
+use crate::Pass;
+use juno::ast::*;
+use juno_support::atom_table::Atom;
+use std::collections::HashMap;
+
+/// How closely lowered classes follow the semantics of native classes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClassMode {
+    /// Check how classes are used and define non-enumerable methods.
+    Spec,
+    /// Assign methods and fields directly, which is smaller and faster.
+    Loose,
+}
+
+pub struct LowerClasses {
+    mode: ClassMode,
+}
+
+impl LowerClasses {
+    pub fn new(mode: ClassMode) -> Self {
+        Self { mode }
+    }
+}
+
+impl Pass for LowerClasses {
+    fn name(&self) -> &'static str {
+        "Lower Classes"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers classes to constructor functions and prototype assignments"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let mut lowering = Lowering {
+            mode: self.mode,
+            names: NameGenerator::new(gc, node),
+            temps: Default::default(),
+            privates: vec![],
+        };
+        VisitorMut::call(&mut lowering, gc, node, None)
+    }
+}
+
+/// Where the values of a private field are stored.
+#[derive(Debug, Copy, Clone)]
+enum PrivateStorage {
+    /// A `WeakMap` from objects to values.
+    WeakMap(Atom),
+    /// A non-enumerable property with a unique name.
+    Property(Atom),
+}
+
+struct Lowering {
+    mode: ClassMode,
+    names: NameGenerator,
+    temps: TempVars,
+    /// Private names declared by the classes being visited, innermost last.
+    privates: Vec<HashMap<Atom, PrivateStorage>>,
+}
+
+impl<'gc> VisitorMut<'gc> for Lowering {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            _ if TempVars::is_scope(node) => {
+                return TempVars::visit_scope(self, |s| &mut s.temps, gc, node);
+            }
+            Node::ClassDeclaration(ClassDeclaration {
+                metadata,
+                id,
+                super_class,
+                body,
+                ..
+            }) => {
+                let (class, privates) = self.lower_class(gc, *id, *super_class, body);
+                let result = match id {
+                    // `export default class {}` needs no binding.
+                    None => class,
+                    Some(id) => builder::VariableDeclaration::build_template(
+                        gc,
+                        template::VariableDeclaration {
+                            metadata: TemplateMetadata {
+                                range: metadata.range,
+                                comments: metadata.comments,
+                                ..Default::default()
+                            },
+                            kind: VariableDeclarationKind::Let,
+                            declarations: NodeList::from_iter(
+                                gc,
+                                [builder::VariableDeclarator::build_template(
+                                    gc,
+                                    template::VariableDeclarator {
+                                        metadata: Default::default(),
+                                        id,
+                                        init: Some(class),
+                                    },
+                                )],
+                            ),
+                        },
+                    ),
+                };
+                self.privates.push(privates);
+                let result = node.replace_with_existing(result, gc, self);
+                self.privates.pop();
+                return result;
+            }
+            Node::ClassExpression(ClassExpression {
+                id,
+                super_class,
+                body,
+                ..
+            }) => {
+                let (class, privates) = self.lower_class(gc, *id, *super_class, body);
+                self.privates.push(privates);
+                let result = node.replace_with_existing(class, gc, self);
+                self.privates.pop();
+                return result;
+            }
+            Node::ExportDefaultDeclaration(ExportDefaultDeclaration {
+                declaration:
+                    class @ Node::ClassDeclaration(ClassDeclaration {
+                        id: Some(Node::Identifier(Identifier { name, .. })),
+                        ..
+                    }),
+                ..
+            }) => {
+                // export default class A {}
+                // =>
+                // let A = ...; export {A as default};
+                let decl = utils::changed_or(
+                    self.call(gc, class, Some(Path::new(node, NodeField::declaration))),
+                    class,
+                );
+                let export = builder::ExportNamedDeclaration::build_template(
+                    gc,
+                    template::ExportNamedDeclaration {
+                        metadata: Default::default(),
+                        declaration: None,
+                        specifiers: NodeList::from_iter(
+                            gc,
+                            [builder::ExportSpecifier::build_template(
+                                gc,
+                                template::ExportSpecifier {
+                                    metadata: Default::default(),
+                                    exported: utils::make_global(gc, "default"),
+                                    local: utils::make_identifier(gc, *name),
+                                },
+                            )],
+                        ),
+                        source: None,
+                        export_kind: ExportKind::Value,
+                    },
+                );
+                return TransformResult::Expanded(vec![decl, export]);
+            }
+            Node::MemberExpression(member) if self.private(member.property).is_some() => {
+                let result = match self.private(member.property).unwrap() {
+                    PrivateStorage::WeakMap(map) => utils::make_call(
+                        gc,
+                        utils::make_member(gc, utils::make_identifier(gc, map), "get"),
+                        vec![member.object],
+                    ),
+                    PrivateStorage::Property(key) => {
+                        let mut builder = builder::MemberExpression::from_node(member);
+                        builder.property(utils::make_identifier(gc, key));
+                        builder.build_forced(gc)
+                    }
+                };
+                return node.replace_with_existing(result, gc, self);
+            }
+            Node::AssignmentExpression(AssignmentExpression {
+                operator,
+                left: Node::MemberExpression(member),
+                right,
+                ..
+            }) if matches!(
+                self.private(member.property),
+                Some(PrivateStorage::WeakMap(_))
+            ) =>
+            {
+                // o.#x += v => _x.set(o, _x.get(o) + v)
+                let map = self.private_map(member.property);
+                let (object, object_init) = match operator {
+                    AssignmentExpressionOperator::Assign => (member.object, member.object),
+                    _ => self.temps.reuse(gc, &mut self.names, member.object, "obj"),
+                };
+                let value = match operator {
+                    AssignmentExpressionOperator::Assign => *right,
+                    _ => make_compound(gc, *operator, make_map_get(gc, map, object), right),
+                };
+                let result = self.make_map_set(gc, map, object_init, value, is_used(path));
+                return node.replace_with_existing(result, gc, self);
+            }
+            Node::UpdateExpression(UpdateExpression {
+                operator,
+                argument: Node::MemberExpression(member),
+                prefix,
+                ..
+            }) if matches!(
+                self.private(member.property),
+                Some(PrivateStorage::WeakMap(_))
+            ) =>
+            {
+                // o.#x++ => _x.set(o, +_x.get(o) + 1)
+                let map = self.private_map(member.property);
+                let (object, object_init) =
+                    self.temps.reuse(gc, &mut self.names, member.object, "obj");
+                let operator = match operator {
+                    UpdateExpressionOperator::Increment => BinaryExpressionOperator::Plus,
+                    UpdateExpressionOperator::Decrement => BinaryExpressionOperator::Minus,
+                };
+                let old_value = builder::UnaryExpression::build_template(
+                    gc,
+                    template::UnaryExpression {
+                        metadata: Default::default(),
+                        operator: UnaryExpressionOperator::Plus,
+                        argument: make_map_get(gc, map, object_init),
+                        prefix: true,
+                    },
+                );
+                let result = if *prefix || !is_used(path) {
+                    let value =
+                        utils::make_binary(gc, operator, old_value, utils::make_number(gc, 1.0));
+                    self.make_map_set(gc, map, object, value, is_used(path))
+                } else {
+                    // (_value = +_x.get(o), _x.set(o, _value + 1), _value)
+                    let temp = self.temps.add(gc, &mut self.names, "value");
+                    utils::make_sequence(
+                        gc,
+                        vec![
+                            utils::make_assignment(gc, utils::make_identifier(gc, temp), old_value),
+                            make_map_set_call(
+                                gc,
+                                map,
+                                object,
+                                utils::make_binary(
+                                    gc,
+                                    operator,
+                                    utils::make_identifier(gc, temp),
+                                    utils::make_number(gc, 1.0),
+                                ),
+                            ),
+                            utils::make_identifier(gc, temp),
+                        ],
+                    )
+                };
+                return node.replace_with_existing(result, gc, self);
+            }
+            Node::BinaryExpression(BinaryExpression {
+                operator: BinaryExpressionOperator::In,
+                left: left @ Node::PrivateName(_),
+                right,
+                ..
+            }) if self.private(left).is_some() => {
+                let result = match self.private(left).unwrap() {
+                    // #x in o => _x.has(o)
+                    PrivateStorage::WeakMap(map) => utils::make_call(
+                        gc,
+                        utils::make_member(gc, utils::make_identifier(gc, map), "has"),
+                        vec![right],
+                    ),
+                    // #x in o => Object.prototype.hasOwnProperty.call(o, "__private_x")
+                    PrivateStorage::Property(key) => utils::make_call(
+                        gc,
+                        utils::make_member(
+                            gc,
+                            utils::make_member(
+                                gc,
+                                utils::make_member(
+                                    gc,
+                                    utils::make_global(gc, "Object"),
+                                    "prototype",
+                                ),
+                                "hasOwnProperty",
+                            ),
+                            "call",
+                        ),
+                        vec![right, utils::make_string(gc, gc.str(key))],
+                    ),
+                };
+                return node.replace_with_existing(result, gc, self);
+            }
+            _ => {}
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+/// A method or accessor which is defined on the prototype or the constructor.
+struct MethodProperty<'gc> {
+    is_static: bool,
+    key: &'gc Node<'gc>,
+    computed: bool,
+    /// Name of the non-computed key, used to define getters and setters together.
+    name: Option<String>,
+    value: Option<&'gc Node<'gc>>,
+    get: Option<&'gc Node<'gc>>,
+    set: Option<&'gc Node<'gc>>,
+}
+
+/// The code in the wrapper function which creates the class, in order of evaluation.
+enum ClassItem<'gc> {
+    Statement(&'gc Node<'gc>),
+    Method(MethodProperty<'gc>),
+}
+
+impl Lowering {
+    /// The storage of the private field `name` if it's declared by a class being visited.
+    fn private(&self, name: &Node) -> Option<PrivateStorage> {
+        match name {
+            Node::PrivateName(PrivateName {
+                id: Node::Identifier(Identifier { name, .. }),
+                ..
+            }) => self
+                .privates
+                .iter()
+                .rev()
+                .find_map(|privates| privates.get(name).copied()),
+            _ => None,
+        }
+    }
+
+    fn private_map(&self, name: &Node) -> Atom {
+        match self.private(name) {
+            Some(PrivateStorage::WeakMap(map)) => map,
+            _ => unreachable!("private field must be stored in a WeakMap"),
+        }
+    }
+
+    /// Build `_x.set(object, value)`, which results in `value` if `used`.
+    fn make_map_set<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        map: Atom,
+        object: &'gc Node<'gc>,
+        value: &'gc Node<'gc>,
+        used: bool,
+    ) -> &'gc Node<'gc> {
+        if !used {
+            return make_map_set_call(gc, map, object, value);
+        }
+        // (_x.set(object, _value = value), _value)
+        let temp = self.temps.add(gc, &mut self.names, "value");
+        utils::make_sequence(
+            gc,
+            vec![
+                make_map_set_call(
+                    gc,
+                    map,
+                    object,
+                    utils::make_assignment(gc, utils::make_identifier(gc, temp), value),
+                ),
+                utils::make_identifier(gc, temp),
+            ],
+        )
+    }
+
+    /// Lower the class with the `id`, `super_class` and `body`, returning the expression
+    /// which creates the constructor and the private names declared by the class.
+    fn lower_class<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        id: Option<&'gc Node<'gc>>,
+        super_class: Option<&'gc Node<'gc>>,
+        body: &'gc Node<'gc>,
+    ) -> (&'gc Node<'gc>, HashMap<Atom, PrivateStorage>) {
+        let name = match id {
+            Some(Node::Identifier(Identifier { name, .. })) => *name,
+            _ => self.names.fresh(gc, "class"),
+        };
+        let super_name = super_class.map(|super_class| match super_class {
+            Node::Identifier(Identifier { name, .. }) => self.names.fresh(gc, gc.str(*name)),
+            _ => self.names.fresh(gc, "super"),
+        });
+        let this_name = super_name.map(|_| self.names.fresh(gc, "this"));
+        let members = node_cast!(Node::ClassBody, body).body;
+        let id = |gc| utils::make_identifier(gc, name);
+
+        let mut items = vec![];
+        let mut privates = HashMap::new();
+        for member in members.iter() {
+            let key = match member {
+                Node::ClassPrivateProperty(ClassPrivateProperty { key, .. })
+                | Node::MethodDefinition(MethodDefinition {
+                    key: key @ Node::PrivateName(_),
+                    ..
+                }) => key,
+                _ => continue,
+            };
+            let private_name = private_key_name(key);
+            let storage = match self.mode {
+                ClassMode::Spec => {
+                    // var _x = new WeakMap();
+                    let map = self.names.fresh(gc, gc.str(private_name));
+                    items.push(ClassItem::Statement(utils::make_var(
+                        gc,
+                        utils::make_identifier(gc, map),
+                        Some(utils::make_new(
+                            gc,
+                            utils::make_global(gc, "WeakMap"),
+                            vec![],
+                        )),
+                    )));
+                    PrivateStorage::WeakMap(map)
+                }
+                ClassMode::Loose => PrivateStorage::Property(
+                    self.names
+                        .fresh(gc, &format!("_private_{}", gc.str(private_name))),
+                ),
+            };
+            privates.insert(private_name, storage);
+        }
+
+        let mut instance_inits = vec![];
+        let mut static_inits = vec![];
+        let mut constructor = None;
+        for member in members.iter() {
+            match member {
+                Node::MethodDefinition(MethodDefinition {
+                    kind: MethodDefinitionKind::Constructor,
+                    value,
+                    ..
+                }) => constructor = Some(*value),
+                Node::MethodDefinition(MethodDefinition {
+                    key: key @ Node::PrivateName(_),
+                    value,
+                    is_static,
+                    ..
+                }) => {
+                    let mut rewriter = self.rewriter(super_name, None, *is_static);
+                    let value = rewriter.rewrite_function(gc, value);
+                    let (inits, target) = if *is_static {
+                        (&mut static_inits, id(gc))
+                    } else {
+                        (&mut instance_inits, self.make_this(gc, this_name))
+                    };
+                    inits.push(self.init_private(gc, &privates, target, key, value));
+                }
+                Node::MethodDefinition(MethodDefinition {
+                    key,
+                    value,
+                    kind,
+                    computed,
+                    is_static,
+                    ..
+                }) => {
+                    let mut rewriter = self.rewriter(super_name, None, *is_static);
+                    let value = rewriter.rewrite_function(gc, value);
+                    let name = if *computed {
+                        None
+                    } else {
+                        property_name(gc, key)
+                    };
+                    let existing = items.iter_mut().find_map(|item| match item {
+                        ClassItem::Method(method)
+                            if *kind != MethodDefinitionKind::Method
+                                && method.value.is_none()
+                                && method.is_static == *is_static
+                                && name.is_some()
+                                && method.name == name =>
+                        {
+                            Some(method)
+                        }
+                        _ => None,
+                    });
+                    let method = match existing {
+                        Some(method) => method,
+                        None => {
+                            items.push(ClassItem::Method(MethodProperty {
+                                is_static: *is_static,
+                                key,
+                                computed: *computed,
+                                name,
+                                value: None,
+                                get: None,
+                                set: None,
+                            }));
+                            match items.last_mut() {
+                                Some(ClassItem::Method(method)) => method,
+                                _ => unreachable!(),
+                            }
+                        }
+                    };
+                    match kind {
+                        MethodDefinitionKind::Get => method.get = Some(value),
+                        MethodDefinitionKind::Set => method.set = Some(value),
+                        _ => method.value = Some(value),
+                    }
+                }
+                Node::ClassProperty(ClassProperty { declare: true, .. }) => {}
+                Node::ClassProperty(ClassProperty {
+                    key,
+                    value,
+                    computed,
+                    is_static,
+                    ..
+                }) => {
+                    let key = if *computed {
+                        // Computed keys are evaluated once, when the class is defined.
+                        let temp = self.names.fresh(gc, "key");
+                        items.push(ClassItem::Statement(utils::make_var(
+                            gc,
+                            utils::make_identifier(gc, temp),
+                            Some(key),
+                        )));
+                        utils::make_identifier(gc, temp)
+                    } else {
+                        key
+                    };
+                    let value =
+                        self.rewrite_field(gc, *value, super_name, this_name, name, *is_static);
+                    let (inits, target) = if *is_static {
+                        (&mut static_inits, id(gc))
+                    } else {
+                        (&mut instance_inits, self.make_this(gc, this_name))
+                    };
+                    inits.push(self.init_field(gc, target, key, *computed, value));
+                }
+                Node::ClassPrivateProperty(ClassPrivateProperty {
+                    key,
+                    value,
+                    is_static,
+                    ..
+                }) => {
+                    let value =
+                        self.rewrite_field(gc, *value, super_name, this_name, name, *is_static);
+                    let (inits, target) = if *is_static {
+                        (&mut static_inits, id(gc))
+                    } else {
+                        (&mut instance_inits, self.make_this(gc, this_name))
+                    };
+                    inits.push(self.init_private(gc, &privates, target, key, value));
+                }
+                _ => {}
+            }
+        }
+
+        let constructor =
+            self.lower_constructor(gc, name, super_name, this_name, constructor, instance_inits);
+        if super_name.is_none() && items.is_empty() && static_inits.is_empty() {
+            return (constructor, privates);
+        }
+
+        // The constructor is declared in a wrapper function which defines its prototype.
+        let ctor = node_cast!(Node::FunctionExpression, constructor);
+        let mut wrapper = vec![];
+        if let Some(super_name) = super_name {
+            if self.mode == ClassMode::Spec {
+                // if (typeof _B !== "function" && _B !== null) throw new TypeError(...);
+                wrapper.push(make_throw_if(
+                    gc,
+                    builder::LogicalExpression::build_template(
+                        gc,
+                        template::LogicalExpression {
+                            metadata: Default::default(),
+                            left: utils::make_binary(
+                                gc,
+                                BinaryExpressionOperator::StrictNotEquals,
+                                make_typeof(gc, utils::make_identifier(gc, super_name)),
+                                utils::make_string(gc, "function"),
+                            ),
+                            right: utils::make_binary(
+                                gc,
+                                BinaryExpressionOperator::StrictNotEquals,
+                                utils::make_identifier(gc, super_name),
+                                builder::NullLiteral::build_template(
+                                    gc,
+                                    template::NullLiteral {
+                                        metadata: Default::default(),
+                                    },
+                                ),
+                            ),
+                            operator: LogicalExpressionOperator::And,
+                        },
+                    ),
+                    "Super expression must either be null or a function",
+                ));
+            }
+        }
+        wrapper.push(builder::FunctionDeclaration::build_template(
+            gc,
+            template::FunctionDeclaration {
+                metadata: Default::default(),
+                id: ctor.id,
+                params: ctor.params,
+                body: ctor.body,
+                type_parameters: None,
+                return_type: None,
+                predicate: None,
+                generator: false,
+                is_async: false,
+            },
+        ));
+        if let Some(super_name) = super_name {
+            wrapper.extend(self.inherit(gc, name, super_name));
+        }
+        for item in items {
+            wrapper.push(match item {
+                ClassItem::Statement(stmt) => stmt,
+                ClassItem::Method(method) => {
+                    utils::make_expression_statement(gc, self.define_method(gc, name, method))
+                }
+            });
+        }
+        wrapper.extend(
+            static_inits
+                .into_iter()
+                .map(|init| utils::make_expression_statement(gc, init)),
+        );
+        wrapper.push(utils::make_return(gc, Some(id(gc))));
+
+        let (params, arguments) = match (super_name, super_class) {
+            (Some(super_name), Some(super_class)) => (
+                vec![utils::make_identifier(gc, super_name)],
+                vec![super_class],
+            ),
+            _ => (vec![], vec![]),
+        };
+        let class = utils::make_call(
+            gc,
+            utils::make_function(gc, None, params, wrapper),
+            arguments,
+        );
+        (class, privates)
+    }
+
+    /// Build the constructor function, which initializes the instance fields with `inits`.
+    fn lower_constructor<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        name: Atom,
+        super_name: Option<Atom>,
+        this_name: Option<Atom>,
+        constructor: Option<&'gc Node<'gc>>,
+        inits: Vec<&'gc Node<'gc>>,
+    ) -> &'gc Node<'gc> {
+        let mut body = vec![];
+        if self.mode == ClassMode::Spec {
+            // if (!(this instanceof A)) throw new TypeError(...);
+            body.push(make_throw_if(
+                gc,
+                builder::UnaryExpression::build_template(
+                    gc,
+                    template::UnaryExpression {
+                        metadata: Default::default(),
+                        operator: UnaryExpressionOperator::Not,
+                        argument: utils::make_binary(
+                            gc,
+                            BinaryExpressionOperator::Instanceof,
+                            utils::make_this(gc),
+                            utils::make_identifier(gc, name),
+                        ),
+                        prefix: true,
+                    },
+                ),
+                "Cannot call a class as a function",
+            ));
+        }
+
+        let mut params = vec![];
+        match this_name {
+            Some(this_name) => {
+                // Derived constructors initialize `_this` by calling the superclass.
+                body.push(utils::make_var(
+                    gc,
+                    utils::make_identifier(gc, this_name),
+                    None,
+                ));
+                let mut rewriter = self.rewriter(super_name, Some(this_name), false);
+                rewriter.inits = Some(inits);
+                match constructor {
+                    Some(constructor) => {
+                        let func = rewriter.rewrite_function(gc, constructor);
+                        let func = node_cast!(Node::FunctionExpression, func);
+                        params.extend(func.params.iter());
+                        let stmts = node_cast!(Node::BlockStatement, func.body).body;
+                        body.extend(stmts.iter());
+                        if !matches!(stmts.iter().last(), Some(Node::ReturnStatement(_))) {
+                            body.push(utils::make_return(
+                                gc,
+                                Some(utils::make_identifier(gc, this_name)),
+                            ));
+                        }
+                    }
+                    None => {
+                        body.push(utils::make_expression_statement(
+                            gc,
+                            rewriter.make_super_call(gc, None),
+                        ));
+                        body.push(utils::make_return(
+                            gc,
+                            Some(utils::make_identifier(gc, this_name)),
+                        ));
+                    }
+                }
+            }
+            None => {
+                body.extend(
+                    inits
+                        .into_iter()
+                        .map(|init| utils::make_expression_statement(gc, init)),
+                );
+                if let Some(constructor) = constructor {
+                    let mut rewriter = self.rewriter(super_name, None, false);
+                    let func = rewriter.rewrite_function(gc, constructor);
+                    let func = node_cast!(Node::FunctionExpression, func);
+                    params.extend(func.params.iter());
+                    body.extend(node_cast!(Node::BlockStatement, func.body).body.iter());
+                }
+            }
+        }
+
+        utils::make_function(gc, Some(name), params, body)
+    }
+
+    fn rewriter<'gc>(
+        &self,
+        super_name: Option<Atom>,
+        this: Option<Atom>,
+        is_static: bool,
+    ) -> MemberRewriter<'gc> {
+        MemberRewriter {
+            mode: self.mode,
+            super_name,
+            this,
+            is_static,
+            inits: None,
+            arrows: 0,
+        }
+    }
+
+    /// Rewrite the initializer of a field, which is evaluated in the constructor or, if the field
+    /// is static, after the class is defined.
+    fn rewrite_field<'gc>(
+        &self,
+        gc: &'gc GCLock,
+        value: Option<&'gc Node<'gc>>,
+        super_name: Option<Atom>,
+        this_name: Option<Atom>,
+        class_name: Atom,
+        is_static: bool,
+    ) -> &'gc Node<'gc> {
+        let value = match value {
+            Some(value) => value,
+            None => return utils::make_undefined(gc),
+        };
+        let this = if is_static {
+            Some(class_name)
+        } else {
+            this_name
+        };
+        let mut rewriter = self.rewriter(super_name, this, is_static);
+        utils::changed_or(VisitorMut::call(&mut rewriter, gc, value, None), value)
+    }
+
+    /// Build `this` in the constructor, which is `_this` in derived classes.
+    fn make_this<'gc>(&self, gc: &'gc GCLock, this_name: Option<Atom>) -> &'gc Node<'gc> {
+        match this_name {
+            Some(this_name) => utils::make_identifier(gc, this_name),
+            None => utils::make_this(gc),
+        }
+    }
+
+    /// Build the initialization of the field `key` of `target`.
+    fn init_field<'gc>(
+        &self,
+        gc: &'gc GCLock,
+        target: &'gc Node<'gc>,
+        key: &'gc Node<'gc>,
+        computed: bool,
+        value: &'gc Node<'gc>,
+    ) -> &'gc Node<'gc> {
+        match self.mode {
+            // this.x = value
+            ClassMode::Loose => {
+                utils::make_assignment(gc, make_property(gc, target, key, computed), value)
+            }
+            // Object.defineProperty(this, "x", {value, enumerable, configurable, writable})
+            ClassMode::Spec => make_define_property(
+                gc,
+                target,
+                make_property_key(gc, key, computed),
+                vec![
+                    ("value", value),
+                    ("enumerable", utils::make_boolean(gc, true)),
+                    ("configurable", utils::make_boolean(gc, true)),
+                    ("writable", utils::make_boolean(gc, true)),
+                ],
+            ),
+        }
+    }
+
+    /// Build the initialization of the private field `key` of `target`.
+    fn init_private<'gc>(
+        &self,
+        gc: &'gc GCLock,
+        privates: &HashMap<Atom, PrivateStorage>,
+        target: &'gc Node<'gc>,
+        key: &'gc Node<'gc>,
+        value: &'gc Node<'gc>,
+    ) -> &'gc Node<'gc> {
+        let name = private_key_name(key);
+        match privates[&name] {
+            // _x.set(this, value)
+            PrivateStorage::WeakMap(map) => make_map_set_call(gc, map, target, value),
+            // Object.defineProperty(this, "__private_x", {value, writable: true})
+            PrivateStorage::Property(key) => make_define_property(
+                gc,
+                target,
+                utils::make_string(gc, gc.str(key)),
+                vec![
+                    ("value", value),
+                    ("writable", utils::make_boolean(gc, true)),
+                ],
+            ),
+        }
+    }
+
+    /// Build the definition of `method` on the class `name` or its prototype.
+    fn define_method<'gc>(
+        &self,
+        gc: &'gc GCLock,
+        name: Atom,
+        method: MethodProperty<'gc>,
+    ) -> &'gc Node<'gc> {
+        let target = if method.is_static {
+            utils::make_identifier(gc, name)
+        } else {
+            utils::make_member(gc, utils::make_identifier(gc, name), "prototype")
+        };
+        match method.value {
+            // A.prototype.m = function () {}
+            Some(value) if self.mode == ClassMode::Loose => utils::make_assignment(
+                gc,
+                make_property(gc, target, method.key, method.computed),
+                value,
+            ),
+            // Object.defineProperty(A.prototype, "m", {value, writable, configurable})
+            Some(value) => make_define_property(
+                gc,
+                target,
+                make_property_key(gc, method.key, method.computed),
+                vec![
+                    ("value", value),
+                    ("writable", utils::make_boolean(gc, true)),
+                    ("configurable", utils::make_boolean(gc, true)),
+                ],
+            ),
+            // Object.defineProperty(A.prototype, "x", {get, set, configurable})
+            None => {
+                let mut descriptor = vec![];
+                if let Some(get) = method.get {
+                    descriptor.push(("get", get));
+                }
+                if let Some(set) = method.set {
+                    descriptor.push(("set", set));
+                }
+                descriptor.push(("configurable", utils::make_boolean(gc, true)));
+                make_define_property(
+                    gc,
+                    target,
+                    make_property_key(gc, method.key, method.computed),
+                    descriptor,
+                )
+            }
+        }
+    }
+
+    /// Build the statements which make the class `name` inherit from `super_name`.
+    fn inherit<'gc>(&self, gc: &'gc GCLock, name: Atom, super_name: Atom) -> Vec<&'gc Node<'gc>> {
+        let class = || utils::make_identifier(gc, name);
+        let super_class = || utils::make_identifier(gc, super_name);
+        let prototype = || utils::make_member(gc, class(), "prototype");
+        let create = |proto, properties: Option<&'gc Node<'gc>>| {
+            let mut arguments = vec![proto];
+            arguments.extend(properties);
+            utils::make_assignment(
+                gc,
+                prototype(),
+                utils::make_call(
+                    gc,
+                    utils::make_member(gc, utils::make_global(gc, "Object"), "create"),
+                    arguments,
+                ),
+            )
+        };
+        let set_proto = utils::make_expression_statement(
+            gc,
+            utils::make_assignment(
+                gc,
+                utils::make_member(gc, class(), "__proto__"),
+                super_class(),
+            ),
+        );
+        match self.mode {
+            ClassMode::Loose => vec![
+                // A.prototype = Object.create(_B.prototype);
+                utils::make_expression_statement(
+                    gc,
+                    create(utils::make_member(gc, super_class(), "prototype"), None),
+                ),
+                // A.prototype.constructor = A;
+                utils::make_expression_statement(
+                    gc,
+                    utils::make_assignment(
+                        gc,
+                        utils::make_member(gc, prototype(), "constructor"),
+                        class(),
+                    ),
+                ),
+                // A.__proto__ = _B;
+                set_proto,
+            ],
+            ClassMode::Spec => vec![
+                // A.prototype = Object.create(_B && _B.prototype,
+                //   {constructor: {value: A, writable: true, configurable: true}});
+                utils::make_expression_statement(
+                    gc,
+                    create(
+                        builder::LogicalExpression::build_template(
+                            gc,
+                            template::LogicalExpression {
+                                metadata: Default::default(),
+                                left: super_class(),
+                                right: utils::make_member(gc, super_class(), "prototype"),
+                                operator: LogicalExpressionOperator::And,
+                            },
+                        ),
+                        Some(utils::make_object(
+                            gc,
+                            vec![(
+                                "constructor",
+                                utils::make_object(
+                                    gc,
+                                    vec![
+                                        ("value", class()),
+                                        ("writable", utils::make_boolean(gc, true)),
+                                        ("configurable", utils::make_boolean(gc, true)),
+                                    ],
+                                ),
+                            )],
+                        )),
+                    ),
+                ),
+                // if (_B) A.__proto__ = _B;
+                builder::IfStatement::build_template(
+                    gc,
+                    template::IfStatement {
+                        metadata: Default::default(),
+                        test: super_class(),
+                        consequent: set_proto,
+                        alternate: None,
+                    },
+                ),
+            ],
+        }
+    }
+}
+
+/// Rewrites `this` and `super` in a class member, which is moved out of the class body.
+struct MemberRewriter<'gc> {
+    mode: ClassMode,
+    /// Parameter of the wrapper function which holds the superclass.
+    super_name: Option<Atom>,
+    /// Replacement for `this`.
+    this: Option<Atom>,
+    is_static: bool,
+    /// Initializers of the instance fields, when rewriting a derived constructor.
+    inits: Option<Vec<&'gc Node<'gc>>>,
+    /// Number of arrow functions being visited.
+    arrows: usize,
+}
+
+impl<'gc> VisitorMut<'gc> for MemberRewriter<'gc> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            // Functions and classes have their own `this` and `super`.
+            Node::FunctionDeclaration(_) | Node::FunctionExpression(_) | Node::ClassBody(_) => {
+                TransformResult::Unchanged
+            }
+            Node::ArrowFunctionExpression(_) => {
+                self.arrows += 1;
+                let result = node.visit_children_mut(gc, self);
+                self.arrows -= 1;
+                result
+            }
+            Node::ThisExpression(_) => match self.this {
+                Some(this) => TransformResult::Changed(utils::make_identifier(gc, this)),
+                None => TransformResult::Unchanged,
+            },
+            Node::CallExpression(CallExpression {
+                callee: Node::Super(_),
+                ..
+            }) if self.inits.is_some() => {
+                // The arguments are rewritten, but the `this` passed to the superclass isn't.
+                let call = utils::changed_or(node.visit_children_mut(gc, self), node);
+                let arguments = node_cast!(Node::CallExpression, call).arguments;
+                TransformResult::Changed(self.make_super_call(gc, Some(arguments)))
+            }
+            Node::CallExpression(
+                call @ CallExpression {
+                    callee:
+                        callee @ Node::MemberExpression(MemberExpression {
+                            object: Node::Super(_),
+                            ..
+                        }),
+                    ..
+                },
+            ) => {
+                // super.m(a) => _B.prototype.m.call(this, a)
+                let mut arguments = vec![utils::make_this(gc)];
+                arguments.extend(call.arguments.iter());
+                let mut builder = builder::CallExpression::from_node(call);
+                builder.callee(utils::make_member(gc, callee, "call"));
+                builder.arguments(NodeList::from_iter(gc, arguments));
+                node.replace_with_existing(builder.build_forced(gc), gc, self)
+            }
+            Node::AssignmentExpression(
+                assignment @ AssignmentExpression {
+                    left:
+                        Node::MemberExpression(
+                            member @ MemberExpression {
+                                object: Node::Super(_),
+                                ..
+                            },
+                        ),
+                    ..
+                },
+            ) => {
+                // Assigning to a property of `super` assigns it to `this`.
+                let mut left = builder::MemberExpression::from_node(member);
+                left.object(utils::make_this(gc));
+                let mut builder = builder::AssignmentExpression::from_node(assignment);
+                builder.left(left.build_forced(gc));
+                node.replace_with_existing(builder.build_forced(gc), gc, self)
+            }
+            Node::MemberExpression(
+                member @ MemberExpression {
+                    object: Node::Super(_),
+                    ..
+                },
+            ) => {
+                let mut builder = builder::MemberExpression::from_node(member);
+                builder.object(self.make_super(gc));
+                node.replace_with_existing(builder.build_forced(gc), gc, self)
+            }
+            Node::ReturnStatement(ReturnStatement { argument: None, .. })
+                if self.inits.is_some() && self.arrows == 0 =>
+            {
+                TransformResult::Changed(utils::make_return(
+                    gc,
+                    Some(utils::make_identifier(gc, self.this.unwrap())),
+                ))
+            }
+            _ => node.visit_children_mut(gc, self),
+        }
+    }
+}
+
+impl<'gc> MemberRewriter<'gc> {
+    /// Rewrite the parameters and body of the function `node`.
+    fn rewrite_function(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>) -> &'gc Node<'gc> {
+        utils::changed_or(node.visit_children_mut(gc, self), node)
+    }
+
+    /// Build the object `super` refers to.
+    fn make_super(&self, gc: &'gc GCLock) -> &'gc Node<'gc> {
+        match (self.super_name, self.is_static) {
+            (Some(super_name), true) => utils::make_identifier(gc, super_name),
+            (Some(super_name), false) => {
+                utils::make_member(gc, utils::make_identifier(gc, super_name), "prototype")
+            }
+            (None, true) => utils::make_member(gc, utils::make_global(gc, "Function"), "prototype"),
+            (None, false) => utils::make_member(gc, utils::make_global(gc, "Object"), "prototype"),
+        }
+    }
+
+    /// Build the call to the superclass constructor with `arguments`, or with the arguments of
+    /// the constructor if `None`, followed by the field initializers.
+    fn make_super_call(&self, gc: &'gc GCLock, arguments: Option<NodeList<'gc>>) -> &'gc Node<'gc> {
+        let super_class = utils::make_identifier(gc, self.super_name.unwrap());
+        let this = || utils::make_identifier(gc, self.this.unwrap());
+        let call = match arguments {
+            // _B.call(this, a)
+            Some(arguments) => {
+                let mut call_arguments = vec![utils::make_this(gc)];
+                call_arguments.extend(arguments.iter());
+                utils::make_call(
+                    gc,
+                    utils::make_member(gc, super_class, "call"),
+                    call_arguments,
+                )
+            }
+            // _B.apply(this, arguments)
+            None => utils::make_call(
+                gc,
+                utils::make_member(gc, super_class, "apply"),
+                vec![utils::make_this(gc), utils::make_global(gc, "arguments")],
+            ),
+        };
+        let mut expressions = match self.mode {
+            // _this = _B.call(this, a) || this
+            ClassMode::Loose => vec![utils::make_assignment(
+                gc,
+                this(),
+                builder::LogicalExpression::build_template(
+                    gc,
+                    template::LogicalExpression {
+                        metadata: Default::default(),
+                        left: call,
+                        right: utils::make_this(gc),
+                        operator: LogicalExpressionOperator::Or,
+                    },
+                ),
+            )],
+            // _this = _B.call(this, a), _this = Object(_this) === _this ? _this : this
+            ClassMode::Spec => vec![
+                utils::make_assignment(gc, this(), call),
+                utils::make_assignment(
+                    gc,
+                    this(),
+                    utils::make_conditional(
+                        gc,
+                        utils::make_binary(
+                            gc,
+                            BinaryExpressionOperator::StrictEquals,
+                            utils::make_call(gc, utils::make_global(gc, "Object"), vec![this()]),
+                            this(),
+                        ),
+                        this(),
+                        utils::make_this(gc),
+                    ),
+                ),
+            ],
+        };
+        let inits = self.inits.as_deref().unwrap_or_default();
+        if !inits.is_empty() {
+            expressions.extend(inits.iter().copied());
+            expressions.push(this());
+        }
+        utils::make_sequence(gc, expressions)
+    }
+}
+
+/// The name of the private member `key`.
+/// The parser represents the keys of private properties as plain identifiers.
+fn private_key_name(key: &Node) -> Atom {
+    match key {
+        Node::PrivateName(PrivateName {
+            id: Node::Identifier(Identifier { name, .. }),
+            ..
+        })
+        | Node::Identifier(Identifier { name, .. }) => *name,
+        _ => unreachable!("invalid private key"),
+    }
+}
+
+/// The name of the non-computed property `key`.
+fn property_name(gc: &GCLock, key: &Node) -> Option<String> {
+    match key {
+        Node::Identifier(Identifier { name, .. }) => Some(gc.str(*name).to_string()),
+        Node::StringLiteral(StringLiteral { value, .. }) => {
+            Some(String::from_utf16_lossy(gc.str_u16(*value)))
+        }
+        _ => None,
+    }
+}
+
+/// Build `target.key` or `target[key]`.
+fn make_property<'gc>(
+    gc: &'gc GCLock,
+    target: &'gc Node<'gc>,
+    key: &'gc Node<'gc>,
+    computed: bool,
+) -> &'gc Node<'gc> {
+    match key {
+        Node::Identifier(Identifier { name, .. }) if !computed => {
+            utils::make_member(gc, target, gc.str(*name))
+        }
+        _ => utils::make_computed_member(gc, target, key),
+    }
+}
+
+/// Build the value of the property `key`, which is a string for identifiers.
+fn make_property_key<'gc>(gc: &'gc GCLock, key: &'gc Node<'gc>, computed: bool) -> &'gc Node<'gc> {
+    match key {
+        Node::Identifier(Identifier { name, .. }) if !computed => {
+            utils::make_string(gc, gc.str(*name))
+        }
+        _ => key,
+    }
+}
+
+/// Build `Object.defineProperty(target, key, {descriptor})`.
+fn make_define_property<'gc>(
+    gc: &'gc GCLock,
+    target: &'gc Node<'gc>,
+    key: &'gc Node<'gc>,
+    descriptor: Vec<(&str, &'gc Node<'gc>)>,
+) -> &'gc Node<'gc> {
+    utils::make_call(
+        gc,
+        utils::make_member(gc, utils::make_global(gc, "Object"), "defineProperty"),
+        vec![target, key, utils::make_object(gc, descriptor)],
+    )
+}
+
+fn make_map_get<'gc>(gc: &'gc GCLock, map: Atom, object: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    utils::make_call(
+        gc,
+        utils::make_member(gc, utils::make_identifier(gc, map), "get"),
+        vec![object],
+    )
+}
+
+fn make_map_set_call<'gc>(
+    gc: &'gc GCLock,
+    map: Atom,
+    object: &'gc Node<'gc>,
+    value: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    utils::make_call(
+        gc,
+        utils::make_member(gc, utils::make_identifier(gc, map), "set"),
+        vec![object, value],
+    )
+}
+
+/// Build the value assigned by the compound assignment `old op= value`.
+fn make_compound<'gc>(
+    gc: &'gc GCLock,
+    operator: AssignmentExpressionOperator,
+    old: &'gc Node<'gc>,
+    value: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    let operator = operator.as_str().trim_end_matches('=');
+    match operator.parse() {
+        Ok(operator) => utils::make_binary(gc, operator, old, value),
+        Err(_) => builder::LogicalExpression::build_template(
+            gc,
+            template::LogicalExpression {
+                metadata: Default::default(),
+                left: old,
+                right: value,
+                operator: operator
+                    .parse()
+                    .expect("assignment operator must be binary or logical"),
+            },
+        ),
+    }
+}
+
+fn make_typeof<'gc>(gc: &'gc GCLock, argument: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    builder::UnaryExpression::build_template(
+        gc,
+        template::UnaryExpression {
+            metadata: Default::default(),
+            operator: UnaryExpressionOperator::Typeof,
+            argument,
+            prefix: true,
+        },
+    )
+}
+
+/// Build `if (test) throw new TypeError(message);`.
+fn make_throw_if<'gc>(gc: &'gc GCLock, test: &'gc Node<'gc>, message: &str) -> &'gc Node<'gc> {
+    builder::IfStatement::build_template(
+        gc,
+        template::IfStatement {
+            metadata: Default::default(),
+            test,
+            consequent: builder::ThrowStatement::build_template(
+                gc,
+                template::ThrowStatement {
+                    metadata: Default::default(),
+                    argument: utils::make_new(
+                        gc,
+                        utils::make_global(gc, "TypeError"),
+                        vec![utils::make_string(gc, message)],
+                    ),
+                },
+            ),
+            alternate: None,
+        },
+    )
+}
+
+/// Whether the value of the expression at `path` is used.
+fn is_used(path: Option<Path>) -> bool {
+    !matches!(
+        path,
+        Some(Path {
+            parent: Node::ExpressionStatement(_),
+            ..
+        })
+    )
+}
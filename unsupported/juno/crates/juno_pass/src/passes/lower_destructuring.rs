@@ -0,0 +1,525 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower destructuring in declarations, assignments, `for-in`/`for-of` heads and `catch` clauses.
+//!
+//! `var {a, b: [c = 1]} = x;` becomes
+//! `var a = x.a, _ref = x.b, _ref2 = _ref[0], c = _ref2 === void 0 ? 1 : _ref2;`
+//! and `[a, b] = [b, a]` becomes `(_ref = [b, a], a = _ref[0], b = _ref[1], _ref)`.
+//! Array patterns index into the value, so they are only supported for array-like values.
+//! Parameter patterns are turned into declarations by the parameters pass.
+
+use crate::utils::{self, NameGenerator, TempVars};
+use crate::Pass;
+use juno::ast::*;
+use juno_support::atom_table::Atom;
+
+#[derive(Default)]
+pub struct LowerDestructuring {}
+
+impl LowerDestructuring {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for LowerDestructuring {
+    fn name(&self) -> &'static str {
+        "Lower Destructuring"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers destructuring patterns to property accesses"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let mut lowering = Lowering {
+            names: NameGenerator::new(gc, node),
+            temps: Default::default(),
+        };
+        VisitorMut::call(&mut lowering, gc, node, None)
+    }
+}
+
+struct Lowering {
+    names: NameGenerator,
+    temps: TempVars,
+}
+
+/// A simple assignment of `value` to the identifier or member expression `target`.
+type Binding<'gc> = (&'gc Node<'gc>, &'gc Node<'gc>);
+
+impl<'gc> VisitorMut<'gc> for Lowering {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            _ if TempVars::is_scope(node) => {
+                return TempVars::visit_scope(self, |s| &mut s.temps, gc, node);
+            }
+            Node::VariableDeclaration(decl)
+                if decl
+                    .declarations
+                    .iter()
+                    .any(|d| is_pattern(node_cast!(Node::VariableDeclarator, d).id)) =>
+            {
+                let mut declarators = vec![];
+                for declarator in decl.declarations.iter() {
+                    match declarator {
+                        Node::VariableDeclarator(VariableDeclarator {
+                            id,
+                            init: Some(init),
+                            ..
+                        }) if is_pattern(id) => {
+                            let mut bindings = vec![];
+                            self.destructure(gc, true, id, init, &mut bindings);
+                            declarators.extend(bindings.into_iter().map(|(id, init)| {
+                                builder::VariableDeclarator::build_template(
+                                    gc,
+                                    template::VariableDeclarator {
+                                        metadata: Default::default(),
+                                        id,
+                                        init: Some(init),
+                                    },
+                                )
+                            }));
+                        }
+                        _ => declarators.push(declarator),
+                    }
+                }
+                let mut builder = builder::VariableDeclaration::from_node(decl);
+                builder.declarations(NodeList::from_iter(gc, declarators));
+                return node.replace_with_new(
+                    builder::Builder::VariableDeclaration(builder),
+                    gc,
+                    self,
+                );
+            }
+            Node::AssignmentExpression(AssignmentExpression {
+                metadata,
+                operator: AssignmentExpressionOperator::Assign,
+                left,
+                right,
+            }) if is_pattern(left) => {
+                let mut bindings = vec![];
+                let value = self.bind(gc, false, left, right, &mut bindings);
+                self.destructure(gc, false, left, value, &mut bindings);
+                let mut exprs = bindings
+                    .into_iter()
+                    .map(|(target, value)| utils::make_assignment(gc, target, value))
+                    .collect::<Vec<_>>();
+                exprs.push(copy(gc, value));
+                let result = builder::SequenceExpression::build_template(
+                    gc,
+                    template::SequenceExpression {
+                        metadata: TemplateMetadata {
+                            range: metadata.range,
+                            comments: metadata.comments,
+                            ..Default::default()
+                        },
+                        expressions: NodeList::from_iter(gc, exprs),
+                    },
+                );
+                return node.replace_with_existing(result, gc, self);
+            }
+            Node::ForInStatement(ForInStatement { left, body, .. })
+            | Node::ForOfStatement(ForOfStatement { left, body, .. }) => {
+                if let Some((left, body)) = self.lower_loop_head(gc, left, body) {
+                    let result = match node {
+                        Node::ForInStatement(n) => {
+                            let mut builder = builder::ForInStatement::from_node(n);
+                            builder.left(left);
+                            builder.body(body);
+                            builder.build_forced(gc)
+                        }
+                        Node::ForOfStatement(n) => {
+                            let mut builder = builder::ForOfStatement::from_node(n);
+                            builder.left(left);
+                            builder.body(body);
+                            builder.build_forced(gc)
+                        }
+                        _ => unreachable!(),
+                    };
+                    return node.replace_with_existing(result, gc, self);
+                }
+            }
+            Node::CatchClause(clause) if clause.param.is_some_and(is_pattern) => {
+                let temp = utils::make_identifier(gc, self.names.fresh(gc, "ref"));
+                let decl = utils::make_var(gc, clause.param.unwrap(), Some(copy(gc, temp)));
+                let mut builder = builder::CatchClause::from_node(clause);
+                builder.param(Some(temp));
+                builder.body(prepend_to_block(gc, decl, clause.body));
+                return node.replace_with_new(builder::Builder::CatchClause(builder), gc, self);
+            }
+            _ => {}
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+impl Lowering {
+    /// Append the bindings which destructure `value` with `pattern` to `bindings`.
+    /// `declare` indicates that the bindings are declarators, which declare their own
+    /// temporaries.
+    fn destructure<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        declare: bool,
+        pattern: &'gc Node<'gc>,
+        value: &'gc Node<'gc>,
+        bindings: &mut Vec<Binding<'gc>>,
+    ) {
+        match pattern {
+            Node::AssignmentPattern(AssignmentPattern { left, right, .. }) => {
+                let temp = self.temp(gc, declare, value, bindings);
+                let value = utils::make_conditional(
+                    gc,
+                    utils::make_is_undefined(gc, copy(gc, temp)),
+                    right,
+                    copy(gc, temp),
+                );
+                self.destructure(gc, declare, left, value, bindings);
+            }
+            Node::ObjectPattern(ObjectPattern { properties, .. }) => {
+                let value = if properties.len() > 1 {
+                    self.bind(gc, declare, pattern, value, bindings)
+                } else {
+                    value
+                };
+                let has_rest = properties.iter().any(|p| matches!(p, Node::RestElement(_)));
+                let mut excluded = vec![];
+                for prop in properties.iter() {
+                    match prop {
+                        Node::Property(Property {
+                            key,
+                            value: target,
+                            computed,
+                            ..
+                        }) => {
+                            let key = match key {
+                                Node::Identifier(Identifier { name, .. }) if !computed => {
+                                    utils::make_string(gc, gc.str(*name))
+                                }
+                                Node::StringLiteral(_) | Node::NumericLiteral(_) => key,
+                                _ if has_rest => self.temp(gc, declare, key, bindings),
+                                _ => key,
+                            };
+                            if has_rest {
+                                excluded.push(match key {
+                                    Node::StringLiteral(_) => key,
+                                    _ => utils::make_binary(
+                                        gc,
+                                        BinaryExpressionOperator::Plus,
+                                        utils::make_string(gc, ""),
+                                        copy(gc, key),
+                                    ),
+                                });
+                            }
+                            let name = match key {
+                                Node::StringLiteral(StringLiteral { value: name, .. }) => {
+                                    Some(String::from_utf16_lossy(gc.str_u16(*name)))
+                                }
+                                _ => None,
+                            };
+                            let access = match name {
+                                Some(name) if is_identifier_name(&name) => {
+                                    utils::make_member(gc, copy(gc, value), &name)
+                                }
+                                _ => {
+                                    utils::make_computed_member(gc, copy(gc, value), copy(gc, key))
+                                }
+                            };
+                            self.destructure(gc, declare, target, access, bindings);
+                        }
+                        Node::RestElement(RestElement { argument, .. }) => {
+                            let rest = make_object_without_properties(
+                                gc,
+                                copy(gc, value),
+                                utils::make_array(gc, std::mem::take(&mut excluded)),
+                            );
+                            self.destructure(gc, declare, argument, rest, bindings);
+                        }
+                        _ => unreachable!("invalid object pattern property"),
+                    }
+                }
+            }
+            Node::ArrayPattern(ArrayPattern { elements, .. }) => {
+                let value = if elements.len() > 1 {
+                    self.bind(gc, declare, pattern, value, bindings)
+                } else {
+                    value
+                };
+                for (i, elem) in elements.iter().enumerate() {
+                    match elem {
+                        Node::Empty(_) => {}
+                        Node::RestElement(RestElement { argument, .. }) => {
+                            let rest = utils::make_array_slice(gc, copy(gc, value), i);
+                            self.destructure(gc, declare, argument, rest, bindings);
+                        }
+                        _ => {
+                            let access = utils::make_computed_member(
+                                gc,
+                                copy(gc, value),
+                                utils::make_number(gc, i as f64),
+                            );
+                            self.destructure(gc, declare, elem, access, bindings);
+                        }
+                    }
+                }
+            }
+            _ => bindings.push((pattern, value)),
+        }
+    }
+
+    /// Return an expression which can be used to evaluate `value` multiple times while
+    /// destructuring it with `pattern`, storing it in a temporary if needed.
+    fn bind<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        declare: bool,
+        pattern: &'gc Node<'gc>,
+        value: &'gc Node<'gc>,
+        bindings: &mut Vec<Binding<'gc>>,
+    ) -> &'gc Node<'gc> {
+        match value {
+            Node::Identifier(Identifier { name, .. }) => {
+                // The identifier could be overwritten by the destructuring itself.
+                let mut targets = vec![];
+                utils::collect_binding_identifiers(pattern, &mut targets);
+                if targets
+                    .iter()
+                    .all(|t| node_cast!(Node::Identifier, t).name != *name)
+                {
+                    return value;
+                }
+            }
+            Node::ThisExpression(_) => return value,
+            _ => {}
+        }
+        self.temp(gc, declare, value, bindings)
+    }
+
+    /// Store `value` in a new temporary, returning the temporary.
+    fn temp<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        declare: bool,
+        value: &'gc Node<'gc>,
+        bindings: &mut Vec<Binding<'gc>>,
+    ) -> &'gc Node<'gc> {
+        let name: Atom = if declare {
+            self.names.fresh(gc, "ref")
+        } else {
+            self.temps.add(gc, &mut self.names, "ref")
+        };
+        let temp = utils::make_identifier(gc, name);
+        bindings.push((temp, value));
+        temp
+    }
+
+    /// Replace the pattern in the head of a `for-in` or `for-of` loop with a temporary,
+    /// which is destructured at the start of the body.
+    fn lower_loop_head<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        left: &'gc Node<'gc>,
+        body: &'gc Node<'gc>,
+    ) -> Option<(&'gc Node<'gc>, &'gc Node<'gc>)> {
+        match left {
+            Node::VariableDeclaration(decl) => {
+                let declarator = node_cast!(Node::VariableDeclarator, decl.declarations.head()?);
+                if !is_pattern(declarator.id) {
+                    return None;
+                }
+                let temp = self.names.fresh(gc, "ref");
+                let mut builder = builder::VariableDeclarator::from_node(declarator);
+                builder.id(utils::make_identifier(gc, temp));
+                let mut left = builder::VariableDeclaration::from_node(decl);
+                left.declarations(NodeList::from_iter(gc, [builder.build_forced(gc)]));
+                let init = builder::VariableDeclaration::build_template(
+                    gc,
+                    template::VariableDeclaration {
+                        metadata: Default::default(),
+                        kind: decl.kind,
+                        declarations: NodeList::from_iter(
+                            gc,
+                            [builder::VariableDeclarator::build_template(
+                                gc,
+                                template::VariableDeclarator {
+                                    metadata: Default::default(),
+                                    id: declarator.id,
+                                    init: Some(utils::make_identifier(gc, temp)),
+                                },
+                            )],
+                        ),
+                    },
+                );
+                Some((left.build_forced(gc), prepend_to_block(gc, init, body)))
+            }
+            _ if is_pattern(left) => {
+                let temp = self.temps.add(gc, &mut self.names, "ref");
+                let init = utils::make_expression_statement(
+                    gc,
+                    utils::make_assignment(gc, left, utils::make_identifier(gc, temp)),
+                );
+                Some((
+                    utils::make_identifier(gc, temp),
+                    prepend_to_block(gc, init, body),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn is_pattern(node: &Node) -> bool {
+    matches!(node, Node::ObjectPattern(_) | Node::ArrayPattern(_))
+}
+
+fn is_identifier_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Return a copy of the simple expression `node`, so that it can be used more than once.
+fn copy<'gc>(gc: &'gc GCLock, node: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    match node {
+        Node::Identifier(Identifier { name, .. }) => utils::make_identifier(gc, *name),
+        _ => node,
+    }
+}
+
+/// Insert `stmt` at the start of the statement `body`, turning it into a block if necessary.
+fn prepend_to_block<'gc>(
+    gc: &'gc GCLock,
+    stmt: &'gc Node<'gc>,
+    body: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    match body {
+        Node::BlockStatement(block) => {
+            let mut stmts = vec![stmt];
+            stmts.extend(block.body.iter());
+            let mut builder = builder::BlockStatement::from_node(block);
+            builder.body(NodeList::from_iter(gc, stmts));
+            builder.build_forced(gc)
+        }
+        _ => utils::make_block(gc, vec![stmt, body]),
+    }
+}
+
+/// Build a call to an inline helper which copies the own enumerable properties of `source`,
+/// except for the keys in the array `excluded`:
+/// ```js
+/// (function (source, excluded) {
+///   var target = {};
+///   for (var key in source)
+///     if (Object.prototype.hasOwnProperty.call(source, key) && excluded.indexOf(key) < 0)
+///       target[key] = source[key];
+///   return target;
+/// })(source, excluded)
+/// ```
+fn make_object_without_properties<'gc>(
+    gc: &'gc GCLock,
+    source: &'gc Node<'gc>,
+    excluded: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    let id = |name: &str| utils::make_identifier(gc, gc.atom(name));
+    let has_own = utils::make_call(
+        gc,
+        utils::make_member(
+            gc,
+            utils::make_member(
+                gc,
+                utils::make_member(gc, utils::make_global(gc, "Object"), "prototype"),
+                "hasOwnProperty",
+            ),
+            "call",
+        ),
+        vec![id("source"), id("key")],
+    );
+    let not_excluded = utils::make_binary(
+        gc,
+        BinaryExpressionOperator::Less,
+        utils::make_call(
+            gc,
+            utils::make_member(gc, id("excluded"), "indexOf"),
+            vec![id("key")],
+        ),
+        utils::make_number(gc, 0.0),
+    );
+    let copy_property = builder::IfStatement::build_template(
+        gc,
+        template::IfStatement {
+            metadata: Default::default(),
+            test: builder::LogicalExpression::build_template(
+                gc,
+                template::LogicalExpression {
+                    metadata: Default::default(),
+                    left: has_own,
+                    right: not_excluded,
+                    operator: LogicalExpressionOperator::And,
+                },
+            ),
+            consequent: utils::make_expression_statement(
+                gc,
+                utils::make_assignment(
+                    gc,
+                    utils::make_computed_member(gc, id("target"), id("key")),
+                    utils::make_computed_member(gc, id("source"), id("key")),
+                ),
+            ),
+            alternate: None,
+        },
+    );
+    let loop_ = builder::ForInStatement::build_template(
+        gc,
+        template::ForInStatement {
+            metadata: Default::default(),
+            left: utils::make_var(gc, id("key"), None),
+            right: id("source"),
+            body: copy_property,
+        },
+    );
+    let target = builder::ObjectExpression::build_template(
+        gc,
+        template::ObjectExpression {
+            metadata: Default::default(),
+            properties: NodeList::new(gc),
+        },
+    );
+    let helper = builder::FunctionExpression::build_template(
+        gc,
+        template::FunctionExpression {
+            metadata: Default::default(),
+            id: None,
+            params: NodeList::from_iter(gc, [id("source"), id("excluded")]),
+            body: utils::make_block(
+                gc,
+                vec![
+                    utils::make_var(gc, id("target"), Some(target)),
+                    loop_,
+                    utils::make_return(gc, Some(id("target"))),
+                ],
+            ),
+            type_parameters: None,
+            return_type: None,
+            predicate: None,
+            generator: false,
+            is_async: false,
+        },
+    );
+    utils::make_call(gc, helper, vec![source, excluded])
+}
@@ -0,0 +1,130 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower the exponent operator to `Math.pow`.
+//!
+//! `a ** b` becomes `Math.pow(a, b)` and `a.b **= c` becomes `a.b = Math.pow(a.b, c)`,
+//! using temporaries for any part of the target which can't be evaluated twice.
+
+use crate::utils::{self, NameGenerator, TempVars};
+use crate::Pass;
+use juno::ast::*;
+
+#[derive(Default)]
+pub struct LowerExponent {}
+
+impl LowerExponent {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for LowerExponent {
+    fn name(&self) -> &'static str {
+        "Lower Exponent"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers the ** operator to Math.pow"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let mut lowering = Lowering {
+            names: NameGenerator::new(gc, node),
+            temps: Default::default(),
+        };
+        VisitorMut::call(&mut lowering, gc, node, None)
+    }
+}
+
+struct Lowering {
+    names: NameGenerator,
+    temps: TempVars,
+}
+
+impl<'gc> VisitorMut<'gc> for Lowering {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            _ if TempVars::is_scope(node) => {
+                return TempVars::visit_scope(self, |s| &mut s.temps, gc, node);
+            }
+            Node::BinaryExpression(BinaryExpression {
+                operator: BinaryExpressionOperator::Exp,
+                left,
+                right,
+                ..
+            }) => {
+                return node.replace_with_existing(make_pow(gc, left, right), gc, self);
+            }
+            Node::AssignmentExpression(AssignmentExpression {
+                metadata,
+                operator: AssignmentExpressionOperator::ExpAssign,
+                left,
+                right,
+            }) => {
+                let (target, value) = match left {
+                    Node::MemberExpression(member) => {
+                        let (object, object_init) =
+                            self.temps.reuse(gc, &mut self.names, member.object, "obj");
+                        let (property, property_init) = if member.computed {
+                            self.temps
+                                .reuse(gc, &mut self.names, member.property, "prop")
+                        } else {
+                            (member.property, member.property)
+                        };
+                        let make = |object, property| {
+                            builder::MemberExpression::build_template(
+                                gc,
+                                template::MemberExpression {
+                                    metadata: Default::default(),
+                                    object,
+                                    property,
+                                    computed: member.computed,
+                                },
+                            )
+                        };
+                        (make(object_init, property_init), make(object, property))
+                    }
+                    _ => (*left, *left),
+                };
+                let assignment = builder::AssignmentExpression::build_template(
+                    gc,
+                    template::AssignmentExpression {
+                        metadata: TemplateMetadata {
+                            range: metadata.range,
+                            comments: metadata.comments,
+                            ..Default::default()
+                        },
+                        operator: AssignmentExpressionOperator::Assign,
+                        left: target,
+                        right: make_pow(gc, value, right),
+                    },
+                );
+                return node.replace_with_existing(assignment, gc, self);
+            }
+            _ => {}
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+/// Build `Math.pow(left, right)`.
+fn make_pow<'gc>(gc: &'gc GCLock, left: &'gc Node<'gc>, right: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    utils::make_call(
+        gc,
+        utils::make_member(gc, utils::make_global(gc, "Math"), "pow"),
+        vec![left, right],
+    )
+}
@@ -0,0 +1,270 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower `for-of` loops to the iterator protocol.
+//!
+//! `for (var x of xs) body` becomes
+//! ```js
+//! for (var _iterator = getIterator(xs), _step; !(_step = _iterator.next()).done;) {
+//!   var x = _step.value;
+//!   body
+//! }
+//! ```
+//! where `getIterator` is an inline helper which falls back to iterating over an array-like
+//! when `Symbol.iterator` isn't available.
+//! Iterators are not closed when the loop exits early, and `for await` is not lowered.
+
+use crate::utils::{self, NameGenerator};
+use crate::Pass;
+use juno::ast::*;
+
+#[derive(Default)]
+pub struct LowerForOf {}
+
+impl LowerForOf {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for LowerForOf {
+    fn name(&self) -> &'static str {
+        "Lower For-Of"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers for-of loops to the iterator protocol"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let mut lowering = Lowering {
+            names: NameGenerator::new(gc, node),
+        };
+        VisitorMut::call(&mut lowering, gc, node, None)
+    }
+}
+
+struct Lowering {
+    names: NameGenerator,
+}
+
+impl<'gc> VisitorMut<'gc> for Lowering {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        if let Node::ForOfStatement(ForOfStatement {
+            metadata,
+            left,
+            right,
+            body,
+            is_await: false,
+        }) = node
+        {
+            let iterator = self.names.fresh(gc, "iterator");
+            let step = self.names.fresh(gc, "step");
+            let value = utils::make_member(gc, utils::make_identifier(gc, step), "value");
+
+            // var x = _step.value; or x = _step.value;
+            let init = match left {
+                Node::VariableDeclaration(decl) => {
+                    let declarator =
+                        node_cast!(Node::VariableDeclarator, decl.declarations.head().unwrap());
+                    let mut builder = builder::VariableDeclarator::from_node(declarator);
+                    builder.init(Some(value));
+                    let mut decl_builder = builder::VariableDeclaration::from_node(decl);
+                    decl_builder.declarations(NodeList::from_iter(gc, [builder.build_forced(gc)]));
+                    decl_builder.build_forced(gc)
+                }
+                _ => utils::make_expression_statement(gc, utils::make_assignment(gc, left, value)),
+            };
+            let mut stmts = vec![init];
+            match body {
+                Node::BlockStatement(block) => stmts.extend(block.body.iter()),
+                _ => stmts.push(body),
+            }
+
+            // !(_step = _iterator.next()).done
+            let test = builder::UnaryExpression::build_template(
+                gc,
+                template::UnaryExpression {
+                    metadata: Default::default(),
+                    operator: UnaryExpressionOperator::Not,
+                    argument: utils::make_member(
+                        gc,
+                        utils::make_assignment(
+                            gc,
+                            utils::make_identifier(gc, step),
+                            utils::make_call(
+                                gc,
+                                utils::make_member(
+                                    gc,
+                                    utils::make_identifier(gc, iterator),
+                                    "next",
+                                ),
+                                vec![],
+                            ),
+                        ),
+                        "done",
+                    ),
+                    prefix: true,
+                },
+            );
+            let declarations = [(iterator, Some(make_get_iterator(gc, right))), (step, None)]
+                .iter()
+                .map(|(name, init)| {
+                    builder::VariableDeclarator::build_template(
+                        gc,
+                        template::VariableDeclarator {
+                            metadata: Default::default(),
+                            id: utils::make_identifier(gc, *name),
+                            init: *init,
+                        },
+                    )
+                })
+                .collect::<Vec<_>>();
+            let result = builder::ForStatement::build_template(
+                gc,
+                template::ForStatement {
+                    metadata: TemplateMetadata {
+                        range: metadata.range,
+                        comments: metadata.comments,
+                        ..Default::default()
+                    },
+                    init: Some(builder::VariableDeclaration::build_template(
+                        gc,
+                        template::VariableDeclaration {
+                            metadata: Default::default(),
+                            kind: VariableDeclarationKind::Var,
+                            declarations: NodeList::from_iter(gc, declarations),
+                        },
+                    )),
+                    test: Some(test),
+                    update: None,
+                    body: utils::make_block(gc, stmts),
+                },
+            );
+            return node.replace_with_existing(result, gc, self);
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+/// Build a call to an inline helper which returns an iterator for `iterable`:
+/// ```js
+/// (function (o) {
+///   if (typeof Symbol !== "undefined" && o[Symbol.iterator]) return o[Symbol.iterator]();
+///   var i = 0;
+///   return {next: function () { return i < o.length ? {done: false, value: o[i++]} : {done: true}; }};
+/// })(iterable)
+/// ```
+fn make_get_iterator<'gc>(gc: &'gc GCLock, iterable: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    let id = |name: &str| utils::make_identifier(gc, gc.atom(name));
+    let symbol_iterator = || utils::make_member(gc, utils::make_global(gc, "Symbol"), "iterator");
+    let function = |params: Vec<&'gc Node<'gc>>, body: Vec<&'gc Node<'gc>>| {
+        builder::FunctionExpression::build_template(
+            gc,
+            template::FunctionExpression {
+                metadata: Default::default(),
+                id: None,
+                params: NodeList::from_iter(gc, params),
+                body: utils::make_block(gc, body),
+                type_parameters: None,
+                return_type: None,
+                predicate: None,
+                generator: false,
+                is_async: false,
+            },
+        )
+    };
+
+    let has_symbol = utils::make_binary(
+        gc,
+        BinaryExpressionOperator::StrictNotEquals,
+        builder::UnaryExpression::build_template(
+            gc,
+            template::UnaryExpression {
+                metadata: Default::default(),
+                operator: UnaryExpressionOperator::Typeof,
+                argument: utils::make_global(gc, "Symbol"),
+                prefix: true,
+            },
+        ),
+        utils::make_string(gc, "undefined"),
+    );
+    let use_symbol = builder::IfStatement::build_template(
+        gc,
+        template::IfStatement {
+            metadata: Default::default(),
+            test: builder::LogicalExpression::build_template(
+                gc,
+                template::LogicalExpression {
+                    metadata: Default::default(),
+                    left: has_symbol,
+                    right: utils::make_computed_member(gc, id("o"), symbol_iterator()),
+                    operator: LogicalExpressionOperator::And,
+                },
+            ),
+            consequent: utils::make_return(
+                gc,
+                Some(utils::make_call(
+                    gc,
+                    utils::make_computed_member(gc, id("o"), symbol_iterator()),
+                    vec![],
+                )),
+            ),
+            alternate: None,
+        },
+    );
+
+    // i < o.length ? {done: false, value: o[i++]} : {done: true}
+    let next_index = builder::UpdateExpression::build_template(
+        gc,
+        template::UpdateExpression {
+            metadata: Default::default(),
+            operator: UpdateExpressionOperator::Increment,
+            argument: id("i"),
+            prefix: false,
+        },
+    );
+    let next_result = utils::make_conditional(
+        gc,
+        utils::make_binary(
+            gc,
+            BinaryExpressionOperator::Less,
+            id("i"),
+            utils::make_member(gc, id("o"), "length"),
+        ),
+        utils::make_object(
+            gc,
+            vec![
+                ("done", utils::make_boolean(gc, false)),
+                (
+                    "value",
+                    utils::make_computed_member(gc, id("o"), next_index),
+                ),
+            ],
+        ),
+        utils::make_object(gc, vec![("done", utils::make_boolean(gc, true))]),
+    );
+    let next = function(vec![], vec![utils::make_return(gc, Some(next_result))]);
+
+    let helper = function(
+        vec![id("o")],
+        vec![
+            use_symbol,
+            utils::make_var(gc, id("i"), Some(utils::make_number(gc, 0.0))),
+            utils::make_return(gc, Some(utils::make_object(gc, vec![("next", next)]))),
+        ],
+    );
+    utils::make_call(gc, helper, vec![iterable])
+}
@@ -0,0 +1,1635 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower generators and async functions to state machines.
+//!
+//! The body of the function is split at every `yield` and `await` into the cases of a `switch`,
+//! which is run by the runtime in [`GENERATOR_RUNTIME`] until it suspends:
+//! ```js
+//! function* f(x) {
+//!   var y = yield x;
+//!   return y;
+//! }
+//! ```
+//! becomes
+//! ```js
+//! function f(x) {
+//!   var y;
+//!   return junoGeneratorRuntime.generator(function (_context) {
+//!     while (1) switch (_context.prev = _context.next) {
+//!       case 0:
+//!         _context.next = 1;
+//!         return x;
+//!       case 1:
+//!         y = _context.sent;
+//!         return _context.abrupt("return", y);
+//!       case 2:
+//!       case "end":
+//!         return _context.stop();
+//!     }
+//!   }, this);
+//! }
+//! ```
+//! Locals must keep their values while the function is suspended, so their declarations are
+//! hoisted out of the state machine, after renaming the block scoped ones which would clash.
+//! Values which are computed before a `yield` in the same expression are kept in temporaries
+//! on the context.
+//!
+//! The locations of try statements are passed to the runtime, which dispatches exceptions to
+//! the right case and runs finally blocks for `break`, `continue` and `return`.
+//! Bindings declared in loops are shared by all iterations once hoisted, and iterators are not
+//! closed when a loop exits early.
+
+use super::lower_destructuring::LowerDestructuring;
+use crate::utils::{self, NameGenerator};
+use crate::Pass;
+use juno::ast::*;
+use juno::sema::{DeclId, DeclKind, FunctionInfoId, LexicalScopeId, Resolution, SemContext};
+use juno_support::atom_table::Atom;
+use std::collections::{HashMap, HashSet};
+
+/// Source of the runtime called by the lowered functions.
+/// It's a script which defines the global `junoGeneratorRuntime`,
+/// and must run before any lowered function is called.
+pub const GENERATOR_RUNTIME: &str = include_str!("../../runtime/generators.js");
+
+/// Global which holds the runtime functions.
+const RUNTIME_NAME: &str = "junoGeneratorRuntime";
+
+#[derive(Default)]
+pub struct LowerGenerators {}
+
+impl LowerGenerators {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for LowerGenerators {
+    fn name(&self) -> &'static str {
+        "Lower Generators"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers generators and async functions to state machines"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let mut names = NameGenerator::new(gc, node);
+
+        // Rename the declarations which would clash once hoisted out of the state machine.
+        let sem = utils::resolve(gc, node);
+        let mut analysis = Analysis::new(&sem);
+        node.visit(gc, &mut analysis, None);
+        let renames = analysis.renames(gc, &mut names);
+        let root = if renames.is_empty() {
+            node
+        } else {
+            utils::changed_or(
+                VisitorMut::call(
+                    &mut utils::Renamer {
+                        sem: &sem,
+                        names: renames,
+                    },
+                    gc,
+                    node,
+                    None,
+                ),
+                node,
+            )
+        };
+
+        let mut lowering = Lowering { names };
+        let result = VisitorMut::call(&mut lowering, gc, root, None);
+        if std::ptr::eq(root, node) {
+            result
+        } else {
+            TransformResult::Changed(utils::changed_or(result, root))
+        }
+    }
+}
+
+/// Collects the names which the hoisted declarations of each lowered function can't use.
+struct Analysis<'s> {
+    sem: &'s SemContext,
+    /// Lexical scopes being visited.
+    scopes: Vec<LexicalScopeId>,
+    /// The functions which are lowered.
+    functions: Vec<FunctionInfoId>,
+    /// Names referenced in each function (including nested functions) which resolve outside it.
+    outer_names: HashMap<FunctionInfoId, HashSet<Atom>>,
+}
+
+impl<'s> Analysis<'s> {
+    fn new(sem: &'s SemContext) -> Self {
+        Self {
+            sem,
+            scopes: sem.global_scope_id().into_iter().collect(),
+            functions: vec![],
+            outer_names: HashMap::new(),
+        }
+    }
+
+    fn current_function(&self) -> FunctionInfoId {
+        match self.scopes.last() {
+            Some(&scope) => self.sem.scope(scope).parent_function,
+            None => FunctionInfoId::GLOBAL_FUNCTION_ID,
+        }
+    }
+
+    /// Record that `name` is referenced in every function from `func` up to, but not
+    /// including, `until`.
+    fn add_outer_name(&mut self, name: Atom, func: FunctionInfoId, until: Option<FunctionInfoId>) {
+        let mut func = Some(func);
+        while let Some(f) = func {
+            if Some(f) == until {
+                break;
+            }
+            self.outer_names.entry(f).or_default().insert(name);
+            func = self.sem.function(f).parent_function;
+        }
+    }
+
+    /// Choose new names for the declarations in nested scopes of the lowered functions which
+    /// clash with another name in their function.
+    fn renames(&self, gc: &GCLock, names: &mut NameGenerator) -> HashMap<DeclId, Atom> {
+        let mut result = HashMap::new();
+        for &func in &self.functions {
+            let scopes = &self.sem.function(func).scopes;
+            let mut taken = self.outer_names.get(&func).cloned().unwrap_or_default();
+            taken.extend(
+                self.sem
+                    .scope(scopes[0])
+                    .decls
+                    .iter()
+                    .map(|&decl| self.sem.decl(decl).name),
+            );
+            for &scope in &scopes[1..] {
+                for &decl_id in &self.sem.scope(scope).decls {
+                    let decl = self.sem.decl(decl_id);
+                    // The names of function expressions aren't hoisted.
+                    if decl.kind == DeclKind::FunctionExprName {
+                        continue;
+                    }
+                    if !taken.insert(decl.name) && decl.can_rename {
+                        let name = names.fresh(gc, gc.str(decl.name));
+                        taken.insert(name);
+                        result.insert(decl_id, name);
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+impl<'gc> Visitor<'gc> for Analysis<'_> {
+    fn call(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>, _path: Option<Path<'gc>>) {
+        let scope = self.sem.node_scope(NodeRc::from_node(gc, node));
+        if let Some(scope) = scope {
+            self.scopes.push(scope);
+        }
+
+        match node {
+            Node::Identifier(Identifier { name, .. }) => {
+                let func = self.current_function();
+                match self.sem.ident_decl(&NodeRc::from_node(gc, node)) {
+                    Some(Resolution::Decl(decl_id)) => {
+                        let decl = self.sem.decl(decl_id);
+                        let decl_func = self.sem.scope(decl.scope).parent_function;
+                        if decl_func != func {
+                            self.add_outer_name(decl.name, func, Some(decl_func));
+                        }
+                    }
+                    Some(Resolution::Unresolvable) => self.add_outer_name(*name, func, None),
+                    None => {}
+                }
+            }
+            _ if is_lowered(node) => {
+                // The function scope belongs to the body.
+                let body = NodeRc::from_node(gc, node.function_like_body());
+                if let Some(scope) = self.sem.node_scope(body) {
+                    self.functions.push(self.sem.scope(scope).parent_function);
+                }
+            }
+            _ => {}
+        }
+        node.visit_children(gc, self);
+
+        if scope.is_some() {
+            self.scopes.pop();
+        }
+    }
+}
+
+struct Lowering {
+    names: NameGenerator,
+}
+
+impl<'gc> VisitorMut<'gc> for Lowering {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        if is_lowered(node) {
+            // Nested functions are lowered first, so they don't contain `yield` anymore.
+            let mut func = utils::changed_or(node.visit_children_mut(gc, self), node);
+            // Default values which suspend must only be evaluated when they're used.
+            if has_pattern_leap(gc, func.function_like_body()) {
+                func = utils::changed_or(LowerDestructuring::new().run(gc, func), func);
+            }
+            return TransformResult::Changed(self.lower_function(gc, func));
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+impl Lowering {
+    /// Replace the body of `func` with a call to the runtime which runs its state machine.
+    fn lower_function<'gc>(&mut self, gc: &'gc GCLock, func: &'gc Node<'gc>) -> &'gc Node<'gc> {
+        let (is_generator, is_async) = match func {
+            Node::FunctionDeclaration(FunctionDeclaration {
+                generator,
+                is_async,
+                ..
+            })
+            | Node::FunctionExpression(FunctionExpression {
+                generator,
+                is_async,
+                ..
+            }) => (*generator, *is_async),
+            Node::ArrowFunctionExpression(ArrowFunctionExpression { is_async, .. }) => {
+                (false, *is_async)
+            }
+            _ => unreachable!("only functions are lowered"),
+        };
+        let mut stmts = match func.function_like_body() {
+            Node::BlockStatement(BlockStatement { body, .. }) => body.iter().collect(),
+            expression => vec![utils::make_return(gc, Some(expression))],
+        };
+        let directives = stmts
+            .iter()
+            .take_while(|stmt| {
+                matches!(
+                    stmt,
+                    Node::ExpressionStatement(ExpressionStatement {
+                        directive: Some(_),
+                        ..
+                    })
+                )
+            })
+            .count();
+        let mut body: Vec<&Node> = stmts.drain(..directives).collect();
+
+        let context = self.names.fresh(gc, "context");
+        let mut emitter = Emitter::new(&mut self.names, context);
+        let start = emitter.new_loc();
+        emitter.mark(start);
+        emitter.explode_statements(gc, stmts);
+        let (machine, try_locs) = emitter.finish(gc);
+
+        // var x, _arguments = arguments;
+        let mut declarations: Vec<&Node> = emitter
+            .hoisted
+            .iter()
+            .map(|&name| make_declarator(gc, name, None))
+            .collect();
+        if let Some(arguments) = emitter.arguments {
+            declarations.push(make_declarator(
+                gc,
+                arguments,
+                Some(utils::make_global(gc, "arguments")),
+            ));
+        }
+        if !declarations.is_empty() {
+            body.push(builder::VariableDeclaration::build_template(
+                gc,
+                template::VariableDeclaration {
+                    metadata: Default::default(),
+                    kind: VariableDeclarationKind::Var,
+                    declarations: NodeList::from_iter(gc, declarations),
+                },
+            ));
+        }
+        body.extend(emitter.functions);
+
+        let wrapper = match (is_generator, is_async) {
+            (true, true) => "asyncGenerator",
+            (true, false) => "generator",
+            (false, _) => "async",
+        };
+        let mut arguments = vec![machine, utils::make_this(gc)];
+        arguments.extend(try_locs);
+        body.push(utils::make_return(
+            gc,
+            Some(utils::make_call(
+                gc,
+                utils::make_member(gc, utils::make_global(gc, RUNTIME_NAME), wrapper),
+                arguments,
+            )),
+        ));
+        let body = utils::make_block(gc, body);
+
+        match func {
+            Node::FunctionDeclaration(decl) => {
+                let mut builder = builder::FunctionDeclaration::from_node(decl);
+                builder.body(body);
+                builder.generator(false);
+                builder.is_async(false);
+                builder.build_forced(gc)
+            }
+            Node::FunctionExpression(expr) => {
+                let mut builder = builder::FunctionExpression::from_node(expr);
+                builder.body(body);
+                builder.generator(false);
+                builder.is_async(false);
+                builder.build_forced(gc)
+            }
+            Node::ArrowFunctionExpression(arrow) => {
+                let mut builder = builder::ArrowFunctionExpression::from_node(arrow);
+                builder.body(body);
+                builder.expression(false);
+                builder.is_async(false);
+                builder.build_forced(gc)
+            }
+            _ => unreachable!("only functions are lowered"),
+        }
+    }
+}
+
+/// A location in the state machine, which can be jumped to.
+/// Locations are numbered in the order they're marked once the state machine is complete.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+struct Loc(usize);
+
+/// The contents of the state machine, in order.
+enum Item<'gc> {
+    Statement(&'gc Node<'gc>),
+    /// Start of the case for a location.
+    Mark(Loc),
+}
+
+/// Statements in the state machine which `break` and `continue` can jump to.
+enum JumpTarget {
+    Loop {
+        labels: Vec<Atom>,
+        break_loc: Loc,
+        continue_loc: Loc,
+    },
+    Switch {
+        break_loc: Loc,
+    },
+    Labeled {
+        label: Atom,
+        break_loc: Loc,
+    },
+}
+
+/// The locations of a try statement, which are passed to the runtime.
+struct TryEntry {
+    try_loc: Loc,
+    catch_loc: Option<Loc>,
+    finally_loc: Option<Loc>,
+    after_loc: Loc,
+}
+
+/// Builds the state machine of a function.
+struct Emitter<'n, 'gc> {
+    names: &'n mut NameGenerator,
+    /// Parameter of the state machine which holds the context.
+    context: Atom,
+    /// Name of the variable which holds the `arguments` of the function, if they're used.
+    arguments: Option<Atom>,
+    items: Vec<Item<'gc>>,
+    num_locs: usize,
+    /// Numeric literals which must be replaced with the number of their location.
+    placeholders: HashMap<*const Node<'gc>, Loc>,
+    /// Statements being exploded which can be jumped to.
+    targets: Vec<JumpTarget>,
+    try_entries: Vec<TryEntry>,
+    num_temps: usize,
+    /// Variables which must be declared outside the state machine.
+    hoisted: Vec<Atom>,
+    /// Function declarations which must be moved out of the state machine.
+    functions: Vec<&'gc Node<'gc>>,
+}
+
+impl<'n, 'gc> Emitter<'n, 'gc> {
+    fn new(names: &'n mut NameGenerator, context: Atom) -> Self {
+        Self {
+            names,
+            context,
+            arguments: None,
+            items: vec![],
+            num_locs: 0,
+            placeholders: HashMap::new(),
+            targets: vec![],
+            try_entries: vec![],
+            num_temps: 0,
+            hoisted: vec![],
+            functions: vec![],
+        }
+    }
+
+    fn new_loc(&mut self) -> Loc {
+        let loc = Loc(self.num_locs);
+        self.num_locs += 1;
+        loc
+    }
+
+    fn mark(&mut self, loc: Loc) {
+        self.items.push(Item::Mark(loc));
+    }
+
+    fn emit(&mut self, stmt: &'gc Node<'gc>) {
+        self.items.push(Item::Statement(stmt));
+    }
+
+    /// Emit `expr` as a statement, unless it has no side effects.
+    fn emit_expression(&mut self, gc: &'gc GCLock, expr: &'gc Node<'gc>) {
+        let pure = match expr {
+            Node::Identifier(_)
+            | Node::NumericLiteral(_)
+            | Node::StringLiteral(_)
+            | Node::BooleanLiteral(_)
+            | Node::NullLiteral(_)
+            | Node::ThisExpression(_) => true,
+            Node::MemberExpression(MemberExpression {
+                object: Node::Identifier(Identifier { name, .. }),
+                ..
+            }) => *name == self.context,
+            _ => false,
+        };
+        if !pure {
+            self.emit(utils::make_expression_statement(gc, expr));
+        }
+    }
+
+    /// Build the number of `loc`, which is only known once the state machine is complete.
+    fn make_loc(&mut self, gc: &'gc GCLock, loc: Loc) -> &'gc Node<'gc> {
+        let placeholder = utils::make_number(gc, loc.0 as f64);
+        self.placeholders.insert(placeholder, loc);
+        placeholder
+    }
+
+    /// Build `_context.name`.
+    fn make_context_member(&self, gc: &'gc GCLock, name: &str) -> &'gc Node<'gc> {
+        utils::make_member(gc, utils::make_identifier(gc, self.context), name)
+    }
+
+    /// Build `_context.name(arguments)`.
+    fn make_context_call(
+        &self,
+        gc: &'gc GCLock,
+        name: &str,
+        arguments: Vec<&'gc Node<'gc>>,
+    ) -> &'gc Node<'gc> {
+        utils::make_call(gc, self.make_context_member(gc, name), arguments)
+    }
+
+    /// Allocate a temporary on the context, which keeps its value while suspended.
+    fn new_temp(&mut self) -> String {
+        let temp = format!("t{}", self.num_temps);
+        self.num_temps += 1;
+        temp
+    }
+
+    /// Build `_context.field = value;`.
+    fn make_context_assignment(
+        &self,
+        gc: &'gc GCLock,
+        field: &str,
+        value: &'gc Node<'gc>,
+    ) -> &'gc Node<'gc> {
+        utils::make_expression_statement(
+            gc,
+            utils::make_assignment(gc, self.make_context_member(gc, field), value),
+        )
+    }
+
+    /// Build `_context.next = loc; break;`.
+    fn make_jump(&mut self, gc: &'gc GCLock, loc: Loc) -> [&'gc Node<'gc>; 2] {
+        let loc = self.make_loc(gc, loc);
+        [
+            self.make_context_assignment(gc, "next", loc),
+            builder::BreakStatement::build_template(
+                gc,
+                template::BreakStatement {
+                    metadata: Default::default(),
+                    label: None,
+                },
+            ),
+        ]
+    }
+
+    fn jump(&mut self, gc: &'gc GCLock, loc: Loc) {
+        for stmt in self.make_jump(gc, loc) {
+            self.emit(stmt);
+        }
+    }
+
+    fn jump_if(&mut self, gc: &'gc GCLock, test: &'gc Node<'gc>, loc: Loc) {
+        let jump = self.make_jump(gc, loc);
+        self.emit(builder::IfStatement::build_template(
+            gc,
+            template::IfStatement {
+                metadata: Default::default(),
+                test,
+                consequent: utils::make_block(gc, jump.to_vec()),
+                alternate: None,
+            },
+        ));
+    }
+
+    fn jump_if_not(&mut self, gc: &'gc GCLock, test: &'gc Node<'gc>, loc: Loc) {
+        let test = builder::UnaryExpression::build_template(
+            gc,
+            template::UnaryExpression {
+                metadata: Default::default(),
+                operator: UnaryExpressionOperator::Not,
+                argument: test,
+                prefix: true,
+            },
+        );
+        self.jump_if(gc, test, loc);
+    }
+
+    /// Suspend with `value`, resuming at `loc`.
+    fn suspend(&mut self, gc: &'gc GCLock, value: Option<&'gc Node<'gc>>, loc: Loc) {
+        let loc = self.make_loc(gc, loc);
+        self.emit(self.make_context_assignment(gc, "next", loc));
+        self.emit(utils::make_return(gc, value));
+    }
+
+    /// Record that the current location is `loc`, for try statements entered without a jump.
+    fn update_prev(&mut self, gc: &'gc GCLock, loc: Loc) {
+        let loc = self.make_loc(gc, loc);
+        self.emit(self.make_context_assignment(gc, "prev", loc));
+    }
+
+    /// Build `return _context.abrupt(kind, arg);`.
+    fn make_abrupt(
+        &self,
+        gc: &'gc GCLock,
+        kind: &str,
+        arg: Option<&'gc Node<'gc>>,
+    ) -> &'gc Node<'gc> {
+        let mut arguments = vec![utils::make_string(gc, kind)];
+        arguments.extend(arg);
+        utils::make_return(gc, Some(self.make_context_call(gc, "abrupt", arguments)))
+    }
+
+    fn hoist(&mut self, pattern: &'gc Node<'gc>) {
+        let mut ids = vec![];
+        utils::collect_binding_identifiers(pattern, &mut ids);
+        for id in ids {
+            let name = node_cast!(Node::Identifier, id).name;
+            if !self.hoisted.contains(&name) {
+                self.hoisted.push(name);
+            }
+        }
+    }
+
+    fn arguments_name(&mut self, gc: &'gc GCLock) -> Atom {
+        match self.arguments {
+            Some(name) => name,
+            None => {
+                let name = self.names.fresh(gc, "arguments");
+                self.arguments = Some(name);
+                name
+            }
+        }
+    }
+
+    fn break_target(&self, label: Option<Atom>) -> Loc {
+        for target in self.targets.iter().rev() {
+            match (target, label) {
+                (JumpTarget::Loop { break_loc, .. }, None)
+                | (JumpTarget::Switch { break_loc }, None) => return *break_loc,
+                (
+                    JumpTarget::Loop {
+                        labels, break_loc, ..
+                    },
+                    Some(label),
+                ) if labels.contains(&label) => return *break_loc,
+                (
+                    JumpTarget::Labeled {
+                        label: target_label,
+                        break_loc,
+                    },
+                    Some(label),
+                ) if *target_label == label => return *break_loc,
+                _ => {}
+            }
+        }
+        unreachable!("break must have a target")
+    }
+
+    fn continue_target(&self, label: Option<Atom>) -> Loc {
+        for target in self.targets.iter().rev() {
+            if let JumpTarget::Loop {
+                labels,
+                continue_loc,
+                ..
+            } = target
+            {
+                if label.is_none_or(|label| labels.contains(&label)) {
+                    return *continue_loc;
+                }
+            }
+        }
+        unreachable!("continue must have a target")
+    }
+
+    /// Rewrite `node`, which doesn't suspend, to run inside the state machine.
+    fn rewrite(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>) -> &'gc Node<'gc> {
+        let mut rewriter = Rewriter {
+            emitter: self,
+            local_targets: vec![],
+            arrows: 0,
+        };
+        utils::changed_or(VisitorMut::call(&mut rewriter, gc, node, None), node)
+    }
+
+    fn explode_statements<I: IntoIterator<Item = &'gc Node<'gc>>>(
+        &mut self,
+        gc: &'gc GCLock,
+        stmts: I,
+    ) {
+        for stmt in stmts {
+            self.explode_statement(gc, stmt, vec![]);
+        }
+    }
+
+    /// Emit `stmt`, which is labeled with `labels`, splitting it at every location it
+    /// may suspend at.
+    fn explode_statement(&mut self, gc: &'gc GCLock, stmt: &'gc Node<'gc>, labels: Vec<Atom>) {
+        match stmt {
+            Node::FunctionDeclaration(_) => self.functions.push(stmt),
+            Node::VariableDeclaration(decl) => self.explode_declaration(gc, decl),
+            Node::ClassDeclaration(class) => {
+                // class A {} => A = class A {};
+                let id = class.id.unwrap();
+                self.hoist(id);
+                let class = builder::ClassExpression::build_template(
+                    gc,
+                    template::ClassExpression {
+                        metadata: TemplateMetadata {
+                            range: class.metadata.range,
+                            ..Default::default()
+                        },
+                        id: class.id,
+                        type_parameters: class.type_parameters,
+                        super_class: class.super_class,
+                        super_type_parameters: class.super_type_parameters,
+                        implements: class.implements,
+                        decorators: class.decorators,
+                        body: class.body,
+                    },
+                );
+                let assignment = utils::make_assignment(gc, id, class);
+                let assignment = self.explode_expression(gc, assignment);
+                self.emit_expression(gc, assignment);
+            }
+            Node::EmptyStatement(_) => {}
+            _ if !contains_leap(gc, stmt) => {
+                let stmt = self.rewrite(gc, stmt);
+                self.emit(stmt);
+            }
+            Node::ExpressionStatement(ExpressionStatement { expression, .. }) => {
+                let expression = self.explode_expression(gc, expression);
+                self.emit_expression(gc, expression);
+            }
+            Node::BlockStatement(BlockStatement { body, .. }) => {
+                self.explode_statements(gc, body.iter());
+            }
+            Node::ReturnStatement(ReturnStatement { argument, .. }) => {
+                let argument = argument.map(|argument| self.explode_expression(gc, argument));
+                self.emit(self.make_abrupt(gc, "return", argument));
+            }
+            Node::ThrowStatement(ThrowStatement { argument, .. }) => {
+                let argument = self.explode_expression(gc, argument);
+                self.emit(builder::ThrowStatement::build_template(
+                    gc,
+                    template::ThrowStatement {
+                        metadata: Default::default(),
+                        argument,
+                    },
+                ));
+            }
+            Node::IfStatement(IfStatement {
+                test,
+                consequent,
+                alternate,
+                ..
+            }) => {
+                let else_loc = alternate.map(|_| self.new_loc());
+                let after = self.new_loc();
+                let test = self.explode_expression(gc, test);
+                self.jump_if_not(gc, test, else_loc.unwrap_or(after));
+                self.explode_statement(gc, consequent, vec![]);
+                if let (Some(alternate), Some(else_loc)) = (alternate, else_loc) {
+                    self.jump(gc, after);
+                    self.mark(else_loc);
+                    self.explode_statement(gc, alternate, vec![]);
+                }
+                self.mark(after);
+            }
+            Node::WhileStatement(WhileStatement { test, body, .. }) => {
+                let before = self.new_loc();
+                let after = self.new_loc();
+                self.mark(before);
+                let test = self.explode_expression(gc, test);
+                self.jump_if_not(gc, test, after);
+                self.explode_loop_body(gc, body, labels, after, before);
+                self.jump(gc, before);
+                self.mark(after);
+            }
+            Node::DoWhileStatement(DoWhileStatement { body, test, .. }) => {
+                let first = self.new_loc();
+                let test_loc = self.new_loc();
+                let after = self.new_loc();
+                self.mark(first);
+                self.explode_loop_body(gc, body, labels, after, test_loc);
+                self.mark(test_loc);
+                let test = self.explode_expression(gc, test);
+                self.jump_if(gc, test, first);
+                self.mark(after);
+            }
+            Node::ForStatement(ForStatement {
+                init,
+                test,
+                update,
+                body,
+                ..
+            }) => {
+                match init {
+                    Some(Node::VariableDeclaration(decl)) => self.explode_declaration(gc, decl),
+                    Some(init) => {
+                        let init = self.explode_expression(gc, init);
+                        self.emit_expression(gc, init);
+                    }
+                    None => {}
+                }
+                let head = self.new_loc();
+                let update_loc = self.new_loc();
+                let after = self.new_loc();
+                self.mark(head);
+                if let Some(test) = test {
+                    let test = self.explode_expression(gc, test);
+                    self.jump_if_not(gc, test, after);
+                }
+                self.explode_loop_body(gc, body, labels, after, update_loc);
+                self.mark(update_loc);
+                if let Some(update) = update {
+                    let update = self.explode_expression(gc, update);
+                    self.emit_expression(gc, update);
+                }
+                self.jump(gc, head);
+                self.mark(after);
+            }
+            Node::ForInStatement(ForInStatement {
+                left, right, body, ..
+            }) => self.explode_for_in(gc, left, right, body, "keys", labels),
+            Node::ForOfStatement(ForOfStatement {
+                left,
+                right,
+                body,
+                is_await,
+                ..
+            }) => {
+                let helper = if *is_await { "asyncIterator" } else { "values" };
+                self.explode_for_in(gc, left, right, body, helper, labels);
+            }
+            Node::LabeledStatement(LabeledStatement {
+                label: Node::Identifier(Identifier { name, .. }),
+                body,
+                ..
+            }) => {
+                if is_loop(body) {
+                    let mut labels = labels;
+                    labels.push(*name);
+                    self.explode_statement(gc, body, labels);
+                } else {
+                    let after = self.new_loc();
+                    self.targets.push(JumpTarget::Labeled {
+                        label: *name,
+                        break_loc: after,
+                    });
+                    self.explode_statement(gc, body, vec![]);
+                    self.targets.pop();
+                    self.mark(after);
+                }
+            }
+            Node::SwitchStatement(SwitchStatement {
+                discriminant,
+                cases,
+                ..
+            }) => {
+                let discriminant = self.explode_expression(gc, discriminant);
+                let temp = self.new_temp();
+                self.emit(self.make_context_assignment(gc, &temp, discriminant));
+                let after = self.new_loc();
+                let mut default_loc = None;
+                let mut case_locs = vec![];
+                for case in cases.iter() {
+                    let loc = self.new_loc();
+                    case_locs.push(loc);
+                    match node_cast!(Node::SwitchCase, case).test {
+                        Some(test) => {
+                            let test = self.explode_expression(gc, test);
+                            let test = utils::make_binary(
+                                gc,
+                                BinaryExpressionOperator::StrictEquals,
+                                self.make_context_member(gc, &temp),
+                                test,
+                            );
+                            self.jump_if(gc, test, loc);
+                        }
+                        None => default_loc = Some(loc),
+                    }
+                }
+                self.jump(gc, default_loc.unwrap_or(after));
+                self.targets.push(JumpTarget::Switch { break_loc: after });
+                for (case, loc) in cases.iter().zip(case_locs) {
+                    self.mark(loc);
+                    self.explode_statements(
+                        gc,
+                        node_cast!(Node::SwitchCase, case).consequent.iter(),
+                    );
+                }
+                self.targets.pop();
+                self.mark(after);
+            }
+            Node::TryStatement(TryStatement {
+                block,
+                handler,
+                finalizer,
+                ..
+            }) => {
+                let try_loc = self.new_loc();
+                let catch_loc = handler.map(|_| self.new_loc());
+                let finally_loc = finalizer.map(|_| self.new_loc());
+                let after = self.new_loc();
+                self.try_entries.push(TryEntry {
+                    try_loc,
+                    catch_loc,
+                    finally_loc,
+                    after_loc: after,
+                });
+
+                self.mark(try_loc);
+                self.update_prev(gc, try_loc);
+                self.explode_statement(gc, block, vec![]);
+                if let (Some(handler), Some(catch_loc)) = (handler, catch_loc) {
+                    self.jump(gc, finally_loc.unwrap_or(after));
+                    self.mark(catch_loc);
+                    self.update_prev(gc, catch_loc);
+                    let CatchClause { param, body, .. } = node_cast!(Node::CatchClause, handler);
+                    let try_loc = self.make_loc(gc, try_loc);
+                    let caught = self.make_context_call(gc, "catch", vec![try_loc]);
+                    match param {
+                        Some(param) => {
+                            // e = _context.catch(tryLoc);
+                            self.hoist(param);
+                            let assignment = utils::make_assignment(gc, param, caught);
+                            let assignment = self.explode_expression(gc, assignment);
+                            self.emit_expression(gc, assignment);
+                        }
+                        None => self.emit_expression(gc, caught),
+                    }
+                    self.explode_statement(gc, body, vec![]);
+                }
+                if let (Some(finalizer), Some(finally_loc)) = (finalizer, finally_loc) {
+                    self.mark(finally_loc);
+                    self.update_prev(gc, finally_loc);
+                    self.explode_statement(gc, finalizer, vec![]);
+                    let finally_loc = self.make_loc(gc, finally_loc);
+                    let finish = self.make_context_call(gc, "finish", vec![finally_loc]);
+                    self.emit(utils::make_return(gc, Some(finish)));
+                }
+                self.mark(after);
+            }
+            _ => {
+                let stmt = self.rewrite(gc, stmt);
+                self.emit(stmt);
+            }
+        }
+    }
+
+    fn explode_loop_body(
+        &mut self,
+        gc: &'gc GCLock,
+        body: &'gc Node<'gc>,
+        labels: Vec<Atom>,
+        break_loc: Loc,
+        continue_loc: Loc,
+    ) {
+        self.targets.push(JumpTarget::Loop {
+            labels,
+            break_loc,
+            continue_loc,
+        });
+        self.explode_statement(gc, body, vec![]);
+        self.targets.pop();
+    }
+
+    /// Hoist the bindings of `decl` and emit the assignments of their initializers.
+    fn explode_declaration(&mut self, gc: &'gc GCLock, decl: &'gc VariableDeclaration<'gc>) {
+        for declarator in decl.declarations.iter() {
+            let VariableDeclarator { id, init, .. } =
+                node_cast!(Node::VariableDeclarator, declarator);
+            self.hoist(id);
+            let value = match init {
+                Some(init) => init,
+                None if decl.kind == VariableDeclarationKind::Var => continue,
+                // `let` bindings start out undefined whenever they're declared.
+                None => utils::make_undefined(gc),
+            };
+            let assignment = utils::make_assignment(gc, id, value);
+            let assignment = self.explode_expression(gc, assignment);
+            self.emit_expression(gc, assignment);
+        }
+    }
+
+    /// Emit a loop over the iterator returned by the runtime function `helper` for `right`.
+    fn explode_for_in(
+        &mut self,
+        gc: &'gc GCLock,
+        left: &'gc Node<'gc>,
+        right: &'gc Node<'gc>,
+        body: &'gc Node<'gc>,
+        helper: &str,
+        labels: Vec<Atom>,
+    ) {
+        // _context.t0 = junoGeneratorRuntime.values(right);
+        let right = self.explode_expression(gc, right);
+        let iterator = self.new_temp();
+        let runtime = utils::make_member(gc, utils::make_global(gc, RUNTIME_NAME), helper);
+        self.emit(self.make_context_assignment(
+            gc,
+            &iterator,
+            utils::make_call(gc, runtime, vec![right]),
+        ));
+
+        let head = self.new_loc();
+        let after = self.new_loc();
+        self.mark(head);
+        let step = self.new_temp();
+        let next = utils::make_call(
+            gc,
+            utils::make_member(gc, self.make_context_member(gc, &iterator), "next"),
+            vec![],
+        );
+        let done = if helper == "asyncIterator" {
+            // _context.t1 = await _context.t0.next(); if (_context.t1.done) ...
+            let resume = self.new_loc();
+            let next = self.make_context_call(gc, "await", vec![next]);
+            self.suspend(gc, Some(next), resume);
+            self.mark(resume);
+            let sent = self.make_context_member(gc, "sent");
+            self.emit(self.make_context_assignment(gc, &step, sent));
+            utils::make_member(gc, self.make_context_member(gc, &step), "done")
+        } else {
+            // if ((_context.t1 = _context.t0.next()).done) ...
+            utils::make_member(
+                gc,
+                utils::make_assignment(gc, self.make_context_member(gc, &step), next),
+                "done",
+            )
+        };
+        self.jump_if(gc, done, after);
+
+        let target = match left {
+            Node::VariableDeclaration(VariableDeclaration { declarations, .. }) => {
+                let id = node_cast!(Node::VariableDeclarator, declarations.head().unwrap()).id;
+                self.hoist(id);
+                id
+            }
+            _ => left,
+        };
+        let value = utils::make_member(gc, self.make_context_member(gc, &step), "value");
+        let assignment = utils::make_assignment(gc, target, value);
+        let assignment = self.explode_expression(gc, assignment);
+        self.emit_expression(gc, assignment);
+
+        self.explode_loop_body(gc, body, labels, after, head);
+        self.jump(gc, head);
+        self.mark(after);
+    }
+
+    /// Emit the parts of `expr` which come before each location it may suspend at,
+    /// and return the expression for the rest.
+    fn explode_expression(&mut self, gc: &'gc GCLock, expr: &'gc Node<'gc>) -> &'gc Node<'gc> {
+        if !contains_leap(gc, expr) {
+            return self.rewrite(gc, expr);
+        }
+        match expr {
+            Node::YieldExpression(YieldExpression {
+                argument: Some(argument),
+                delegate: true,
+                ..
+            }) => {
+                // return _context.delegateYield(argument, "t0", after);
+                let argument = self.explode_expression(gc, argument);
+                let result = self.new_temp();
+                let after = self.new_loc();
+                let after_number = self.make_loc(gc, after);
+                let delegate = self.make_context_call(
+                    gc,
+                    "delegateYield",
+                    vec![argument, utils::make_string(gc, &result), after_number],
+                );
+                self.emit(utils::make_return(gc, Some(delegate)));
+                self.mark(after);
+                self.make_context_member(gc, &result)
+            }
+            Node::YieldExpression(YieldExpression { argument, .. }) => {
+                let argument = argument.map(|argument| self.explode_expression(gc, argument));
+                let after = self.new_loc();
+                self.suspend(gc, argument, after);
+                self.mark(after);
+                self.make_context_member(gc, "sent")
+            }
+            Node::AwaitExpression(AwaitExpression { argument, .. }) => {
+                let argument = self.explode_expression(gc, argument);
+                let after = self.new_loc();
+                let value = self.make_context_call(gc, "await", vec![argument]);
+                self.suspend(gc, Some(value), after);
+                self.mark(after);
+                self.make_context_member(gc, "sent")
+            }
+            Node::SequenceExpression(SequenceExpression { expressions, .. }) => {
+                let mut last = None;
+                for expression in expressions.iter() {
+                    if let Some(last) = last {
+                        self.emit_expression(gc, last);
+                    }
+                    last = Some(self.explode_expression(gc, expression));
+                }
+                last.unwrap()
+            }
+            Node::LogicalExpression(LogicalExpression {
+                left,
+                right,
+                operator,
+                ..
+            }) if contains_leap(gc, right) => {
+                // The right side is only evaluated if the left side doesn't decide the result.
+                let result = self.new_temp();
+                let after = self.new_loc();
+                let left = self.explode_expression(gc, left);
+                self.emit(self.make_context_assignment(gc, &result, left));
+                let value = self.make_context_member(gc, &result);
+                match operator {
+                    LogicalExpressionOperator::And => self.jump_if_not(gc, value, after),
+                    LogicalExpressionOperator::Or => self.jump_if(gc, value, after),
+                    LogicalExpressionOperator::NullishCoalesce => {
+                        let test = utils::make_binary(
+                            gc,
+                            BinaryExpressionOperator::LooseNotEquals,
+                            value,
+                            builder::NullLiteral::build_template(
+                                gc,
+                                template::NullLiteral {
+                                    metadata: Default::default(),
+                                },
+                            ),
+                        );
+                        self.jump_if(gc, test, after);
+                    }
+                }
+                let right = self.explode_expression(gc, right);
+                self.emit(self.make_context_assignment(gc, &result, right));
+                self.mark(after);
+                self.make_context_member(gc, &result)
+            }
+            Node::ConditionalExpression(ConditionalExpression {
+                test,
+                consequent,
+                alternate,
+                ..
+            }) if contains_leap(gc, consequent) || contains_leap(gc, alternate) => {
+                let result = self.new_temp();
+                let else_loc = self.new_loc();
+                let after = self.new_loc();
+                let test = self.explode_expression(gc, test);
+                self.jump_if_not(gc, test, else_loc);
+                let consequent = self.explode_expression(gc, consequent);
+                self.emit(self.make_context_assignment(gc, &result, consequent));
+                self.jump(gc, after);
+                self.mark(else_loc);
+                let alternate = self.explode_expression(gc, alternate);
+                self.emit(self.make_context_assignment(gc, &result, alternate));
+                self.mark(after);
+                self.make_context_member(gc, &result)
+            }
+            _ => self.explode_children(gc, expr, false),
+        }
+    }
+
+    /// Explode the children of `node` in evaluation order, saving the values of the children
+    /// which are followed by a location the state machine may suspend at, or all of them if
+    /// `save_all`.
+    fn explode_children(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        save_all: bool,
+    ) -> &'gc Node<'gc> {
+        let mut collector = ChildCollector { children: vec![] };
+        node.visit_children(gc, &mut collector);
+        let mut save = vec![false; collector.children.len()];
+        let mut leap_after = false;
+        for (i, child) in collector.children.iter().enumerate().rev() {
+            save[i] = save_all || leap_after;
+            leap_after = leap_after || contains_leap(gc, child);
+        }
+        let mut exploder = ChildExploder {
+            emitter: self,
+            save,
+            index: 0,
+        };
+        utils::changed_or(node.visit_children_mut(gc, &mut exploder), node)
+    }
+
+    /// Explode the child `node` at `path`, saving its value in a temporary if `save`.
+    fn explode_child(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        path: Option<Path<'gc>>,
+        save: bool,
+    ) -> &'gc Node<'gc> {
+        if !utils::is_reference(path) {
+            return node;
+        }
+        let is_target = match path {
+            Some(Path { parent, field }) => matches!(
+                (parent, field),
+                (Node::AssignmentExpression(_), NodeField::left)
+                    | (Node::UpdateExpression(_), NodeField::argument)
+                    | (Node::CallExpression(_), NodeField::callee)
+                    | (
+                        Node::UnaryExpression(UnaryExpression {
+                            operator: UnaryExpressionOperator::Delete,
+                            ..
+                        }),
+                        NodeField::argument
+                    )
+            ),
+            None => false,
+        };
+        match node {
+            // Assigning to a variable or calling it doesn't read it early.
+            Node::Identifier(_) if is_target => node,
+            // Keep the reference, but save the object it refers to.
+            Node::MemberExpression(_) if is_target => self.explode_children(gc, node, save),
+            Node::Property(_)
+            | Node::SpreadElement(_)
+            | Node::TemplateElement(_)
+            | Node::ObjectPattern(_)
+            | Node::ArrayPattern(_)
+            | Node::AssignmentPattern(_)
+            | Node::RestElement(_) => self.explode_children(gc, node, save),
+            _ => {
+                let value = self.explode_expression(gc, node);
+                let needs_temp = !matches!(
+                    value,
+                    Node::NumericLiteral(_)
+                        | Node::StringLiteral(_)
+                        | Node::BooleanLiteral(_)
+                        | Node::NullLiteral(_)
+                        | Node::ThisExpression(_)
+                        | Node::Super(_)
+                        | Node::FunctionExpression(_)
+                        | Node::ArrowFunctionExpression(_)
+                );
+                if !save || !needs_temp {
+                    return value;
+                }
+                let temp = self.new_temp();
+                self.emit(self.make_context_assignment(gc, &temp, value));
+                self.make_context_member(gc, &temp)
+            }
+        }
+    }
+
+    /// Build the function which runs the state machine, and the locations of its try
+    /// statements, if there are any.
+    fn finish(&mut self, gc: &'gc GCLock) -> (&'gc Node<'gc>, Option<&'gc Node<'gc>>) {
+        let end = self.new_loc();
+        self.mark(end);
+
+        // Number the locations in the order they're marked.
+        let mut numbers = HashMap::new();
+        for item in &self.items {
+            if let Item::Mark(loc) = item {
+                let number = numbers.len();
+                numbers.insert(*loc, number);
+            }
+        }
+        let mut patcher = LocPatcher {
+            placeholders: &self.placeholders,
+            numbers: &numbers,
+        };
+
+        let mut cases: Vec<(&Node, Vec<&Node>)> = vec![];
+        for item in &self.items {
+            match item {
+                Item::Mark(loc) => {
+                    cases.push((utils::make_number(gc, numbers[loc] as f64), vec![]));
+                }
+                Item::Statement(stmt) => {
+                    let stmt =
+                        utils::changed_or(VisitorMut::call(&mut patcher, gc, stmt, None), stmt);
+                    cases.last_mut().unwrap().1.push(stmt);
+                }
+            }
+        }
+        // case "end": return _context.stop();
+        cases.push((
+            utils::make_string(gc, "end"),
+            vec![utils::make_return(
+                gc,
+                Some(self.make_context_call(gc, "stop", vec![])),
+            )],
+        ));
+
+        // while (1) switch (_context.prev = _context.next) { ... }
+        let cases = cases.into_iter().map(|(test, consequent)| {
+            builder::SwitchCase::build_template(
+                gc,
+                template::SwitchCase {
+                    metadata: Default::default(),
+                    test: Some(test),
+                    consequent: NodeList::from_iter(gc, consequent),
+                },
+            )
+        });
+        let switch = builder::SwitchStatement::build_template(
+            gc,
+            template::SwitchStatement {
+                metadata: Default::default(),
+                discriminant: utils::make_assignment(
+                    gc,
+                    self.make_context_member(gc, "prev"),
+                    self.make_context_member(gc, "next"),
+                ),
+                cases: NodeList::from_iter(gc, cases),
+            },
+        );
+        let machine = builder::WhileStatement::build_template(
+            gc,
+            template::WhileStatement {
+                metadata: Default::default(),
+                body: switch,
+                test: utils::make_number(gc, 1.0),
+            },
+        );
+        let machine = utils::make_function(
+            gc,
+            None,
+            vec![utils::make_identifier(gc, self.context)],
+            vec![machine],
+        );
+
+        if self.try_entries.is_empty() {
+            return (machine, None);
+        }
+        // [[tryLoc, catchLoc, finallyLoc, afterLoc], ...]
+        let number = |loc: Option<Loc>| match loc {
+            Some(loc) => utils::make_number(gc, numbers[&loc] as f64),
+            None => builder::NullLiteral::build_template(
+                gc,
+                template::NullLiteral {
+                    metadata: Default::default(),
+                },
+            ),
+        };
+        let try_locs = self
+            .try_entries
+            .iter()
+            .map(|entry| {
+                utils::make_array(
+                    gc,
+                    vec![
+                        number(Some(entry.try_loc)),
+                        number(entry.catch_loc),
+                        number(entry.finally_loc),
+                        number(Some(entry.after_loc)),
+                    ],
+                )
+            })
+            .collect();
+        (machine, Some(utils::make_array(gc, try_locs)))
+    }
+}
+
+/// Collects the children of a node.
+struct ChildCollector<'gc> {
+    children: Vec<&'gc Node<'gc>>,
+}
+
+impl<'gc> Visitor<'gc> for ChildCollector<'gc> {
+    fn call(&mut self, _gc: &'gc GCLock, node: &'gc Node<'gc>, _path: Option<Path<'gc>>) {
+        self.children.push(node);
+    }
+}
+
+/// Explodes the children of a node, in the same order as `ChildCollector`.
+struct ChildExploder<'e, 'n, 'gc> {
+    emitter: &'e mut Emitter<'n, 'gc>,
+    /// Whether the value of each child must be saved.
+    save: Vec<bool>,
+    index: usize,
+}
+
+impl<'gc> VisitorMut<'gc> for ChildExploder<'_, '_, 'gc> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let save = self.save[self.index];
+        self.index += 1;
+        let result = self.emitter.explode_child(gc, node, path, save);
+        if std::ptr::eq(result, node) {
+            TransformResult::Unchanged
+        } else {
+            TransformResult::Changed(result)
+        }
+    }
+}
+
+/// Statements inside a rewritten node which `break` and `continue` can jump to.
+#[derive(PartialEq)]
+enum LocalTarget {
+    Loop,
+    Switch,
+    Label(Atom),
+}
+
+/// Rewrites code which doesn't suspend to run inside the state machine.
+struct Rewriter<'e, 'n, 'gc> {
+    emitter: &'e mut Emitter<'n, 'gc>,
+    local_targets: Vec<LocalTarget>,
+    /// Number of arrow functions being visited.
+    arrows: usize,
+}
+
+impl<'gc> VisitorMut<'gc> for Rewriter<'_, '_, 'gc> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            // Functions and classes have their own `arguments`, and can't jump out.
+            Node::FunctionDeclaration(_) | Node::FunctionExpression(_) | Node::ClassBody(_) => {
+                TransformResult::Unchanged
+            }
+            Node::ArrowFunctionExpression(_) => {
+                self.arrows += 1;
+                let result = node.visit_children_mut(gc, self);
+                self.arrows -= 1;
+                result
+            }
+            Node::Identifier(Identifier { name, .. })
+                if gc.str(*name) == "arguments" && utils::is_reference(path) =>
+            {
+                let arguments = self.emitter.arguments_name(gc);
+                TransformResult::Changed(utils::make_identifier(gc, arguments))
+            }
+            Node::ReturnStatement(_) if self.arrows == 0 => {
+                let ret = utils::changed_or(node.visit_children_mut(gc, self), node);
+                let argument = node_cast!(Node::ReturnStatement, ret).argument;
+                TransformResult::Changed(self.emitter.make_abrupt(gc, "return", argument))
+            }
+            Node::BreakStatement(BreakStatement { label, .. }) if self.arrows == 0 => {
+                let label = label.map(|label| node_cast!(Node::Identifier, label).name);
+                let local = match label {
+                    Some(label) => self.local_targets.contains(&LocalTarget::Label(label)),
+                    None => self.local_targets.iter().any(|target| {
+                        *target == LocalTarget::Loop || *target == LocalTarget::Switch
+                    }),
+                };
+                if local {
+                    return TransformResult::Unchanged;
+                }
+                let loc = self.emitter.break_target(label);
+                let loc = self.emitter.make_loc(gc, loc);
+                TransformResult::Changed(self.emitter.make_abrupt(gc, "break", Some(loc)))
+            }
+            Node::ContinueStatement(ContinueStatement { label, .. }) if self.arrows == 0 => {
+                let label = label.map(|label| node_cast!(Node::Identifier, label).name);
+                let local = match label {
+                    Some(label) => self.local_targets.contains(&LocalTarget::Label(label)),
+                    None => self.local_targets.contains(&LocalTarget::Loop),
+                };
+                if local {
+                    return TransformResult::Unchanged;
+                }
+                let loc = self.emitter.continue_target(label);
+                let loc = self.emitter.make_loc(gc, loc);
+                TransformResult::Changed(self.emitter.make_abrupt(gc, "continue", Some(loc)))
+            }
+            Node::LabeledStatement(LabeledStatement {
+                label: Node::Identifier(Identifier { name, .. }),
+                ..
+            }) => self.visit_target(gc, node, LocalTarget::Label(*name)),
+            Node::SwitchStatement(_) => self.visit_target(gc, node, LocalTarget::Switch),
+            _ if is_loop(node) => self.visit_target(gc, node, LocalTarget::Loop),
+            Node::VariableDeclaration(VariableDeclaration {
+                kind: VariableDeclarationKind::Var,
+                declarations,
+                ..
+            }) if self.arrows == 0 => {
+                // var declarations are hoisted out of the state machine.
+                let mut assignments = vec![];
+                for declarator in declarations.iter() {
+                    let VariableDeclarator { id, init, .. } =
+                        node_cast!(Node::VariableDeclarator, declarator);
+                    self.emitter.hoist(id);
+                    if let Some(init) = init {
+                        let init = utils::changed_or(
+                            VisitorMut::call(
+                                self,
+                                gc,
+                                init,
+                                Some(Path::new(declarator, NodeField::init)),
+                            ),
+                            init,
+                        );
+                        assignments.push(utils::make_assignment(gc, id, init));
+                    }
+                }
+                match path {
+                    // for (var x in o) => for (x in o)
+                    Some(Path {
+                        parent: Node::ForInStatement(_) | Node::ForOfStatement(_),
+                        ..
+                    }) => {
+                        let declarator = declarations.head().unwrap();
+                        TransformResult::Changed(
+                            node_cast!(Node::VariableDeclarator, declarator).id,
+                        )
+                    }
+                    _ if assignments.is_empty() => TransformResult::Removed,
+                    Some(Path {
+                        parent: Node::ForStatement(_),
+                        ..
+                    }) => TransformResult::Changed(utils::make_sequence(gc, assignments)),
+                    _ => TransformResult::Changed(utils::make_expression_statement(
+                        gc,
+                        utils::make_sequence(gc, assignments),
+                    )),
+                }
+            }
+            _ => node.visit_children_mut(gc, self),
+        }
+    }
+}
+
+impl<'gc> Rewriter<'_, '_, 'gc> {
+    /// Visit the children of `node`, which `break` or `continue` can jump to as `target`.
+    fn visit_target(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        target: LocalTarget,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        self.local_targets.push(target);
+        let result = node.visit_children_mut(gc, self);
+        self.local_targets.pop();
+        result
+    }
+}
+
+/// Replaces the placeholders for location numbers.
+struct LocPatcher<'a, 'gc> {
+    placeholders: &'a HashMap<*const Node<'gc>, Loc>,
+    numbers: &'a HashMap<Loc, usize>,
+}
+
+impl<'gc> VisitorMut<'gc> for LocPatcher<'_, 'gc> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        _path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match self.placeholders.get(&(node as *const Node)) {
+            Some(loc) => TransformResult::Changed(utils::make_number(gc, self.numbers[loc] as f64)),
+            None => node.visit_children_mut(gc, self),
+        }
+    }
+}
+
+/// Finds the locations where the state machine may suspend.
+struct LeapFinder {
+    found: bool,
+}
+
+impl<'gc> Visitor<'gc> for LeapFinder {
+    fn call(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>, _path: Option<Path<'gc>>) {
+        match node {
+            _ if self.found => {}
+            Node::YieldExpression(_)
+            | Node::AwaitExpression(_)
+            | Node::ForOfStatement(ForOfStatement { is_await: true, .. }) => self.found = true,
+            Node::FunctionDeclaration(_)
+            | Node::FunctionExpression(_)
+            | Node::ArrowFunctionExpression(_)
+            | Node::ClassBody(_) => {}
+            _ => node.visit_children(gc, self),
+        }
+    }
+}
+
+/// Whether `node` contains a location where the state machine may suspend.
+fn contains_leap<'gc>(gc: &'gc GCLock, node: &'gc Node<'gc>) -> bool {
+    let mut finder = LeapFinder { found: false };
+    node.visit(gc, &mut finder, None);
+    finder.found
+}
+
+/// Finds destructuring patterns which contain a location where the state machine may suspend.
+struct PatternLeapFinder {
+    found: bool,
+}
+
+impl<'gc> Visitor<'gc> for PatternLeapFinder {
+    fn call(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>, _path: Option<Path<'gc>>) {
+        match node {
+            _ if self.found => {}
+            Node::ObjectPattern(_) | Node::ArrayPattern(_) => self.found = contains_leap(gc, node),
+            Node::FunctionDeclaration(_)
+            | Node::FunctionExpression(_)
+            | Node::ArrowFunctionExpression(_)
+            | Node::ClassBody(_) => {}
+            _ => node.visit_children(gc, self),
+        }
+    }
+}
+
+/// Whether `node` contains a destructuring pattern which may suspend.
+fn has_pattern_leap<'gc>(gc: &'gc GCLock, node: &'gc Node<'gc>) -> bool {
+    let mut finder = PatternLeapFinder { found: false };
+    node.visit(gc, &mut finder, None);
+    finder.found
+}
+
+/// Whether `node` is a function which is lowered to a state machine.
+fn is_lowered(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::FunctionDeclaration(FunctionDeclaration {
+            generator: true,
+            ..
+        }) | Node::FunctionDeclaration(FunctionDeclaration { is_async: true, .. })
+            | Node::FunctionExpression(FunctionExpression {
+                generator: true,
+                ..
+            })
+            | Node::FunctionExpression(FunctionExpression { is_async: true, .. })
+            | Node::ArrowFunctionExpression(ArrowFunctionExpression { is_async: true, .. })
+    )
+}
+
+fn is_loop(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::ForStatement(_)
+            | Node::ForInStatement(_)
+            | Node::ForOfStatement(_)
+            | Node::WhileStatement(_)
+            | Node::DoWhileStatement(_)
+    )
+}
+
+fn make_declarator<'gc>(
+    gc: &'gc GCLock,
+    name: Atom,
+    init: Option<&'gc Node<'gc>>,
+) -> &'gc Node<'gc> {
+    builder::VariableDeclarator::build_template(
+        gc,
+        template::VariableDeclarator {
+            metadata: Default::default(),
+            id: utils::make_identifier(gc, name),
+            init,
+        },
+    )
+}
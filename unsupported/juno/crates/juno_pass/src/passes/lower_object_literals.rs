@@ -0,0 +1,172 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower the ES2015 object literal extensions.
+//!
+//! Shorthand properties `{a}` become `{a: a}` and methods `{f() {}}` become
+//! `{f: function() {}}`.
+//! Starting with the first computed key, the properties are assigned to a temporary in order:
+//! `{a: 1, [b]: 2, c: 3}` becomes `(_obj = {a: 1}, _obj[b] = 2, _obj.c = 3, _obj)`,
+//! with accessors defined by `Object.defineProperty`.
+
+use crate::utils::{self, NameGenerator, TempVars};
+use crate::Pass;
+use juno::ast::*;
+
+#[derive(Default)]
+pub struct LowerObjectLiterals {}
+
+impl LowerObjectLiterals {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for LowerObjectLiterals {
+    fn name(&self) -> &'static str {
+        "Lower Object Literals"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers shorthand properties, methods and computed keys"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let mut lowering = Lowering {
+            names: NameGenerator::new(gc, node),
+            temps: Default::default(),
+        };
+        VisitorMut::call(&mut lowering, gc, node, None)
+    }
+}
+
+struct Lowering {
+    names: NameGenerator,
+    temps: TempVars,
+}
+
+impl<'gc> VisitorMut<'gc> for Lowering {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            _ if TempVars::is_scope(node) => {
+                return TempVars::visit_scope(self, |s| &mut s.temps, gc, node);
+            }
+            Node::Property(prop) if prop.shorthand || prop.method => {
+                let mut builder = builder::Property::from_node(prop);
+                builder.shorthand(false);
+                builder.method(false);
+                return node.replace_with_new(builder::Builder::Property(builder), gc, self);
+            }
+            Node::ObjectExpression(ObjectExpression {
+                metadata,
+                properties,
+            }) => {
+                let split = properties
+                    .iter()
+                    .position(|p| matches!(p, Node::Property(Property { computed: true, .. })));
+                if let Some(split) = split {
+                    let obj = self.temps.add(gc, &mut self.names, "obj");
+                    let temp = || utils::make_identifier(gc, obj);
+                    let mut exprs = vec![utils::make_assignment(
+                        gc,
+                        temp(),
+                        builder::ObjectExpression::build_template(
+                            gc,
+                            template::ObjectExpression {
+                                metadata: Default::default(),
+                                properties: NodeList::from_iter(gc, properties.iter().take(split)),
+                            },
+                        ),
+                    )];
+                    for prop in properties.iter().skip(split) {
+                        exprs.push(assign_property(gc, temp(), prop));
+                    }
+                    exprs.push(temp());
+                    let result = builder::SequenceExpression::build_template(
+                        gc,
+                        template::SequenceExpression {
+                            metadata: TemplateMetadata {
+                                range: metadata.range,
+                                comments: metadata.comments,
+                                ..Default::default()
+                            },
+                            expressions: NodeList::from_iter(gc, exprs),
+                        },
+                    );
+                    return node.replace_with_existing(result, gc, self);
+                }
+            }
+            _ => {}
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+/// Build the expression which adds `prop` to the object `obj`.
+fn assign_property<'gc>(
+    gc: &'gc GCLock,
+    obj: &'gc Node<'gc>,
+    prop: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    let Property {
+        key,
+        value,
+        kind,
+        computed,
+        ..
+    } = match prop {
+        Node::Property(prop) => prop,
+        Node::SpreadElement(SpreadElement { argument, .. }) => {
+            return utils::make_call(
+                gc,
+                utils::make_member(gc, utils::make_global(gc, "Object"), "assign"),
+                vec![obj, argument],
+            );
+        }
+        _ => unreachable!("invalid object property"),
+    };
+    let key = match (key, computed) {
+        (Node::Identifier(id), false) => utils::make_string(gc, gc.str(id.name)),
+        _ => key,
+    };
+    match kind {
+        PropertyKind::Init => {
+            utils::make_assignment(gc, utils::make_computed_member(gc, obj, key), value)
+        }
+        PropertyKind::Get | PropertyKind::Set => {
+            // Object.defineProperty(obj, key, {get: value, configurable: true, enumerable: true})
+            let descriptor = utils::make_object(
+                gc,
+                vec![
+                    (
+                        if *kind == PropertyKind::Get {
+                            "get"
+                        } else {
+                            "set"
+                        },
+                        value,
+                    ),
+                    ("configurable", utils::make_boolean(gc, true)),
+                    ("enumerable", utils::make_boolean(gc, true)),
+                ],
+            );
+            utils::make_call(
+                gc,
+                utils::make_member(gc, utils::make_global(gc, "Object"), "defineProperty"),
+                vec![obj, key, descriptor],
+            )
+        }
+    }
+}
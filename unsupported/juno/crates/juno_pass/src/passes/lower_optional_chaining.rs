@@ -0,0 +1,319 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower optional chaining and nullish coalescing to conditionals.
+//!
+//! `a?.b.c()` becomes `a == null ? void 0 : a.b.c()`,
+//! `o.m?.(x)` becomes `(_ref = o.m) == null ? void 0 : _ref.call(o, x)`
+//! and `x ?? y` becomes `x != null ? x : y`.
+//! Operands which are checked and then used again are stored in temporaries, unless they're
+//! declared variables, so each of them is evaluated once.
+//! The temporaries are declared with `var` in the enclosing function, with names which don't
+//! clash with the bindings visible in it.
+
+use crate::utils::{self, ScopedNameGenerator, TempVars};
+use crate::Pass;
+use juno::ast::*;
+use juno::sema::{DeclKind, Resolution, SemContext};
+
+#[derive(Default)]
+pub struct LowerOptionalChaining {}
+
+impl LowerOptionalChaining {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for LowerOptionalChaining {
+    fn name(&self) -> &'static str {
+        "Lower Optional Chaining"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers optional chaining and nullish coalescing to conditionals"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let sem = utils::resolve(gc, node);
+        let mut lowering = Lowering {
+            sem: &sem,
+            names: ScopedNameGenerator::new(gc, &sem),
+            temps: Default::default(),
+        };
+        VisitorMut::call(&mut lowering, gc, node, None)
+    }
+}
+
+/// A member access or call in an optional chain.
+struct Link<'gc> {
+    node: &'gc Node<'gc>,
+    /// Whether the chain stops here if the value so far is `null` or `undefined`.
+    optional: bool,
+}
+
+struct Lowering<'s> {
+    sem: &'s SemContext,
+    names: ScopedNameGenerator<'s>,
+    temps: TempVars,
+}
+
+impl<'gc> VisitorMut<'gc> for Lowering<'_> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            _ if TempVars::is_scope(node) => {
+                self.names.enter_function(gc, node);
+                let result = TempVars::visit_scope(self, |s| &mut s.temps, gc, node);
+                self.names.exit_function();
+                return result;
+            }
+            // The chain is lowered from its outermost node, which contains all of its links.
+            Node::OptionalMemberExpression(_) | Node::OptionalCallExpression(_) => {
+                let lowered = self.lower_chain(gc, node, None);
+                return node.replace_with_existing(lowered, gc, self);
+            }
+            // delete a?.b => a == null ? true : delete a.b
+            Node::UnaryExpression(UnaryExpression {
+                operator: UnaryExpressionOperator::Delete,
+                argument: argument @ Node::OptionalMemberExpression(_),
+                ..
+            }) => {
+                let lowered = self.lower_chain(gc, argument, Some(node));
+                return node.replace_with_existing(lowered, gc, self);
+            }
+            Node::LogicalExpression(LogicalExpression {
+                metadata,
+                operator: LogicalExpressionOperator::NullishCoalesce,
+                left,
+                right,
+            }) => {
+                // x ?? y => (_ref = x) != null ? _ref : y
+                let (value, value_init) = self.reuse(gc, left);
+                let conditional = builder::ConditionalExpression::build_template(
+                    gc,
+                    template::ConditionalExpression {
+                        metadata: TemplateMetadata {
+                            range: metadata.range,
+                            comments: metadata.comments,
+                            ..Default::default()
+                        },
+                        test: make_null_check(
+                            gc,
+                            BinaryExpressionOperator::LooseNotEquals,
+                            value_init,
+                        ),
+                        alternate: right,
+                        consequent: value,
+                    },
+                );
+                return node.replace_with_existing(conditional, gc, self);
+            }
+            _ => {}
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+impl Lowering<'_> {
+    /// Return the expressions to use for the second and first evaluation of `node`, like
+    /// `TempVars::reuse`, but storing undeclared globals in a temporary as well, since reading
+    /// them may run a getter.
+    fn reuse<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+    ) -> (&'gc Node<'gc>, &'gc Node<'gc>) {
+        if let Node::Identifier(_) = node {
+            // Identifiers without a resolution are the temporaries added by this pass.
+            let global = match self.sem.ident_decl(&NodeRc::from_node(gc, node)) {
+                Some(Resolution::Decl(decl)) => {
+                    self.sem.decl(decl).kind == DeclKind::UndeclaredGlobalProperty
+                }
+                Some(Resolution::Unresolvable) => true,
+                None => false,
+            };
+            if global {
+                let temp = utils::make_identifier(gc, self.temps.add(gc, &mut self.names, "ref"));
+                return (temp, utils::make_assignment(gc, temp, node));
+            }
+        }
+        self.temps.reuse(gc, &mut self.names, node, "ref")
+    }
+
+    /// Lower the optional chain whose outermost node is `chain`.
+    /// If `delete` is the `delete` expression applied to the chain, lower it too.
+    fn lower_chain<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        chain: &'gc Node<'gc>,
+        delete: Option<&'gc Node<'gc>>,
+    ) -> &'gc Node<'gc> {
+        // Collect the links from the innermost to the outermost.
+        let mut links = vec![];
+        let mut base = chain;
+        loop {
+            match base {
+                Node::OptionalMemberExpression(OptionalMemberExpression {
+                    object,
+                    optional,
+                    ..
+                }) => {
+                    links.push(Link {
+                        node: base,
+                        optional: *optional,
+                    });
+                    base = object;
+                }
+                Node::OptionalCallExpression(OptionalCallExpression {
+                    callee, optional, ..
+                }) => {
+                    links.push(Link {
+                        node: base,
+                        optional: *optional,
+                    });
+                    base = callee;
+                }
+                _ => break,
+            }
+        }
+        links.reverse();
+
+        // Null checks of the values which end the chain, joined with `||`.
+        let mut checks: Option<&Node> = None;
+        let mut check = |this: &mut Self, value: &'gc Node<'gc>| {
+            let (value, value_init) = this.reuse(gc, value);
+            let test = make_null_check(gc, BinaryExpressionOperator::LooseEquals, value_init);
+            checks = Some(match checks {
+                Some(checks) => make_or(gc, checks, test),
+                None => test,
+            });
+            value
+        };
+
+        // The object a member access was applied to, if the member is called by a later link.
+        let mut this_value = None;
+        let mut value = base;
+        if let (Node::MemberExpression(member), Some(first)) = (base, links.first()) {
+            if first.optional && matches!(first.node, Node::OptionalCallExpression(_)) {
+                let (object, object_init) = self.reuse_object(gc, member.object);
+                value = make_member(gc, object_init, member.property, member.computed);
+                this_value = Some(object);
+            }
+        }
+        for (i, link) in links.iter().enumerate() {
+            if link.optional {
+                value = check(self, value);
+            }
+            match link.node {
+                Node::OptionalMemberExpression(member) => {
+                    let called_later = links.get(i + 1).is_some_and(|next| {
+                        next.optional && matches!(next.node, Node::OptionalCallExpression(_))
+                    });
+                    this_value = None;
+                    let object = if called_later {
+                        let (object, object_init) = self.reuse_object(gc, value);
+                        this_value = Some(object);
+                        object_init
+                    } else {
+                        value
+                    };
+                    value = make_member(gc, object, member.property, member.computed);
+                }
+                Node::OptionalCallExpression(call) => {
+                    value = match this_value.take() {
+                        // The callee was stored in a temporary, so call it with its object.
+                        Some(object) if link.optional => {
+                            let mut arguments = vec![object];
+                            arguments.extend(call.arguments.iter());
+                            utils::make_call(gc, utils::make_member(gc, value, "call"), arguments)
+                        }
+                        _ => utils::make_call(gc, value, call.arguments.iter().collect()),
+                    };
+                }
+                _ => unreachable!("only optional nodes are links"),
+            }
+        }
+
+        let (value, short_circuit) = match delete {
+            Some(Node::UnaryExpression(unary)) => {
+                let mut builder = builder::UnaryExpression::from_node(unary);
+                builder.argument(value);
+                (builder.build_forced(gc), utils::make_boolean(gc, true))
+            }
+            _ => (value, utils::make_undefined(gc)),
+        };
+        match checks {
+            Some(checks) => utils::make_conditional(gc, checks, short_circuit, value),
+            None => value,
+        }
+    }
+
+    /// Like `reuse`, for the object of a method which is called, which must be passed as `this`.
+    fn reuse_object<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        object: &'gc Node<'gc>,
+    ) -> (&'gc Node<'gc>, &'gc Node<'gc>) {
+        match object {
+            // super.m?.() => super.m == null ? void 0 : super.m.call(this)
+            Node::Super(_) => (utils::make_this(gc), object),
+            _ => self.reuse(gc, object),
+        }
+    }
+}
+
+fn make_member<'gc>(
+    gc: &'gc GCLock,
+    object: &'gc Node<'gc>,
+    property: &'gc Node<'gc>,
+    computed: bool,
+) -> &'gc Node<'gc> {
+    builder::MemberExpression::build_template(
+        gc,
+        template::MemberExpression {
+            metadata: Default::default(),
+            object,
+            property,
+            computed,
+        },
+    )
+}
+
+/// Build `value == null` or `value != null`.
+fn make_null_check<'gc>(
+    gc: &'gc GCLock,
+    operator: BinaryExpressionOperator,
+    value: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    let null = builder::NullLiteral::build_template(
+        gc,
+        template::NullLiteral {
+            metadata: Default::default(),
+        },
+    );
+    utils::make_binary(gc, operator, value, null)
+}
+
+fn make_or<'gc>(gc: &'gc GCLock, left: &'gc Node<'gc>, right: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    builder::LogicalExpression::build_template(
+        gc,
+        template::LogicalExpression {
+            metadata: Default::default(),
+            left,
+            right,
+            operator: LogicalExpressionOperator::Or,
+        },
+    )
+}
@@ -0,0 +1,287 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower default, rest and pattern parameters, and spread arguments and elements.
+//!
+//! `function f(a = 1, {b}, ...c) {}` becomes
+//! `function f(a, _ref) { if (a === void 0) a = 1; var {b} = _ref;
+//! var c = Array.prototype.slice.call(arguments, 2); }`,
+//! leaving the pattern to be lowered by the destructuring pass.
+//! Spread arguments are passed with `apply`: `o.f(a, ...b)` becomes
+//! `o.f.apply(o, [a].concat(Array.prototype.slice.call(b)))`.
+//! Rest parameters of arrow functions are kept, since arrows have no `arguments`.
+
+use crate::utils::{self, NameGenerator, TempVars};
+use crate::Pass;
+use juno::ast::*;
+
+#[derive(Default)]
+pub struct LowerParameters {}
+
+impl LowerParameters {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for LowerParameters {
+    fn name(&self) -> &'static str {
+        "Lower Parameters"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers default, rest and pattern parameters and spread arguments"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let mut lowering = Lowering {
+            names: NameGenerator::new(gc, node),
+            temps: Default::default(),
+        };
+        VisitorMut::call(&mut lowering, gc, node, None)
+    }
+}
+
+struct Lowering {
+    names: NameGenerator,
+    temps: TempVars,
+}
+
+impl<'gc> VisitorMut<'gc> for Lowering {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            _ if TempVars::is_scope(node) => {
+                let lowered = self.lower_params(gc, node);
+                let func = lowered.unwrap_or(node);
+                let result = TempVars::visit_scope(self, |s| &mut s.temps, gc, func);
+                if lowered.is_some() {
+                    return TransformResult::Changed(utils::changed_or(result, func));
+                }
+                return result;
+            }
+            Node::CallExpression(CallExpression {
+                metadata,
+                callee,
+                arguments,
+                ..
+            }) if has_spread(arguments) && !matches!(callee, Node::Super(_)) => {
+                let (func, this) = match callee {
+                    Node::MemberExpression(member) if !matches!(member.object, Node::Super(_)) => {
+                        let (object, object_init) =
+                            self.temps.reuse(gc, &mut self.names, member.object, "obj");
+                        let mut builder = builder::MemberExpression::from_node(member);
+                        builder.object(object_init);
+                        (builder.build_forced(gc), object)
+                    }
+                    _ => (*callee, utils::make_undefined(gc)),
+                };
+                let call = builder::CallExpression::build_template(
+                    gc,
+                    template::CallExpression {
+                        metadata: TemplateMetadata {
+                            range: metadata.range,
+                            comments: metadata.comments,
+                            ..Default::default()
+                        },
+                        callee: utils::make_member(gc, func, "apply"),
+                        type_arguments: None,
+                        arguments: NodeList::from_iter(
+                            gc,
+                            [this, make_spread_array(gc, arguments.iter().collect())],
+                        ),
+                    },
+                );
+                return node.replace_with_existing(call, gc, self);
+            }
+            Node::NewExpression(NewExpression {
+                metadata,
+                callee,
+                arguments,
+                ..
+            }) if has_spread(arguments) => {
+                // new (Function.prototype.bind.apply(F, [null].concat(args)))()
+                let mut elements = vec![builder::NullLiteral::build_template(
+                    gc,
+                    template::NullLiteral {
+                        metadata: Default::default(),
+                    },
+                )];
+                elements.extend(arguments.iter());
+                let bind = utils::make_member(
+                    gc,
+                    utils::make_member(gc, utils::make_global(gc, "Function"), "prototype"),
+                    "bind",
+                );
+                let new = builder::NewExpression::build_template(
+                    gc,
+                    template::NewExpression {
+                        metadata: TemplateMetadata {
+                            range: metadata.range,
+                            comments: metadata.comments,
+                            ..Default::default()
+                        },
+                        callee: utils::make_call(
+                            gc,
+                            utils::make_member(gc, bind, "apply"),
+                            vec![callee, make_spread_array(gc, elements)],
+                        ),
+                        type_arguments: None,
+                        arguments: NodeList::new(gc),
+                    },
+                );
+                return node.replace_with_existing(new, gc, self);
+            }
+            Node::ArrayExpression(ArrayExpression { elements, .. }) if has_spread(elements) => {
+                let array = make_spread_array(gc, elements.iter().collect());
+                return node.replace_with_existing(array, gc, self);
+            }
+            _ => {}
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+impl Lowering {
+    /// Replace the parameters of the function `node` which aren't plain identifiers,
+    /// returning the new function if there are any.
+    fn lower_params<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+    ) -> Option<&'gc Node<'gc>> {
+        let (params, is_arrow) = match node {
+            Node::FunctionDeclaration(FunctionDeclaration { params, .. })
+            | Node::FunctionExpression(FunctionExpression { params, .. }) => (params, false),
+            Node::ArrowFunctionExpression(ArrowFunctionExpression { params, .. }) => (params, true),
+            _ => return None,
+        };
+        if params.iter().all(|param| {
+            matches!(param, Node::Identifier(_))
+                || (is_arrow && matches!(param, Node::RestElement(_)))
+        }) {
+            return None;
+        }
+
+        let mut new_params = vec![];
+        let mut stmts = vec![];
+        for (i, param) in params.iter().enumerate() {
+            match param {
+                Node::Identifier(_) => new_params.push(param),
+                Node::AssignmentPattern(AssignmentPattern {
+                    left: left @ Node::Identifier(Identifier { name, .. }),
+                    right,
+                    ..
+                }) => {
+                    // if (a === void 0) a = default;
+                    new_params.push(left);
+                    stmts.push(builder::IfStatement::build_template(
+                        gc,
+                        template::IfStatement {
+                            metadata: Default::default(),
+                            test: utils::make_is_undefined(gc, utils::make_identifier(gc, *name)),
+                            consequent: utils::make_expression_statement(
+                                gc,
+                                utils::make_assignment(
+                                    gc,
+                                    utils::make_identifier(gc, *name),
+                                    right,
+                                ),
+                            ),
+                            alternate: None,
+                        },
+                    ));
+                }
+                Node::AssignmentPattern(AssignmentPattern { left, right, .. }) => {
+                    // var pattern = _ref === void 0 ? default : _ref;
+                    let temp = self.names.fresh(gc, "ref");
+                    new_params.push(utils::make_identifier(gc, temp));
+                    let value = utils::make_conditional(
+                        gc,
+                        utils::make_is_undefined(gc, utils::make_identifier(gc, temp)),
+                        right,
+                        utils::make_identifier(gc, temp),
+                    );
+                    stmts.push(utils::make_var(gc, left, Some(value)));
+                }
+                Node::RestElement(_) if is_arrow => new_params.push(param),
+                Node::RestElement(RestElement { argument, .. }) => {
+                    let rest = utils::make_array_slice(gc, utils::make_global(gc, "arguments"), i);
+                    stmts.push(utils::make_var(gc, argument, Some(rest)));
+                }
+                _ => {
+                    let temp = self.names.fresh(gc, "ref");
+                    new_params.push(utils::make_identifier(gc, temp));
+                    stmts.push(utils::make_var(
+                        gc,
+                        param,
+                        Some(utils::make_identifier(gc, temp)),
+                    ));
+                }
+            }
+        }
+
+        let params = NodeList::from_iter(gc, new_params);
+        let func = match node {
+            Node::FunctionDeclaration(n) => {
+                let mut builder = builder::FunctionDeclaration::from_node(n);
+                builder.params(params);
+                builder.build_forced(gc)
+            }
+            Node::FunctionExpression(n) => {
+                let mut builder = builder::FunctionExpression::from_node(n);
+                builder.params(params);
+                builder.build_forced(gc)
+            }
+            Node::ArrowFunctionExpression(n) => {
+                let mut builder = builder::ArrowFunctionExpression::from_node(n);
+                builder.params(params);
+                builder.build_forced(gc)
+            }
+            _ => unreachable!(),
+        };
+        Some(utils::prepend_statements(gc, func, stmts))
+    }
+}
+
+fn has_spread(list: &NodeList) -> bool {
+    list.iter().any(|n| matches!(n, Node::SpreadElement(_)))
+}
+
+/// Build an array of `elements`, where spread elements are flattened with `concat`:
+/// `[a, ...b]` becomes `[a].concat(Array.prototype.slice.call(b))`.
+fn make_spread_array<'gc>(gc: &'gc GCLock, elements: Vec<&'gc Node<'gc>>) -> &'gc Node<'gc> {
+    let mut segments = vec![];
+    let mut current = vec![];
+    for elem in elements {
+        match elem {
+            Node::SpreadElement(SpreadElement { argument, .. }) => {
+                if !current.is_empty() {
+                    segments.push(utils::make_array(gc, std::mem::take(&mut current)));
+                }
+                segments.push(utils::make_array_slice(gc, argument, 0));
+            }
+            _ => current.push(elem),
+        }
+    }
+    if !current.is_empty() || segments.is_empty() {
+        segments.push(utils::make_array(gc, current));
+    }
+    let first = segments.remove(0);
+    if segments.is_empty() {
+        first
+    } else {
+        utils::make_call(gc, utils::make_member(gc, first, "concat"), segments)
+    }
+}
@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Lower template literals to string concatenation.
+//!
+//! `` `a${b}c` `` becomes `"a" + b + "c"`.
+//! Tagged templates pass the tag a frozen array of the cooked strings,
+//! with the raw strings in its `raw` property, followed by the substitutions.
+
+use crate::utils;
+use crate::Pass;
+use juno::ast::*;
+
+#[derive(Default)]
+pub struct LowerTemplateLiterals {}
+
+impl LowerTemplateLiterals {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for LowerTemplateLiterals {
+    fn name(&self) -> &'static str {
+        "Lower Template Literals"
+    }
+    fn description(&self) -> &'static str {
+        "Lowers template literals to string concatenation"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        VisitorMut::call(self, gc, node, None)
+    }
+}
+
+impl<'gc> VisitorMut<'gc> for LowerTemplateLiterals {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            Node::TemplateLiteral(TemplateLiteral {
+                quasis,
+                expressions,
+                ..
+            }) => {
+                let metadata = TemplateMetadata {
+                    range: *node.range(),
+                    comments: node.comments(),
+                    ..Default::default()
+                };
+                let mut parts = vec![];
+                for (i, quasi) in quasis.iter().enumerate() {
+                    let cooked = match quasi {
+                        Node::TemplateElement(TemplateElement {
+                            cooked: Some(cooked),
+                            ..
+                        }) => *cooked,
+                        _ => unreachable!("untagged template must have cooked strings"),
+                    };
+                    // The first string is always kept so that the result is a string.
+                    if i == 0 || !gc.str_u16(cooked).is_empty() {
+                        parts.push(make_string_u16(gc, Default::default(), cooked));
+                    }
+                    if let Some(expr) = expressions.iter().nth(i) {
+                        parts.push(expr);
+                    }
+                }
+                let result = if parts.len() == 1 {
+                    let first = node_cast!(Node::TemplateElement, quasis.head().unwrap());
+                    make_string_u16(gc, metadata, first.cooked.unwrap())
+                } else {
+                    let last = parts.pop().unwrap();
+                    let mut parts = parts.into_iter();
+                    let first = parts.next().unwrap();
+                    let left = parts.fold(first, |left, right| {
+                        utils::make_binary(gc, BinaryExpressionOperator::Plus, left, right)
+                    });
+                    builder::BinaryExpression::build_template(
+                        gc,
+                        template::BinaryExpression {
+                            metadata,
+                            operator: BinaryExpressionOperator::Plus,
+                            left,
+                            right: last,
+                        },
+                    )
+                };
+                return node.replace_with_existing(result, gc, self);
+            }
+            Node::TaggedTemplateExpression(TaggedTemplateExpression {
+                tag,
+                quasi:
+                    Node::TemplateLiteral(TemplateLiteral {
+                        quasis,
+                        expressions,
+                        ..
+                    }),
+                ..
+            }) => {
+                let mut cooked = vec![];
+                let mut raw = vec![];
+                for quasi in quasis.iter() {
+                    let elem = node_cast!(Node::TemplateElement, quasi);
+                    // Invalid escapes are allowed in tagged templates, and are undefined.
+                    cooked.push(match elem.cooked {
+                        Some(value) => make_string_u16(gc, Default::default(), value),
+                        None => utils::make_undefined(gc),
+                    });
+                    raw.push(utils::make_string(gc, gc.str(elem.raw)));
+                }
+                let strings = make_frozen(gc, utils::make_array(gc, cooked));
+                let raw = make_frozen(gc, utils::make_array(gc, raw));
+                // Object.defineProperties(strings, {raw: {value: raw}})
+                let strings = utils::make_call(
+                    gc,
+                    utils::make_member(gc, utils::make_global(gc, "Object"), "defineProperties"),
+                    vec![
+                        strings,
+                        utils::make_object(
+                            gc,
+                            vec![("raw", utils::make_object(gc, vec![("value", raw)]))],
+                        ),
+                    ],
+                );
+                let mut arguments = vec![strings];
+                arguments.extend(expressions.iter());
+                let call = builder::CallExpression::build_template(
+                    gc,
+                    template::CallExpression {
+                        metadata: TemplateMetadata {
+                            range: *node.range(),
+                            comments: node.comments(),
+                            ..Default::default()
+                        },
+                        callee: tag,
+                        type_arguments: None,
+                        arguments: NodeList::from_iter(gc, arguments),
+                    },
+                );
+                return node.replace_with_existing(call, gc, self);
+            }
+            _ => {}
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+fn make_string_u16<'gc>(
+    gc: &'gc GCLock,
+    metadata: TemplateMetadata<'gc>,
+    value: NodeString,
+) -> &'gc Node<'gc> {
+    builder::StringLiteral::build_template(gc, template::StringLiteral { metadata, value })
+}
+
+/// Build `Object.freeze(value)`.
+fn make_frozen<'gc>(gc: &'gc GCLock, value: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    utils::make_call(
+        gc,
+        utils::make_member(gc, utils::make_global(gc, "Object"), "freeze"),
+        vec![value],
+    )
+}
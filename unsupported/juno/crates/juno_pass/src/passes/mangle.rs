@@ -0,0 +1,272 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Pass for renaming local variables to the shortest available names.
+//!
+//! For example, transforms
+//! ```js
+//! function foo(first, second) {
+//!   var result = first + second;
+//!   return result * result;
+//! }
+//! ```
+//! into
+//! ```js
+//! function foo(b, c) {
+//!   var a = b + c;
+//!   return a * a;
+//! }
+//! ```
+//!
+//! Globals, `arguments`, declarations visible to a local `eval()` and exported declarations
+//! are never renamed. Within every scope, the most frequently referenced declarations are
+//! assigned the shortest names.
+
+use crate::Pass;
+use crate::utils;
+use juno::ast::*;
+use juno::sema::{DeclId, Resolution, SemContext};
+use juno_support::atom_table::Atom;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Default)]
+pub struct Mangle {}
+
+impl Mangle {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for Mangle {
+    fn name(&self) -> &'static str {
+        "Mangle"
+    }
+    fn description(&self) -> &'static str {
+        "Renames local variables to short names"
+    }
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let sem = utils::resolve(gc, node);
+        let names = assign_names(gc, &sem, node);
+        if names.is_empty() {
+            return TransformResult::Unchanged;
+        }
+        VisitorMut::call(&mut utils::Renamer { sem: &sem, names }, gc, node, None)
+    }
+}
+
+/// Characters which may start a generated name.
+const FIRST_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_$";
+/// Characters which may follow the first character of a generated name.
+const REST_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ_$0123456789";
+
+/// Names which can't be used as identifiers, or have special meaning when used as one.
+const RESERVED_NAMES: &[&str] = &[
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Return the name with the specified index in the sequence of all generated names,
+/// which is ordered by length.
+fn generated_name(mut index: usize) -> String {
+    let mut result = String::new();
+    result.push(FIRST_CHARS[index % FIRST_CHARS.len()] as char);
+    index /= FIRST_CHARS.len();
+    while index > 0 {
+        index -= 1;
+        result.push(REST_CHARS[index % REST_CHARS.len()] as char);
+        index /= REST_CHARS.len();
+    }
+    result
+}
+
+/// Compute the new names of all declarations which can be renamed.
+fn assign_names<'gc>(
+    gc: &'gc GCLock,
+    sem: &SemContext,
+    root: &'gc Node<'gc>,
+) -> HashMap<DeclId, Atom> {
+    // Names which must never be assigned, because a reference to them which isn't
+    // renamed could be captured.
+    let mut reserved: HashSet<Atom> = RESERVED_NAMES.iter().map(|name| gc.atom(*name)).collect();
+
+    // Identifiers which couldn't be resolved may refer to any declaration with their name.
+    let mut unresolved = UnresolvedNames::default();
+    root.visit(gc, &mut unresolved, None);
+    let mut counts: HashMap<DeclId, usize> = HashMap::new();
+    for (ident, resolution) in sem.all_ident_decls() {
+        match resolution {
+            Resolution::Unresolvable => {
+                unresolved
+                    .names
+                    .insert(node_cast!(Node::Identifier, ident.node(gc)).name);
+            }
+            Resolution::Decl(decl) => *counts.entry(*decl).or_default() += 1,
+        }
+    }
+
+    let exported: HashSet<DeclId> = unresolved
+        .exported
+        .iter()
+        .filter_map(
+            |ident| match sem.ident_decl(&NodeRc::from_node(gc, ident)) {
+                Some(Resolution::Decl(decl)) => Some(decl),
+                _ => None,
+            },
+        )
+        .collect();
+
+    let can_rename = |id: DeclId| {
+        let decl = sem.decl(id);
+        decl.can_rename
+            && !sem.scope(decl.scope).local_eval
+            && !unresolved.names.contains(&decl.name)
+            && !exported.contains(&id)
+    };
+    for scope in sem.all_scopes() {
+        for &decl in &scope.decls {
+            if !can_rename(decl) {
+                reserved.insert(sem.decl(decl).name);
+            }
+        }
+    }
+    reserved.extend(unresolved.names.iter().copied());
+
+    let mut names: HashMap<DeclId, Atom> = HashMap::new();
+    // Parent scopes are always created before their children,
+    // so their names have been assigned by the time the children are visited.
+    for scope in sem.all_scopes() {
+        // Names visible in this scope, which can't be reused without shadowing them.
+        let mut taken: HashSet<Atom> = HashSet::new();
+        let mut ancestor = scope.parent_scope;
+        while let Some(id) = ancestor {
+            let ancestor_scope = sem.scope(id);
+            for &decl in &ancestor_scope.decls {
+                taken.insert(*names.get(&decl).unwrap_or(&sem.decl(decl).name));
+            }
+            ancestor = ancestor_scope.parent_scope;
+        }
+
+        let mut decls: Vec<DeclId> = scope
+            .decls
+            .iter()
+            .copied()
+            .filter(|&decl| can_rename(decl))
+            .collect();
+        for &decl in &scope.decls {
+            if !can_rename(decl) {
+                taken.insert(sem.decl(decl).name);
+            }
+        }
+        // Assign the shortest names to the most referenced declarations.
+        // The sort is stable, so ties keep declaration order.
+        decls.sort_by_key(|decl| std::cmp::Reverse(counts.get(decl).copied().unwrap_or(0)));
+        let mut next_index = 0;
+        for decl in decls {
+            let name = loop {
+                let name = gc.atom(generated_name(next_index));
+                next_index += 1;
+                if !reserved.contains(&name) && !taken.contains(&name) {
+                    break name;
+                }
+            };
+            taken.insert(name);
+            names.insert(decl, name);
+        }
+    }
+    names
+}
+
+/// Collects the names which are referenced without resolution information,
+/// and the declarations which are part of the module interface.
+#[derive(Default)]
+struct UnresolvedNames<'gc> {
+    /// Names which may refer to a declaration without being resolved to it.
+    names: HashSet<Atom>,
+    /// Identifiers declared by `export` declarations.
+    exported: Vec<&'gc Node<'gc>>,
+}
+
+impl<'gc> Visitor<'gc> for UnresolvedNames<'gc> {
+    fn call(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>, _path: Option<Path<'gc>>) {
+        match node {
+            // JSX element names are references which aren't resolved.
+            Node::JSXIdentifier(JSXIdentifier { name, .. }) => {
+                self.names.insert(*name);
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                declaration: Some(declaration),
+                ..
+            }) => match declaration {
+                Node::FunctionDeclaration(FunctionDeclaration { id: Some(id), .. })
+                | Node::ClassDeclaration(ClassDeclaration { id: Some(id), .. }) => {
+                    self.exported.push(id)
+                }
+                Node::VariableDeclaration(VariableDeclaration { declarations, .. }) => {
+                    for declarator in declarations.iter() {
+                        let VariableDeclarator { id, .. } =
+                            node_cast!(Node::VariableDeclarator, declarator);
+                        utils::collect_binding_identifiers(id, &mut self.exported);
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        node.visit_children(gc, self);
+    }
+}
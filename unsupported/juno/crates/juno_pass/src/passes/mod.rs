@@ -6,5 +6,26 @@
  */
 
 pub mod add_negative;
+pub mod bundle;
+pub mod commonjs;
+pub mod constant_fold;
+pub mod dead_code_elimination;
+pub mod define;
+pub mod jsx;
+pub mod lower_arrow_functions;
+pub mod lower_block_scoping;
+pub mod lower_classes;
+pub mod lower_destructuring;
+pub mod lower_exponent;
+pub mod lower_for_of;
+pub mod lower_generators;
+pub mod lower_object_literals;
+pub mod lower_optional_chaining;
+pub mod lower_parameters;
+pub mod lower_template_literals;
+pub mod mangle;
 pub mod reduce_conditional;
+pub mod scope_hoist;
 pub mod strip_flow;
+pub mod strip_typescript;
+pub mod tree_shake;
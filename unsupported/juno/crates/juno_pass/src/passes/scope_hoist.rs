@@ -0,0 +1,979 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Concatenate ES modules into the scope of the module importing them, like Rollup.
+//!
+//! [`ScopeHoisting::analyze`] finds the ES modules of a bundle which are only imported by
+//! modules which end up in the same scope, and concatenates them into that scope in the order
+//! they run. Their top-level declarations are renamed where they would clash, and references
+//! to imported bindings become direct references to the exporting module's declarations.
+//! For example,
+//! ```js
+//! // main.js
+//! import {f} from './lib';
+//! const x = 1;
+//! export default f(x);
+//! // lib.js
+//! const x = 2;
+//! export function f(y) { return x + y; }
+//! ```
+//! becomes the single module
+//! ```js
+//! const x = 2;
+//! function f(y) { return x + y; }
+//! const _x = 1;
+//! var _main_default = f(_x);
+//! export {_main_default as default};
+//! ```
+//!
+//! Modules which use `eval` or `with`, which are part of an import cycle, whose namespace
+//! object is used, or which are loaded with `require` stay separate modules in the bundle.
+
+use crate::passes::bundle::resolved_specifiers;
+use crate::passes::commonjs::module_name_base;
+use crate::utils::{self, NameGenerator};
+use juno::ast::*;
+use juno::sema::{DeclId, LexicalScopeId, Resolution, SemContext, Special};
+use juno_support::atom_table::Atom;
+use juno_support::source_manager::SourceId;
+use std::collections::{HashMap, HashSet};
+
+/// A module which has been resolved by `sema::resolve_module`.
+pub struct HoistModule<'a, 'gc> {
+    pub id: SourceId,
+    pub ast: &'gc Node<'gc>,
+    pub sem: &'a SemContext,
+}
+
+/// A module which other modules were concatenated into.
+pub struct ConcatenatedModule {
+    /// The ES module containing all the concatenated modules.
+    pub ast: NodeRc,
+    /// The modules which the specifiers of its imports and `require` calls were resolved to.
+    pub dependencies: HashMap<String, SourceId>,
+}
+
+/// A module which another module depends on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Dep {
+    /// The module the specifier was resolved to, if any.
+    id: Option<SourceId>,
+    /// How the concatenated module refers to it. Specifiers which were resolved are replaced
+    /// with the name of the module, because relative specifiers depend on the importing module.
+    specifier: String,
+}
+
+/// Which export of a module is imported.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Imported {
+    Named(Atom),
+    Namespace,
+}
+
+/// What an export of a module refers to.
+enum Export {
+    /// A top-level declaration of the module.
+    Decl(DeclId),
+    /// The value of an `export default` which doesn't declare a name.
+    Default,
+    /// An export of another module.
+    Reexport(Dep, Imported),
+}
+
+/// The binding which an export resolves to.
+enum Binding {
+    /// A top-level declaration of a concatenated module.
+    Decl(SourceId, DeclId),
+    /// The default export of a concatenated module, which doesn't declare a name.
+    Default(SourceId),
+    /// An export of a module which wasn't concatenated.
+    External(Dep, Imported),
+}
+
+/// Everything the analysis needs to know about a module.
+#[derive(Default)]
+struct ModuleInfo {
+    /// Whether the module is an ES module which doesn't use `eval` or `with`.
+    can_hoist: bool,
+    /// The scope of the top-level declarations.
+    scope: Option<LexicalScopeId>,
+    /// The modules it resolved each specifier to.
+    specifiers: HashMap<String, SourceId>,
+    /// The modules it imports or re-exports, in the order they're first imported.
+    dependencies: Vec<SourceId>,
+    /// The `require` calls and `import()` expressions and the modules they were resolved to.
+    requires: HashMap<NodeRc, SourceId>,
+    /// Modules whose namespace object it uses.
+    namespaces: Vec<SourceId>,
+    imports: HashMap<DeclId, (Dep, Imported)>,
+    /// Exported names in the order they're exported.
+    exports: Vec<(Atom, Export)>,
+    star_exports: Vec<Dep>,
+    /// Names declared outside of the module scope, including the globals it uses, which
+    /// declarations from other modules mustn't shadow.
+    outer_names: HashSet<Atom>,
+}
+
+impl ModuleInfo {
+    fn new<'gc>(gc: &'gc GCLock, module: &HoistModule<'_, 'gc>) -> Self {
+        let sem = module.sem;
+        let scope = sem.node_scope(NodeRc::from_node(gc, module.ast));
+        let mut info = ModuleInfo {
+            can_hoist: matches!(module.ast, Node::Module(_))
+                && !sem.all_scopes().iter().any(|scope| scope.local_eval)
+                && !sem
+                    .all_ident_decls()
+                    .values()
+                    .any(|res| matches!(res, Resolution::Unresolvable)),
+            scope,
+            specifiers: resolved_specifiers(gc, sem),
+            ..Default::default()
+        };
+        for decl in sem.all_decls() {
+            if Some(decl.scope) != scope {
+                info.outer_names.insert(decl.name);
+            }
+        }
+        for (node, &target) in sem.all_requires() {
+            if let Node::CallExpression(_) | Node::ImportExpression(_) = node.node(gc) {
+                info.requires.insert(node.clone(), target);
+            }
+        }
+        if let Node::Module(Module { body, .. }) = module.ast {
+            for stmt in body.iter() {
+                info.collect_statement(gc, sem, stmt);
+            }
+        }
+        info
+    }
+
+    fn collect_statement<'gc>(&mut self, gc: &'gc GCLock, sem: &SemContext, stmt: &'gc Node<'gc>) {
+        match stmt {
+            Node::ImportDeclaration(ImportDeclaration {
+                specifiers,
+                source,
+                import_kind: ImportKind::Value,
+                ..
+            }) => {
+                let dep = self.add_dependency(gc, source);
+                for specifier in specifiers.iter() {
+                    let (local, imported) = match specifier {
+                        Node::ImportSpecifier(ImportSpecifier {
+                            import_kind: ImportKind::Value,
+                            imported,
+                            local,
+                            ..
+                        }) => (*local, Imported::Named(utils::ident_name(imported))),
+                        Node::ImportDefaultSpecifier(ImportDefaultSpecifier { local, .. }) => {
+                            (*local, Imported::Named(gc.atom("default")))
+                        }
+                        Node::ImportNamespaceSpecifier(ImportNamespaceSpecifier {
+                            local, ..
+                        }) => (*local, Imported::Namespace),
+                        _ => continue,
+                    };
+                    if imported == Imported::Namespace {
+                        self.namespaces.extend(dep.id);
+                    }
+                    if let Some(decl) = self.top_level_decl(gc, sem, local) {
+                        self.imports.insert(decl, (dep.clone(), imported));
+                    }
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                specifiers,
+                source: Some(source),
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                let dep = self.add_dependency(gc, source);
+                for specifier in specifiers.iter() {
+                    let (exported, imported) = match specifier {
+                        Node::ExportSpecifier(ExportSpecifier {
+                            exported, local, ..
+                        }) => (
+                            utils::ident_name(exported),
+                            Imported::Named(utils::ident_name(local)),
+                        ),
+                        Node::ExportNamespaceSpecifier(ExportNamespaceSpecifier {
+                            exported,
+                            ..
+                        }) => (utils::ident_name(exported), Imported::Namespace),
+                        _ => continue,
+                    };
+                    if imported == Imported::Namespace {
+                        self.namespaces.extend(dep.id);
+                    }
+                    self.exports
+                        .push((exported, Export::Reexport(dep.clone(), imported)));
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                declaration: Some(declaration),
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                let mut ids = vec![];
+                match declaration {
+                    Node::VariableDeclaration(VariableDeclaration { declarations, .. }) => {
+                        for declarator in declarations.iter() {
+                            let VariableDeclarator { id, .. } =
+                                node_cast!(Node::VariableDeclarator, declarator);
+                            utils::collect_binding_identifiers(id, &mut ids);
+                        }
+                    }
+                    Node::FunctionDeclaration(FunctionDeclaration { id: Some(id), .. })
+                    | Node::ClassDeclaration(ClassDeclaration { id: Some(id), .. }) => {
+                        ids.push(*id)
+                    }
+                    _ => {}
+                }
+                for id in ids {
+                    if let Some(decl) = self.top_level_decl(gc, sem, id) {
+                        self.exports
+                            .push((utils::ident_name(id), Export::Decl(decl)));
+                    }
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                specifiers,
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                for specifier in specifiers.iter() {
+                    if let Node::ExportSpecifier(ExportSpecifier {
+                        exported, local, ..
+                    }) = specifier
+                    {
+                        if let Some(decl) = self.top_level_decl(gc, sem, local) {
+                            self.exports
+                                .push((utils::ident_name(exported), Export::Decl(decl)));
+                        }
+                    }
+                }
+            }
+            Node::ExportDefaultDeclaration(ExportDefaultDeclaration { declaration, .. }) => {
+                let decl = match declaration {
+                    Node::FunctionDeclaration(FunctionDeclaration { id: Some(id), .. })
+                    | Node::ClassDeclaration(ClassDeclaration { id: Some(id), .. }) => {
+                        self.top_level_decl(gc, sem, id)
+                    }
+                    _ => None,
+                };
+                self.exports.push((
+                    gc.atom("default"),
+                    decl.map_or(Export::Default, Export::Decl),
+                ));
+            }
+            Node::ExportAllDeclaration(ExportAllDeclaration {
+                source,
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                let dep = self.add_dependency(gc, source);
+                self.star_exports.push(dep);
+            }
+            _ => {}
+        }
+    }
+
+    /// Record that the module depends on the module `source`.
+    fn add_dependency(&mut self, gc: &GCLock, source: &Node) -> Dep {
+        let specifier =
+            String::from_utf16_lossy(gc.str_u16(node_cast!(Node::StringLiteral, source).value));
+        let id = self.specifiers.get(&specifier).copied();
+        if let Some(id) = id {
+            if !self.dependencies.contains(&id) {
+                self.dependencies.push(id);
+            }
+        }
+        Dep {
+            id,
+            specifier: match id {
+                Some(id) => gc.sm().source_name(id).to_string(),
+                None => specifier,
+            },
+        }
+    }
+
+    /// The top-level declaration which the identifier `id` resolves to, if any.
+    fn top_level_decl(&self, gc: &GCLock, sem: &SemContext, id: &Node) -> Option<DeclId> {
+        match sem.ident_decl(&NodeRc::from_node(gc, id)) {
+            Some(Resolution::Decl(decl)) if Some(sem.decl(decl).scope) == self.scope => Some(decl),
+            _ => None,
+        }
+    }
+}
+
+/// The analyzed modules.
+struct Modules {
+    infos: HashMap<SourceId, ModuleInfo>,
+    default: Atom,
+}
+
+impl Modules {
+    /// The modules which `id` imports or requires.
+    fn edges(&self, id: SourceId) -> impl Iterator<Item = SourceId> + '_ {
+        self.infos.get(&id).into_iter().flat_map(|info| {
+            info.dependencies
+                .iter()
+                .chain(info.requires.values())
+                .copied()
+        })
+    }
+
+    /// Whether the module `id` depends on itself, so the modules in the cycle may observe each
+    /// other's bindings before they're initialized.
+    fn in_cycle(&self, id: SourceId) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack: Vec<SourceId> = self.edges(id).collect();
+        while let Some(dep) = stack.pop() {
+            if dep == id {
+                return true;
+            }
+            if visited.insert(dep) {
+                stack.extend(self.edges(dep));
+            }
+        }
+        false
+    }
+
+    /// Find the binding which the export `name` of the module `id` refers to, following the
+    /// re-exports of the modules which are `inlined`.
+    fn resolve_export(
+        &self,
+        inlined: &dyn Fn(SourceId) -> bool,
+        id: SourceId,
+        name: Atom,
+        visited: &mut HashSet<SourceId>,
+    ) -> Option<Binding> {
+        let info = self.infos.get(&id)?;
+        if !visited.insert(id) {
+            return None;
+        }
+        match info.exports.iter().find(|(exported, _)| *exported == name) {
+            Some((_, Export::Decl(decl))) => match info.imports.get(decl) {
+                Some((dep, imported)) => self.resolve_import(inlined, dep, *imported, visited),
+                None => Some(Binding::Decl(id, *decl)),
+            },
+            Some((_, Export::Default)) => Some(Binding::Default(id)),
+            Some((_, Export::Reexport(dep, imported))) => {
+                self.resolve_import(inlined, dep, *imported, visited)
+            }
+            // `export *` doesn't re-export the default export.
+            None if name == self.default => None,
+            None => info
+                .star_exports
+                .iter()
+                .filter(|star| star.id.is_some_and(inlined))
+                .find_map(|star| self.resolve_export(inlined, star.id.unwrap(), name, visited))
+                .or_else(|| {
+                    // Assume that a module which isn't inlined provides the export.
+                    info.star_exports
+                        .iter()
+                        .find(|star| !star.id.is_some_and(inlined))
+                        .map(|star| Binding::External(star.clone(), Imported::Named(name)))
+                }),
+        }
+    }
+
+    /// Find the binding which `imported` from `dep` refers to.
+    fn resolve_import(
+        &self,
+        inlined: &dyn Fn(SourceId) -> bool,
+        dep: &Dep,
+        imported: Imported,
+        visited: &mut HashSet<SourceId>,
+    ) -> Option<Binding> {
+        match (dep.id, imported) {
+            (Some(id), Imported::Named(name)) if inlined(id) => {
+                self.resolve_export(inlined, id, name, visited)
+            }
+            _ => Some(Binding::External(dep.clone(), imported)),
+        }
+    }
+}
+
+/// Which modules of a bundle are concatenated into the modules importing them.
+pub struct ScopeHoisting {
+    /// The module each concatenated module was concatenated into.
+    concatenated_into: HashMap<SourceId, SourceId>,
+    /// The result of concatenating the modules into each module.
+    modules: HashMap<SourceId, ConcatenatedModule>,
+}
+
+impl ScopeHoisting {
+    /// Analyze the imports of `modules`, which must all be parts of the same AST context, and
+    /// concatenate the modules which can share a scope. The `entry_points` are never
+    /// concatenated into other modules.
+    pub fn analyze<'gc>(
+        gc: &'gc GCLock,
+        modules: &[HoistModule<'_, 'gc>],
+        entry_points: &[SourceId],
+    ) -> Self {
+        let all = Modules {
+            infos: modules
+                .iter()
+                .map(|module| (module.id, ModuleInfo::new(gc, module)))
+                .collect(),
+            default: gc.atom("default"),
+        };
+
+        // Modules which may be concatenated into the modules importing them.
+        let mut candidates: HashSet<SourceId> = all
+            .infos
+            .iter()
+            .filter(|(&id, info)| info.can_hoist && !all.in_cycle(id))
+            .map(|(&id, _)| id)
+            .collect();
+        for info in all.infos.values() {
+            // These need a namespace object or `module.exports`.
+            for id in info.namespaces.iter().chain(info.requires.values()) {
+                candidates.remove(id);
+            }
+        }
+        // Imports of missing exports can't refer to a declaration, so the modules they import
+        // stay separate and the imports keep failing at runtime.
+        loop {
+            let mut unresolved = vec![];
+            let inlined = |id: SourceId| candidates.contains(&id);
+            for id in &candidates {
+                let info = &all.infos[id];
+                let reexports = info.exports.iter().filter_map(|(_, export)| match export {
+                    Export::Reexport(dep, imported) => Some((dep, imported)),
+                    _ => None,
+                });
+                for (dep, imported) in info.imports.values().map(|(d, i)| (d, i)).chain(reexports) {
+                    if let (Some(target), Imported::Named(name)) = (dep.id, imported) {
+                        if inlined(target)
+                            && all
+                                .resolve_export(&inlined, target, *name, &mut HashSet::new())
+                                .is_none()
+                        {
+                            unresolved.push(target);
+                        }
+                    }
+                }
+            }
+            if unresolved.is_empty() {
+                break;
+            }
+            for id in unresolved {
+                candidates.remove(&id);
+            }
+        }
+
+        let mut importers: HashMap<SourceId, Vec<SourceId>> = HashMap::new();
+        for &id in all.infos.keys() {
+            for dep in all.edges(id) {
+                importers.entry(dep).or_default().push(id);
+            }
+        }
+        let mut groups = HashMap::new();
+        for &id in &candidates {
+            group_of(&candidates, &importers, entry_points, &mut groups, id);
+        }
+        let concatenated_into: HashMap<SourceId, SourceId> = groups
+            .into_iter()
+            .filter(|(id, group)| id != group)
+            .collect();
+
+        let mut roots: Vec<SourceId> = concatenated_into.values().copied().collect();
+        roots.sort_unstable_by_key(|id| id.0);
+        roots.dedup();
+        let modules_by_id: HashMap<SourceId, &HoistModule> =
+            modules.iter().map(|module| (module.id, module)).collect();
+        let modules = roots
+            .into_iter()
+            .map(|root| {
+                // Concatenate the modules in the order they run.
+                let mut order = vec![];
+                let mut visited = HashSet::new();
+                let mut stack = vec![(root, false)];
+                while let Some((id, deps_done)) = stack.pop() {
+                    if deps_done {
+                        order.push(id);
+                    } else if visited.insert(id) {
+                        stack.push((id, true));
+                        for &dep in all.infos[&id].dependencies.iter().rev() {
+                            if concatenated_into.get(&dep) == Some(&root) {
+                                stack.push((dep, false));
+                            }
+                        }
+                    }
+                }
+                let mut concatenation = Concatenation {
+                    all: &all,
+                    root,
+                    concatenated_into: &concatenated_into,
+                    names: NameGenerator::new(gc, modules_by_id[&root].ast),
+                    taken: HashSet::new(),
+                    decl_names: HashMap::new(),
+                    default_names: HashMap::new(),
+                    external_imports: vec![],
+                    dependencies: HashMap::new(),
+                };
+                let order: Vec<&HoistModule> = order.iter().map(|id| modules_by_id[id]).collect();
+                (root, concatenation.run(gc, &order))
+            })
+            .collect();
+
+        ScopeHoisting {
+            concatenated_into,
+            modules,
+        }
+    }
+
+    /// Whether the module `id` was concatenated into another module, so it isn't needed on its
+    /// own anymore.
+    pub fn is_concatenated(&self, id: SourceId) -> bool {
+        self.concatenated_into.contains_key(&id)
+    }
+
+    /// Take the result of concatenating other modules into the module `id`, if any were.
+    pub fn take_module(&mut self, id: SourceId) -> Option<ConcatenatedModule> {
+        self.modules.remove(&id)
+    }
+}
+
+/// Find the module whose scope the candidate `id` is concatenated into, which is the one all of
+/// its importers are concatenated into, if they're all candidates, or else itself.
+fn group_of(
+    candidates: &HashSet<SourceId>,
+    importers: &HashMap<SourceId, Vec<SourceId>>,
+    entry_points: &[SourceId],
+    groups: &mut HashMap<SourceId, SourceId>,
+    id: SourceId,
+) -> SourceId {
+    if let Some(&group) = groups.get(&id) {
+        return group;
+    }
+    let importers_of = importers.get(&id).map_or(&[][..], Vec::as_slice);
+    let group = if entry_points.contains(&id)
+        || importers_of.is_empty()
+        || importers_of.iter().any(|i| !candidates.contains(i))
+    {
+        id
+    } else {
+        let mut parents = importers_of
+            .iter()
+            .map(|&importer| group_of(candidates, importers, entry_points, groups, importer));
+        let first = parents.next().unwrap();
+        if parents.all(|group| group == first) {
+            first
+        } else {
+            id
+        }
+    };
+    groups.insert(id, group);
+    group
+}
+
+/// Builder of the module which other modules are concatenated into.
+struct Concatenation<'a> {
+    all: &'a Modules,
+    root: SourceId,
+    concatenated_into: &'a HashMap<SourceId, SourceId>,
+    names: NameGenerator,
+    /// Names of the top-level declarations so far.
+    taken: HashSet<Atom>,
+    /// New names of the top-level declarations of the modules.
+    decl_names: HashMap<(SourceId, DeclId), Atom>,
+    /// Names of the variables holding the default exports which don't declare a name.
+    default_names: HashMap<SourceId, Atom>,
+    /// Imports added for bindings which are re-exported from modules that aren't inlined.
+    external_imports: Vec<(Dep, Imported, Atom)>,
+    dependencies: HashMap<String, SourceId>,
+}
+
+impl Concatenation<'_> {
+    /// Concatenate the modules in `order`, which ends with the root.
+    fn run<'gc>(&mut self, gc: &'gc GCLock, order: &[&HoistModule<'_, 'gc>]) -> ConcatenatedModule {
+        for module in order {
+            self.names.reserve(gc, module.ast);
+        }
+        for module in order {
+            self.assign_names(gc, module, order);
+        }
+        let mut body = vec![];
+        for module in order {
+            self.rewrite_module(gc, module, &mut body);
+        }
+
+        // The concatenated module exports what the root exports.
+        let mut exports = vec![];
+        let mut stars = vec![];
+        self.collect_exports(self.root, &mut exports, &mut stars, &mut HashSet::new());
+        let exports: Vec<(Atom, Atom)> = exports
+            .into_iter()
+            .map(|(exported, binding)| (exported, self.binding_name(gc, binding)))
+            .collect();
+        if !exports.is_empty() {
+            body.push(builder::ExportNamedDeclaration::build_template(
+                gc,
+                template::ExportNamedDeclaration {
+                    metadata: Default::default(),
+                    declaration: None,
+                    specifiers: NodeList::from_iter(
+                        gc,
+                        exports.into_iter().map(|(exported, local)| {
+                            builder::ExportSpecifier::build_template(
+                                gc,
+                                template::ExportSpecifier {
+                                    metadata: Default::default(),
+                                    exported: utils::make_identifier(gc, exported),
+                                    local: utils::make_identifier(gc, local),
+                                },
+                            )
+                        }),
+                    ),
+                    source: None,
+                    export_kind: ExportKind::Value,
+                },
+            ));
+        }
+        for star in stars {
+            body.push(builder::ExportAllDeclaration::build_template(
+                gc,
+                template::ExportAllDeclaration {
+                    metadata: Default::default(),
+                    source: utils::make_string(gc, &star.specifier),
+                    export_kind: ExportKind::Value,
+                },
+            ));
+            if let Some(id) = star.id {
+                self.dependencies.insert(star.specifier, id);
+            }
+        }
+
+        let imports = self.external_imports.iter().map(|(dep, imported, local)| {
+            let local = utils::make_identifier(gc, *local);
+            let specifier = match imported {
+                Imported::Named(name) => builder::ImportSpecifier::build_template(
+                    gc,
+                    template::ImportSpecifier {
+                        metadata: Default::default(),
+                        imported: utils::make_identifier(gc, *name),
+                        local,
+                        import_kind: ImportKind::Value,
+                    },
+                ),
+                Imported::Namespace => builder::ImportNamespaceSpecifier::build_template(
+                    gc,
+                    template::ImportNamespaceSpecifier {
+                        metadata: Default::default(),
+                        local,
+                    },
+                ),
+            };
+            builder::ImportDeclaration::build_template(
+                gc,
+                template::ImportDeclaration {
+                    metadata: Default::default(),
+                    specifiers: NodeList::from_iter(gc, [specifier]),
+                    source: utils::make_string(gc, &dep.specifier),
+                    assertions: None,
+                    import_kind: ImportKind::Value,
+                },
+            )
+        });
+        let imports: Vec<&Node> = imports.collect();
+
+        let root = order.last().expect("the root is concatenated last").ast;
+        let module = builder::Module::build_template(
+            gc,
+            template::Module {
+                metadata: TemplateMetadata {
+                    range: *root.range(),
+                    ..Default::default()
+                },
+                body: NodeList::from_iter(gc, imports.into_iter().chain(body)),
+            },
+        );
+        ConcatenatedModule {
+            ast: NodeRc::from_node(gc, module),
+            dependencies: std::mem::take(&mut self.dependencies),
+        }
+    }
+
+    fn is_inlined(&self, id: SourceId) -> bool {
+        self.concatenated_into.get(&id) == Some(&self.root)
+    }
+
+    /// Choose the names of the top-level declarations of `module`. Names are kept unless an
+    /// earlier module already declared them, or another module in `order` declares or uses
+    /// them in an inner scope, where they would be shadowed.
+    fn assign_names<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        module: &HoistModule<'_, 'gc>,
+        order: &[&HoistModule<'_, 'gc>],
+    ) {
+        let info = &self.all.infos[&module.id];
+        let scope = match info.scope {
+            Some(scope) => scope,
+            None => return,
+        };
+        for &decl in &module.sem.scope(scope).decls {
+            let name = module.sem.decl(decl).name;
+            if module.sem.decl(decl).special != Special::NotSpecial
+                || self.is_inlined_import(info, decl)
+            {
+                continue;
+            }
+            let shadowed = order.iter().any(|other| {
+                other.id != module.id && self.all.infos[&other.id].outer_names.contains(&name)
+            });
+            let new_name = if shadowed || self.taken.contains(&name) {
+                self.names.fresh(gc, gc.str(name))
+            } else {
+                name
+            };
+            self.taken.insert(new_name);
+            self.decl_names.insert((module.id, decl), new_name);
+        }
+        if info
+            .exports
+            .iter()
+            .any(|(_, export)| matches!(export, Export::Default))
+        {
+            let base = format!(
+                "{}_default",
+                module_name_base(gc.sm().source_name(module.id))
+            );
+            let name = self.names.fresh(gc, &base);
+            self.default_names.insert(module.id, name);
+        }
+    }
+
+    /// Whether `decl` is imported from a module which is concatenated into the same module.
+    fn is_inlined_import(&self, info: &ModuleInfo, decl: DeclId) -> bool {
+        info.imports
+            .get(&decl)
+            .is_some_and(|(dep, _)| dep.id.is_some_and(|id| self.is_inlined(id)))
+    }
+
+    /// Append the statements of `module`, with its declarations renamed and the imports of the
+    /// concatenated modules and the exports removed, to `body`.
+    fn rewrite_module<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        module: &HoistModule<'_, 'gc>,
+        body: &mut Vec<&'gc Node<'gc>>,
+    ) {
+        let all = self.all;
+        let info = &all.infos[&module.id];
+        let mut names: HashMap<DeclId, Atom> = self
+            .decl_names
+            .iter()
+            .filter(|((id, _), _)| *id == module.id)
+            .map(|(&(_, decl), &name)| (decl, name))
+            .collect();
+        for (&decl, (dep, imported)) in &info.imports {
+            if !self.is_inlined_import(info, decl) {
+                continue;
+            }
+            let (root, concatenated_into) = (self.root, self.concatenated_into);
+            let inlined = |id: SourceId| concatenated_into.get(&id) == Some(&root);
+            if let Some(binding) = all.resolve_import(&inlined, dep, *imported, &mut HashSet::new())
+            {
+                names.insert(decl, self.binding_name(gc, binding));
+            }
+        }
+
+        let mut node = module.ast;
+        if !info.requires.is_empty() {
+            for &target in info.requires.values() {
+                self.dependencies
+                    .insert(gc.sm().source_name(target).to_string(), target);
+            }
+            let mut rewriter = RewriteRequires {
+                requires: &info.requires,
+            };
+            node = utils::changed_or(VisitorMut::call(&mut rewriter, gc, node, None), node);
+        }
+        let mut renamer = utils::Renamer {
+            sem: module.sem,
+            names,
+        };
+        node = utils::changed_or(VisitorMut::call(&mut renamer, gc, node, None), node);
+
+        for stmt in node_cast!(Node::Module, node).body.iter() {
+            if let Some(stmt) = self.rewrite_statement(gc, module.id, stmt) {
+                body.push(stmt);
+            }
+        }
+    }
+
+    /// Return what `stmt` of the module `id` becomes, or `None` if it's removed.
+    fn rewrite_statement<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        id: SourceId,
+        stmt: &'gc Node<'gc>,
+    ) -> Option<&'gc Node<'gc>> {
+        match stmt {
+            Node::ImportDeclaration(
+                import @ ImportDeclaration {
+                    source,
+                    import_kind: ImportKind::Value,
+                    ..
+                },
+            ) => {
+                let specifier = String::from_utf16_lossy(
+                    gc.str_u16(node_cast!(Node::StringLiteral, source).value),
+                );
+                match self.all.infos[&id].specifiers.get(&specifier) {
+                    Some(&dep) if self.is_inlined(dep) => None,
+                    Some(&dep) => {
+                        let name = gc.sm().source_name(dep);
+                        self.dependencies.insert(name.to_string(), dep);
+                        let mut builder = builder::ImportDeclaration::from_node(import);
+                        builder.source(utils::make_string(gc, name));
+                        Some(builder.build_forced(gc))
+                    }
+                    None => Some(stmt),
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                declaration,
+                export_kind: ExportKind::Value,
+                ..
+            }) => *declaration,
+            Node::ExportAllDeclaration(ExportAllDeclaration {
+                export_kind: ExportKind::Value,
+                ..
+            }) => None,
+            Node::ExportDefaultDeclaration(ExportDefaultDeclaration { declaration, .. }) => {
+                let name = match self.default_names.get(&id) {
+                    Some(&name) => utils::make_identifier(gc, name),
+                    None => return Some(declaration),
+                };
+                Some(match declaration {
+                    Node::FunctionDeclaration(func) => {
+                        let mut builder = builder::FunctionDeclaration::from_node(func);
+                        builder.id(Some(name));
+                        builder.build_forced(gc)
+                    }
+                    Node::ClassDeclaration(class) => {
+                        let mut builder = builder::ClassDeclaration::from_node(class);
+                        builder.id(Some(name));
+                        builder.build_forced(gc)
+                    }
+                    _ => utils::make_var(gc, name, Some(declaration)),
+                })
+            }
+            // The concatenated module is strict like all ES modules.
+            Node::ExpressionStatement(ExpressionStatement {
+                directive: Some(_), ..
+            }) => None,
+            _ => Some(stmt),
+        }
+    }
+
+    /// Append the exports of the module `id` which aren't in `exports` yet, and the modules
+    /// which aren't inlined that it exports everything from, to `stars`.
+    fn collect_exports(
+        &self,
+        id: SourceId,
+        exports: &mut Vec<(Atom, Binding)>,
+        stars: &mut Vec<Dep>,
+        visited: &mut HashSet<SourceId>,
+    ) {
+        if !visited.insert(id) {
+            return;
+        }
+        let info = &self.all.infos[&id];
+        let inlined = |id: SourceId| self.is_inlined(id);
+        for (name, _) in &info.exports {
+            if (id != self.root && *name == self.all.default)
+                || exports.iter().any(|(exported, _)| exported == name)
+            {
+                continue;
+            }
+            if let Some(binding) = self
+                .all
+                .resolve_export(&inlined, id, *name, &mut HashSet::new())
+            {
+                exports.push((*name, binding));
+            }
+        }
+        for star in &info.star_exports {
+            match star.id {
+                Some(star_id) if self.is_inlined(star_id) => {
+                    self.collect_exports(star_id, exports, stars, visited)
+                }
+                _ => {
+                    if !stars.contains(star) {
+                        stars.push(star.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// The name of the variable holding `binding` in the concatenated module.
+    fn binding_name(&mut self, gc: &GCLock, binding: Binding) -> Atom {
+        match binding {
+            Binding::Decl(id, decl) => self.decl_names[&(id, decl)],
+            Binding::Default(id) => self.default_names[&id],
+            Binding::External(dep, imported) => {
+                if let Some((_, _, name)) = self
+                    .external_imports
+                    .iter()
+                    .find(|(d, i, _)| *d == dep && *i == imported)
+                {
+                    return *name;
+                }
+                let name = match imported {
+                    Imported::Named(name) if name != self.all.default => {
+                        self.names.fresh(gc, gc.str(name))
+                    }
+                    _ => self.names.fresh(gc, &module_name_base(&dep.specifier)),
+                };
+                if let Some(id) = dep.id {
+                    self.dependencies.insert(dep.specifier.clone(), id);
+                }
+                self.external_imports.push((dep, imported, name));
+                name
+            }
+        }
+    }
+}
+
+/// Replace the specifiers passed to `require` and `import()` with the names of the modules
+/// they were resolved to.
+struct RewriteRequires<'a> {
+    requires: &'a HashMap<NodeRc, SourceId>,
+}
+
+impl<'gc> VisitorMut<'gc> for RewriteRequires<'_> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        if let Some(&id) = self.requires.get(&NodeRc::from_node(gc, node)) {
+            let name = utils::make_string(gc, gc.sm().source_name(id));
+            match node {
+                Node::CallExpression(call) => {
+                    let mut builder = builder::CallExpression::from_node(call);
+                    builder.arguments(NodeList::from_iter(gc, [name]));
+                    return TransformResult::Changed(builder.build_forced(gc));
+                }
+                Node::ImportExpression(import) => {
+                    let mut builder = builder::ImportExpression::from_node(import);
+                    builder.source(name);
+                    return TransformResult::Changed(builder.build_forced(gc));
+                }
+                _ => {}
+            }
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
@@ -202,6 +202,7 @@ impl<'gc> VisitorMut<'gc> for StripFlow {
                                 template::ExportDefaultDeclaration {
                                     metadata: TemplateMetadata {
                                         range: metadata.range,
+                                        comments: metadata.comments,
                                         ..Default::default()
                                     },
                                     declaration: e.id,
@@ -280,6 +281,7 @@ fn transform_enum<'gc>(gc: &'gc GCLock<'_, '_>, n: &'gc EnumDeclaration<'gc>) ->
                                     template::Property {
                                         metadata: TemplateMetadata {
                                             range: metadata.range,
+                                            comments: metadata.comments,
                                             ..Default::default()
                                         },
                                         kind: PropertyKind::Init,
@@ -297,6 +299,7 @@ fn transform_enum<'gc>(gc: &'gc GCLock<'_, '_>, n: &'gc EnumDeclaration<'gc>) ->
                                     template::Property {
                                         metadata: TemplateMetadata {
                                             range: m.metadata.range,
+                                            comments: m.metadata.comments,
                                             ..Default::default()
                                         },
                                         kind: PropertyKind::Init,
@@ -379,6 +382,7 @@ fn transform_enum<'gc>(gc: &'gc GCLock<'_, '_>, n: &'gc EnumDeclaration<'gc>) ->
         template::VariableDeclaration {
             metadata: TemplateMetadata {
                 range: n.metadata.range,
+                comments: n.metadata.comments,
                 ..Default::default()
             },
             kind: VariableDeclarationKind::Const,
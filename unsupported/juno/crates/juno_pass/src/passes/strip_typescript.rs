@@ -0,0 +1,743 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Strip TypeScript syntax, leaving plain JavaScript.
+//!
+//! Parameter properties are lowered to assignments in the constructor and `enum` declarations
+//! are lowered to functions which fill in an object, like the TypeScript compiler does.
+//! Namespaces which only contain types are removed, and other namespaces are lowered to
+//! functions which add their exported declarations to an object.
+//!
+//! The TypeScript parser doesn't accept parameter properties in constructors, `this` parameters or
+//! `implements` clauses yet. They are stripped from ASTs which contain them, but `--strip-ts` can't
+//! read them from TypeScript sources.
+//!
+//! TODO: Strip non-null assertions (`x!`), `satisfies` expressions and `declare` statements. They
+//! need parser support first: the parser reports them as syntax errors and the AST has no nodes
+//! for them.
+
+use crate::utils;
+use crate::Pass;
+use juno::ast::*;
+use juno_support::atom_table::Atom;
+
+#[derive(Default)]
+pub struct StripTypeScript {}
+
+impl StripTypeScript {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Pass for StripTypeScript {
+    fn name(&self) -> &'static str {
+        "Strip TypeScript"
+    }
+    fn description(&self) -> &'static str {
+        "Strip TypeScript types and lower TypeScript-only constructs"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        VisitorMut::call(self, gc, node, None)
+    }
+}
+
+impl<'gc> VisitorMut<'gc> for StripTypeScript {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            Node::TSAsExpression(TSAsExpression { expression, .. })
+            | Node::TSTypeAssertion(TSTypeAssertion { expression, .. }) => {
+                return node.replace_with_existing(expression, gc, self);
+            }
+
+            Node::Identifier(id) if id.type_annotation.is_some() || id.optional => {
+                let mut builder = builder::Identifier::from_node(id);
+                builder.type_annotation(None);
+                builder.optional(false);
+                return node.replace_with_new(builder::Builder::Identifier(builder), gc, self);
+            }
+            Node::ObjectPattern(pat) if pat.type_annotation.is_some() => {
+                let mut builder = builder::ObjectPattern::from_node(pat);
+                builder.type_annotation(None);
+                return node.replace_with_new(builder::Builder::ObjectPattern(builder), gc, self);
+            }
+            Node::ArrayPattern(pat) if pat.type_annotation.is_some() => {
+                let mut builder = builder::ArrayPattern::from_node(pat);
+                builder.type_annotation(None);
+                return node.replace_with_new(builder::Builder::ArrayPattern(builder), gc, self);
+            }
+
+            Node::ImportDeclaration(decl) if decl.import_kind != ImportKind::Value => {
+                return TransformResult::Removed;
+            }
+            Node::ImportSpecifier(spec) if spec.import_kind != ImportKind::Value => {
+                return TransformResult::Removed;
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                metadata,
+                declaration:
+                    Some(
+                        decl @ (Node::TSEnumDeclaration(_)
+                        | Node::TSModuleDeclaration(_)
+                        | Node::TSModuleMember(_)),
+                    ),
+                ..
+            }) if !is_type_declaration(decl) => {
+                // export var E; (function (E) {...})(E || (E = {}));
+                let mut stmts = match decl {
+                    Node::TSEnumDeclaration(n) => transform_enum(gc, n, None),
+                    _ => lower_namespace(gc, decl, None),
+                };
+                if stmts.is_empty() {
+                    return TransformResult::Removed;
+                }
+                stmts[0] = builder::ExportNamedDeclaration::build_template(
+                    gc,
+                    template::ExportNamedDeclaration {
+                        metadata: TemplateMetadata {
+                            range: metadata.range,
+                            comments: metadata.comments,
+                            ..Default::default()
+                        },
+                        declaration: Some(stmts[0]),
+                        specifiers: NodeList::new(gc),
+                        source: None,
+                        export_kind: ExportKind::Value,
+                    },
+                );
+                return node.replace_with_multiple(
+                    stmts.into_iter().map(builder::Builder::from_node).collect(),
+                    gc,
+                    self,
+                );
+            }
+            Node::ExportNamedDeclaration(decl)
+                if decl.export_kind != ExportKind::Value
+                    || decl.declaration.is_some_and(is_type_declaration) =>
+            {
+                return TransformResult::Removed;
+            }
+            Node::TSTypeAliasDeclaration { .. } | Node::TSInterfaceDeclaration { .. } => {
+                return TransformResult::Removed;
+            }
+            Node::TSModuleDeclaration { .. } | Node::TSModuleMember { .. } => {
+                let stmts = lower_namespace(gc, node, None);
+                if stmts.is_empty() {
+                    return TransformResult::Removed;
+                }
+                return node.replace_with_multiple(
+                    stmts.into_iter().map(builder::Builder::from_node).collect(),
+                    gc,
+                    self,
+                );
+            }
+
+            Node::FunctionDeclaration(n) => {
+                let mut builder = builder::FunctionDeclaration::from_node(n);
+                builder.params(strip_this_param(gc, n.params));
+                builder.type_parameters(None);
+                builder.return_type(None);
+                return node.replace_with_new(
+                    builder::Builder::FunctionDeclaration(builder),
+                    gc,
+                    self,
+                );
+            }
+            Node::FunctionExpression(n) => {
+                let params = strip_this_param(gc, n.params);
+                let mut builder = builder::FunctionExpression::from_node(n);
+                builder.type_parameters(None);
+                builder.return_type(None);
+                if params
+                    .iter()
+                    .any(|p| matches!(p, Node::TSParameterProperty(_)))
+                {
+                    // Only constructors may contain parameter properties.
+                    builder.params(NodeList::from_iter(
+                        gc,
+                        params.iter().map(|p| match p {
+                            Node::TSParameterProperty(prop) => prop.parameter,
+                            _ => p,
+                        }),
+                    ));
+                    builder.body(lower_parameter_properties(gc, n));
+                } else {
+                    builder.params(params);
+                }
+                return node.replace_with_new(
+                    builder::Builder::FunctionExpression(builder),
+                    gc,
+                    self,
+                );
+            }
+            Node::ArrowFunctionExpression(n) => {
+                let mut builder = builder::ArrowFunctionExpression::from_node(n);
+                builder.type_parameters(None);
+                builder.return_type(None);
+                return node.replace_with_new(
+                    builder::Builder::ArrowFunctionExpression(builder),
+                    gc,
+                    self,
+                );
+            }
+
+            Node::ClassDeclaration(n) => {
+                let mut builder = builder::ClassDeclaration::from_node(n);
+                builder.implements(NodeList::new(gc));
+                builder.super_type_parameters(None);
+                builder.type_parameters(None);
+                return node.replace_with_new(
+                    builder::Builder::ClassDeclaration(builder),
+                    gc,
+                    self,
+                );
+            }
+            Node::ClassExpression(n) => {
+                let mut builder = builder::ClassExpression::from_node(n);
+                builder.implements(NodeList::new(gc));
+                builder.super_type_parameters(None);
+                builder.type_parameters(None);
+                return node.replace_with_new(builder::Builder::ClassExpression(builder), gc, self);
+            }
+
+            Node::ClassProperty(n) => {
+                if n.value.is_none() || n.declare {
+                    return TransformResult::Removed;
+                }
+
+                let mut builder = builder::ClassProperty::from_node(n);
+                builder.type_annotation(None);
+                builder.optional(false);
+                return node.replace_with_new(builder::Builder::ClassProperty(builder), gc, self);
+            }
+            Node::ClassPrivateProperty(n) => {
+                if n.value.is_none() || n.declare {
+                    return TransformResult::Removed;
+                }
+
+                let mut builder = builder::ClassPrivateProperty::from_node(n);
+                builder.type_annotation(None);
+                builder.optional(false);
+                return node.replace_with_new(
+                    builder::Builder::ClassPrivateProperty(builder),
+                    gc,
+                    self,
+                );
+            }
+
+            Node::CallExpression(n) => {
+                let mut builder = builder::CallExpression::from_node(n);
+                builder.type_arguments(None);
+                return node.replace_with_new(builder::Builder::CallExpression(builder), gc, self);
+            }
+            Node::OptionalCallExpression(n) => {
+                let mut builder = builder::OptionalCallExpression::from_node(n);
+                builder.type_arguments(None);
+                return node.replace_with_new(
+                    builder::Builder::OptionalCallExpression(builder),
+                    gc,
+                    self,
+                );
+            }
+            Node::NewExpression(n) => {
+                let mut builder = builder::NewExpression::from_node(n);
+                builder.type_arguments(None);
+                return node.replace_with_new(builder::Builder::NewExpression(builder), gc, self);
+            }
+
+            Node::TSEnumDeclaration(n) => {
+                return node.replace_with_multiple(
+                    transform_enum(gc, n, None)
+                        .into_iter()
+                        .map(builder::Builder::from_node)
+                        .collect(),
+                    gc,
+                    self,
+                );
+            }
+            _ => {}
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+/// Whether `node` is a declaration which only exists in the type namespace, including namespaces
+/// which only contain types.
+fn is_type_declaration(node: &Node) -> bool {
+    match node {
+        Node::TSTypeAliasDeclaration(_) | Node::TSInterfaceDeclaration(_) => true,
+        Node::TSModuleDeclaration(_) | Node::TSModuleMember(_) => {
+            namespace_body(node).map_or(true, |body| {
+                body.iter().all(|stmt| match stmt {
+                    Node::EmptyStatement(_) => true,
+                    Node::ExportNamedDeclaration(decl) => {
+                        decl.export_kind != ExportKind::Value
+                            || decl.declaration.is_some_and(is_type_declaration)
+                    }
+                    _ => is_type_declaration(stmt),
+                })
+            })
+        }
+        _ => false,
+    }
+}
+
+/// The statements of the namespace `node`, if it has a body.
+fn namespace_body<'gc>(node: &'gc Node<'gc>) -> Option<NodeList<'gc>> {
+    let body = match node {
+        Node::TSModuleDeclaration(TSModuleDeclaration { body, .. }) => Some(*body),
+        Node::TSModuleMember(TSModuleMember { initializer, .. }) => *initializer,
+        _ => unreachable!("namespace must be a module declaration"),
+    };
+    match body {
+        Some(Node::TSModuleBlock(TSModuleBlock { body, .. })) => Some(*body),
+        _ => None,
+    }
+}
+
+/// Append the names of the possibly qualified namespace name `id` to `names`:
+/// `namespace A.B {}` declares `A` and `B`.
+fn namespace_names(id: &Node, names: &mut Vec<Atom>) -> bool {
+    match id {
+        Node::Identifier(id) => {
+            names.push(id.name);
+            true
+        }
+        Node::TSQualifiedName(TSQualifiedName {
+            left,
+            right: Some(right),
+            ..
+        }) => namespace_names(left, names) && namespace_names(right, names),
+        _ => false,
+    }
+}
+
+/// Lower the namespace `node` to `var N; (function (N) {...})(N || (N = {}));`, exporting its
+/// members by assigning them to `N`. `outer` is the namespace which exports `node`, if any.
+/// Namespaces which only contain types are removed, and parts which can't be lowered are reported
+/// as errors.
+fn lower_namespace<'gc>(
+    gc: &'gc GCLock<'_, '_>,
+    node: &'gc Node<'gc>,
+    outer: Option<Atom>,
+) -> Vec<&'gc Node<'gc>> {
+    if is_type_declaration(node) {
+        return vec![];
+    }
+    let id = match node {
+        Node::TSModuleDeclaration(TSModuleDeclaration { id, .. })
+        | Node::TSModuleMember(TSModuleMember { id, .. }) => id,
+        _ => unreachable!("namespace must be a module declaration"),
+    };
+    let mut names = vec![];
+    if !namespace_names(id, &mut names) {
+        gc.sm().error(
+            *node.range(),
+            "only namespaces named by identifiers can contain values",
+        );
+        return vec![];
+    }
+    let metadata = TemplateMetadata {
+        range: *node.range(),
+        comments: node.comments(),
+        ..Default::default()
+    };
+    let body = namespace_body(node).unwrap();
+
+    // namespace A.B {} is namespace A { export namespace B {} }.
+    let (inner, outers) = names.split_last().unwrap();
+    let mut stmts = lower_namespace_body(gc, *inner, body);
+    let mut outer_names = outers.iter().rev().copied();
+    let mut name = *inner;
+    loop {
+        match outer_names.next() {
+            Some(parent) => {
+                stmts = make_merged_declaration(gc, name, Some(parent), Default::default(), stmts);
+                name = parent;
+            }
+            None => return make_merged_declaration(gc, name, outer, metadata, stmts),
+        }
+    }
+}
+
+/// Lower the statements `body` of the namespace `name`, assigning the exported declarations to
+/// `name`: `export const x = 1;` becomes `const x = 1; N.x = x;`.
+fn lower_namespace_body<'gc>(
+    gc: &'gc GCLock<'_, '_>,
+    name: Atom,
+    body: NodeList<'gc>,
+) -> Vec<&'gc Node<'gc>> {
+    let export = |id: &'gc Node<'gc>| {
+        let id_name = utils::ident_name(id);
+        utils::make_expression_statement(
+            gc,
+            utils::make_assignment(
+                gc,
+                utils::make_member(gc, utils::make_identifier(gc, name), gc.str(id_name)),
+                utils::make_identifier(gc, id_name),
+            ),
+        )
+    };
+    let mut result = vec![];
+    for stmt in body.iter() {
+        let decl = match stmt {
+            Node::ExportNamedDeclaration(decl)
+                if decl.export_kind != ExportKind::Value
+                    || decl.declaration.is_some_and(is_type_declaration) =>
+            {
+                continue;
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                declaration: Some(decl),
+                ..
+            }) => decl,
+            Node::ExportNamedDeclaration(_)
+            | Node::ExportDefaultDeclaration(_)
+            | Node::ExportAllDeclaration(_) => {
+                gc.sm().error(
+                    *stmt.range(),
+                    "only declarations can be exported from namespaces",
+                );
+                continue;
+            }
+            _ => {
+                result.push(stmt);
+                continue;
+            }
+        };
+        match decl {
+            Node::VariableDeclaration(VariableDeclaration {
+                kind: VariableDeclarationKind::Const,
+                declarations,
+                ..
+            }) => {
+                result.push(decl);
+                let mut ids = vec![];
+                for declarator in declarations.iter() {
+                    let VariableDeclarator { id, .. } =
+                        node_cast!(Node::VariableDeclarator, declarator);
+                    utils::collect_binding_identifiers(id, &mut ids);
+                }
+                result.extend(ids.into_iter().map(|id| export(id)));
+            }
+            Node::VariableDeclaration(_) => {
+                // Assignments to the variable would have to update the namespace as well.
+                gc.sm().error(
+                    *decl.range(),
+                    "only `const` variables can be exported from namespaces",
+                );
+            }
+            Node::FunctionDeclaration(FunctionDeclaration { id: Some(id), .. })
+            | Node::ClassDeclaration(ClassDeclaration { id: Some(id), .. }) => {
+                result.push(decl);
+                result.push(export(id));
+            }
+            Node::TSEnumDeclaration(n) => result.extend(transform_enum(gc, n, Some(name))),
+            Node::TSModuleDeclaration(_) | Node::TSModuleMember(_) => {
+                result.extend(lower_namespace(gc, decl, Some(name)))
+            }
+            _ => gc.sm().error(
+                *decl.range(),
+                format!("unsupported export from a namespace: {}", decl.name()),
+            ),
+        }
+    }
+    result
+}
+
+/// Remove the `this` parameter, which only annotates the type of `this`.
+fn strip_this_param<'gc>(gc: &'gc GCLock<'_, '_>, params: NodeList<'gc>) -> NodeList<'gc> {
+    NodeList::from_iter(
+        gc,
+        params.iter().filter(|p| match p {
+            Node::Identifier(Identifier { name, .. }) => gc.str(*name) != "this",
+            _ => true,
+        }),
+    )
+}
+
+/// Build the body of the constructor `func`, assigning each parameter property to `this`.
+/// The assignments are placed after the `super()` call if there is one, because `this` can't be
+/// accessed before it.
+fn lower_parameter_properties<'gc>(
+    gc: &'gc GCLock<'_, '_>,
+    func: &'gc FunctionExpression<'gc>,
+) -> &'gc Node<'gc> {
+    let assignments = func.params.iter().filter_map(|p| {
+        let prop = match p {
+            Node::TSParameterProperty(prop) => prop,
+            _ => return None,
+        };
+        let id = match prop.parameter {
+            Node::Identifier(id) => id,
+            Node::AssignmentPattern(AssignmentPattern {
+                left: Node::Identifier(id),
+                ..
+            }) => id,
+            _ => unreachable!("parameter property must be an identifier"),
+        };
+        Some(builder::ExpressionStatement::build_template(
+            gc,
+            template::ExpressionStatement {
+                metadata: TemplateMetadata {
+                    range: prop.metadata.range,
+                    ..Default::default()
+                },
+                expression: utils::make_assignment(
+                    gc,
+                    utils::make_member(gc, utils::make_this(gc), gc.str(id.name)),
+                    utils::make_identifier(gc, id.name),
+                ),
+                directive: None,
+            },
+        ))
+    });
+    let assignments: Vec<&Node> = assignments.collect();
+
+    let block = match func.body {
+        Node::BlockStatement(block) => block,
+        _ => unreachable!("function body must be a block"),
+    };
+    let is_super_call = |stmt: &Node| {
+        matches!(
+            stmt,
+            Node::ExpressionStatement(ExpressionStatement {
+                expression: Node::CallExpression(CallExpression {
+                    callee: Node::Super(_),
+                    ..
+                }),
+                ..
+            })
+        )
+    };
+    let insert_at = block
+        .body
+        .iter()
+        .position(is_super_call)
+        .map_or(0, |i| i + 1);
+
+    let mut body: Vec<&Node> = block.body.iter().collect();
+    body.splice(insert_at..insert_at, assignments);
+
+    let mut builder = builder::BlockStatement::from_node(block);
+    builder.body(NodeList::from_iter(gc, body));
+    builder.build_forced(gc)
+}
+
+/// Lower `enum E {...}` to `var E; (function (E) {...})(E || (E = {}));`, like TypeScript, so
+/// that several declarations of the same enum are merged.
+/// Numeric members are mapped back to their names: `E[E.A = 0] = 'A'`.
+/// Members without an initializer continue counting from the previous member, and references to
+/// other members in initializers are qualified with the enum.
+fn transform_enum<'gc>(
+    gc: &'gc GCLock<'_, '_>,
+    n: &'gc TSEnumDeclaration<'gc>,
+    outer: Option<Atom>,
+) -> Vec<&'gc Node<'gc>> {
+    let name = utils::ident_name(n.id);
+    let members: Vec<&'gc TSEnumMember<'gc>> = n
+        .members
+        .iter()
+        .map(|m| match m {
+            Node::TSEnumMember(member) => member,
+            _ => unreachable!("enum must only contain members"),
+        })
+        .collect();
+    let member_names: Vec<Atom> = members
+        .iter()
+        .filter_map(|member| match member.id {
+            Node::Identifier(id) => Some(id.name),
+            _ => None,
+        })
+        .collect();
+    let mut values: Vec<&'gc Node<'gc>> = vec![];
+    let mut body = vec![];
+    for (i, member) in members.iter().enumerate() {
+        let value = match (member.initializer, i.checked_sub(1)) {
+            (Some(init), _) => {
+                let mut qualify = QualifyEnumMembers {
+                    name,
+                    members: &member_names,
+                };
+                utils::changed_or(VisitorMut::call(&mut qualify, gc, init, None), init)
+            }
+            (None, None) => utils::make_number(gc, 0.0),
+            (None, Some(prev)) => match values[prev] {
+                Node::NumericLiteral(NumericLiteral { value, .. }) => {
+                    utils::make_number(gc, value + 1.0)
+                }
+                _ => utils::make_binary(
+                    gc,
+                    BinaryExpressionOperator::Plus,
+                    make_enum_member(gc, name, members[prev]),
+                    utils::make_number(gc, 1.0),
+                ),
+            },
+        };
+        values.push(value);
+
+        let assignment = utils::make_assignment(gc, make_enum_member(gc, name, member), value);
+        let expression = if matches!(value, Node::StringLiteral(_) | Node::TemplateLiteral(_)) {
+            assignment
+        } else {
+            let member_name = match member.id {
+                Node::Identifier(id) => utils::make_string(gc, gc.str(id.name)),
+                Node::StringLiteral(lit) => builder::StringLiteral::build_template(
+                    gc,
+                    template::StringLiteral {
+                        metadata: Default::default(),
+                        value: lit.value,
+                    },
+                ),
+                _ => unreachable!("enum member must be named by an identifier or a string"),
+            };
+            utils::make_assignment(
+                gc,
+                utils::make_computed_member(gc, utils::make_identifier(gc, name), assignment),
+                member_name,
+            )
+        };
+        body.push(builder::ExpressionStatement::build_template(
+            gc,
+            template::ExpressionStatement {
+                metadata: TemplateMetadata {
+                    range: member.metadata.range,
+                    comments: member.metadata.comments,
+                    ..Default::default()
+                },
+                expression,
+                directive: None,
+            },
+        ));
+    }
+
+    make_merged_declaration(
+        gc,
+        name,
+        outer,
+        TemplateMetadata {
+            range: n.metadata.range,
+            comments: n.metadata.comments,
+            ..Default::default()
+        },
+        body,
+    )
+}
+
+/// Qualify the references to the members of the enum `name` in an initializer: `A` becomes
+/// `E.A`.
+struct QualifyEnumMembers<'a> {
+    name: Atom,
+    members: &'a [Atom],
+}
+
+impl<'gc> VisitorMut<'gc> for QualifyEnumMembers<'_> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            Node::Identifier(id)
+                if utils::is_reference(parent) && self.members.contains(&id.name) =>
+            {
+                TransformResult::Changed(utils::make_member(
+                    gc,
+                    utils::make_identifier(gc, self.name),
+                    gc.str(id.name),
+                ))
+            }
+            _ => node.visit_children_mut(gc, self),
+        }
+    }
+}
+
+/// Build `var E; (function (E) {...body})(E || (E = {}));`, which creates the object `E` or
+/// extends the one created by a previous declaration of `E`. The `var` has the `metadata` of the
+/// declaration.
+/// If `E` is exported from the namespace `outer`, the object is shared with `outer.E`:
+/// `(function (E) {...body})(E = N.E || (N.E = {}))`.
+fn make_merged_declaration<'gc>(
+    gc: &'gc GCLock<'_, '_>,
+    name: Atom,
+    outer: Option<Atom>,
+    metadata: TemplateMetadata<'gc>,
+    body: Vec<&'gc Node<'gc>>,
+) -> Vec<&'gc Node<'gc>> {
+    let var = builder::VariableDeclaration::build_template(
+        gc,
+        template::VariableDeclaration {
+            metadata,
+            kind: VariableDeclarationKind::Var,
+            declarations: NodeList::from_iter(
+                gc,
+                [builder::VariableDeclarator::build_template(
+                    gc,
+                    template::VariableDeclarator {
+                        metadata: Default::default(),
+                        id: utils::make_identifier(gc, name),
+                        init: None,
+                    },
+                )],
+            ),
+        },
+    );
+    let target = || match outer {
+        Some(outer) => utils::make_member(gc, utils::make_identifier(gc, outer), gc.str(name)),
+        None => utils::make_identifier(gc, name),
+    };
+    let object = utils::make_logical(
+        gc,
+        LogicalExpressionOperator::Or,
+        target(),
+        utils::make_assignment(gc, target(), utils::make_object(gc, vec![])),
+    );
+    let object = match outer {
+        Some(_) => utils::make_assignment(gc, utils::make_identifier(gc, name), object),
+        None => object,
+    };
+    let function = utils::make_function(gc, None, vec![utils::make_identifier(gc, name)], body);
+    vec![
+        var,
+        utils::make_expression_statement(gc, utils::make_call(gc, function, vec![object])),
+    ]
+}
+
+/// Build a reference to `member` of the enum `name`: `E.A` or `E['a']`.
+fn make_enum_member<'gc>(
+    gc: &'gc GCLock<'_, '_>,
+    name: Atom,
+    member: &'gc TSEnumMember<'gc>,
+) -> &'gc Node<'gc> {
+    let object = utils::make_identifier(gc, name);
+    match member.id {
+        Node::Identifier(id) => utils::make_member(gc, object, gc.str(id.name)),
+        Node::StringLiteral(lit) => utils::make_computed_member(
+            gc,
+            object,
+            builder::StringLiteral::build_template(
+                gc,
+                template::StringLiteral {
+                    metadata: Default::default(),
+                    value: lit.value,
+                },
+            ),
+        ),
+        _ => unreachable!("enum member must be named by an identifier or a string"),
+    }
+}
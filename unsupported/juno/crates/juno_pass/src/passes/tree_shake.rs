@@ -0,0 +1,946 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Remove the exports of ES modules which aren't imported by any other module.
+//!
+//! [`TreeShaking::analyze`] follows the imports between a set of modules from their entry
+//! points, finding which exports are used and which top-level declarations they need.
+//! The [`TreeShake`] pass of each module then removes everything else. For example, if only
+//! `f` is imported from
+//! ```js
+//! import {h} from './b';
+//! function helper(x) { return h(x); }
+//! export function f() { return 1; }
+//! export function g() { return helper(2); }
+//! ```
+//! it becomes
+//! ```js
+//! export function f() { return 1; }
+//! ```
+//! and `h` isn't used from `./b` anymore either.
+//!
+//! Statements which may have side effects are kept along with the declarations they use.
+//! Modules which may have side effects are kept when they're imported, even if none of their
+//! exports are used, while other modules are dropped entirely.
+
+use crate::utils;
+use crate::Pass;
+use juno::ast::*;
+use juno::sema::{DeclId, Resolution, SemContext};
+use juno_support::atom_table::Atom;
+use juno_support::source_manager::SourceId;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default)]
+pub struct TreeShakeOptions {
+    /// Modules all of whose exports are used.
+    pub entry_points: Vec<SourceId>,
+    /// Glob patterns matching the names of the modules which may have side effects when
+    /// they're imported, like the `sideEffects` field of `package.json`.
+    /// `None` means that every module may have side effects.
+    pub side_effects: Option<Vec<String>>,
+}
+
+/// A module which has been resolved by `sema::resolve_module`.
+pub struct ShakeModule<'a, 'gc> {
+    pub id: SourceId,
+    pub ast: &'gc Node<'gc>,
+    pub sem: &'a SemContext,
+}
+
+/// Which export of a module is imported.
+#[derive(Debug, Copy, Clone)]
+enum Imported {
+    Named(Atom),
+    Namespace,
+}
+
+/// What an export of a module refers to.
+enum Export {
+    /// A top-level declaration of the module.
+    Decl(DeclId),
+    /// The value of an `export default` which doesn't declare a name, which is the item with
+    /// this index.
+    Item(usize),
+    /// An export of another module, if it was resolved.
+    Reexport(Option<SourceId>, Imported),
+}
+
+/// A part of the top level of a module which can be removed on its own.
+struct Item {
+    /// The statement, the declaration of an `export`, or a declarator of a top-level
+    /// variable declaration.
+    node: NodeRc,
+    side_effects: bool,
+    /// Declarations referenced in the item, including the ones it declares.
+    references: Vec<DeclId>,
+}
+
+/// What a top-level statement of a module consists of.
+enum Statement {
+    /// Statements and declarations made of the items with these indices.
+    Items(Vec<usize>),
+    /// Exported declaration made of the items with these indices.
+    ExportDeclaration {
+        items: Vec<usize>,
+        names: Vec<Atom>,
+        /// Whether the declaration is valid without the `export`.
+        named: bool,
+    },
+    /// Import, and the declarations of its specifiers.
+    Import {
+        source: Option<SourceId>,
+        specifiers: Vec<(NodeRc, Option<DeclId>)>,
+    },
+    /// Re-export of another module, and the exported names of its specifiers.
+    ExportFrom {
+        source: Option<SourceId>,
+        specifiers: Vec<(NodeRc, Atom)>,
+    },
+    /// Export of local bindings, and the exported names of its specifiers.
+    ExportSpecifiers(Vec<(NodeRc, Atom)>),
+    ExportAll(Option<SourceId>),
+    /// Statement which is always kept, like type-only imports and exports.
+    Other,
+}
+
+/// Everything the analysis needs to know about a module.
+#[derive(Default)]
+struct ModuleInfo {
+    side_effects: bool,
+    /// Whether the module uses `eval` or `with`, so any of its bindings may be used.
+    unresolvable: bool,
+    statements: Vec<(NodeRc, Statement)>,
+    items: Vec<Item>,
+    /// Items declaring each top-level declaration.
+    decl_items: HashMap<DeclId, Vec<usize>>,
+    exports: HashMap<Atom, Export>,
+    star_exports: Vec<Option<SourceId>>,
+    imports: HashMap<DeclId, (Option<SourceId>, Imported)>,
+    /// Modules passed to `require` or `import()`, which may use any of their exports.
+    requires: Vec<SourceId>,
+}
+
+impl ModuleInfo {
+    fn new<'gc>(gc: &'gc GCLock, module: &ShakeModule<'_, 'gc>, side_effects: bool) -> Self {
+        let sem = module.sem;
+        let mut info = ModuleInfo {
+            side_effects,
+            unresolvable: sem.all_scopes().iter().any(|scope| scope.local_eval)
+                || sem
+                    .all_ident_decls()
+                    .values()
+                    .any(|res| matches!(res, Resolution::Unresolvable)),
+            ..Default::default()
+        };
+        for (node, &target) in sem.all_requires() {
+            if let Node::CallExpression(_) | Node::ImportExpression(_) = node.node(gc) {
+                info.requires.push(target);
+            }
+        }
+        if let Node::Module(Module { body, .. }) = module.ast {
+            for stmt in body.iter() {
+                let statement = info.collect_statement(gc, sem, stmt);
+                info.statements
+                    .push((NodeRc::from_node(gc, stmt), statement));
+            }
+        }
+        info
+    }
+
+    fn collect_statement<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        sem: &SemContext,
+        stmt: &'gc Node<'gc>,
+    ) -> Statement {
+        let source = sem
+            .all_requires()
+            .get(&NodeRc::from_node(gc, stmt))
+            .copied();
+        match stmt {
+            Node::ImportDeclaration(ImportDeclaration {
+                specifiers,
+                import_kind: ImportKind::Value,
+                ..
+            }) => {
+                let mut result = vec![];
+                for specifier in specifiers.iter() {
+                    let (local, imported) = match specifier {
+                        Node::ImportSpecifier(ImportSpecifier {
+                            import_kind: ImportKind::Value,
+                            imported,
+                            local,
+                            ..
+                        }) => (*local, Imported::Named(utils::ident_name(imported))),
+                        Node::ImportDefaultSpecifier(ImportDefaultSpecifier { local, .. }) => {
+                            (*local, Imported::Named(gc.atom("default")))
+                        }
+                        Node::ImportNamespaceSpecifier(ImportNamespaceSpecifier {
+                            local, ..
+                        }) => (*local, Imported::Namespace),
+                        _ => continue,
+                    };
+                    let decl = match sem.ident_decl(&NodeRc::from_node(gc, local)) {
+                        Some(Resolution::Decl(decl)) => {
+                            self.imports.insert(decl, (source, imported));
+                            Some(decl)
+                        }
+                        _ => None,
+                    };
+                    result.push((NodeRc::from_node(gc, specifier), decl));
+                }
+                Statement::Import {
+                    source,
+                    specifiers: result,
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                specifiers,
+                source: Some(_),
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                let mut result = vec![];
+                for specifier in specifiers.iter() {
+                    let (exported, imported) = match specifier {
+                        Node::ExportSpecifier(ExportSpecifier {
+                            exported, local, ..
+                        }) => (
+                            utils::ident_name(exported),
+                            Imported::Named(utils::ident_name(local)),
+                        ),
+                        Node::ExportNamespaceSpecifier(ExportNamespaceSpecifier {
+                            exported,
+                            ..
+                        }) => (utils::ident_name(exported), Imported::Namespace),
+                        _ => continue,
+                    };
+                    self.exports
+                        .insert(exported, Export::Reexport(source, imported));
+                    result.push((NodeRc::from_node(gc, specifier), exported));
+                }
+                Statement::ExportFrom {
+                    source,
+                    specifiers: result,
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                declaration: Some(declaration),
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                let (items, declared) = self.collect_declaration(gc, sem, declaration);
+                let mut names = vec![];
+                for (name, decl) in declared {
+                    self.exports.insert(name, Export::Decl(decl));
+                    names.push(name);
+                }
+                Statement::ExportDeclaration {
+                    items,
+                    names,
+                    named: true,
+                }
+            }
+            Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                specifiers,
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                let mut result = vec![];
+                for specifier in specifiers.iter() {
+                    if let Node::ExportSpecifier(ExportSpecifier {
+                        exported, local, ..
+                    }) = specifier
+                    {
+                        let exported = utils::ident_name(exported);
+                        if let Some(Resolution::Decl(decl)) =
+                            sem.ident_decl(&NodeRc::from_node(gc, local))
+                        {
+                            self.exports.insert(exported, Export::Decl(decl));
+                        }
+                        result.push((NodeRc::from_node(gc, specifier), exported));
+                    }
+                }
+                Statement::ExportSpecifiers(result)
+            }
+            Node::ExportDefaultDeclaration(ExportDefaultDeclaration { declaration, .. }) => {
+                let default = gc.atom("default");
+                match declaration {
+                    Node::FunctionDeclaration(FunctionDeclaration { id: Some(_), .. })
+                    | Node::ClassDeclaration(ClassDeclaration { id: Some(_), .. }) => {
+                        let (items, declared) = self.collect_declaration(gc, sem, declaration);
+                        for (_, decl) in declared {
+                            self.exports.insert(default, Export::Decl(decl));
+                        }
+                        Statement::ExportDeclaration {
+                            items,
+                            names: vec![default],
+                            named: true,
+                        }
+                    }
+                    _ => {
+                        let side_effects = match declaration {
+                            Node::FunctionDeclaration(_) => false,
+                            Node::ClassDeclaration(ClassDeclaration {
+                                super_class, body, ..
+                            }) => !utils::is_pure_class(gc, sem, *super_class, body),
+                            _ => !utils::is_pure(gc, sem, declaration),
+                        };
+                        let item = self.add_item(gc, sem, declaration, side_effects, &[]);
+                        self.exports.insert(default, Export::Item(item));
+                        Statement::ExportDeclaration {
+                            items: vec![item],
+                            names: vec![default],
+                            named: !matches!(
+                                declaration,
+                                Node::FunctionDeclaration(_) | Node::ClassDeclaration(_)
+                            ),
+                        }
+                    }
+                }
+            }
+            Node::ExportAllDeclaration(ExportAllDeclaration {
+                export_kind: ExportKind::Value,
+                ..
+            }) => {
+                self.star_exports.push(source);
+                Statement::ExportAll(source)
+            }
+            Node::ImportDeclaration(_)
+            | Node::ExportNamedDeclaration(_)
+            | Node::ExportAllDeclaration(_) => Statement::Other,
+            _ => Statement::Items(self.collect_declaration(gc, sem, stmt).0),
+        }
+    }
+
+    /// Add the items of the declaration or statement `node`.
+    /// Return their indices and the names and declarations of the bindings they declare.
+    fn collect_declaration<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        sem: &SemContext,
+        node: &'gc Node<'gc>,
+    ) -> (Vec<usize>, Vec<(Atom, DeclId)>) {
+        let mut items = vec![];
+        let mut ids = vec![];
+        match node {
+            Node::VariableDeclaration(VariableDeclaration { declarations, .. }) => {
+                for declarator in declarations.iter() {
+                    let VariableDeclarator { id, init, .. } =
+                        node_cast!(Node::VariableDeclarator, declarator);
+                    // Destructuring may call getters and iterators.
+                    let side_effects = !matches!(id, Node::Identifier(_))
+                        || init.is_some_and(|init| !utils::is_pure(gc, sem, init));
+                    let start = ids.len();
+                    utils::collect_binding_identifiers(id, &mut ids);
+                    items.push(self.add_item(gc, sem, declarator, side_effects, &ids[start..]));
+                }
+            }
+            Node::FunctionDeclaration(FunctionDeclaration { id: Some(id), .. }) => {
+                ids.push(*id);
+                items.push(self.add_item(gc, sem, node, false, &ids));
+            }
+            Node::ClassDeclaration(ClassDeclaration {
+                id: Some(id),
+                super_class,
+                body,
+                ..
+            }) => {
+                ids.push(*id);
+                let side_effects = !utils::is_pure_class(gc, sem, *super_class, body);
+                items.push(self.add_item(gc, sem, node, side_effects, &ids));
+            }
+            Node::EmptyStatement(_) => items.push(self.add_item(gc, sem, node, false, &[])),
+            _ => items.push(self.add_item(gc, sem, node, true, &[])),
+        }
+        let declared = ids
+            .iter()
+            .filter_map(|id| match sem.ident_decl(&NodeRc::from_node(gc, id)) {
+                Some(Resolution::Decl(decl)) => Some((utils::ident_name(id), decl)),
+                _ => None,
+            })
+            .collect();
+        (items, declared)
+    }
+
+    /// Add the item `node` declaring the identifiers `ids` and return its index.
+    fn add_item<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        sem: &SemContext,
+        node: &'gc Node<'gc>,
+        side_effects: bool,
+        ids: &[&'gc Node<'gc>],
+    ) -> usize {
+        let index = self.items.len();
+        for id in ids {
+            if let Some(Resolution::Decl(decl)) = sem.ident_decl(&NodeRc::from_node(gc, id)) {
+                self.decl_items.entry(decl).or_default().push(index);
+            }
+        }
+        let mut references = References { sem, decls: vec![] };
+        references.call(gc, node, None);
+        self.items.push(Item {
+            node: NodeRc::from_node(gc, node),
+            side_effects: side_effects || self.unresolvable,
+            references: references.decls,
+        });
+        index
+    }
+}
+
+/// Collects the declarations which identifiers resolve to.
+struct References<'a> {
+    sem: &'a SemContext,
+    decls: Vec<DeclId>,
+}
+
+impl<'gc> Visitor<'gc> for References<'_> {
+    fn call(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>, _path: Option<Path<'gc>>) {
+        if let Node::Identifier(_) = node {
+            if let Some(Resolution::Decl(decl)) = self.sem.ident_decl(&NodeRc::from_node(gc, node))
+            {
+                self.decls.push(decl);
+            }
+        }
+        node.visit_children(gc, self);
+    }
+}
+
+/// Whether the file `name` matches the glob `pattern`, where `*` matches anything but `/`
+/// and `**` matches anything. Patterns without a `/` are matched against the file name.
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[u8], name: &[u8]) -> bool {
+        match pattern {
+            [] => name.is_empty(),
+            [b'*', b'*', rest @ ..] => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+            [b'*', rest @ ..] => (0..=name.len())
+                .take_while(|&i| i == 0 || name[i - 1] != b'/')
+                .any(|i| matches(rest, &name[i..])),
+            [b'?', rest @ ..] => !name.is_empty() && name[0] != b'/' && matches(rest, &name[1..]),
+            [c, rest @ ..] => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+    let name = if pattern.contains('/') {
+        name.strip_prefix("./").unwrap_or(name)
+    } else {
+        name.rsplit('/').next().unwrap_or(name)
+    };
+    matches(
+        pattern.strip_prefix("./").unwrap_or(pattern).as_bytes(),
+        name.as_bytes(),
+    )
+}
+
+enum Work {
+    Include(SourceId),
+    UseAll(SourceId),
+    UseExport(SourceId, Atom),
+    LiveDecl(SourceId, DeclId),
+    LiveItem(SourceId, usize),
+}
+
+/// Which modules, exports and top-level items are used from the entry points.
+#[derive(Default)]
+struct Marks {
+    included: HashSet<SourceId>,
+    used_all: HashSet<SourceId>,
+    used: HashSet<(SourceId, Atom)>,
+    live_decls: HashSet<(SourceId, DeclId)>,
+    live_items: HashSet<(SourceId, usize)>,
+}
+
+impl Marks {
+    fn is_used(&self, id: SourceId, name: Atom) -> bool {
+        self.used_all.contains(&id) || self.used.contains(&(id, name))
+    }
+}
+
+/// Which exports of a set of modules are used, starting from their entry points.
+pub struct TreeShaking {
+    marks: Marks,
+    passes: HashMap<SourceId, TreeShake>,
+}
+
+impl TreeShaking {
+    /// Analyze the imports and exports of `modules`, which must all be parts of the
+    /// same AST context.
+    pub fn analyze<'gc>(
+        gc: &'gc GCLock,
+        modules: &[ShakeModule<'_, 'gc>],
+        options: &TreeShakeOptions,
+    ) -> Self {
+        let infos: HashMap<SourceId, ModuleInfo> = modules
+            .iter()
+            .map(|module| {
+                let side_effects = match &options.side_effects {
+                    None => true,
+                    Some(patterns) => {
+                        let name = gc.sm().source_name(module.id);
+                        patterns.iter().any(|pattern| matches_glob(pattern, name))
+                    }
+                };
+                (module.id, ModuleInfo::new(gc, module, side_effects))
+            })
+            .collect();
+
+        let mut marks = Marks::default();
+        let mut work: Vec<Work> = options
+            .entry_points
+            .iter()
+            .map(|&id| Work::UseAll(id))
+            .collect();
+        while let Some(item) = work.pop() {
+            mark(&infos, &mut marks, &mut work, item);
+        }
+
+        let passes = infos
+            .iter()
+            .map(|(&id, info)| (id, TreeShake::new(&infos, &marks, id, info)))
+            .collect();
+        TreeShaking { marks, passes }
+    }
+
+    /// Whether any part of the module `id` is needed.
+    pub fn is_included(&self, id: SourceId) -> bool {
+        self.marks.included.contains(&id)
+    }
+
+    /// Whether the export `name` of the module `id` is imported by another module.
+    pub fn is_export_used(&self, id: SourceId, name: Atom) -> bool {
+        self.marks.is_used(id, name)
+    }
+
+    /// Take the pass which removes what isn't needed from the module `id`.
+    /// Modules which weren't analyzed are left alone.
+    pub fn take_pass(&mut self, id: SourceId) -> TreeShake {
+        self.passes.remove(&id).unwrap_or_default()
+    }
+}
+
+/// Whether the module `id` exports `name`, possibly through `export *`.
+fn provides(
+    infos: &HashMap<SourceId, ModuleInfo>,
+    id: SourceId,
+    name: Atom,
+    visited: &mut HashSet<SourceId>,
+) -> bool {
+    let info = match infos.get(&id) {
+        Some(info) => info,
+        // The module wasn't analyzed, so it may export anything.
+        None => return true,
+    };
+    if !visited.insert(id) {
+        return false;
+    }
+    info.exports.contains_key(&name)
+        || info.star_exports.iter().any(|star| match star {
+            Some(star) => provides(infos, *star, name, visited),
+            None => true,
+        })
+}
+
+fn mark(
+    infos: &HashMap<SourceId, ModuleInfo>,
+    marks: &mut Marks,
+    work: &mut Vec<Work>,
+    item: Work,
+) {
+    let use_export = |work: &mut Vec<Work>, id: SourceId, export: &Export| match export {
+        Export::Decl(decl) => work.push(Work::LiveDecl(id, *decl)),
+        Export::Item(item) => work.push(Work::LiveItem(id, *item)),
+        Export::Reexport(Some(source), Imported::Named(name)) => {
+            work.push(Work::UseExport(*source, *name))
+        }
+        Export::Reexport(Some(source), Imported::Namespace) => work.push(Work::UseAll(*source)),
+        Export::Reexport(None, _) => {}
+    };
+    let use_import = |work: &mut Vec<Work>, (source, imported): (Option<SourceId>, Imported)| match (
+        source, imported,
+    ) {
+        (Some(source), Imported::Named(name)) => work.push(Work::UseExport(source, name)),
+        (Some(source), Imported::Namespace) => work.push(Work::UseAll(source)),
+        (None, _) => {}
+    };
+
+    match item {
+        Work::Include(id) => {
+            let info = match infos.get(&id) {
+                Some(info) if marks.included.insert(id) => info,
+                _ => return,
+            };
+            for (index, item) in info.items.iter().enumerate() {
+                if item.side_effects {
+                    work.push(Work::LiveItem(id, index));
+                }
+            }
+            for (_, statement) in &info.statements {
+                if let Statement::Import {
+                    source: Some(source),
+                    ..
+                }
+                | Statement::ExportFrom {
+                    source: Some(source),
+                    ..
+                }
+                | Statement::ExportAll(Some(source)) = statement
+                {
+                    if infos.get(source).is_some_and(|info| info.side_effects) {
+                        work.push(Work::Include(*source));
+                    }
+                }
+            }
+            for source in &info.requires {
+                work.push(Work::UseAll(*source));
+            }
+            if info.unresolvable {
+                for &decl in info.imports.keys() {
+                    work.push(Work::LiveDecl(id, decl));
+                }
+            }
+        }
+        Work::UseAll(id) => {
+            let info = match infos.get(&id) {
+                Some(info) if marks.used_all.insert(id) => info,
+                _ => return,
+            };
+            work.push(Work::Include(id));
+            for export in info.exports.values() {
+                use_export(work, id, export);
+            }
+            for star in info.star_exports.iter().flatten() {
+                work.push(Work::UseAll(*star));
+            }
+        }
+        Work::UseExport(id, name) => {
+            let info = match infos.get(&id) {
+                Some(info) => info,
+                None => return,
+            };
+            if marks.used_all.contains(&id)
+                || !provides(infos, id, name, &mut HashSet::new())
+                || !marks.used.insert((id, name))
+            {
+                return;
+            }
+            work.push(Work::Include(id));
+            match info.exports.get(&name) {
+                Some(export) => use_export(work, id, export),
+                None => {
+                    for star in info.star_exports.iter().flatten() {
+                        work.push(Work::UseExport(*star, name));
+                    }
+                }
+            }
+        }
+        Work::LiveDecl(id, decl) => {
+            if !marks.live_decls.insert((id, decl)) {
+                return;
+            }
+            let info = &infos[&id];
+            for &item in info.decl_items.get(&decl).into_iter().flatten() {
+                work.push(Work::LiveItem(id, item));
+            }
+            if let Some(import) = info.imports.get(&decl) {
+                use_import(work, *import);
+            }
+        }
+        Work::LiveItem(id, item) => {
+            if !marks.live_items.insert((id, item)) {
+                return;
+            }
+            work.push(Work::Include(id));
+            for &decl in &infos[&id].items[item].references {
+                work.push(Work::LiveDecl(id, decl));
+            }
+        }
+    }
+}
+
+/// Remove the parts of a module which aren't used, as found by [`TreeShaking::analyze`].
+#[derive(Default)]
+pub struct TreeShake {
+    /// Whether nothing in the module is used.
+    remove_all: bool,
+    /// Statements, declarations, declarators and specifiers to remove.
+    remove: HashSet<NodeRc>,
+    /// Exported declarations which are used, but whose exports aren't.
+    unexport: HashSet<NodeRc>,
+    /// Imports and re-exports to replace with an import of the module for its side effects.
+    bare_import: HashSet<NodeRc>,
+}
+
+impl TreeShake {
+    fn new(
+        infos: &HashMap<SourceId, ModuleInfo>,
+        marks: &Marks,
+        id: SourceId,
+        info: &ModuleInfo,
+    ) -> Self {
+        let mut result = TreeShake {
+            remove_all: !marks.included.contains(&id),
+            ..Default::default()
+        };
+        if result.remove_all {
+            return result;
+        }
+        let has_side_effects = |source: Option<SourceId>| {
+            source.is_none_or(|s| infos.get(&s).is_none_or(|i| i.side_effects))
+        };
+        let is_live = |item: &usize| marks.live_items.contains(&(id, *item));
+        for (stmt, statement) in &info.statements {
+            let (source, kept, total) = match statement {
+                Statement::Items(items) => {
+                    for item in items.iter().filter(|item| !is_live(item)) {
+                        result.remove.insert(info.items[*item].node.clone());
+                    }
+                    continue;
+                }
+                Statement::ExportDeclaration {
+                    items,
+                    names,
+                    named,
+                } => {
+                    for item in items.iter().filter(|item| !is_live(item)) {
+                        result.remove.insert(info.items[*item].node.clone());
+                    }
+                    if *named
+                        && items.iter().any(is_live)
+                        && !names.iter().any(|name| marks.is_used(id, *name))
+                    {
+                        result.unexport.insert(stmt.clone());
+                    }
+                    continue;
+                }
+                Statement::Import { source, specifiers } => {
+                    let mut kept = 0;
+                    for (specifier, decl) in specifiers {
+                        match decl {
+                            Some(decl) if !marks.live_decls.contains(&(id, *decl)) => {
+                                result.remove.insert(specifier.clone());
+                            }
+                            _ => kept += 1,
+                        }
+                    }
+                    (*source, kept, specifiers.len())
+                }
+                Statement::ExportFrom { source, specifiers } => {
+                    let mut kept = 0;
+                    for (specifier, name) in specifiers {
+                        if marks.is_used(id, *name) {
+                            kept += 1;
+                        } else {
+                            result.remove.insert(specifier.clone());
+                        }
+                    }
+                    (*source, kept, specifiers.len())
+                }
+                Statement::ExportSpecifiers(specifiers) => {
+                    let mut kept = 0;
+                    for (specifier, name) in specifiers {
+                        if marks.is_used(id, *name) {
+                            kept += 1;
+                        } else {
+                            result.remove.insert(specifier.clone());
+                        }
+                    }
+                    if kept == 0 {
+                        result.remove.insert(stmt.clone());
+                    }
+                    continue;
+                }
+                Statement::ExportAll(source) => {
+                    if source
+                        .is_some_and(|s| infos.contains_key(&s) && !marks.included.contains(&s))
+                    {
+                        result.remove.insert(stmt.clone());
+                    }
+                    continue;
+                }
+                Statement::Other => continue,
+            };
+            // Nothing is imported from the module anymore.
+            if kept == 0 {
+                if !has_side_effects(source) {
+                    result.remove.insert(stmt.clone());
+                } else if total > 0 {
+                    result.bare_import.insert(stmt.clone());
+                }
+            }
+        }
+        result
+    }
+
+    fn contains<'gc>(set: &HashSet<NodeRc>, gc: &'gc GCLock, node: &'gc Node<'gc>) -> bool {
+        !set.is_empty() && set.contains(&NodeRc::from_node(gc, node))
+    }
+
+    /// Return what `stmt` becomes, or `None` if it's removed.
+    fn shake_statement<'gc>(
+        &self,
+        gc: &'gc GCLock,
+        stmt: &'gc Node<'gc>,
+    ) -> Option<&'gc Node<'gc>> {
+        if Self::contains(&self.remove, gc, stmt) {
+            return None;
+        }
+        match stmt {
+            Node::ImportDeclaration(ImportDeclaration { source, .. })
+            | Node::ExportNamedDeclaration(ExportNamedDeclaration {
+                source: Some(source),
+                ..
+            }) if Self::contains(&self.bare_import, gc, stmt) => {
+                Some(builder::ImportDeclaration::build_template(
+                    gc,
+                    template::ImportDeclaration {
+                        metadata: TemplateMetadata {
+                            range: *stmt.range(),
+                            ..Default::default()
+                        },
+                        specifiers: NodeList::new(gc),
+                        source,
+                        assertions: None,
+                        import_kind: ImportKind::Value,
+                    },
+                ))
+            }
+            Node::ExportNamedDeclaration(
+                export @ ExportNamedDeclaration {
+                    declaration: Some(declaration),
+                    ..
+                },
+            ) => {
+                let new_declaration = self.shake_declaration(gc, declaration)?;
+                if Self::contains(&self.unexport, gc, stmt) {
+                    Some(new_declaration)
+                } else if new_declaration.ptr_eq(declaration) {
+                    Some(stmt)
+                } else {
+                    let mut builder = builder::ExportNamedDeclaration::from_node(export);
+                    builder.declaration(Some(new_declaration));
+                    Some(builder.build_forced(gc))
+                }
+            }
+            Node::ExportDefaultDeclaration(ExportDefaultDeclaration { declaration, .. }) => {
+                if Self::contains(&self.remove, gc, declaration) {
+                    None
+                } else if !Self::contains(&self.unexport, gc, stmt) {
+                    Some(stmt)
+                } else if let Node::FunctionDeclaration(_) | Node::ClassDeclaration(_) = declaration
+                {
+                    Some(declaration)
+                } else {
+                    Some(utils::make_expression_statement(gc, declaration))
+                }
+            }
+            Node::ExportNamedDeclaration(export @ ExportNamedDeclaration { specifiers, .. }) => {
+                let kept = match self.shake_list(gc, *specifiers) {
+                    Some(kept) => kept,
+                    None => return Some(stmt),
+                };
+                let mut builder = builder::ExportNamedDeclaration::from_node(export);
+                builder.specifiers(kept);
+                Some(builder.build_forced(gc))
+            }
+            Node::ImportDeclaration(import @ ImportDeclaration { specifiers, .. }) => {
+                let kept = match self.shake_list(gc, *specifiers) {
+                    Some(kept) => kept,
+                    None => return Some(stmt),
+                };
+                let mut builder = builder::ImportDeclaration::from_node(import);
+                builder.specifiers(kept);
+                Some(builder.build_forced(gc))
+            }
+            _ => self.shake_declaration(gc, stmt),
+        }
+    }
+
+    /// Return what the declaration `node` becomes, or `None` if it's removed.
+    fn shake_declaration<'gc>(
+        &self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+    ) -> Option<&'gc Node<'gc>> {
+        if Self::contains(&self.remove, gc, node) {
+            return None;
+        }
+        match node {
+            Node::VariableDeclaration(decl @ VariableDeclaration { declarations, .. }) => {
+                let kept = match self.shake_list(gc, *declarations) {
+                    Some(kept) => kept,
+                    None => return Some(node),
+                };
+                if kept.is_empty() {
+                    return None;
+                }
+                let mut builder = builder::VariableDeclaration::from_node(decl);
+                builder.declarations(kept);
+                Some(builder.build_forced(gc))
+            }
+            _ => Some(node),
+        }
+    }
+
+    /// Remove the removed nodes from `list`.
+    /// Return `None` if none of them were in it.
+    fn shake_list<'gc>(&self, gc: &'gc GCLock, list: NodeList<'gc>) -> Option<NodeList<'gc>> {
+        if !list
+            .iter()
+            .any(|node| Self::contains(&self.remove, gc, node))
+        {
+            return None;
+        }
+        Some(NodeList::from_iter(
+            gc,
+            list.iter()
+                .filter(|node| !Self::contains(&self.remove, gc, node)),
+        ))
+    }
+}
+
+impl Pass for TreeShake {
+    fn name(&self) -> &'static str {
+        "Tree Shake"
+    }
+    fn description(&self) -> &'static str {
+        "Removes exports and declarations which aren't used by any module"
+    }
+
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        let module = match node {
+            Node::Module(module) => module,
+            _ => return TransformResult::Unchanged,
+        };
+        let mut changed = false;
+        let mut body = vec![];
+        for stmt in module.body.iter() {
+            let new_stmt = if self.remove_all {
+                None
+            } else {
+                self.shake_statement(gc, stmt)
+            };
+            match new_stmt {
+                Some(new_stmt) => {
+                    changed |= !new_stmt.ptr_eq(stmt);
+                    body.push(new_stmt);
+                }
+                None => changed = true,
+            }
+        }
+        if !changed {
+            return TransformResult::Unchanged;
+        }
+        let mut builder = builder::Module::from_node(module);
+        builder.body(NodeList::from_iter(gc, body));
+        TransformResult::Changed(builder.build_forced(gc))
+    }
+}
@@ -0,0 +1,1036 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Helpers shared between passes.
+
+use juno::ast::*;
+use juno::resolve_dependency::DefaultResolver;
+use juno::sema::{self, DeclId, DeclKind, LexicalScopeId, Resolution, SemContext};
+use juno_support::atom_table::Atom;
+use std::collections::{HashMap, HashSet};
+
+/// Perform semantic resolution of `root`, which must be a `Program` or a `Module`.
+/// Passes run after the input has been validated, so the diagnostics reported by the
+/// resolver have already been seen and are suppressed.
+pub(crate) fn resolve<'gc>(gc: &'gc GCLock, root: &'gc Node<'gc>) -> SemContext {
+    let file_id = root.range().file;
+    gc.sm().suppress_diagnostics(|| match root {
+        Node::Module(_) => sema::resolve_module(gc, root, file_id, &DefaultResolver::new(gc.sm())),
+        _ => sema::resolve_program(gc, file_id, root),
+    })
+}
+
+/// Append all the identifiers declared by the pattern `node` to `result`.
+pub(crate) fn collect_binding_identifiers<'gc>(
+    node: &'gc Node<'gc>,
+    result: &mut Vec<&'gc Node<'gc>>,
+) {
+    match node {
+        Node::Identifier(_) => result.push(node),
+        Node::ObjectPattern(ObjectPattern { properties, .. }) => {
+            for prop in properties.iter() {
+                match prop {
+                    Node::Property(Property { value, .. }) => {
+                        collect_binding_identifiers(value, result)
+                    }
+                    _ => collect_binding_identifiers(prop, result),
+                }
+            }
+        }
+        Node::ArrayPattern(ArrayPattern { elements, .. }) => {
+            for elem in elements.iter() {
+                collect_binding_identifiers(elem, result);
+            }
+        }
+        Node::AssignmentPattern(AssignmentPattern { left, .. }) => {
+            collect_binding_identifiers(left, result)
+        }
+        Node::RestElement(RestElement { argument, .. }) => {
+            collect_binding_identifiers(argument, result)
+        }
+        _ => {}
+    }
+}
+
+/// The name of the identifier `node`.
+pub(crate) fn ident_name(node: &Node) -> Atom {
+    node_cast!(Node::Identifier, node).name
+}
+
+/// Whether evaluating `node` certainly has no observable side effects.
+pub(crate) fn is_pure<'gc>(gc: &'gc GCLock, sem: &SemContext, node: &'gc Node<'gc>) -> bool {
+    match node {
+        Node::NullLiteral(_)
+        | Node::BooleanLiteral(_)
+        | Node::NumericLiteral(_)
+        | Node::StringLiteral(_)
+        | Node::BigIntLiteral(_)
+        | Node::RegExpLiteral(_)
+        | Node::ThisExpression(_)
+        | Node::FunctionExpression(_)
+        | Node::ArrowFunctionExpression(_) => true,
+        // Reading a global may throw or invoke a getter, and reading a let-like
+        // variable may throw before it is initialized.
+        Node::Identifier(_) => match sem.ident_decl(&NodeRc::from_node(gc, node)) {
+            Some(Resolution::Decl(decl)) => matches!(
+                sem.decl(decl).kind,
+                DeclKind::Var
+                    | DeclKind::Parameter
+                    | DeclKind::ScopedFunction
+                    | DeclKind::FunctionExprName
+            ),
+            _ => false,
+        },
+        Node::TemplateLiteral(TemplateLiteral { expressions, .. }) => expressions.is_empty(),
+        Node::ClassExpression(ClassExpression {
+            super_class, body, ..
+        }) => is_pure_class(gc, sem, *super_class, body),
+        Node::UnaryExpression(UnaryExpression {
+            operator:
+                UnaryExpressionOperator::Not
+                | UnaryExpressionOperator::Void
+                | UnaryExpressionOperator::Typeof,
+            argument,
+            ..
+        }) => is_pure(gc, sem, argument),
+        // Other operators may convert objects to primitives, which calls their methods.
+        Node::UnaryExpression(UnaryExpression {
+            operator:
+                UnaryExpressionOperator::Minus
+                | UnaryExpressionOperator::Plus
+                | UnaryExpressionOperator::BitNot,
+            argument: Node::NumericLiteral(_),
+            ..
+        }) => true,
+        Node::BinaryExpression(BinaryExpression {
+            operator:
+                BinaryExpressionOperator::StrictEquals | BinaryExpressionOperator::StrictNotEquals,
+            left,
+            right,
+            ..
+        }) => is_pure(gc, sem, left) && is_pure(gc, sem, right),
+        Node::LogicalExpression(LogicalExpression { left, right, .. }) => {
+            is_pure(gc, sem, left) && is_pure(gc, sem, right)
+        }
+        Node::ConditionalExpression(ConditionalExpression {
+            test,
+            consequent,
+            alternate,
+            ..
+        }) => is_pure(gc, sem, test) && is_pure(gc, sem, consequent) && is_pure(gc, sem, alternate),
+        Node::SequenceExpression(SequenceExpression { expressions, .. }) => {
+            expressions.iter().all(|expr| is_pure(gc, sem, expr))
+        }
+        Node::ArrayExpression(ArrayExpression { elements, .. }) => {
+            elements.iter().all(|element| match element {
+                Node::Empty(_) => true,
+                Node::SpreadElement(_) => false,
+                _ => is_pure(gc, sem, element),
+            })
+        }
+        // Computed keys are converted to property keys, which may call `toString()`.
+        Node::ObjectExpression(ObjectExpression { properties, .. }) => {
+            properties.iter().all(|prop| match prop {
+                Node::Property(Property {
+                    value, computed, ..
+                }) => !*computed && is_pure(gc, sem, value),
+                _ => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+/// Whether defining a class with `super_class` and `body` certainly has no observable side
+/// effects.
+pub(crate) fn is_pure_class<'gc>(
+    gc: &'gc GCLock,
+    sem: &SemContext,
+    super_class: Option<&'gc Node<'gc>>,
+    body: &'gc Node<'gc>,
+) -> bool {
+    if super_class.is_some_and(|super_class| !is_pure(gc, sem, super_class)) {
+        return false;
+    }
+    let ClassBody { body, .. } = node_cast!(Node::ClassBody, body);
+    body.iter().all(|member| match member {
+        Node::MethodDefinition(MethodDefinition { computed, .. }) => !*computed,
+        Node::ClassProperty(ClassProperty {
+            value,
+            computed,
+            is_static,
+            ..
+        }) => !*computed && (!*is_static || value.is_none_or(|value| is_pure(gc, sem, value))),
+        Node::ClassPrivateProperty(ClassPrivateProperty {
+            value, is_static, ..
+        }) => !*is_static || value.is_none_or(|value| is_pure(gc, sem, value)),
+        _ => false,
+    })
+}
+
+/// Replaces identifiers resolved to renamed declarations.
+pub(crate) struct Renamer<'s> {
+    pub sem: &'s SemContext,
+    pub names: HashMap<DeclId, Atom>,
+}
+
+impl<'gc> VisitorMut<'gc> for Renamer<'_> {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+        path: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        match node {
+            Node::Identifier(ident) => {
+                // These identifiers may be the same node as the local name,
+                // but they don't refer to it.
+                if let Some(Path { parent, field }) = path {
+                    match (parent, field) {
+                        (
+                            Node::Property(Property {
+                                computed: false, ..
+                            }),
+                            NodeField::key,
+                        )
+                        | (Node::ImportSpecifier(_), NodeField::imported)
+                        | (Node::ExportSpecifier(_), NodeField::exported) => {
+                            return TransformResult::Unchanged;
+                        }
+                        _ => {}
+                    }
+                }
+                if let Some(Resolution::Decl(decl)) =
+                    self.sem.ident_decl(&NodeRc::from_node(gc, node))
+                {
+                    if let Some(&name) = self.names.get(&decl) {
+                        if name != ident.name {
+                            let mut builder = builder::Identifier::from_node(ident);
+                            builder.name(name);
+                            return node.replace_with_new(
+                                builder::Builder::Identifier(builder),
+                                gc,
+                                self,
+                            );
+                        }
+                    }
+                }
+                node.visit_children_mut(gc, self)
+            }
+            Node::Property(Property {
+                shorthand: true, ..
+            }) => {
+                let result = node.visit_children_mut(gc, self);
+                match result {
+                    // The key still has the old name, so the property can't be shorthand anymore.
+                    TransformResult::Changed(Node::Property(prop)) => {
+                        let mut builder = builder::Property::from_node(prop);
+                        builder.shorthand(false);
+                        TransformResult::Changed(builder.build_forced(gc))
+                    }
+                    _ => result,
+                }
+            }
+            _ => node.visit_children_mut(gc, self),
+        }
+    }
+}
+
+/// Generates names which don't clash with any identifier in the program.
+///
+/// The scopes computed by semantic resolution aren't consulted: instead, the name of every
+/// identifier in the program is reserved. A name which no identifier uses is free in every scope,
+/// so a generated name can be declared in any function without shadowing another binding or
+/// being shadowed by one, even after the AST is transformed and the scopes no longer match it.
+/// The cost is that a name is never reused in unrelated scopes. Passes which don't otherwise
+/// change the bindings can use `ScopedNameGenerator` for their temporaries instead.
+pub(crate) struct NameGenerator {
+    used: HashSet<Atom>,
+}
+
+impl NameGenerator {
+    /// Reserve the names of all identifiers in `root`.
+    pub fn new<'gc>(gc: &'gc GCLock, root: &'gc Node<'gc>) -> Self {
+        let mut result = Self {
+            used: HashSet::new(),
+        };
+        result.reserve(gc, root);
+        result
+    }
+
+    /// Also reserve the names of all identifiers in `root`.
+    pub fn reserve<'gc>(&mut self, gc: &'gc GCLock, root: &'gc Node<'gc>) {
+        collect_identifier_names(gc, root, &mut self.used);
+    }
+
+    /// Return a new name based on `base`, such as `_base` or `_base2`.
+    pub fn fresh(&mut self, gc: &GCLock, base: &str) -> Atom {
+        fresh_name(gc, base, &mut self.used)
+    }
+}
+
+/// Generates the names of the temporaries in `TempVars`.
+pub(crate) trait FreshNames {
+    /// Return a new name based on `base`, such as `_base` or `_base2`.
+    fn fresh(&mut self, gc: &GCLock, base: &str) -> Atom;
+}
+
+impl FreshNames for NameGenerator {
+    fn fresh(&mut self, gc: &GCLock, base: &str) -> Atom {
+        NameGenerator::fresh(self, gc, base)
+    }
+}
+
+/// Generates the names of temporaries declared with `var` in the function being visited, which
+/// don't clash with the bindings visible in it according to the scopes computed by semantic
+/// resolution.
+///
+/// Unlike `NameGenerator`, a name can be reused by unrelated functions, and by the functions
+/// nested in a function since its temporaries are only used in its own scopes. The scopes must
+/// still match the AST, so passes using it may only add temporaries, not other bindings.
+pub(crate) struct ScopedNameGenerator<'s> {
+    sem: &'s SemContext,
+    /// Names of identifiers which couldn't be resolved because of `eval` or `with`, which could
+    /// refer to any variable with their name.
+    unresolvable: HashSet<Atom>,
+    /// The names which are taken in each function being visited, innermost last:
+    /// the bindings visible in its scopes and the temporaries generated for it.
+    stack: Vec<HashSet<Atom>>,
+}
+
+impl<'s> ScopedNameGenerator<'s> {
+    /// Create a generator for the program which was resolved to `sem`.
+    pub fn new(gc: &GCLock, sem: &'s SemContext) -> Self {
+        let unresolvable = sem
+            .all_ident_decls()
+            .iter()
+            .filter(|(_, resolution)| matches!(resolution, Resolution::Unresolvable))
+            .filter_map(|(node, _)| match node.node(gc) {
+                Node::Identifier(Identifier { name, .. }) => Some(*name),
+                _ => None,
+            })
+            .collect();
+        Self {
+            sem,
+            unresolvable,
+            stack: vec![],
+        }
+    }
+
+    /// Start generating names for the function or program `node`.
+    pub fn enter_function<'gc>(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>) {
+        let scope_node = match node {
+            Node::Program(_) | Node::Module(_) => node,
+            _ => node.function_like_body(),
+        };
+        let mut taken = self.unresolvable.clone();
+        match self.sem.node_scope(NodeRc::from_node(gc, scope_node)) {
+            Some(scope) => {
+                // Declarations in the enclosing scopes would be shadowed, and declarations in
+                // the blocks of the function would shadow the temporaries.
+                let mut scopes: Vec<LexicalScopeId> = self
+                    .sem
+                    .function(self.sem.scope(scope).parent_function)
+                    .scopes
+                    .clone();
+                let mut enclosing = self.sem.scope(scope).parent_scope;
+                while let Some(scope) = enclosing {
+                    scopes.push(scope);
+                    enclosing = self.sem.scope(scope).parent_scope;
+                }
+                for scope in scopes {
+                    for &decl in &self.sem.scope(scope).decls {
+                        taken.insert(self.sem.decl(decl).name);
+                    }
+                }
+            }
+            // Functions created by the pass weren't resolved, so avoid every name which is
+            // used in them or taken around them.
+            None => {
+                if let Some(enclosing) = self.stack.last() {
+                    taken.extend(enclosing);
+                }
+                collect_identifier_names(gc, node, &mut taken);
+            }
+        }
+        self.stack.push(taken);
+    }
+
+    /// Stop generating names for the innermost function.
+    pub fn exit_function(&mut self) {
+        self.stack.pop();
+    }
+}
+
+impl FreshNames for ScopedNameGenerator<'_> {
+    fn fresh(&mut self, gc: &GCLock, base: &str) -> Atom {
+        let taken = self
+            .stack
+            .last_mut()
+            .expect("temporary generated outside of a function");
+        fresh_name(gc, base, taken)
+    }
+}
+
+/// Return the first name based on `base` which isn't in `taken`, and add it.
+fn fresh_name(gc: &GCLock, base: &str, taken: &mut HashSet<Atom>) -> Atom {
+    let mut suffix = 1;
+    loop {
+        let name = if suffix == 1 {
+            gc.atom(format!("_{}", base))
+        } else {
+            gc.atom(format!("_{}{}", base, suffix))
+        };
+        if taken.insert(name) {
+            return name;
+        }
+        suffix += 1;
+    }
+}
+
+/// Add the names of all identifiers in `root` to `names`.
+fn collect_identifier_names<'gc>(gc: &'gc GCLock, root: &'gc Node<'gc>, names: &mut HashSet<Atom>) {
+    struct Collector<'a> {
+        names: &'a mut HashSet<Atom>,
+    }
+    impl<'gc> Visitor<'gc> for Collector<'_> {
+        fn call(&mut self, gc: &'gc GCLock, node: &'gc Node<'gc>, _path: Option<Path<'gc>>) {
+            match node {
+                Node::Identifier(Identifier { name, .. })
+                | Node::JSXIdentifier(JSXIdentifier { name, .. }) => {
+                    self.names.insert(*name);
+                }
+                _ => {}
+            }
+            node.visit_children(gc, self);
+        }
+    }
+    root.visit(gc, &mut Collector { names }, None);
+}
+
+/// Temporary variables which must be declared with `var` in the enclosing functions.
+#[derive(Default)]
+pub(crate) struct TempVars {
+    stack: Vec<Vec<Atom>>,
+}
+
+impl TempVars {
+    /// Whether `node` is a function or program, which declares its own temporaries.
+    pub fn is_scope(node: &Node) -> bool {
+        matches!(
+            node,
+            Node::Program(_)
+                | Node::Module(_)
+                | Node::FunctionDeclaration(_)
+                | Node::FunctionExpression(_)
+                | Node::ArrowFunctionExpression(_)
+        )
+    }
+
+    /// Declare a new temporary named after `base` in the innermost function.
+    pub fn add(&mut self, gc: &GCLock, names: &mut impl FreshNames, base: &str) -> Atom {
+        let name = names.fresh(gc, base);
+        self.stack
+            .last_mut()
+            .expect("temporary declared outside of a function")
+            .push(name);
+        name
+    }
+
+    /// Return the expressions to use for the second and first evaluation of `node`.
+    /// If evaluating `node` twice isn't safe, it's stored in a temporary by the first evaluation.
+    pub fn reuse<'gc>(
+        &mut self,
+        gc: &'gc GCLock,
+        names: &mut impl FreshNames,
+        node: &'gc Node<'gc>,
+        base: &str,
+    ) -> (&'gc Node<'gc>, &'gc Node<'gc>) {
+        match node {
+            Node::Identifier(_)
+            | Node::ThisExpression(_)
+            | Node::Super(_)
+            | Node::StringLiteral(_)
+            | Node::NumericLiteral(_) => (node, node),
+            _ => {
+                let temp = make_identifier(gc, self.add(gc, names, base));
+                (temp, make_assignment(gc, temp, node))
+            }
+        }
+    }
+
+    /// Visit the children of the function or program `node` with `visitor`, then declare the
+    /// temporaries added while visiting them.
+    pub fn visit_scope<'gc, V: VisitorMut<'gc>>(
+        visitor: &mut V,
+        temps: impl Fn(&mut V) -> &mut TempVars,
+        gc: &'gc GCLock,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        temps(visitor).stack.push(vec![]);
+        let result = node.visit_children_mut(gc, visitor);
+        let names = temps(visitor).stack.pop().unwrap();
+        if names.is_empty() {
+            return result;
+        }
+        let decl = builder::VariableDeclaration::build_template(
+            gc,
+            template::VariableDeclaration {
+                metadata: Default::default(),
+                kind: VariableDeclarationKind::Var,
+                declarations: NodeList::from_iter(
+                    gc,
+                    names.into_iter().map(|name| {
+                        builder::VariableDeclarator::build_template(
+                            gc,
+                            template::VariableDeclarator {
+                                metadata: Default::default(),
+                                id: make_identifier(gc, name),
+                                init: None,
+                            },
+                        )
+                    }),
+                ),
+            },
+        );
+        TransformResult::Changed(prepend_statements(gc, changed_or(result, node), vec![decl]))
+    }
+}
+
+/// The node resulting from `result`, which was the result of transforming `node`.
+pub(crate) fn changed_or<'gc>(
+    result: TransformResult<&'gc Node<'gc>>,
+    node: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    match result {
+        TransformResult::Changed(new_node) => new_node,
+        _ => node,
+    }
+}
+
+/// Insert `stmts` at the start of the body of the function or program `node`, after the
+/// directives. Arrow functions with an expression body are given a block body.
+pub(crate) fn prepend_statements<'gc>(
+    gc: &'gc GCLock,
+    node: &'gc Node<'gc>,
+    stmts: Vec<&'gc Node<'gc>>,
+) -> &'gc Node<'gc> {
+    let insert = |body: NodeList<'gc>, stmts: Vec<&'gc Node<'gc>>| {
+        let mut result: Vec<&Node> = body.iter().collect();
+        let directives = result
+            .iter()
+            .take_while(|stmt| {
+                matches!(
+                    stmt,
+                    Node::ExpressionStatement(ExpressionStatement {
+                        directive: Some(_),
+                        ..
+                    })
+                )
+            })
+            .count();
+        result.splice(directives..directives, stmts);
+        NodeList::from_iter(gc, result)
+    };
+    let insert_block = |body: &'gc Node<'gc>, stmts| match body {
+        Node::BlockStatement(block) => {
+            let mut builder = builder::BlockStatement::from_node(block);
+            builder.body(insert(block.body, stmts));
+            builder.build_forced(gc)
+        }
+        _ => unreachable!("function body must be a block"),
+    };
+    match node {
+        Node::Program(n) => {
+            let mut builder = builder::Program::from_node(n);
+            builder.body(insert(n.body, stmts));
+            builder.build_forced(gc)
+        }
+        Node::Module(n) => {
+            let mut builder = builder::Module::from_node(n);
+            builder.body(insert(n.body, stmts));
+            builder.build_forced(gc)
+        }
+        Node::FunctionDeclaration(n) => {
+            let mut builder = builder::FunctionDeclaration::from_node(n);
+            builder.body(insert_block(n.body, stmts));
+            builder.build_forced(gc)
+        }
+        Node::FunctionExpression(n) => {
+            let mut builder = builder::FunctionExpression::from_node(n);
+            builder.body(insert_block(n.body, stmts));
+            builder.build_forced(gc)
+        }
+        Node::ArrowFunctionExpression(n) => {
+            let mut builder = builder::ArrowFunctionExpression::from_node(n);
+            if n.expression {
+                builder.expression(false);
+                builder.body(make_block(gc, vec![make_return(gc, Some(n.body))]));
+                let arrow = builder.build_forced(gc);
+                return prepend_statements(gc, arrow, stmts);
+            }
+            builder.body(insert_block(n.body, stmts));
+            builder.build_forced(gc)
+        }
+        _ => unreachable!("statements can only be prepended to functions"),
+    }
+}
+
+/// Whether `node` is an import or export declaration, which makes the program containing it a
+/// module.
+pub(crate) fn is_module_declaration(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::ImportDeclaration(_)
+            | Node::ExportNamedDeclaration(_)
+            | Node::ExportDefaultDeclaration(_)
+            | Node::ExportAllDeclaration(_)
+    )
+}
+
+/// Whether the identifier `node` at `path` refers to a variable, as opposed to being the name of
+/// a property, label or import/export.
+pub(crate) fn is_reference(path: Option<Path>) -> bool {
+    match path {
+        Some(Path { parent, field }) => !matches!(
+            (parent, field),
+            (
+                Node::Property(Property {
+                    computed: false,
+                    ..
+                }) | Node::MethodDefinition(MethodDefinition {
+                    computed: false,
+                    ..
+                }) | Node::ClassProperty(ClassProperty {
+                    computed: false,
+                    ..
+                }),
+                NodeField::key
+            ) | (
+                Node::MemberExpression(MemberExpression {
+                    computed: false,
+                    ..
+                }) | Node::OptionalMemberExpression(OptionalMemberExpression {
+                    computed: false,
+                    ..
+                }),
+                NodeField::property
+            ) | (
+                Node::LabeledStatement(_) | Node::BreakStatement(_) | Node::ContinueStatement(_),
+                NodeField::label
+            ) | (Node::ImportSpecifier(_), NodeField::imported)
+                | (Node::ExportSpecifier(_), NodeField::exported)
+                | (Node::MetaProperty(_), _)
+        ),
+        None => true,
+    }
+}
+
+pub(crate) fn make_identifier<'gc>(gc: &'gc GCLock, name: Atom) -> &'gc Node<'gc> {
+    builder::Identifier::build_template(
+        gc,
+        template::Identifier {
+            metadata: Default::default(),
+            name,
+            optional: false,
+            type_annotation: None,
+        },
+    )
+}
+
+/// Build a reference to the global `name`.
+pub(crate) fn make_global<'gc>(gc: &'gc GCLock, name: &str) -> &'gc Node<'gc> {
+    make_identifier(gc, gc.atom(name))
+}
+
+/// Build `object.property`.
+pub(crate) fn make_member<'gc>(
+    gc: &'gc GCLock,
+    object: &'gc Node<'gc>,
+    property: &str,
+) -> &'gc Node<'gc> {
+    builder::MemberExpression::build_template(
+        gc,
+        template::MemberExpression {
+            metadata: Default::default(),
+            object,
+            property: make_identifier(gc, gc.atom(property)),
+            computed: false,
+        },
+    )
+}
+
+/// Build `object[property]`.
+pub(crate) fn make_computed_member<'gc>(
+    gc: &'gc GCLock,
+    object: &'gc Node<'gc>,
+    property: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    builder::MemberExpression::build_template(
+        gc,
+        template::MemberExpression {
+            metadata: Default::default(),
+            object,
+            property,
+            computed: true,
+        },
+    )
+}
+
+pub(crate) fn make_call<'gc>(
+    gc: &'gc GCLock,
+    callee: &'gc Node<'gc>,
+    arguments: Vec<&'gc Node<'gc>>,
+) -> &'gc Node<'gc> {
+    builder::CallExpression::build_template(
+        gc,
+        template::CallExpression {
+            metadata: Default::default(),
+            callee,
+            type_arguments: None,
+            arguments: NodeList::from_iter(gc, arguments),
+        },
+    )
+}
+
+/// Build `new callee(arguments)`.
+pub(crate) fn make_new<'gc>(
+    gc: &'gc GCLock,
+    callee: &'gc Node<'gc>,
+    arguments: Vec<&'gc Node<'gc>>,
+) -> &'gc Node<'gc> {
+    builder::NewExpression::build_template(
+        gc,
+        template::NewExpression {
+            metadata: Default::default(),
+            callee,
+            type_arguments: None,
+            arguments: NodeList::from_iter(gc, arguments),
+        },
+    )
+}
+
+pub(crate) fn make_assignment<'gc>(
+    gc: &'gc GCLock,
+    left: &'gc Node<'gc>,
+    right: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    builder::AssignmentExpression::build_template(
+        gc,
+        template::AssignmentExpression {
+            metadata: Default::default(),
+            operator: AssignmentExpressionOperator::Assign,
+            left,
+            right,
+        },
+    )
+}
+
+pub(crate) fn make_binary<'gc>(
+    gc: &'gc GCLock,
+    operator: BinaryExpressionOperator,
+    left: &'gc Node<'gc>,
+    right: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    builder::BinaryExpression::build_template(
+        gc,
+        template::BinaryExpression {
+            metadata: Default::default(),
+            operator,
+            left,
+            right,
+        },
+    )
+}
+
+pub(crate) fn make_logical<'gc>(
+    gc: &'gc GCLock,
+    operator: LogicalExpressionOperator,
+    left: &'gc Node<'gc>,
+    right: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    builder::LogicalExpression::build_template(
+        gc,
+        template::LogicalExpression {
+            metadata: Default::default(),
+            left,
+            right,
+            operator,
+        },
+    )
+}
+
+/// Build `(a, b, ...)`, or just the expression if there is only one.
+pub(crate) fn make_sequence<'gc>(
+    gc: &'gc GCLock,
+    mut expressions: Vec<&'gc Node<'gc>>,
+) -> &'gc Node<'gc> {
+    if expressions.len() == 1 {
+        return expressions.pop().unwrap();
+    }
+    builder::SequenceExpression::build_template(
+        gc,
+        template::SequenceExpression {
+            metadata: Default::default(),
+            expressions: NodeList::from_iter(gc, expressions),
+        },
+    )
+}
+
+pub(crate) fn make_number<'gc>(gc: &'gc GCLock, value: f64) -> &'gc Node<'gc> {
+    builder::NumericLiteral::build_template(
+        gc,
+        template::NumericLiteral {
+            metadata: Default::default(),
+            value,
+        },
+    )
+}
+
+pub(crate) fn make_string<'gc>(gc: &'gc GCLock, value: &str) -> &'gc Node<'gc> {
+    builder::StringLiteral::build_template(
+        gc,
+        template::StringLiteral {
+            metadata: Default::default(),
+            value: gc.atom_u16(value.encode_utf16().collect::<Vec<u16>>()),
+        },
+    )
+}
+
+/// Build `void 0`.
+pub(crate) fn make_undefined<'gc>(gc: &'gc GCLock) -> &'gc Node<'gc> {
+    builder::UnaryExpression::build_template(
+        gc,
+        template::UnaryExpression {
+            metadata: Default::default(),
+            operator: UnaryExpressionOperator::Void,
+            argument: make_number(gc, 0.0),
+            prefix: true,
+        },
+    )
+}
+
+pub(crate) fn make_boolean<'gc>(gc: &'gc GCLock, value: bool) -> &'gc Node<'gc> {
+    builder::BooleanLiteral::build_template(
+        gc,
+        template::BooleanLiteral {
+            metadata: Default::default(),
+            value,
+        },
+    )
+}
+
+pub(crate) fn make_this<'gc>(gc: &'gc GCLock) -> &'gc Node<'gc> {
+    builder::ThisExpression::build_template(
+        gc,
+        template::ThisExpression {
+            metadata: Default::default(),
+        },
+    )
+}
+
+pub(crate) fn make_array<'gc>(gc: &'gc GCLock, elements: Vec<&'gc Node<'gc>>) -> &'gc Node<'gc> {
+    builder::ArrayExpression::build_template(
+        gc,
+        template::ArrayExpression {
+            metadata: Default::default(),
+            elements: NodeList::from_iter(gc, elements),
+            trailing_comma: false,
+        },
+    )
+}
+
+/// Build an object literal with the non-computed `properties`.
+pub(crate) fn make_object<'gc>(
+    gc: &'gc GCLock,
+    properties: Vec<(&str, &'gc Node<'gc>)>,
+) -> &'gc Node<'gc> {
+    let properties = properties
+        .into_iter()
+        .map(|(key, value)| {
+            make_property(
+                gc,
+                Default::default(),
+                make_identifier(gc, gc.atom(key)),
+                value,
+            )
+        })
+        .collect();
+    make_object_expression(gc, properties)
+}
+
+/// Build an object literal from `properties`, which may also contain spread elements.
+pub(crate) fn make_object_expression<'gc>(
+    gc: &'gc GCLock,
+    properties: Vec<&'gc Node<'gc>>,
+) -> &'gc Node<'gc> {
+    builder::ObjectExpression::build_template(
+        gc,
+        template::ObjectExpression {
+            metadata: Default::default(),
+            properties: NodeList::from_iter(gc, properties),
+        },
+    )
+}
+
+/// Build the non-computed property `key: value`.
+pub(crate) fn make_property<'gc>(
+    gc: &'gc GCLock,
+    metadata: TemplateMetadata<'gc>,
+    key: &'gc Node<'gc>,
+    value: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    builder::Property::build_template(
+        gc,
+        template::Property {
+            metadata,
+            key,
+            value,
+            kind: PropertyKind::Init,
+            computed: false,
+            method: false,
+            shorthand: false,
+        },
+    )
+}
+
+/// Build `function id(params) { body }`.
+pub(crate) fn make_function<'gc>(
+    gc: &'gc GCLock,
+    id: Option<Atom>,
+    params: Vec<&'gc Node<'gc>>,
+    body: Vec<&'gc Node<'gc>>,
+) -> &'gc Node<'gc> {
+    builder::FunctionExpression::build_template(
+        gc,
+        template::FunctionExpression {
+            metadata: Default::default(),
+            id: id.map(|name| make_identifier(gc, name)),
+            params: NodeList::from_iter(gc, params),
+            body: make_block(gc, body),
+            type_parameters: None,
+            return_type: None,
+            predicate: None,
+            generator: false,
+            is_async: false,
+        },
+    )
+}
+
+pub(crate) fn make_block<'gc>(gc: &'gc GCLock, body: Vec<&'gc Node<'gc>>) -> &'gc Node<'gc> {
+    builder::BlockStatement::build_template(
+        gc,
+        template::BlockStatement {
+            metadata: Default::default(),
+            body: NodeList::from_iter(gc, body),
+        },
+    )
+}
+
+pub(crate) fn make_return<'gc>(
+    gc: &'gc GCLock,
+    argument: Option<&'gc Node<'gc>>,
+) -> &'gc Node<'gc> {
+    builder::ReturnStatement::build_template(
+        gc,
+        template::ReturnStatement {
+            metadata: Default::default(),
+            argument,
+        },
+    )
+}
+
+pub(crate) fn make_expression_statement<'gc>(
+    gc: &'gc GCLock,
+    expression: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    builder::ExpressionStatement::build_template(
+        gc,
+        template::ExpressionStatement {
+            metadata: Default::default(),
+            expression,
+            directive: None,
+        },
+    )
+}
+
+/// Build `var id = init;`.
+pub(crate) fn make_var<'gc>(
+    gc: &'gc GCLock,
+    id: &'gc Node<'gc>,
+    init: Option<&'gc Node<'gc>>,
+) -> &'gc Node<'gc> {
+    builder::VariableDeclaration::build_template(
+        gc,
+        template::VariableDeclaration {
+            metadata: Default::default(),
+            kind: VariableDeclarationKind::Var,
+            declarations: NodeList::from_iter(
+                gc,
+                [builder::VariableDeclarator::build_template(
+                    gc,
+                    template::VariableDeclarator {
+                        metadata: Default::default(),
+                        id,
+                        init,
+                    },
+                )],
+            ),
+        },
+    )
+}
+
+/// Build `value === void 0`.
+pub(crate) fn make_is_undefined<'gc>(gc: &'gc GCLock, value: &'gc Node<'gc>) -> &'gc Node<'gc> {
+    make_binary(
+        gc,
+        BinaryExpressionOperator::StrictEquals,
+        value,
+        make_undefined(gc),
+    )
+}
+
+/// Build `test ? consequent : alternate`.
+pub(crate) fn make_conditional<'gc>(
+    gc: &'gc GCLock,
+    test: &'gc Node<'gc>,
+    consequent: &'gc Node<'gc>,
+    alternate: &'gc Node<'gc>,
+) -> &'gc Node<'gc> {
+    builder::ConditionalExpression::build_template(
+        gc,
+        template::ConditionalExpression {
+            metadata: Default::default(),
+            test,
+            alternate,
+            consequent,
+        },
+    )
+}
+
+/// Build `Array.prototype.slice.call(value, start)`, which copies an array-like `value` into an
+/// array.
+pub(crate) fn make_array_slice<'gc>(
+    gc: &'gc GCLock,
+    value: &'gc Node<'gc>,
+    start: usize,
+) -> &'gc Node<'gc> {
+    let slice = make_member(
+        gc,
+        make_member(
+            gc,
+            make_member(gc, make_global(gc, "Array"), "prototype"),
+            "slice",
+        ),
+        "call",
+    );
+    let mut arguments = vec![value];
+    if start > 0 {
+        arguments.push(make_number(gc, start as f64));
+    }
+    make_call(gc, slice, arguments)
+}
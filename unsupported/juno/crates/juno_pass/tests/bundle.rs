@@ -0,0 +1,161 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use crate::common::{generate, parse_and_resolve_modules};
+use juno::{
+    ast::{self, validate_tree},
+    hparser,
+};
+use juno_pass::{BundleFormat, Bundler, PassManager, CLOSURE_RUNTIME, METRO_RUNTIME};
+
+#[test]
+fn metro() {
+    assert_bundle(
+        BundleFormat::Metro,
+        &[
+            (
+                "./main.js",
+                r#"
+                    const lib = require('./lib');
+                    const ext = require('external');
+                    lib.f(ext, require('./lib'));
+                "#,
+            ),
+            (
+                "./lib.js",
+                r#"
+                    exports.f = function (require) { return require('./main'); };
+                "#,
+            ),
+        ],
+        &format!(
+            r#"
+                {}
+                __d(function (global, require, _$$_IMPORT_DEFAULT, _$$_IMPORT_ALL, module, exports,
+                              _dependencyMap) {{
+                    const lib = require(1);
+                    const ext = require('external');
+                    lib.f(ext, require(1));
+                }}, 0, [1]);
+                __d(function (global, require, _$$_IMPORT_DEFAULT, _$$_IMPORT_ALL, module, exports,
+                              _dependencyMap) {{
+                    exports.f = function (require) {{ return require('./main'); }};
+                }}, 1, []);
+                __r(0);
+            "#,
+            METRO_RUNTIME
+        ),
+    );
+}
+
+#[test]
+fn closure() {
+    assert_bundle(
+        BundleFormat::Closure,
+        &[
+            (
+                "./main.js",
+                "import {f} from './lib'; export * from './util'; f();",
+            ),
+            ("./lib.js", "exports.f = function () {};"),
+            ("./util.js", "require('./lib');"),
+        ],
+        &format!(
+            r#"
+                {}({{
+                    0: function (global, require, module, exports) {{
+                        'use strict';
+                        Object.defineProperty(exports, '__esModule', {{value: true}});
+                        var _lib = require(1);
+                        _exportStar(require(2));
+                        (0, _lib.f)();
+                        function _exportStar(obj) {{
+                            Object.keys(obj).forEach(function (k) {{
+                                if (k !== 'default' && !(k in exports))
+                                    Object.defineProperty(exports, k, {{
+                                        enumerable: true,
+                                        get: function () {{ return obj[k]; }},
+                                    }});
+                            }});
+                        }}
+                    }},
+                    1: function (global, require, module, exports) {{
+                        exports.f = function () {{}};
+                    }},
+                    2: function (global, require, module, exports) {{
+                        require(1);
+                    }},
+                }}, [0]);
+            "#,
+            CLOSURE_RUNTIME.trim_end().trim_end_matches(';')
+        ),
+    );
+}
+
+#[test]
+fn lazy() {
+    assert_bundle(
+        BundleFormat::Metro,
+        &[
+            (
+                "./main.js",
+                r#"
+                    import('./lib').then(lib => lib.f());
+                    const id = require.resolve('./lib');
+                    require.async('./util');
+                    import('external');
+                "#,
+            ),
+            ("./lib.js", "exports.f = function () {};"),
+            ("./util.js", "exports.g = 1;"),
+        ],
+        &format!(
+            r#"
+                {}
+                __d(function (global, require, _$$_IMPORT_DEFAULT, _$$_IMPORT_ALL, module, exports,
+                              _dependencyMap) {{
+                    Promise.resolve().then(function () {{ return require(1); }})
+                        .then(lib => lib.f());
+                    const id = 1;
+                    Promise.resolve().then(function () {{ return require(2); }});
+                    import('external');
+                }}, 0, [1, 2]);
+                __d(function (global, require, _$$_IMPORT_DEFAULT, _$$_IMPORT_ALL, module, exports,
+                              _dependencyMap) {{
+                    exports.f = function () {{}};
+                }}, 1, []);
+                __d(function (global, require, _$$_IMPORT_DEFAULT, _$$_IMPORT_ALL, module, exports,
+                              _dependencyMap) {{
+                    exports.g = 1;
+                }}, 2, []);
+                __r(0);
+            "#,
+            METRO_RUNTIME
+        ),
+    );
+}
+
+/// Bundle the `(name, input)` modules in `format`, running the first one, and compare the
+/// generated JS with the generated JS for `expected`.
+fn assert_bundle(format: BundleFormat, modules: &[(&str, &str)], expected: &str) {
+    let mut ctx = ast::Context::new();
+    let mut bundler = Bundler::new(format);
+    let mut entry_point = None;
+    for (id, ast, sem) in parse_and_resolve_modules(&mut ctx, modules) {
+        let ast = PassManager::to_commonjs(Default::default()).run(&mut ctx, ast);
+        bundler.add_module(&mut ctx, id, &ast, &sem);
+        entry_point.get_or_insert(id);
+    }
+    let bundle = bundler.finish(&mut ctx, &[entry_point.unwrap()]);
+    validate_tree(&mut ctx, &bundle).unwrap();
+    let actual = generate(&mut ctx, &bundle);
+
+    let mut ctx_expected = ast::Context::new();
+    let ast_expected = hparser::parse(&mut ctx_expected, expected).unwrap();
+    let expected = generate(&mut ctx_expected, &ast_expected);
+    assert_eq!(expected, actual);
+}
@@ -0,0 +1,120 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+//! Helpers shared by the tests of the passes.
+
+use juno::{
+    ast::{self, node_cast, validate_tree, NodeRc},
+    gen_js, hparser, resolve_dependency,
+    sema::{self, SemContext},
+};
+use juno_pass::PassManager;
+use juno_support::{source_manager::SourceId, NullTerminatedBuf};
+
+/// Parse `input` as the source named `name` with `flags`, asserting that it has no errors.
+pub fn parse_with_flags(
+    ctx: &mut ast::Context,
+    name: &str,
+    input: &str,
+    flags: hparser::ParserFlags,
+) -> NodeRc {
+    let file_id = ctx
+        .sm_mut()
+        .add_source(name, NullTerminatedBuf::from_str_copy(input));
+    let buf = ctx.sm().source_buffer_rc(file_id);
+    let parsed = hparser::ParsedJS::parse(flags, &buf);
+
+    assert!(!parsed.has_errors(), "{:?}", parsed.first_error());
+
+    let gc = ast::GCLock::new(ctx);
+    NodeRc::from_node(&gc, parsed.to_ast(&gc, file_id).unwrap())
+}
+
+/// Parse `input` as a script.
+pub fn parse(ctx: &mut ast::Context, input: &str) -> NodeRc {
+    parse_with_flags(ctx, "input", input, Default::default())
+}
+
+/// Parse `input` as the ES module named `name`.
+pub fn parse_module(ctx: &mut ast::Context, name: &str, input: &str) -> NodeRc {
+    parse_module_with_flags(ctx, name, input, Default::default())
+}
+
+/// Parse `input` as the ES module named `name` with `flags`.
+pub fn parse_module_with_flags(
+    ctx: &mut ast::Context,
+    name: &str,
+    input: &str,
+    flags: hparser::ParserFlags,
+) -> NodeRc {
+    let program = parse_with_flags(ctx, name, input, flags);
+    let gc = ast::GCLock::new(ctx);
+    let program = node_cast!(ast::Node::Program, program.node(&gc));
+    let module = ast::builder::Module::build_template(
+        &gc,
+        ast::template::Module {
+            metadata: ast::TemplateMetadata {
+                range: program.metadata.range,
+                ..Default::default()
+            },
+            body: program.body,
+        },
+    );
+    NodeRc::from_node(&gc, module)
+}
+
+/// Parse the `(name, input)` ES modules and resolve them with the default resolver, asserting
+/// that they have no errors.
+pub fn parse_and_resolve_modules(
+    ctx: &mut ast::Context,
+    modules: &[(&str, &str)],
+) -> Vec<(SourceId, NodeRc, SemContext)> {
+    let asts: Vec<NodeRc> = modules
+        .iter()
+        .map(|(name, input)| parse_module(ctx, name, input))
+        .collect();
+
+    let resolver = resolve_dependency::DefaultResolver::new(ctx.sm());
+    let mut result = vec![];
+    for ((name, _), ast) in modules.iter().zip(asts) {
+        let gc = ast::GCLock::new(ctx);
+        let id = gc.sm().lookup_name(name).unwrap();
+        let sem = sema::resolve_module(&gc, ast.node(&gc), id, &resolver);
+        assert_eq!(gc.sm().num_errors(), 0);
+        result.push((id, ast, sem));
+    }
+    result
+}
+
+/// Generate JS for `ast` with `opt`.
+pub fn generate_with_opt(ctx: &mut ast::Context, ast: &NodeRc, opt: gen_js::Opt) -> String {
+    let mut out = vec![];
+    gen_js::generate(&mut out, ctx, ast, opt).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Generate JS for `ast` with the default options.
+pub fn generate(ctx: &mut ast::Context, ast: &NodeRc) -> String {
+    generate_with_opt(ctx, ast, gen_js::Opt::new())
+}
+
+/// Run `pipeline` on the script `input` and compare the generated JS with the generated JS for
+/// `expected`.
+pub fn assert_transform(pipeline: PassManager, input: &str, expected: &str) {
+    let mut ctx = ast::Context::new();
+    let ast = parse(&mut ctx, input);
+    let ast = pipeline.run(&mut ctx, ast);
+    validate_tree(&mut ctx, &ast).unwrap();
+
+    let mut ctx_expected = ast::Context::new();
+    let ast_expected = parse(&mut ctx_expected, expected);
+
+    assert_eq!(
+        generate(&mut ctx_expected, &ast_expected),
+        generate(&mut ctx, &ast)
+    );
+}
@@ -0,0 +1,158 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use crate::common::{assert_transform, generate, parse, parse_module};
+use juno::ast;
+use juno_pass::{CommonJsOptions, PassManager};
+
+#[test]
+fn imports() {
+    assert_commonjs(
+        false,
+        r#"
+            import {a, b as c} from './named';
+            import * as ns from 'ns';
+            import './side-effect';
+            a(c, ns.x, {c});
+        "#,
+        r#"
+            'use strict';
+            var _named = require('./named');
+            var _ns = _interopRequireWildcard(require('ns'));
+            require('./side-effect');
+            (0, _named.a)(_named.b, _ns.x, {c: _named.b});
+            function _interopRequireWildcard(obj) {
+                if (obj && obj.__esModule) return obj;
+                var ns = {default: obj};
+                if (obj != null)
+                    for (var k in obj)
+                        if (k !== 'default' && Object.prototype.hasOwnProperty.call(obj, k))
+                            ns[k] = obj[k];
+                return ns;
+            }
+        "#,
+    );
+    assert_commonjs(
+        false,
+        r#"
+            'use strict';
+            import React from 'react';
+            React.render();
+        "#,
+        r#"
+            'use strict';
+            var _react = _interopRequireDefault(require('react'));
+            _react.default.render();
+            function _interopRequireDefault(obj) {
+                return obj && obj.__esModule ? obj : {default: obj};
+            }
+        "#,
+    );
+}
+
+#[test]
+fn exports() {
+    assert_commonjs(
+        false,
+        r#"
+            export let x = 1, {y} = o;
+            export function f() {}
+            export {x as z};
+            export default 1 + 2;
+        "#,
+        r#"
+            'use strict';
+            Object.defineProperty(exports, '__esModule', {value: true});
+            Object.defineProperty(exports, 'x', {enumerable: true, get: function () { return x; }});
+            Object.defineProperty(exports, 'y', {enumerable: true, get: function () { return y; }});
+            Object.defineProperty(exports, 'f', {enumerable: true, get: function () { return f; }});
+            Object.defineProperty(exports, 'z', {enumerable: true, get: function () { return x; }});
+            let x = 1, {y} = o;
+            function f() {}
+            exports.default = 1 + 2;
+        "#,
+    );
+    assert_commonjs(
+        false,
+        r#"
+            import {a} from './a';
+            export {a};
+            export {b as c} from './b';
+            export * from './d';
+            export default class {}
+        "#,
+        r#"
+            'use strict';
+            Object.defineProperty(exports, '__esModule', {value: true});
+            Object.defineProperty(exports, 'a', {enumerable: true, get: function () { return _a.a; }});
+            Object.defineProperty(exports, 'c', {enumerable: true, get: function () { return _b.b; }});
+            Object.defineProperty(exports, 'default', {enumerable: true, get: function () { return _default; }});
+            var _a = require('./a');
+            var _b = require('./b');
+            _exportStar(require('./d'));
+            class _default {}
+            function _exportStar(obj) {
+                Object.keys(obj).forEach(function (k) {
+                    if (k !== 'default' && !(k in exports))
+                        Object.defineProperty(exports, k, {enumerable: true, get: function () { return obj[k]; }});
+                });
+            }
+        "#,
+    );
+}
+
+#[test]
+fn inline_requires() {
+    assert_commonjs(
+        true,
+        r#"
+            import {a} from './a';
+            import b from './b';
+            export function f() { return a(b); }
+        "#,
+        r#"
+            'use strict';
+            Object.defineProperty(exports, '__esModule', {value: true});
+            Object.defineProperty(exports, 'f', {enumerable: true, get: function () { return f; }});
+            function f() { return (0, require('./a').a)(_interopRequireDefault(require('./b')).default); }
+            function _interopRequireDefault(obj) {
+                return obj && obj.__esModule ? obj : {default: obj};
+            }
+        "#,
+    );
+}
+
+#[test]
+fn scripts() {
+    // Scripts without imports or exports are left alone.
+    assert_commonjs(false, "var x = require('x');", "var x = require('x');");
+}
+
+#[test]
+fn modules_without_imports_or_exports() {
+    // They are CommonJS modules, which must not become strict.
+    let input = "x = 1; module.exports = arguments.callee;";
+    let mut ctx = ast::Context::new();
+    let ast = parse_module(&mut ctx, "./lib.js", input);
+    let ast = PassManager::to_commonjs(Default::default()).run(&mut ctx, ast);
+
+    let mut ctx_expected = ast::Context::new();
+    let ast_expected = parse(&mut ctx_expected, input);
+    assert_eq!(
+        generate(&mut ctx_expected, &ast_expected),
+        generate(&mut ctx, &ast)
+    );
+}
+
+/// Convert `input` and compare the generated JS with the generated JS for `expected`.
+fn assert_commonjs(inline_requires: bool, input: &str, expected: &str) {
+    assert_transform(
+        PassManager::to_commonjs(CommonJsOptions { inline_requires }),
+        input,
+        expected,
+    );
+}
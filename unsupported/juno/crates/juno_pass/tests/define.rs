@@ -0,0 +1,119 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use crate::common::assert_transform;
+use juno_pass::{Define, DefineValue, PassManager};
+
+#[test]
+fn parse_defines() {
+    assert_eq!(
+        "__DEV__=false".parse(),
+        Ok(Define::new("__DEV__", DefineValue::Boolean(false)).unwrap())
+    );
+    assert_eq!(
+        r#"process.env.NODE_ENV="production""#.parse(),
+        Ok(Define::new(
+            "process.env.NODE_ENV",
+            DefineValue::String("production".to_string())
+        )
+        .unwrap())
+    );
+    assert_eq!(
+        "x='a=b'".parse(),
+        Ok(Define::new("x", DefineValue::String("a=b".to_string())).unwrap())
+    );
+    assert_eq!(
+        "x=1.5".parse(),
+        Ok(Define::new("x", DefineValue::Number(1.5)).unwrap())
+    );
+    assert!("x".parse::<Define>().is_err());
+    assert!("x=production".parse::<Define>().is_err());
+    assert!("a..b=1".parse::<Define>().is_err());
+    assert!("a[0]=1".parse::<Define>().is_err());
+}
+
+#[test]
+fn globals() {
+    assert_define(
+        r#"
+            if (__DEV__) check();
+            var mode = process.env.NODE_ENV !== 'production' ? 'dev' : 'prod';
+            var env = process.env['NODE_ENV'];
+            var o = {__DEV__, length: process.env.NODE_ENV.length};
+            f(typeof __DEV__, __DEV__ && dev());
+        "#,
+        r#"
+            var mode = 'prod';
+            var env = 'production';
+            var o = {__DEV__: false, length: 'production'.length};
+            f('boolean', false);
+        "#,
+    );
+}
+
+#[test]
+fn shadowed() {
+    assert_define(
+        r#"
+            function f(__DEV__, process) {
+                if (__DEV__) return process.env.NODE_ENV;
+            }
+            {
+                let __DEV__ = true;
+                g(__DEV__);
+            }
+            o.__DEV__;
+        "#,
+        r#"
+            function f(__DEV__, process) {
+                if (__DEV__) return process.env.NODE_ENV;
+            }
+            {
+                let __DEV__ = true;
+                g(__DEV__);
+            }
+            o.__DEV__;
+        "#,
+    );
+    // Globals declared by the program aren't replaced.
+    assert_define(
+        "var __DEV__ = true; g(__DEV__);",
+        "var __DEV__ = true; g(__DEV__);",
+    );
+}
+
+#[test]
+fn assignments() {
+    assert_define(
+        r#"
+            __DEV__ = true;
+            process.env.NODE_ENV = 'test';
+            __DEV__++;
+            delete process.env.NODE_ENV;
+            [__DEV__] = a;
+            for (__DEV__ in o);
+        "#,
+        r#"
+            __DEV__ = true;
+            process.env.NODE_ENV = 'test';
+            __DEV__++;
+            delete process.env.NODE_ENV;
+            [__DEV__] = a;
+            for (__DEV__ in o);
+        "#,
+    );
+}
+
+/// Replace `__DEV__` with `false` and `process.env.NODE_ENV` with `"production"` in `input`,
+/// and compare the generated JS with the generated JS for `expected`.
+fn assert_define(input: &str, expected: &str) {
+    let defines = vec![
+        "__DEV__=false".parse().unwrap(),
+        r#"process.env.NODE_ENV="production""#.parse().unwrap(),
+    ];
+    assert_transform(PassManager::define(defines), input, expected);
+}
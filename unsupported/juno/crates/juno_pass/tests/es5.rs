@@ -0,0 +1,616 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use crate::common::{assert_transform, generate, parse};
+use juno::ast;
+use juno_pass::{ClassMode, PassManager, GENERATOR_RUNTIME};
+
+#[test]
+fn arrow_functions() {
+    assert_lowered(
+        PassManager::lower_arrow_functions,
+        "function f() { return () => this.x + arguments[0]; }",
+        "function f() {
+            var _this = this;
+            var _arguments = arguments;
+            return function () { return _this.x + _arguments[0]; };
+        }",
+    );
+    assert_lowered(
+        PassManager::lower_arrow_functions,
+        "var f = (a) => { g(a); }; function h() { return () => function () { return this; }; }",
+        "var f = function (a) { g(a); };
+        function h() { return function () { return function () { return this; }; }; }",
+    );
+}
+
+#[test]
+fn block_scoping() {
+    assert_lowered(
+        PassManager::lower_block_scoping,
+        "var x = 1; { let x = 2; const y = x; print(y); } print(x);",
+        "var x = 1; { var _x = 2; var y = _x; print(y); } print(x);",
+    );
+    assert_lowered(
+        PassManager::lower_block_scoping,
+        "function f() { if (a) { let g = 1; return g; } return function () { return g; }; }",
+        "function f() { if (a) { var _g = 1; return _g; } return function () { return g; }; }",
+    );
+    assert_lowered(
+        PassManager::lower_block_scoping,
+        "while (a) { let x; f(x); x = 1; }",
+        "while (a) { var x = void 0; f(x); x = 1; }",
+    );
+}
+
+#[test]
+fn block_scoping_loop_closures() {
+    assert_lowered(
+        PassManager::lower_block_scoping,
+        "function f() {
+            for (let i = 0; i < 3; i++) {
+                fns.push(() => i);
+                if (i) break;
+                if (!i) continue;
+                if (g(i)) return i;
+            }
+        }",
+        "function f() {
+            for (var i = 0; i < 3; i++) {
+                var _loop = (i) => {
+                    fns.push(() => i);
+                    if (i) return 'break';
+                    if (!i) return;
+                    if (g(i)) return {v: i};
+                };
+                var _ret = _loop(i);
+                if (_ret === 'break') break;
+                if (typeof _ret === 'object') return _ret.v;
+            }
+        }",
+    );
+    // Bindings which aren't captured don't need a function.
+    assert_lowered(
+        PassManager::lower_block_scoping,
+        "for (let i = 0; i < 3; i++) { let j = i; f(j); }",
+        "for (var i = 0; i < 3; i++) { var j = i; f(j); }",
+    );
+    // Loops which can't be moved into a function keep their block scoped declarations,
+    // so that each iteration still has its own binding.
+    assert_lowered(
+        PassManager::lower_block_scoping,
+        "for (let i = 0; i < 3; i++) { var x = i; let y = x; fns.push(() => i + y); }
+        let z = 1;",
+        "for (let i = 0; i < 3; i++) { var x = i; let y = x; fns.push(() => i + y); }
+        var z = 1;",
+    );
+    assert_lowered(
+        PassManager::lower_block_scoping,
+        "function* g() { for (const i of a) { yield i; fns.push(() => i); } }",
+        "function* g() { for (const i of a) { yield i; fns.push(() => i); } }",
+    );
+}
+
+#[test]
+fn template_literals() {
+    assert_lowered(
+        PassManager::lower_template_literals,
+        "`a${b}c${d}`; `${x}`; `plain`;",
+        "'a' + b + 'c' + d; '' + x; 'plain';",
+    );
+    assert_lowered(
+        PassManager::lower_template_literals,
+        r"tag`a\n${b}`;",
+        r"tag(
+            Object.defineProperties(
+                Object.freeze(['a\n', '']),
+                {raw: {value: Object.freeze(['a\\n', ''])}}
+            ),
+            b
+        );",
+    );
+}
+
+#[test]
+fn object_literals() {
+    assert_lowered(
+        PassManager::lower_object_literals,
+        "({a, b() {}, [c]: 1, d: 2, get [e]() { return 1; }});",
+        "var _obj;
+        _obj = {a: a, b: function () {}},
+            _obj[c] = 1,
+            _obj['d'] = 2,
+            Object.defineProperty(_obj, e, {
+                get: function () { return 1; },
+                configurable: true,
+                enumerable: true
+            }),
+            _obj;",
+    );
+}
+
+#[test]
+fn parameters() {
+    assert_lowered(
+        PassManager::lower_parameters,
+        "function f(a = 1, {b}, ...c) { g(...c); }",
+        "function f(a, _ref) {
+            if (a === void 0) a = 1;
+            var {b} = _ref;
+            var c = Array.prototype.slice.call(arguments, 2);
+            g.apply(void 0, Array.prototype.slice.call(c));
+        }",
+    );
+    assert_lowered(
+        PassManager::lower_parameters,
+        "o.m(1, ...x); a.b().m(...x, 2); new F(...x); [1, ...x];",
+        "var _obj;
+        o.m.apply(o, [1].concat(Array.prototype.slice.call(x)));
+        (_obj = a.b()).m.apply(_obj, Array.prototype.slice.call(x).concat([2]));
+        new (Function.prototype.bind.apply(F, [null].concat(Array.prototype.slice.call(x))))();
+        [1].concat(Array.prototype.slice.call(x));",
+    );
+}
+
+#[test]
+fn destructuring() {
+    assert_lowered(
+        PassManager::lower_destructuring,
+        "var {a, b: [c, d = 1]} = obj;",
+        "var a = obj.a, _ref = obj.b, c = _ref[0], _ref2 = _ref[1],
+            d = _ref2 === void 0 ? 1 : _ref2;",
+    );
+    assert_lowered(
+        PassManager::lower_destructuring,
+        "[a, b] = [b, a];",
+        "var _ref; _ref = [b, a], a = _ref[0], b = _ref[1], _ref;",
+    );
+    assert_lowered(
+        PassManager::lower_destructuring,
+        "var {a, ...rest} = obj;",
+        "var a = obj.a, rest = (function (source, excluded) {
+            var target = {};
+            for (var key in source)
+                if (Object.prototype.hasOwnProperty.call(source, key) && excluded.indexOf(key) < 0)
+                    target[key] = source[key];
+            return target;
+        })(obj, ['a']);",
+    );
+    assert_lowered(
+        PassManager::lower_destructuring,
+        "for (const [k, v] of m) f(k, v); try {} catch ({message}) { f(message); }",
+        "for (const _ref of m) { const k = _ref[0], v = _ref[1]; f(k, v); }
+        try {} catch (_ref2) { var message = _ref2.message; f(message); }",
+    );
+}
+
+#[test]
+fn for_of() {
+    assert_lowered(
+        PassManager::lower_for_of,
+        "for (const x of xs) f(x);",
+        "for (
+            var _iterator = (function (o) {
+                if (typeof Symbol !== 'undefined' && o[Symbol.iterator]) return o[Symbol.iterator]();
+                var i = 0;
+                return {
+                    next: function () {
+                        return i < o.length ? {done: false, value: o[i++]} : {done: true};
+                    }
+                };
+            })(xs), _step;
+            !(_step = _iterator.next()).done;
+        ) {
+            const x = _step.value;
+            f(x);
+        }",
+    );
+}
+
+#[test]
+fn exponent() {
+    assert_lowered(
+        PassManager::lower_exponent,
+        "a ** b; x.y **= 2; f().z **= 3; n **= 2;",
+        "var _obj;
+        Math.pow(a, b);
+        x.y = Math.pow(x.y, 2);
+        (_obj = f()).z = Math.pow(_obj.z, 3);
+        n = Math.pow(n, 2);",
+    );
+}
+
+#[test]
+fn optional_chaining() {
+    assert_lowered(
+        PassManager::lower_optional_chaining,
+        "function f(a, o) { return [a?.b.c(), o.m?.(1), a?.[k]?.(), g()?.x, undeclared?.y]; }",
+        "function f(a, o) {
+            var _ref, _ref2, _ref3, _ref4;
+            return [
+                a == null ? void 0 : a.b.c(),
+                (_ref = o.m) == null ? void 0 : _ref.call(o, 1),
+                a == null || (_ref2 = a[k]) == null ? void 0 : _ref2.call(a),
+                (_ref3 = g()) == null ? void 0 : _ref3.x,
+                (_ref4 = undeclared) == null ? void 0 : _ref4.y
+            ];
+        }",
+    );
+    assert_lowered(
+        PassManager::lower_optional_chaining,
+        "function f(a, b) { return [a ?? b, g() ?? h(), delete a?.b]; }",
+        "function f(a, b) {
+            var _ref;
+            return [a != null ? a : b, (_ref = g()) != null ? _ref : h(), a == null ? true : delete a.b];
+        }",
+    );
+    // Temporaries only avoid the bindings visible in their function.
+    assert_lowered(
+        PassManager::lower_optional_chaining,
+        "function f(o) { return o.m?.(); }
+        function g(o, _ref) { { let _ref2; } return o.m?.(_ref); }
+        function h(o) { return () => o.m?.(); }",
+        "function f(o) { var _ref; return (_ref = o.m) == null ? void 0 : _ref.call(o); }
+        function g(o, _ref) {
+            var _ref3;
+            { let _ref2; }
+            return (_ref3 = o.m) == null ? void 0 : _ref3.call(o, _ref);
+        }
+        function h(o) {
+            return () => { var _ref; return (_ref = o.m) == null ? void 0 : _ref.call(o); };
+        }",
+    );
+}
+
+#[test]
+fn classes_loose() {
+    assert_lowered(
+        || PassManager::lower_classes(ClassMode::Loose),
+        "class A { x = 1; constructor(y) { this.y = y; } m() { return this.x; } static s() {} }",
+        "let A = (function () {
+            function A(y) { this.x = 1; this.y = y; }
+            A.prototype.m = function () { return this.x; };
+            A.s = function () {};
+            return A;
+        })();",
+    );
+    assert_lowered(
+        || PassManager::lower_classes(ClassMode::Loose),
+        "class A extends B { x = 1; constructor(y) { super(y); } m() { return super.m(); } }",
+        "let A = (function (_B) {
+            function A(y) {
+                var _this;
+                _this = _B.call(this, y) || this, _this.x = 1, _this;
+                return _this;
+            }
+            A.prototype = Object.create(_B.prototype);
+            A.prototype.constructor = A;
+            A.__proto__ = _B;
+            A.prototype.m = function () { return _B.prototype.m.call(this); };
+            return A;
+        })(B);",
+    );
+    assert_lowered(
+        || PassManager::lower_classes(ClassMode::Loose),
+        "f(class {}); class C extends D {}",
+        "f(function _class() {});
+        let C = (function (_D) {
+            function C() {
+                var _this;
+                _this = _D.apply(this, arguments) || this;
+                return _this;
+            }
+            C.prototype = Object.create(_D.prototype);
+            C.prototype.constructor = C;
+            C.__proto__ = _D;
+            return C;
+        })(D);",
+    );
+}
+
+#[test]
+fn classes_spec() {
+    assert_lowered(
+        || PassManager::lower_classes(ClassMode::Spec),
+        "class A extends B { get x() { return 1; } set x(v) {} m() {} static [k] = 2; }",
+        "let A = (function (_B) {
+            if (typeof _B !== 'function' && _B !== null)
+                throw new TypeError('Super expression must either be null or a function');
+            function A() {
+                if (!(this instanceof A)) throw new TypeError('Cannot call a class as a function');
+                var _this;
+                _this = _B.apply(this, arguments),
+                    _this = Object(_this) === _this ? _this : this;
+                return _this;
+            }
+            A.prototype = Object.create(_B && _B.prototype, {
+                constructor: {value: A, writable: true, configurable: true}
+            });
+            if (_B) A.__proto__ = _B;
+            Object.defineProperty(A.prototype, 'x', {
+                get: function () { return 1; },
+                set: function (v) {},
+                configurable: true
+            });
+            Object.defineProperty(A.prototype, 'm', {
+                value: function () {},
+                writable: true,
+                configurable: true
+            });
+            var _key = k;
+            Object.defineProperty(A, _key, {
+                value: 2,
+                enumerable: true,
+                configurable: true,
+                writable: true
+            });
+            return A;
+        })(B);",
+    );
+}
+
+#[test]
+fn private_fields() {
+    assert_lowered(
+        || PassManager::lower_classes(ClassMode::Spec),
+        "class A { #x = 1; m(o) { this.#x += 1; o.#x++; f(o.#x = 2); return #x in o ? o.#x : 0; } }",
+        "let A = (function () {
+            function A() {
+                if (!(this instanceof A)) throw new TypeError('Cannot call a class as a function');
+                _x.set(this, 1);
+            }
+            var _x = new WeakMap();
+            Object.defineProperty(A.prototype, 'm', {
+                value: function (o) {
+                    var _value;
+                    _x.set(this, _x.get(this) + 1);
+                    _x.set(o, +_x.get(o) + 1);
+                    f((_x.set(o, _value = 2), _value));
+                    return _x.has(o) ? _x.get(o) : 0;
+                },
+                writable: true,
+                configurable: true
+            });
+            return A;
+        })();",
+    );
+    assert_lowered(
+        || PassManager::lower_classes(ClassMode::Loose),
+        "class A { #x; m(o) { return #x in o ? o.#x : 0; } }",
+        "let A = (function () {
+            function A() {
+                Object.defineProperty(this, '__private_x', {value: void 0, writable: true});
+            }
+            A.prototype.m = function (o) {
+                return Object.prototype.hasOwnProperty.call(o, '__private_x') ? o.__private_x : 0;
+            };
+            return A;
+        })();",
+    );
+}
+
+#[test]
+fn generators() {
+    assert_lowered(
+        PassManager::lower_generators,
+        "function* f(x) { var y = yield x; return y; }",
+        "function f(x) {
+            var y;
+            return junoGeneratorRuntime.generator(function (_context) {
+                while (1) switch (_context.prev = _context.next) {
+                    case 0:
+                        _context.next = 1;
+                        return x;
+                    case 1:
+                        y = _context.sent;
+                        return _context.abrupt('return', y);
+                    case 2:
+                    case 'end':
+                        return _context.stop();
+                }
+            }, this);
+        }",
+    );
+    assert_lowered(
+        PassManager::lower_generators,
+        "function* g(a) {
+            for (let i = 0; i < a; i++) {
+                if (i % 2) continue;
+                yield i;
+            }
+            return foo(a, yield);
+        }",
+        "function g(a) {
+            var i;
+            return junoGeneratorRuntime.generator(function (_context) {
+                while (1) switch (_context.prev = _context.next) {
+                    case 0:
+                        i = 0;
+                    case 1:
+                        if (!(i < a)) {
+                            _context.next = 4;
+                            break;
+                        }
+                        if (i % 2) return _context.abrupt('continue', 3);
+                        _context.next = 2;
+                        return i;
+                    case 2:
+                    case 3:
+                        i++;
+                        _context.next = 1;
+                        break;
+                    case 4:
+                        _context.t0 = a;
+                        _context.next = 5;
+                        return;
+                    case 5:
+                        return _context.abrupt('return', foo(_context.t0, _context.sent));
+                    case 6:
+                    case 'end':
+                        return _context.stop();
+                }
+            }, this);
+        }",
+    );
+}
+
+#[test]
+fn generator_runtime() {
+    // The runtime is loaded as is, so it must be an ES5 script.
+    let mut ctx = ast::Context::new();
+    let ast = parse(&mut ctx, GENERATOR_RUNTIME);
+    let lowered = PassManager::es5().run(&mut ctx, ast.clone());
+    assert_eq!(generate(&mut ctx, &ast), generate(&mut ctx, &lowered));
+}
+
+#[test]
+fn async_functions() {
+    assert_lowered(
+        PassManager::lower_generators,
+        "async function f(x) {
+            try {
+                return await x;
+            } catch (e) {
+                log(e);
+            } finally {
+                done();
+            }
+        }",
+        "function f(x) {
+            var e;
+            return junoGeneratorRuntime.async(function (_context) {
+                while (1) switch (_context.prev = _context.next) {
+                    case 0:
+                    case 1:
+                        _context.prev = 1;
+                        _context.next = 2;
+                        return _context.await(x);
+                    case 2:
+                        return _context.abrupt('return', _context.sent);
+                        _context.next = 4;
+                        break;
+                    case 3:
+                        _context.prev = 3;
+                        e = _context.catch(1);
+                        {
+                            log(e);
+                        }
+                    case 4:
+                        _context.prev = 4;
+                        {
+                            done();
+                        }
+                        return _context.finish(4);
+                    case 5:
+                    case 6:
+                    case 'end':
+                        return _context.stop();
+                }
+            }, this, [[1, 3, 4, 5]]);
+        }",
+    );
+    assert_lowered(
+        PassManager::lower_generators,
+        "const g = async () => (await a) + (await b);",
+        "const g = () => {
+            return junoGeneratorRuntime.async(function (_context) {
+                while (1) switch (_context.prev = _context.next) {
+                    case 0:
+                        _context.next = 1;
+                        return _context.await(a);
+                    case 1:
+                        _context.t0 = _context.sent;
+                        _context.next = 2;
+                        return _context.await(b);
+                    case 2:
+                        return _context.abrupt('return', _context.t0 + _context.sent);
+                    case 3:
+                    case 'end':
+                        return _context.stop();
+                }
+            }, this);
+        };",
+    );
+}
+
+#[test]
+fn es5() {
+    assert_lowered(
+        PassManager::es5,
+        "const f = (...args) => args.map(x => x ** 2);",
+        "var f = function () {
+            var args = Array.prototype.slice.call(arguments);
+            return args.map(function (x) { return Math.pow(x, 2); });
+        };",
+    );
+    assert_lowered(
+        PassManager::es5,
+        "for (let i = 0; i < 3; i++) fns.push(() => `${i}`);",
+        "for (var i = 0; i < 3; i++) {
+            var _loop = function (i) { fns.push(function () { return '' + i; }); };
+            _loop(i);
+        }",
+    );
+    assert_lowered(
+        PassManager::es5,
+        "class A { m(x = 1) {} }",
+        "var A = (function () {
+            function A() {
+                if (!(this instanceof A)) throw new TypeError('Cannot call a class as a function');
+            }
+            Object.defineProperty(A.prototype, 'm', {
+                value: function (x) { if (x === void 0) x = 1; },
+                writable: true,
+                configurable: true
+            });
+            return A;
+        })();",
+    );
+    assert_lowered(
+        PassManager::es5,
+        "async function f(xs) { for (const x of xs) await x; return arguments.length; }",
+        "function f(xs) {
+            var x, _arguments = arguments;
+            return junoGeneratorRuntime.async(function (_context) {
+                while (1) switch (_context.prev = _context.next) {
+                    case 0:
+                        _context.t0 = junoGeneratorRuntime.values(xs);
+                    case 1:
+                        if ((_context.t1 = _context.t0.next()).done) {
+                            _context.next = 3;
+                            break;
+                        }
+                        x = _context.t1.value;
+                        _context.next = 2;
+                        return _context.await(x);
+                    case 2:
+                        _context.next = 1;
+                        break;
+                    case 3:
+                        return _context.abrupt('return', _arguments.length);
+                    case 4:
+                    case 'end':
+                        return _context.stop();
+                }
+            }, this);
+        }",
+    );
+    assert_lowered(
+        PassManager::es5,
+        "const f = (o) => o?.x ?? `${o}`;",
+        "var f = function (o) {
+            var _ref;
+            return (_ref = o == null ? void 0 : o.x) != null ? _ref : '' + o;
+        };",
+    );
+}
+
+/// Run the pipeline created by `pipeline` on `input` and compare the generated JS with the
+/// generated JS for `expected`.
+fn assert_lowered(pipeline: fn() -> PassManager, input: &str, expected: &str) {
+    assert_transform(pipeline(), input, expected);
+}
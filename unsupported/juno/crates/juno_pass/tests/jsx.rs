@@ -0,0 +1,200 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use crate::common;
+use juno::{
+    ast::{self, validate_tree, NodeRc},
+    gen_js, hparser,
+};
+use juno_pass::{JsxOptions, JsxRuntime, PassManager};
+
+#[test]
+fn classic() {
+    assert_jsx(
+        JsxRuntime::Classic,
+        r#"
+            <div className="a" hidden {...rest} data-x={1}>
+                Hello, {name}!
+                <Foo.Bar />
+            </div>;
+        "#,
+        r#"
+            React.createElement(
+                "div",
+                {className: "a", hidden: true, ...rest, "data-x": 1},
+                "Hello, ",
+                name,
+                "!",
+                React.createElement(Foo.Bar, null)
+            );
+        "#,
+    );
+}
+
+#[test]
+fn classic_fragment() {
+    assert_jsx(
+        JsxRuntime::Classic,
+        r#"<><a key="k" /><svg:rect xlink:href="x" /></>;"#,
+        r#"
+            React.createElement(
+                React.Fragment,
+                null,
+                React.createElement("a", {key: "k"}),
+                React.createElement("svg:rect", {"xlink:href": "x"})
+            );
+        "#,
+    );
+}
+
+#[test]
+fn automatic() {
+    assert_jsx_module(
+        r#"
+            'use strict';
+            <div key={id} a="1">{x}</div>;
+            <ul><li /><li /></ul>;
+            <>text</>;
+        "#,
+        r#"
+            'use strict';
+            import {jsx as _jsx, jsxs as _jsxs, Fragment as _Fragment} from "react/jsx-runtime";
+            _jsx("div", {a: "1", children: x}, id);
+            _jsxs("ul", {children: [_jsx("li", {}), _jsx("li", {})]});
+            _jsx(_Fragment, {children: "text"});
+        "#,
+    );
+}
+
+#[test]
+fn automatic_script() {
+    assert_jsx(
+        JsxRuntime::Automatic,
+        r#"
+            'use strict';
+            <ul><li /><li /></ul>;
+        "#,
+        r#"
+            'use strict';
+            var _jsx = require("react/jsx-runtime").jsx;
+            var _jsxs = require("react/jsx-runtime").jsxs;
+            _jsxs("ul", {children: [_jsx("li", {}), _jsx("li", {})]});
+        "#,
+    );
+}
+
+#[test]
+fn automatic_names() {
+    assert_jsx_module(
+        r#"
+            import {jsx as _jsx} from "other";
+            function f(_Fragment) {
+                return <><_jsx /></>;
+            }
+        "#,
+        r#"
+            import {jsx as _jsx2, Fragment as _Fragment2} from "react/jsx-runtime";
+            import {jsx as _jsx} from "other";
+            function f(_Fragment) {
+                return _jsx2(_Fragment2, {children: _jsx2(_jsx, {})});
+            }
+        "#,
+    );
+}
+
+#[test]
+fn text() {
+    assert_jsx(
+        JsxRuntime::Classic,
+        "<p>  a &amp; b\n    c  \n\n  {x}  &nbsp;</p>;",
+        "React.createElement('p', null, '  a & b c', x, '  \u{a0}');",
+    );
+    assert_jsx(
+        JsxRuntime::Classic,
+        "<p title=\"a\n    b\">\n  {/* comment */}\n</p>;",
+        "React.createElement('p', {title: 'a b'});",
+    );
+}
+
+#[test]
+fn pragmas() {
+    let mut options = JsxOptions::default();
+    options.read_pragmas("* @flow\n * @jsx h\n * @jsxFrag Fragment");
+    assert_eq!(options.pragma, "h");
+    assert_eq!(options.pragma_frag, "Fragment");
+    assert_eq!(options.runtime, JsxRuntime::Classic);
+
+    options.read_pragmas("@jsxRuntime automatic @jsxImportSource preact");
+    assert_eq!(options.runtime, JsxRuntime::Automatic);
+    assert_eq!(options.import_source, "preact");
+}
+
+#[test]
+fn pragma_comment() {
+    let mut ctx = ast::Context::new();
+    let ast = parse(&mut ctx, "/** @jsx h */\n<a />;", true);
+    let ast = PassManager::transform_jsx(Default::default()).run(&mut ctx, ast);
+    validate_tree(&mut ctx, &ast).unwrap();
+    assert_eq!(generate(&mut ctx, &ast), "/** @jsx h */\nh(\"a\", null);\n");
+}
+
+fn parse(ctx: &mut ast::Context, input: &str, jsx: bool) -> NodeRc {
+    let flags = if jsx { jsx_flags() } else { Default::default() };
+    common::parse_with_flags(ctx, "input", input, flags)
+}
+
+fn jsx_flags() -> hparser::ParserFlags {
+    hparser::ParserFlags {
+        enable_jsx: true,
+        store_comments: true,
+        ..Default::default()
+    }
+}
+
+fn generate(ctx: &mut ast::Context, ast: &NodeRc) -> String {
+    common::generate_with_opt(
+        ctx,
+        ast,
+        gen_js::Opt {
+            quote: gen_js::QuoteChar::Double,
+            ..gen_js::Opt::new()
+        },
+    )
+}
+
+/// Transform the script `input` and compare the generated JS with the generated JS for
+/// `expected`.
+fn assert_jsx(runtime: JsxRuntime, input: &str, expected: &str) {
+    let mut ctx = ast::Context::new();
+    let ast = parse(&mut ctx, input, true);
+    assert_transformed(ctx, ast, runtime, expected);
+}
+
+/// Transform the ES module `input` with the automatic runtime, and compare the generated JS with
+/// the generated JS for `expected`.
+fn assert_jsx_module(input: &str, expected: &str) {
+    let mut ctx = ast::Context::new();
+    let ast = common::parse_module_with_flags(&mut ctx, "input", input, jsx_flags());
+    assert_transformed(ctx, ast, JsxRuntime::Automatic, expected);
+}
+
+fn assert_transformed(mut ctx: ast::Context, ast: NodeRc, runtime: JsxRuntime, expected: &str) {
+    let options = JsxOptions {
+        runtime,
+        ..Default::default()
+    };
+    let ast = PassManager::transform_jsx(options).run(&mut ctx, ast);
+    validate_tree(&mut ctx, &ast).unwrap();
+
+    let mut ctx_expected = ast::Context::new();
+    let ast_expected = parse(&mut ctx_expected, expected, false);
+
+    assert_eq!(
+        generate(&mut ctx_expected, &ast_expected),
+        generate(&mut ctx, &ast)
+    );
+}
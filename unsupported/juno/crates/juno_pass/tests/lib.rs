@@ -5,5 +5,14 @@
  * LICENSE file in the root directory of this source tree.
  */
 
+mod bundle;
+mod common;
+mod commonjs;
+mod define;
+mod es5;
+mod jsx;
+mod scope_hoist;
 #[macro_use]
 mod strip_flow;
+mod strip_typescript;
+mod tree_shake;
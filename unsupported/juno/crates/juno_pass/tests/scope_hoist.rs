@@ -0,0 +1,142 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use crate::common::{generate, parse_and_resolve_modules, parse_module};
+use juno::ast::{self, validate_tree};
+use juno_pass::{HoistModule, ScopeHoisting};
+
+#[test]
+fn rename() {
+    assert_scope_hoist(&[
+        (
+            "./main.js",
+            "import {f} from './lib'; const x = 1; export default f(x);",
+            r#"
+                const x = 2;
+                function f(y) { return x + y; }
+                const _x = 1;
+                var _main_default = f(_x);
+                export {_main_default as default};
+            "#,
+        ),
+        (
+            "./lib.js",
+            "const x = 2; export function f(y) { return x + y; }",
+            "",
+        ),
+    ]);
+}
+
+#[test]
+fn reexports() {
+    assert_scope_hoist(&[
+        (
+            "./main.js",
+            r#"
+                import {b} from './barrel';
+                import {h} from 'ext';
+                export {a as x} from './barrel';
+                h(b);
+            "#,
+            r#"
+                const a = 1;
+                const b = 2;
+                import {h} from 'ext';
+                h(b);
+                export {a as x};
+            "#,
+        ),
+        (
+            "./barrel.js",
+            "export * from './a'; export {b} from './b';",
+            "",
+        ),
+        ("./a.js", "export const a = 1;", ""),
+        ("./b.js", "export const b = 2;", ""),
+    ]);
+}
+
+#[test]
+fn eval() {
+    // The bindings of a module which uses `eval` may be referenced by name.
+    assert_scope_hoist(&[
+        (
+            "./main.js",
+            "import {a} from './lib'; import {b} from './dep'; f(a, b);",
+            "const b = 2; import {a} from './lib.js'; f(a, b);",
+        ),
+        (
+            "./lib.js",
+            "export const a = 1; eval('a');",
+            "export const a = 1; eval('a');",
+        ),
+        ("./dep.js", "export const b = 2;", ""),
+    ]);
+}
+
+#[test]
+fn cycle() {
+    assert_scope_hoist(&[
+        (
+            "./main.js",
+            "import {a} from './a'; import {c} from './c'; f(a, c);",
+            "const c = 3; import {a} from './a.js'; f(a, c);",
+        ),
+        (
+            "./a.js",
+            "import {b} from './b'; export const a = () => b;",
+            "import {b} from './b'; export const a = () => b;",
+        ),
+        (
+            "./b.js",
+            "import {a} from './a'; export const b = () => a;",
+            "import {a} from './a'; export const b = () => a;",
+        ),
+        ("./c.js", "export const c = 3;", ""),
+    ]);
+}
+
+/// Scope hoist the `(name, input, expected)` modules with the first one as the entry point,
+/// and compare the generated JS of each module with the generated JS for its `expected`.
+/// Modules which were concatenated into another module generate nothing.
+fn assert_scope_hoist(modules: &[(&str, &str, &str)]) {
+    let mut ctx = ast::Context::new();
+    let inputs: Vec<(&str, &str)> = modules
+        .iter()
+        .map(|&(name, input, _)| (name, input))
+        .collect();
+    let parsed = parse_and_resolve_modules(&mut ctx, &inputs);
+
+    let mut hoisting = {
+        let gc = ast::GCLock::new(&mut ctx);
+        let hoist_modules: Vec<HoistModule> = parsed
+            .iter()
+            .map(|(id, ast, sem)| HoistModule {
+                id: *id,
+                ast: ast.node(&gc),
+                sem,
+            })
+            .collect();
+        ScopeHoisting::analyze(&gc, &hoist_modules, &[parsed[0].0])
+    };
+
+    for ((name, _, expected), (id, ast, _)) in modules.iter().zip(parsed) {
+        let actual = if hoisting.is_concatenated(id) {
+            String::new()
+        } else if let Some(concatenated) = hoisting.take_module(id) {
+            validate_tree(&mut ctx, &concatenated.ast).unwrap();
+            generate(&mut ctx, &concatenated.ast)
+        } else {
+            generate(&mut ctx, &ast)
+        };
+
+        let mut ctx_expected = ast::Context::new();
+        let ast_expected = parse_module(&mut ctx_expected, name, expected);
+        let expected = generate(&mut ctx_expected, &ast_expected);
+        assert_eq!(expected.trim(), actual.trim(), "{}", name);
+    }
+}
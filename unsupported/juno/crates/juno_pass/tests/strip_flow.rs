@@ -378,6 +378,35 @@ fn this_params() {
     );
 }
 
+#[test]
+fn keeps_comments() {
+    let mut ctx = ast::Context::new();
+    let ast = hparser::parse_with_flags(
+        hparser::ParserFlags {
+            dialect: ParserDialect::Flow,
+            store_comments: true,
+            ..Default::default()
+        },
+        "// Adds numbers.
+function add(x: number, y: number): number {
+  return x + y; // sum
+}",
+        &mut ctx,
+    )
+    .unwrap();
+    let ast = PassManager::strip_flow().run(&mut ctx, ast);
+    let mut out = vec![];
+    gen_js::generate(&mut out, &mut ctx, &ast, gen_js::Opt::new()).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "// Adds numbers.
+function add(x, y) {
+  return x + y; // sum
+}
+"
+    );
+}
+
 fn parse(input: &str) -> (ast::Context, NodeRc) {
     let mut ctx = ast::Context::new();
 
@@ -0,0 +1,481 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use crate::common::{self, generate};
+use juno::{
+    ast::{self, builder, template, GCLock, Node, NodeRc, Path, TransformResult, VisitorMut},
+    hparser::{self, ParserDialect},
+};
+use juno_pass::{Pass, PassManager};
+use juno_support::NullTerminatedBuf;
+
+#[test]
+fn annotations() {
+    assert_strip(
+        r#"
+            let x: number = 1;
+            function foo<T>(a: T, b?: string, ...rest: number[]): T {
+                return a;
+            }
+            const f = (x: number): number => x;
+            function bar({a}: {a: number}, [b]: string[]) {}
+            foo<number>(1);
+            new Map<string, number>();
+        "#,
+        r#"
+            let x = 1;
+            function foo(a, b, ...rest) {
+                return a;
+            }
+            const f = x => x;
+            function bar({a}, [b]) {}
+            foo(1);
+            new Map();
+        "#,
+    );
+}
+
+#[test]
+fn expressions() {
+    assert_strip(
+        r#"
+            const a = x as number;
+            const b = (y as any).z;
+            const c = <string>w;
+        "#,
+        r#"
+            const a = x;
+            const b = y.z;
+            const c = w;
+        "#,
+    );
+}
+
+#[test]
+fn declarations() {
+    assert_strip(
+        r#"
+            type T = string | number;
+            interface I extends J {
+                x: number;
+            }
+            namespace N {
+                type U = T;
+            }
+            export type V = T;
+            export interface W {}
+            export const v = 1;
+        "#,
+        r#"
+            export const v = 1;
+        "#,
+    );
+}
+
+#[test]
+fn namespaces() {
+    assert_strip(
+        r#"
+            namespace N {
+                export const x = 1, {y} = o;
+                const hidden = 2;
+                export function f(): number {
+                    return x + hidden;
+                }
+                export class C {}
+                export enum E {
+                    A,
+                }
+                export namespace M {
+                    export const z = 3;
+                }
+                export type T = number;
+                namespace Types {
+                    type U = T;
+                }
+            }
+            namespace A.B {
+                export const w = 4;
+            }
+            export namespace X {
+                export const v = 5;
+            }
+            namespace Empty {}
+        "#,
+        r#"
+            var N;
+            (function (N) {
+                const x = 1, {y} = o;
+                N.x = x;
+                N.y = y;
+                const hidden = 2;
+                function f() {
+                    return x + hidden;
+                }
+                N.f = f;
+                class C {}
+                N.C = C;
+                var E;
+                (function (E) {
+                    E[E.A = 0] = 'A';
+                })(E = N.E || (N.E = {}));
+                var M;
+                (function (M) {
+                    const z = 3;
+                    M.z = z;
+                })(M = N.M || (N.M = {}));
+            })(N || (N = {}));
+            var A;
+            (function (A) {
+                var B;
+                (function (B) {
+                    const w = 4;
+                    B.w = w;
+                })(B = A.B || (A.B = {}));
+            })(A || (A = {}));
+            export var X;
+            (function (X) {
+                const v = 5;
+                X.v = v;
+            })(X || (X = {}));
+        "#,
+    );
+}
+
+#[test]
+fn namespace_errors() {
+    // Namespaces with values are never removed silently.
+    for input in [
+        "namespace N { export let x = 1; }",
+        "namespace N { const a = 1; export { a }; }",
+    ] {
+        let mut ctx = ast::Context::new();
+        let ast = parse(&mut ctx, input, ParserDialect::TypeScript);
+        PassManager::strip_typescript().run(&mut ctx, ast);
+        assert_eq!(ctx.sm().num_errors(), 1, "{}", input);
+    }
+}
+
+#[test]
+fn imports() {
+    assert_strip(
+        r#"
+            import type { T } from 'types';
+            import { x } from 'values';
+        "#,
+        r#"
+            import { x } from 'values';
+        "#,
+    );
+}
+
+#[test]
+fn classes() {
+    assert_strip(
+        r#"
+            class A<T> extends B<T> {
+                x: number;
+                y: string = 'y';
+                constructor(a: number, b = 2) {
+                    super();
+                    foo();
+                }
+                method(p: T) {}
+            }
+        "#,
+        r#"
+            class A extends B {
+                y = 'y';
+                constructor(a, b = 2) {
+                    super();
+                    foo();
+                }
+                method(p) {}
+            }
+        "#,
+    );
+}
+
+#[test]
+fn parameter_properties() {
+    let mut ctx = ast::Context::new();
+    let ast = parse(
+        &mut ctx,
+        r#"
+            class A extends B {
+                constructor(a: number, b = 2, c: string) {
+                    super();
+                    foo();
+                }
+            }
+            class C {
+                constructor(x) {}
+            }
+        "#,
+        ParserDialect::TypeScript,
+    );
+    let mut pm = PassManager::new();
+    pm.add_pass(Box::new(AddParameterProperties {
+        names: &["a", "b", "x"],
+    }));
+    let ast = pm.run(&mut ctx, ast);
+    assert_stripped(
+        ctx,
+        ast,
+        r#"
+            class A extends B {
+                constructor(a, b = 2, c) {
+                    super();
+                    this.a = a;
+                    this.b = b;
+                    foo();
+                }
+            }
+            class C {
+                constructor(x) {
+                    this.x = x;
+                }
+            }
+        "#,
+    );
+}
+
+#[test]
+fn this_params_and_implements() {
+    // The TypeScript parser doesn't accept `this` parameters and `implements` clauses yet,
+    // but the Flow parser builds the same nodes for them.
+    let mut ctx = ast::Context::new();
+    let ast = parse(
+        &mut ctx,
+        r#"
+            class A extends B implements I, J {
+                method(this: A, p) {}
+            }
+            function foo(this: Window, a) {}
+        "#,
+        ParserDialect::Flow,
+    );
+    assert_stripped(
+        ctx,
+        ast,
+        r#"
+            class A extends B {
+                method(p) {}
+            }
+            function foo(a) {}
+        "#,
+    );
+}
+
+#[test]
+fn needs_parser_support() {
+    // The parser rejects these, so the pass can't strip them yet.
+    // Replace this with stripping tests once the parser accepts them.
+    for input in ["x!.y;", "x satisfies T;", "declare const x: number;"] {
+        let buf = NullTerminatedBuf::from_str_copy(input);
+        let parsed = hparser::ParsedJS::parse(
+            hparser::ParserFlags {
+                dialect: ParserDialect::TypeScript,
+                ..Default::default()
+            },
+            &buf,
+        );
+        assert!(parsed.has_errors(), "{}", input);
+    }
+}
+
+#[test]
+fn enums() {
+    assert_strip(
+        r#"
+            enum Color {
+                Red,
+                Green,
+                Blue = 10,
+                Alpha,
+            }
+            export enum Str {
+                A = 'a',
+                B = 'b',
+            }
+            enum Computed {
+                A = f(),
+                B,
+                'c-d' = 1 << 2,
+                E,
+                F = g(),
+                'g-h',
+            }
+        "#,
+        r#"
+            var Color;
+            (function (Color) {
+                Color[Color.Red = 0] = 'Red';
+                Color[Color.Green = 1] = 'Green';
+                Color[Color.Blue = 10] = 'Blue';
+                Color[Color.Alpha = 11] = 'Alpha';
+            })(Color || (Color = {}));
+            export var Str;
+            (function (Str) {
+                Str.A = 'a';
+                Str.B = 'b';
+            })(Str || (Str = {}));
+            var Computed;
+            (function (Computed) {
+                Computed[Computed.A = f()] = 'A';
+                Computed[Computed.B = Computed.A + 1] = 'B';
+                Computed[Computed['c-d'] = 1 << 2] = 'c-d';
+                Computed[Computed.E = Computed['c-d'] + 1] = 'E';
+                Computed[Computed.F = g()] = 'F';
+                Computed[Computed['g-h'] = Computed.F + 1] = 'g-h';
+            })(Computed || (Computed = {}));
+        "#,
+    );
+}
+
+#[test]
+fn enum_member_references() {
+    assert_strip(
+        r#"
+            enum Flags {
+                A = 1,
+                B = A << 1,
+                C = A | B,
+            }
+        "#,
+        r#"
+            var Flags;
+            (function (Flags) {
+                Flags[Flags.A = 1] = 'A';
+                Flags[Flags.B = Flags.A << 1] = 'B';
+                Flags[Flags.C = Flags.A | Flags.B] = 'C';
+            })(Flags || (Flags = {}));
+        "#,
+    );
+}
+
+#[test]
+fn enum_merging() {
+    assert_strip(
+        r#"
+            enum E {
+                A,
+            }
+            enum E {
+                B = 1,
+            }
+        "#,
+        r#"
+            var E;
+            (function (E) {
+                E[E.A = 0] = 'A';
+            })(E || (E = {}));
+            var E;
+            (function (E) {
+                E[E.B = 1] = 'B';
+            })(E || (E = {}));
+        "#,
+    );
+}
+
+fn parse(ctx: &mut ast::Context, input: &str, dialect: ParserDialect) -> NodeRc {
+    common::parse_with_flags(
+        ctx,
+        "input",
+        input,
+        hparser::ParserFlags {
+            dialect,
+            ..Default::default()
+        },
+    )
+}
+
+/// Wraps the constructor parameters in `names` in parameter properties,
+/// which the parser doesn't accept yet.
+struct AddParameterProperties {
+    names: &'static [&'static str],
+}
+
+impl Pass for AddParameterProperties {
+    fn name(&self) -> &'static str {
+        "Add parameter properties"
+    }
+    fn description(&self) -> &'static str {
+        "Wraps constructor parameters in parameter properties"
+    }
+    fn run<'gc>(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        VisitorMut::call(self, gc, node, None)
+    }
+}
+
+impl<'gc> VisitorMut<'gc> for AddParameterProperties {
+    fn call(
+        &mut self,
+        gc: &'gc GCLock<'_, '_>,
+        node: &'gc Node<'gc>,
+        _parent: Option<Path<'gc>>,
+    ) -> TransformResult<&'gc Node<'gc>> {
+        if let Node::FunctionExpression(func) = node {
+            let params = func.params.iter().map(|param| {
+                let id = match param {
+                    Node::AssignmentPattern(ast::AssignmentPattern { left, .. }) => left,
+                    _ => param,
+                };
+                match id {
+                    Node::Identifier(id)
+                        if self.names.iter().any(|name| *name == gc.str(id.name)) =>
+                    {
+                        builder::TSParameterProperty::build_template(
+                            gc,
+                            template::TSParameterProperty {
+                                metadata: Default::default(),
+                                parameter: param,
+                                accessibility: Some(gc.atom("public")),
+                                readonly: false,
+                                is_static: false,
+                                export: false,
+                            },
+                        )
+                    }
+                    _ => param,
+                }
+            });
+            let mut builder = builder::FunctionExpression::from_node(func);
+            builder.params(ast::NodeList::from_iter(gc, params));
+            return TransformResult::Changed(builder.build_forced(gc));
+        }
+        node.visit_children_mut(gc, self)
+    }
+}
+
+/// Strip `input` and compare the generated JS with the generated JS for `expected`.
+fn assert_strip(input: &str, expected: &str) {
+    let mut ctx = ast::Context::new();
+    let ast = parse(&mut ctx, input, ParserDialect::TypeScript);
+    assert_stripped(ctx, ast, expected);
+}
+
+/// Strip `ast` and compare the generated JS with the generated JS for `expected`.
+fn assert_stripped(mut ctx: ast::Context, ast: NodeRc, expected: &str) {
+    let ast = PassManager::strip_typescript().run(&mut ctx, ast);
+    let transformed = generate(&mut ctx, &ast);
+
+    let mut ctx_expected = ast::Context::new();
+    let ast_expected = parse(&mut ctx_expected, expected, ParserDialect::JavaScript);
+    let expected = generate(&mut ctx_expected, &ast_expected);
+
+    assert_eq!(expected, transformed);
+
+    // The output must be valid JS which survives another round trip.
+    let mut ctx_output = ast::Context::new();
+    let ast_output = parse(&mut ctx_output, &transformed, ParserDialect::JavaScript);
+    assert_eq!(transformed, generate(&mut ctx_output, &ast_output));
+}
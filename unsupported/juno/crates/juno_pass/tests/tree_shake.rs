@@ -0,0 +1,247 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use crate::common::{generate, parse_and_resolve_modules, parse_module};
+use juno::ast::{self, validate_tree};
+use juno_pass::{PassManager, ShakeModule, TreeShakeOptions, TreeShaking};
+
+#[test]
+fn unused_exports() {
+    assert_tree_shake(
+        None,
+        &[
+            (
+                "./main.js",
+                "import {f, used} from './lib'; f(used);",
+                "import {f, used} from './lib'; f(used);",
+            ),
+            (
+                "./lib.js",
+                r#"
+                    import {h, unused as u} from './dep';
+                    function helper(x) { return h(x); }
+                    const local = 1, other = sideEffect();
+                    export function f() { return local; }
+                    export function g() { return helper(2); }
+                    export const used = 3, unused = 4;
+                    export default function () {}
+                    export {u};
+                "#,
+                r#"
+                    import './dep';
+                    const local = 1, other = sideEffect();
+                    export function f() { return local; }
+                    export const used = 3;
+                "#,
+            ),
+            (
+                "./dep.js",
+                "export function h(x) { return x; } export function unused() {}",
+                "",
+            ),
+        ],
+    );
+}
+
+#[test]
+fn used_locally() {
+    assert_tree_shake(
+        None,
+        &[
+            (
+                "./main.js",
+                "import {f} from './lib'; f();",
+                "import {f} from './lib'; f();",
+            ),
+            (
+                "./lib.js",
+                r#"
+                    export function f() { return g() + value; }
+                    export function g() { return 1; }
+                    export default 'unused';
+                    var value = 1;
+                    export {value};
+                "#,
+                r#"
+                    export function f() { return g() + value; }
+                    function g() { return 1; }
+                    var value = 1;
+                "#,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn reexports() {
+    assert_tree_shake(
+        Some(&[]),
+        &[
+            (
+                "./main.js",
+                "import {a, b} from './barrel'; import * as ns from './ns'; f(a, b, ns);",
+                "import {a, b} from './barrel'; import * as ns from './ns'; f(a, b, ns);",
+            ),
+            (
+                "./barrel.js",
+                r#"
+                    export * from './a';
+                    export * from './unused';
+                    export {b, c} from './b';
+                    export {d} from './d';
+                "#,
+                r#"
+                    export * from './a';
+                    export {b} from './b';
+                "#,
+            ),
+            ("./a.js", "export const a = 1;", "export const a = 1;"),
+            (
+                "./b.js",
+                "export const b = 1, c = 2;",
+                "export const b = 1;",
+            ),
+            ("./d.js", "export const d = 1;", ""),
+            ("./unused.js", "export const u = 1;", ""),
+            (
+                "./ns.js",
+                "export const x = 1, y = 2;",
+                "export const x = 1, y = 2;",
+            ),
+        ],
+    );
+}
+
+#[test]
+fn side_effects() {
+    let modules: &[(&str, &str, &str)] = &[
+        (
+            "./main.js",
+            "import './effect'; import {p} from './pure'; import './unused';",
+            "import './effect';",
+        ),
+        (
+            "./effect.js",
+            "export const e = 1; window.loaded = true;",
+            "window.loaded = true;",
+        ),
+        ("./pure.js", "export const p = 1; init();", ""),
+        ("./unused.js", "export const u = 1;", ""),
+    ];
+    assert_tree_shake(Some(&["effect.js"]), modules);
+
+    // Every module may have side effects by default.
+    assert_tree_shake(
+        None,
+        &[
+            (
+                "./main.js",
+                "import './effect'; import {p} from './pure';",
+                "import './effect'; import './pure';",
+            ),
+            (
+                "./effect.js",
+                "window.loaded = true;",
+                "window.loaded = true;",
+            ),
+            ("./pure.js", "export const p = 1; init();", "init();"),
+        ],
+    );
+}
+
+#[test]
+fn impure_declarations() {
+    assert_tree_shake(
+        None,
+        &[
+            ("./main.js", "import './lib';", "import './lib';"),
+            (
+                "./lib.js",
+                r#"
+                    export const a = [1, {b: 'c'}], d = `e`, f = () => {}, g = !a;
+                    export const h = i(), {j} = k;
+                    export class L { m() {} static n = 1; }
+                    export class O extends P {}
+                    export default q();
+                "#,
+                r#"
+                    const a = [1, {b: 'c'}], g = !a;
+                    const h = i(), {j} = k;
+                    class O extends P {}
+                    q();
+                "#,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn eval() {
+    // Any binding of a module which uses `eval` may be used.
+    assert_tree_shake(
+        None,
+        &[
+            ("./main.js", "import './lib';", "import './lib';"),
+            (
+                "./lib.js",
+                "import {a} from './dep'; function f() {} eval('f(a)');",
+                "import {a} from './dep'; function f() {} eval('f(a)');",
+            ),
+            (
+                "./dep.js",
+                "export const a = 1, b = 2;",
+                "export const a = 1;",
+            ),
+        ],
+    );
+}
+
+/// Tree shake the `(name, input, expected)` modules from the first one, with the modules
+/// matching `side_effects` having side effects, and compare the generated JS of each module
+/// with the generated JS for its `expected`. Modules which are removed entirely generate
+/// nothing.
+fn assert_tree_shake(side_effects: Option<&[&str]>, modules: &[(&str, &str, &str)]) {
+    let mut ctx = ast::Context::new();
+    let inputs: Vec<(&str, &str)> = modules
+        .iter()
+        .map(|&(name, input, _)| (name, input))
+        .collect();
+    let parsed = parse_and_resolve_modules(&mut ctx, &inputs);
+
+    let mut shaking = {
+        let gc = ast::GCLock::new(&mut ctx);
+        let shake_modules: Vec<ShakeModule> = parsed
+            .iter()
+            .map(|(id, ast, sem)| ShakeModule {
+                id: *id,
+                ast: ast.node(&gc),
+                sem,
+            })
+            .collect();
+        let options = TreeShakeOptions {
+            entry_points: vec![parsed[0].0],
+            side_effects: side_effects
+                .map(|patterns| patterns.iter().map(|p| p.to_string()).collect()),
+        };
+        TreeShaking::analyze(&gc, &shake_modules, &options)
+    };
+
+    for ((name, _, expected), (id, ast, _)) in modules.iter().zip(parsed) {
+        let actual = if shaking.is_included(id) {
+            let ast = PassManager::tree_shake(shaking.take_pass(id)).run(&mut ctx, ast);
+            validate_tree(&mut ctx, &ast).unwrap();
+            generate(&mut ctx, &ast)
+        } else {
+            String::new()
+        };
+
+        let mut ctx_expected = ast::Context::new();
+        let ast_expected = parse_module(&mut ctx_expected, name, expected);
+        let expected = generate(&mut ctx_expected, &ast_expected);
+        assert_eq!(expected.trim(), actual.trim(), "{}", name);
+    }
+}
@@ -71,17 +71,21 @@ impl SourceId {
 
 #[derive(Debug, Default)]
 struct Inner {
+    /// Sources are only ever appended, and their names and buffers are allocated separately,
+    /// so references to them stay valid while more sources are added.
+    sources: Vec<(String, Rc<NullTerminatedBuf>)>,
+    filenames: HashMap<String, SourceId>,
     num_errors: usize,
     num_warnings: usize,
     num_notes: usize,
+    /// Whether diagnostics are currently being discarded.
+    suppressed: bool,
 }
 
 /// SourceManager owns a collection of source buffers and their names and handles
 /// reporting errors.
 #[derive(Debug, Default)]
 pub struct SourceManager {
-    sources: Vec<(String, Rc<NullTerminatedBuf>)>,
-    filenames: HashMap<String, SourceId>,
     inner: UnsafeCell<Inner>,
 }
 
@@ -92,40 +96,56 @@ impl SourceManager {
 
     /// Register a source buffer with its name.
     pub fn add_source<S: Into<String>>(&mut self, name: S, buf: NullTerminatedBuf) -> SourceId {
+        self.add_source_on_demand(name, buf)
+    }
+
+    /// Register a source buffer with its name while the SourceManager is shared, such as when
+    /// a dependency resolver finds a file which hasn't been loaded yet.
+    pub fn add_source_on_demand<S: Into<String>>(
+        &self,
+        name: S,
+        buf: NullTerminatedBuf,
+    ) -> SourceId {
+        let inner = unsafe { self.inner_mut() };
         assert!(
-            self.sources.len() < SourceId::INVALID.0 as usize,
+            inner.sources.len() < SourceId::INVALID.0 as usize,
             "Too many sources",
         );
-        let id = SourceId(self.sources.len() as u32);
+        let id = SourceId(inner.sources.len() as u32);
         let name_str = name.into();
-        self.filenames.insert(name_str.clone(), id);
-        self.sources.push((name_str, Rc::new(buf)));
+        inner.filenames.insert(name_str.clone(), id);
+        inner.sources.push((name_str, Rc::new(buf)));
         id
     }
 
     /// Obtain the number of registered sources.
     pub fn num_sources(&self) -> usize {
-        self.sources.len()
+        unsafe { self.inner() }.sources.len()
     }
 
     /// Obtain the name of a previously registered source buffer.
     pub fn source_name(&self, source_id: SourceId) -> &str {
-        self.sources[source_id.as_usize()].0.as_str()
+        unsafe { self.inner() }.sources[source_id.as_usize()]
+            .0
+            .as_str()
     }
 
     /// Obtain a reference to a previously registered source buffer.
     pub fn source_buffer(&self, source_id: SourceId) -> &NullTerminatedBuf {
-        &self.sources[source_id.as_usize()].1
+        &unsafe { self.inner() }.sources[source_id.as_usize()].1
     }
 
     /// Obtain a Rc of a previously registered source buffer.
     pub fn source_buffer_rc(&self, source_id: SourceId) -> Rc<NullTerminatedBuf> {
-        Rc::clone(&self.sources[source_id.as_usize()].1)
+        Rc::clone(&unsafe { self.inner() }.sources[source_id.as_usize()].1)
     }
 
     /// Get the `SourceId` for a given file `name`.
     pub fn lookup_name<S: AsRef<str>>(&self, name: S) -> Option<SourceId> {
-        self.filenames.get(name.as_ref()).copied()
+        unsafe { self.inner() }
+            .filenames
+            .get(name.as_ref())
+            .copied()
     }
 
     /// Gain mutable access to the mutable inner object.
@@ -154,9 +174,21 @@ impl SourceManager {
         unsafe { self.inner() }.num_warnings
     }
 
+    /// Run `f`, discarding all diagnostics it reports without printing or counting them.
+    /// Used when re-running an analysis whose diagnostics have already been reported.
+    pub fn suppress_diagnostics<R, F: FnOnce() -> R>(&self, f: F) -> R {
+        let prev = std::mem::replace(&mut unsafe { self.inner_mut() }.suppressed, true);
+        let result = f();
+        unsafe { self.inner_mut() }.suppressed = prev;
+        result
+    }
+
     /// Report an error at the specified range in the specified source buffer.
     pub fn error<S: Into<String>>(&self, range: SourceRange, msg: S) {
         let inner = unsafe { self.inner_mut() };
+        if inner.suppressed {
+            return;
+        }
         inner.num_errors += 1;
 
         eprintln!(
@@ -169,6 +201,9 @@ impl SourceManager {
     }
     pub fn note<S: Into<String>>(&self, range: SourceRange, msg: S) {
         let inner = unsafe { self.inner_mut() };
+        if inner.suppressed {
+            return;
+        }
         inner.num_notes += 1;
 
         eprintln!(
@@ -182,6 +217,9 @@ impl SourceManager {
     /// Report a warning at the specified range in the specified source buffer.
     pub fn warning<S: Into<String>>(&self, range: SourceRange, msg: S) {
         let inner = unsafe { self.inner_mut() };
+        if inner.suppressed {
+            return;
+        }
         inner.num_warnings += 1;
 
         eprintln!(
@@ -196,7 +234,8 @@ impl SourceManager {
 
 impl HeapSize for SourceManager {
     fn heap_size(&self) -> usize {
-        self.sources.heap_size() + self.filenames.heap_size()
+        let inner = unsafe { self.inner() };
+        inner.sources.heap_size() + inner.filenames.heap_size()
     }
 }
 
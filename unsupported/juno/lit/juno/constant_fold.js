@@ -0,0 +1,76 @@
+/**
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+// RUN: %juno --gen-js -O %s | %FileCheck %s --match-full-lines
+
+function arith() {
+  return [1 + 2 * 3, 10 / 4, 7 % 3, 2 ** 10, 1 - 3, -(-5), +'12', 1 / 0];
+}
+
+// CHECK-LABEL: function arith() {
+// CHECK-NEXT:   return [7, 2.5, 1, 1024, -2, 5, 12, 1 / 0];
+// CHECK-NEXT: }
+
+function bitwise() {
+  return [5 & 3, 5 | 3, 5 ^ 3, ~5, 1 << 31, -16 >> 2, -16 >>> 28];
+}
+
+// CHECK-LABEL: function bitwise() {
+// CHECK-NEXT:   return [1, 7, 6, -6, -2147483648, -4, 15];
+// CHECK-NEXT: }
+
+function compare() {
+  return [1 < 2, 'b' < 'a', null == void 0, '1' == 1, NaN == NaN, 0 === -0];
+}
+
+// CHECK-LABEL: function compare() {
+// CHECK-NEXT:   return [true, false, true, true, NaN == NaN, true];
+// CHECK-NEXT: }
+
+function strings(x) {
+  return ['a' + 1, typeof null, !'', `a${1 + 1}b${null}`, x`a${1}`];
+}
+
+// CHECK-LABEL: function strings(x) {
+// CHECK-NEXT:   return ['a1', 'object', true, 'a2bnull', x`a${1}`];
+// CHECK-NEXT: }
+
+function cond(x, y) {
+  if (1 + 1 === 2) {
+    return x;
+  }
+  return 0 || y;
+}
+
+// CHECK-LABEL: function cond(x, y) {
+// CHECK-NEXT:   {
+// CHECK-NEXT:     return x;
+// CHECK-NEXT:   }
+// CHECK-NEXT:   return y;
+// CHECK-NEXT: }
+
+function logical(x, y) {
+  return [0 || y, 1 && x, null ?? y];
+}
+
+// CHECK-LABEL: function logical(x, y) {
+// CHECK-NEXT:   return [y, x, y];
+// CHECK-NEXT: }
+
+function references(o, x) {
+  (0 || o.f)();
+  (1 && o.f)`a`;
+  delete (1 && o.f);
+  return [typeof (0 || x), typeof (0 || 'a')];
+}
+
+// CHECK-LABEL: function references(o, x) {
+// CHECK-NEXT:   (0 || o.f)();
+// CHECK-NEXT:   (1 && o.f)`a`;
+// CHECK-NEXT:   delete (1 && o.f);
+// CHECK-NEXT:   return [typeof (0 || x), 'string'];
+// CHECK-NEXT: }
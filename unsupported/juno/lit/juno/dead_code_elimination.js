@@ -0,0 +1,104 @@
+/**
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+// RUN: %juno --gen-js --dce %s | %FileCheck %s --match-full-lines
+
+function unreachable(x) {
+  if (x) {
+    throw x;
+  }
+  return g();
+  x++;
+  var y = 10, {z} = x;
+  function g() {
+    return y + z;
+  }
+}
+
+// CHECK-LABEL: function unreachable(x) {
+// CHECK-NEXT:   if (x) {
+// CHECK-NEXT:     throw x;
+// CHECK-NEXT:   }
+// CHECK-NEXT:   return g();
+// CHECK-NEXT:   var y, z;
+// CHECK-NEXT:   function g() {
+// CHECK-NEXT:     return y + z;
+// CHECK-NEXT:   }
+// CHECK-NEXT: }
+
+function loops(x) {
+  for (;;) {
+    if (x) {
+      continue;
+      x();
+    }
+    break;
+    x();
+  }
+}
+
+// CHECK-LABEL: function loops(x) {
+// CHECK-NEXT:   for(;;) {
+// CHECK-NEXT:     if (x) {
+// CHECK-NEXT:       continue;
+// CHECK-NEXT:     }
+// CHECK-NEXT:     break;
+// CHECK-NEXT:   }
+// CHECK-NEXT: }
+
+function empty(x) {
+  {}
+  ;
+  if (x) {} else {
+    x();
+  }
+  if (x) {
+    x();
+  } else {}
+  if (x()) {}
+  if (x) {}
+}
+
+// CHECK-LABEL: function empty(x) {
+// CHECK-NEXT:   if (!x) {
+// CHECK-NEXT:     x();
+// CHECK-NEXT:   }
+// CHECK-NEXT:   if (x) {
+// CHECK-NEXT:     x();
+// CHECK-NEXT:   }
+// CHECK-NEXT:   x();
+// CHECK-NEXT: }
+
+function unused(x) {
+  var a = 1, b = x(), c = [x, {d: 'e'}], d = {[x]: 1};
+  function f() {
+    return h();
+  }
+  function h() {}
+  function used() {}
+  return used;
+}
+
+// CHECK-LABEL: function unused(x) {
+// CHECK-NEXT:   var b = x(), d = {[x]: 1};
+// CHECK-NEXT:   function used() {}
+// CHECK-NEXT:   return used;
+// CHECK-NEXT: }
+
+function lexical() {
+  return () => a + C;
+  let a = 1, b = 2;
+  const c = 3;
+  class C {}
+  class D {}
+}
+
+// CHECK-LABEL: function lexical() {
+// CHECK-NEXT:   return () => a + C;
+// CHECK-NEXT:   let a = 1, b = 2;
+// CHECK-NEXT:   class C {}
+// CHECK-NEXT: }
@@ -0,0 +1,50 @@
+/**
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+// RUN: %juno --gen-js --mangle %s | %FileCheck %s --match-full-lines
+
+function locals(first, second) {
+  var result = first + second;
+  return result * result;
+}
+
+// CHECK-LABEL: function locals(b, c) {
+// CHECK-NEXT:   var a = b + c;
+// CHECK-NEXT:   return a * a;
+// CHECK-NEXT: }
+
+function nested(x) {
+  function inner(y) {
+    return x + y;
+  }
+  return inner(x) + {x}.x;
+}
+
+// CHECK-LABEL: function nested(a) {
+// CHECK-NEXT:   function b(c) {
+// CHECK-NEXT:     return a + c;
+// CHECK-NEXT:   }
+// CHECK-NEXT:   return b(a) + {x: a}.x;
+// CHECK-NEXT: }
+
+function usesGlobal(long) {
+  return a + long;
+}
+
+// CHECK-LABEL: function usesGlobal(b) {
+// CHECK-NEXT:   return a + b;
+// CHECK-NEXT: }
+
+function withEval(value) {
+  var local = 1;
+  return eval(value) + local;
+}
+
+// CHECK-LABEL: function withEval(value) {
+// CHECK-NEXT:   var local = 1;
+// CHECK-NEXT:   return eval(value) + local;
+// CHECK-NEXT: }
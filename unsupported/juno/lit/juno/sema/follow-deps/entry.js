@@ -0,0 +1,20 @@
+/**
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+// RUN: %juno %s --follow-deps --resolver=node 2>&1 | %FileCheck %s --match-full-lines
+
+import './dep';
+import('./lazy');
+import './missing';
+
+// CHECK: {{.*}}/entry.js:12:8: warning: Unable to resolve import for ./missing
+// CHECK: Module: {{.*}}/entry.js
+// CHECK: 2 require resolutions
+// CHECK: Module: {{.*}}/dep.js
+// CHECK: 1 require resolutions
+// CHECK: Module: {{.*}}/lazy.js
+// CHECK: Module: {{.*}}/nested/index.js
@@ -0,0 +1,24 @@
+/**
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+// RUN: %juno %s --gen-deps --follow-deps --resolver=node --deps-format=dot | %FileCheck %s --match-full-lines
+// RUN: %juno %s --gen-deps --follow-deps --resolver=node --pretty=0 | %FileCheck %s --check-prefix=JSON
+
+import './a';
+import('./b');
+
+// CHECK: digraph dependencies {
+// CHECK-NEXT:   m0 [label="{{.*}}/entry.js"];
+// CHECK-NEXT:   m1 [label="{{.*}}/a.js"];
+// CHECK-NEXT:   m2 [label="{{.*}}/b.js"];
+// CHECK-NEXT:   m0 -> m1 [label="./a"];
+// CHECK-NEXT:   m0 -> m2 [label="./b", style=dashed];
+// CHECK-NEXT:   m1 -> m2 [label="./b", color=red];
+// CHECK-NEXT:   m2 -> m1 [label="./a", color=red];
+// CHECK-NEXT: }
+
+// JSON: {"modules":[{"id":0,"name":"{{.*}}/entry.js","dependencies":2,"dependents":0,"transitiveDependencies":2},{{.*}}],"edges":[{"from":0,"to":1,"kind":"import","lazy":false,"specifier":"./a","resolved":true,"line":11,"column":8},{{.*}}],"cycles":{{\[\[}}1,2,1]]}
@@ -0,0 +1,15 @@
+/**
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+// RUN: (%juno %s --follow-deps --resolver=node 2>&1 || true) | %FileCheck %s --match-full-lines
+
+import {a, missing} from './a';
+import {dup} from './stars';
+a, missing, dup;
+
+// CHECK: {{.*}}/entry.js:10:26: error: 'missing' is not exported by ./a
+// CHECK: {{.*}}/entry.js:11:19: error: 'dup' is ambiguous in ./stars, which exports it from {{.*}}/b.js and {{.*}}/c.js with 'export *'
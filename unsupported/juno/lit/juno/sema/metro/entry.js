@@ -0,0 +1,19 @@
+/**
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is licensed under the MIT license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+// RUN: %juno %s --follow-deps --resolver=metro --resolver-config=%S/resolver.json 2>&1 | %FileCheck %s --match-full-lines
+
+import './Button';
+import '@app/theme';
+import './missing';
+
+// CHECK: {{.*}}/entry.js:12:8: warning: Unable to resolve import for ./missing
+// CHECK: {{.*}}/entry.js:12:8: note: tried {{.*}}/missing, {{.*}}/missing.ios.js, {{.*}}/missing.native.js, {{.*}}/missing.js
+// CHECK: Module: {{.*}}/entry.js
+// CHECK: 2 require resolutions
+// CHECK: Module: {{.*}}/Button.ios.js
+// CHECK: Module: {{.*}}/src/app/theme.js
@@ -382,6 +382,7 @@ static void genConvert() {
                   "        start: cvt.cvt_smloc(nr.source_range.start),\n"
                   "        end: ast::SourceLoc::invalid(),\n"
                   "    };\n"
+                  "    let leading_comments = cvt.enter_node(nr.source_range);\n"
                   "\n";
 
   llvh::outs() << "    let res = match nr.kind {\n";
@@ -461,6 +462,8 @@ static void genConvert() {
            // location.
            "if nr.source_range.is_empty() { template.metadata.range.start } "
            "else { cvt.cvt_smloc(nr.source_range.end.pred()) };\n"
+           "          template.metadata.comments = "
+           "cvt.exit_node(gc, leading_comments, nr.source_range);\n"
         << "          ast::builder::" << cls.name
         << "::build_template(gc, template)\n"
         << "        }\n"; // match block
@@ -475,6 +478,7 @@ static void genConvert() {
   llvh::outs()
       << "        _ => {\n"
          "          cvt.report_invalid_node(gc, n, range);\n"
+         "          cvt.exit_node(gc, leading_comments, nr.source_range);\n"
          "          let template = ast::template::Empty {\n"
          "            metadata: ast::TemplateMetadata {range, ..Default::default()}\n"
          "          };\n"